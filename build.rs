@@ -0,0 +1,7 @@
+fn main() {
+    // Generates the gRPC service from proto/agent.proto; needs `protoc` on the PATH
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/agent.proto").expect("failed to compile proto/agent.proto");
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
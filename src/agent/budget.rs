@@ -0,0 +1,187 @@
+use super::models::ChatModel;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::*;
+
+/// Maximum number of overflow characters handed to the summary model.
+const MAX_OVERFLOW_FOR_SUMMARY: usize = 60_000;
+
+/// Output size limits for tool results fed back to the model.
+///
+/// Every tool gets the default budget unless overridden by name. When a summarizer
+/// is configured, the part of a result that doesn't fit is summarized instead of
+/// being dropped.
+#[derive(Clone)]
+pub struct OutputBudgets {
+    default_max_chars: usize,
+    overrides: HashMap<String, usize>,
+    summarizer: Option<Arc<dyn ChatModel>>,
+}
+
+impl OutputBudgets {
+    /// Creates budgets from a default size and `tool_name=max_chars` override entries.
+    pub fn new(
+        default_max_chars: usize,
+        overrides: &[String],
+        summarizer: Option<Arc<dyn ChatModel>>,
+    ) -> Self {
+        let overrides = overrides
+            .iter()
+            .filter_map(|entry| {
+                let (name, max) = entry.split_once('=')?;
+                match max.trim().parse() {
+                    Ok(max) => Some((name.trim().to_string(), max)),
+                    Err(_) => {
+                        warn!("Ignoring invalid tool output budget: {}", entry);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        OutputBudgets {
+            default_max_chars,
+            overrides,
+            summarizer,
+        }
+    }
+
+    /// Wraps a tool so its output is held to the budget configured for it.
+    pub fn apply<T: Tool>(&self, tool: T) -> Budgeted<T> {
+        let name = tool.name();
+        let max_chars = self
+            .overrides
+            .get(&name)
+            .copied()
+            .unwrap_or(self.default_max_chars);
+
+        Budgeted {
+            inner: tool,
+            name,
+            max_chars,
+            summarizer: self.summarizer.clone(),
+        }
+    }
+}
+
+/// A tool whose (stringified) output is truncated or summarized to fit its budget.
+pub struct Budgeted<T> {
+    inner: T,
+    name: String,
+    max_chars: usize,
+    summarizer: Option<Arc<dyn ChatModel>>,
+}
+
+impl<T: Tool> Budgeted<T> {
+    /// Shrinks `text` to the budget, summarizing the overflow when possible.
+    async fn fit(&self, text: String) -> String {
+        let total = text.chars().count();
+        if total <= self.max_chars {
+            return text;
+        }
+
+        let Some(summarizer) = &self.summarizer else {
+            debug!(
+                tool = %self.name,
+                "Truncating tool output from {} to {} chars", total, self.max_chars
+            );
+            return smart_truncate(&text, self.max_chars);
+        };
+
+        // Keep most of the budget verbatim and use the rest for a summary of the overflow
+        let head = smart_truncate_raw(&text, self.max_chars * 3 / 4);
+        let overflow: String = text[head.len()..]
+            .chars()
+            .take(MAX_OVERFLOW_FOR_SUMMARY)
+            .collect();
+        let prompt = format!(
+            "The following is the overflow of a `{}` tool result that did not fit the context window. Summarize it in at most {} characters, preserving names, counts, errors, and anything unusual:\n\n{}",
+            self.name,
+            self.max_chars / 4,
+            overflow
+        );
+
+        match summarizer.chat(&prompt, Vec::new(), 0).await {
+            Ok(summary) => {
+                debug!(
+                    tool = %self.name,
+                    "Summarized {} overflow chars of tool output",
+                    total - head.chars().count()
+                );
+                let summary: String = summary.chars().take(self.max_chars / 4).collect();
+                format!("{}\n… [remaining output summarized]\n{}", head, summary)
+            }
+            Err(e) => {
+                warn!(
+                    tool = %self.name,
+                    "Failed to summarize tool output overflow, truncating: {}", e
+                );
+                smart_truncate(&text, self.max_chars)
+            }
+        }
+    }
+}
+
+impl<T: Tool> Tool for Budgeted<T> {
+    const NAME: &'static str = T::NAME;
+    type Args = T::Args;
+    type Output = String;
+    type Error = T::Error;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn definition(&self, prompt: String) -> ToolDefinition {
+        self.inner.definition(prompt).await
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let output = self.inner.call(args).await?;
+
+        // Plain strings are passed through as-is; structured output is pretty-printed.
+        // Taking the output by value drops it here: it needn't be Send, so it mustn't be
+        // held across the await below
+        let text = match serde_json::to_value(output) {
+            Ok(serde_json::Value::String(text)) => text,
+            Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_default(),
+            Err(e) => {
+                error!(tool = %self.name, "Failed to serialize tool output: {}", e);
+                String::new()
+            }
+        };
+
+        Ok(self.fit(text).await)
+    }
+}
+
+/// Cuts `text` to at most `max_chars` characters, preferring a line boundary.
+fn smart_truncate_raw(text: &str, max_chars: usize) -> &str {
+    let cut = match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => idx,
+        None => return text,
+    };
+
+    // Prefer ending on a full line, unless that would throw away more than half
+    match text[..cut].rfind('\n') {
+        Some(newline) if newline >= cut / 2 => &text[..newline],
+        _ => &text[..cut],
+    }
+}
+
+/// Truncates `text` to the budget with a marker telling the model how much was omitted.
+pub fn smart_truncate(text: &str, max_chars: usize) -> String {
+    let head = smart_truncate_raw(text, max_chars);
+    if head.len() == text.len() {
+        return text.to_string();
+    }
+
+    let total = text.chars().count();
+    let omitted = total - head.chars().count();
+    format!(
+        "{}\n… [truncated: {} of {} characters omitted; narrow the query (e.g. namespace or limit) to see more]",
+        head, omitted, total
+    )
+}
@@ -0,0 +1,264 @@
+use super::models::{self, ModelSpec, Provider};
+use super::toolset::{AgentTool, ToolSet};
+use super::{Agent, PREAMBLE};
+use crate::environment::Environment;
+use crate::error::AppError;
+use crate::sessions::SessionStore;
+use rig::client::CompletionClient;
+use rig::completion::CompletionModel;
+use rig::providers::openai::responses_api::ResponsesCompletionModel;
+use std::sync::Arc;
+use tracing::*;
+
+/// Rounds of tool calling allowed per prompt unless [`AgentBuilder::max_turns`] is set.
+pub const DEFAULT_MAX_TURNS: usize = 2;
+
+/// Configures and builds an [`Agent`].
+///
+/// Settings left unset fall back to what [`Agent::new`] uses: the portfolio preamble,
+/// 2 rounds of tool calling, and the provider's default temperature. Registered tools
+/// come on top of the tools enabled in the environment, and the
+/// first model of the chain also summarizes unless a summary model is set.
+pub struct AgentBuilder<M: CompletionModel> {
+    pub(super) sessions: Arc<SessionStore>,
+    pub(super) models: Vec<(String, M)>,
+    pub(super) summary_model: Option<(String, M)>,
+    pub(super) preamble: String,
+    pub(super) tools: ToolSet,
+    pub(super) max_turns: usize,
+    pub(super) temperature: Option<f64>,
+}
+
+// The server only configures the model chain; the rest is for embedding the agent
+#[cfg_attr(not(test), allow(dead_code))]
+impl<M: CompletionModel + 'static> AgentBuilder<M> {
+    /// Starts a builder with no models; add at least one with [`AgentBuilder::model`].
+    pub fn new(sessions: Arc<SessionStore>) -> Self {
+        AgentBuilder {
+            sessions,
+            models: Vec::new(),
+            summary_model: None,
+            preamble: PREAMBLE.to_string(),
+            tools: ToolSet::new(),
+            max_turns: DEFAULT_MAX_TURNS,
+            temperature: None,
+        }
+    }
+
+    /// Appends a model to the fallback chain, reported under `name` (e.g. "openai:gpt-5.1").
+    pub fn model(mut self, name: impl Into<String>, model: M) -> Self {
+        self.models.push((name.into(), model));
+        self
+    }
+
+    /// Summarizes conversations and suggests follow-ups with `model`.
+    pub fn summary_model(mut self, name: impl Into<String>, model: M) -> Self {
+        self.summary_model = Some((name.into(), model));
+        self
+    }
+
+    /// Replaces the system prompt of the models in the chain.
+    pub fn preamble(mut self, preamble: impl Into<String>) -> Self {
+        self.preamble = preamble.into();
+        self
+    }
+
+    /// Gives every model in the chain `tool`, traced and output-budgeted like the
+    /// built-in tools.
+    pub fn tool(self, tool: impl AgentTool) -> Self {
+        self.register_tool(Arc::new(tool))
+    }
+
+    /// Registers a tool behind a trait object; see [`ToolSet::register`].
+    pub fn register_tool(mut self, tool: Arc<dyn AgentTool>) -> Self {
+        self.tools.register(tool);
+        self
+    }
+
+    /// Registers every tool of `tools`.
+    pub fn tools(mut self, tools: ToolSet) -> Self {
+        self.tools.extend(tools);
+        self
+    }
+
+    /// Allows up to `max_turns` rounds of tool calling before the model must answer.
+    pub fn max_turns(mut self, max_turns: usize) -> Self {
+        self.max_turns = max_turns;
+        self
+    }
+
+    /// Samples the models in the chain at `temperature`.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Builds the agent, starting the background tasks its tools need.
+    pub fn build(self) -> Result<Agent, AppError> {
+        Agent::assemble(self)
+    }
+}
+
+impl AgentBuilder<ResponsesCompletionModel> {
+    /// Starts a builder with the OpenAI model chain (`AGENT_MODELS`) and summary model
+    /// (`SUMMARY_MODEL`) configured in the environment.
+    pub fn openai(api_key: &str, sessions: Arc<SessionStore>) -> Result<Self, AppError> {
+        info!("Initializing AI agent with OpenAI backend");
+
+        debug!("open ai api key: {}", api_key);
+
+        let env = Environment::global();
+        let openai_client = models::openai_client(api_key, env.openai_base_url.as_deref())
+            .map_err(|e| {
+                error!("Failed to create OpenAI client: {}", e);
+                e
+            })?;
+
+        debug!("OpenAI client created successfully");
+
+        let chain = models::parse_model_chain(&env.agent_models).map_err(|e| {
+            error!("Invalid AGENT_MODELS configuration: {}", e);
+            AppError::Config(e)
+        })?;
+
+        let summary_spec: ModelSpec = env.summary_model.parse().map_err(|e: String| {
+            error!("Invalid SUMMARY_MODEL configuration: {}", e);
+            AppError::Config(e)
+        })?;
+
+        let completion_model = |spec: &ModelSpec| match spec.provider {
+            Provider::OpenAI => openai_client.completion_model(&spec.model),
+        };
+        let builder = chain
+            .iter()
+            .fold(AgentBuilder::new(sessions), |builder, spec| {
+                builder.model(spec.to_string(), completion_model(spec))
+            });
+        Ok(builder.summary_model(summary_spec.to_string(), completion_model(&summary_spec)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::error::ChatError;
+    use crate::agent::mock::{MockCompletionModel, MockReply};
+    use crate::agent::ChatOptions;
+    use rig::completion::ToolDefinition;
+    use rig::tool::Tool;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::convert::Infallible;
+
+    #[derive(Deserialize)]
+    struct ShoutArgs {
+        text: String,
+    }
+
+    struct Shout;
+
+    impl Tool for Shout {
+        const NAME: &'static str = "shout";
+        type Args = ShoutArgs;
+        type Output = String;
+        type Error = Infallible;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "Upper-cases text".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": { "text": { "type": "string" } },
+                    "required": ["text"]
+                }),
+            }
+        }
+
+        async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Ok(args.text.to_uppercase())
+        }
+    }
+
+    fn shout(text: &str) -> MockReply {
+        MockReply::ToolCall {
+            name: "shout".to_string(),
+            args: json!({ "text": text }),
+        }
+    }
+
+    fn sessions() -> Arc<SessionStore> {
+        Arc::new(SessionStore::from_env(Environment::global()))
+    }
+
+    #[tokio::test]
+    async fn builds_an_agent_with_the_configured_preamble_and_tools() {
+        let model = MockCompletionModel::new([shout("hi"), MockReply::Text("done".to_string())]);
+        let agent = AgentBuilder::new(sessions())
+            .model("mock", model.clone())
+            .preamble("Be brief.")
+            .temperature(0.2)
+            .tool(Shout)
+            .build()
+            .unwrap();
+
+        let response = agent
+            .chat("Shout hi".to_string(), Vec::new(), ChatOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(response.text, "done");
+        assert_eq!(response.tool_trace.len(), 1);
+        assert_eq!(response.tool_trace[0].tool, "shout");
+        assert_eq!(response.tool_trace[0].result, "\"HI\"");
+
+        let requests = model.requests();
+        assert_eq!(requests[0].preamble.as_deref(), Some("Be brief."));
+        assert!(requests[0].tools.iter().any(|tool| tool == "shout"));
+    }
+
+    #[tokio::test]
+    async fn default_preamble_asks_for_plain_text_answers() {
+        let model = MockCompletionModel::always("All pods are running.");
+        let agent = AgentBuilder::new(sessions())
+            .model("mock", model.clone())
+            .build()
+            .unwrap();
+
+        let response = agent
+            .chat(
+                "Are the pods up?".to_string(),
+                Vec::new(),
+                ChatOptions::default(),
+            )
+            .await
+            .unwrap();
+        // The answer is the model's text as is, not a JSON object serialized into it
+        assert_eq!(response.text, "All pods are running.");
+        assert!(serde_json::from_str::<serde_json::Value>(&response.text).is_err());
+
+        let preamble = model.requests()[0].preamble.clone().unwrap();
+        assert!(preamble.contains("plain text"));
+        assert!(!preamble.contains("JSON schema"));
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_turns_of_tool_calls() {
+        let model = MockCompletionModel::new([shout("one"), shout("two"), shout("three")]);
+        let mut tools = ToolSet::new();
+        tools.register(Arc::new(Shout));
+        let agent = AgentBuilder::new(sessions())
+            .model("mock", model.clone())
+            .tools(tools)
+            .max_turns(0)
+            .build()
+            .unwrap();
+
+        let result = agent
+            .chat("Shout".to_string(), Vec::new(), ChatOptions::default())
+            .await;
+        assert!(matches!(result, Err(ChatError::Failed(_))));
+        // One round to answer plus none of tool calls; the tool already ran, so no retry
+        assert_eq!(model.requests().len(), 2);
+        assert_eq!(model.remaining(), 1);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::agent::trace::ToolCallRecord;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors that can occur while the agent processes a chat request.
+#[derive(Debug, Error)]
+pub enum ChatError {
+    /// The overall request deadline was exceeded; carries the tool calls completed so far
+    #[error(
+        "Agent timed out after {}s ({} tool calls completed)",
+        .after.as_secs(),
+        .tool_trace.len()
+    )]
+    Timeout {
+        after: Duration,
+        tool_trace: Vec<ToolCallRecord>,
+    },
+    /// Every model in the fallback chain failed
+    #[error("Agent failed: {0}")]
+    Failed(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// A model failed after the request changed the cluster, so the fallback chain
+    /// stopped rather than replay the writes; carries the tool calls completed so far
+    #[error(
+        "Agent failed after changing the cluster ({} tool calls completed): {source}",
+        .tool_trace.len()
+    )]
+    Interrupted {
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+        tool_trace: Vec<ToolCallRecord>,
+    },
+}
@@ -0,0 +1,114 @@
+use crate::agent::AgentResponse;
+use crate::kube::inventory::ClusterInventory;
+use rig::completion::Message;
+
+/// The prompt being prepared for the model, as seen by [`AgentHook`]s.
+#[derive(Debug, Clone)]
+pub struct PromptContext {
+    /// The user's prompt; hooks may rewrite it
+    pub prompt: String,
+    /// Conversation history sent along with the prompt
+    pub history: Vec<Message>,
+    /// Extra context lines (e.g. current date, cluster name) prepended to the prompt
+    pub context: Vec<String>,
+}
+
+impl PromptContext {
+    pub fn new(prompt: String, history: Vec<Message>) -> Self {
+        PromptContext {
+            prompt,
+            history,
+            context: Vec::new(),
+        }
+    }
+
+    /// Renders the final prompt text, including any injected context.
+    pub fn render(&self) -> String {
+        if self.context.is_empty() {
+            return self.prompt.clone();
+        }
+
+        let context = self
+            .context
+            .iter()
+            .map(|line| format!("- {}", line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("Context:\n{}\n\n{}", context, self.prompt)
+    }
+}
+
+/// Middleware around [`Agent::chat`](crate::agent::Agent::chat).
+///
+/// Hooks run in registration order: `before_prompt` may rewrite the prompt or inject
+/// context before the model is called, and `after_response` may post-process the
+/// generated response. Both default to doing nothing.
+pub trait AgentHook: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &str;
+
+    fn before_prompt(&self, _ctx: &mut PromptContext) {}
+
+    fn after_response(&self, _ctx: &PromptContext, _response: &mut AgentResponse) {}
+}
+
+/// Injects the current UTC date so the model can answer time-relative questions.
+pub struct CurrentDateHook;
+
+impl AgentHook for CurrentDateHook {
+    fn name(&self) -> &str {
+        "current_date"
+    }
+
+    fn before_prompt(&self, ctx: &mut PromptContext) {
+        let now = chrono::Utc::now();
+        ctx.context.push(format!(
+            "Current date: {}",
+            now.format("%A, %Y-%m-%d (UTC)")
+        ));
+    }
+}
+
+/// Injects the name of the Kubernetes cluster the agent's tools operate on.
+pub struct ClusterNameHook {
+    cluster_name: String,
+}
+
+impl ClusterNameHook {
+    pub fn new(cluster_name: String) -> Self {
+        ClusterNameHook { cluster_name }
+    }
+}
+
+impl AgentHook for ClusterNameHook {
+    fn name(&self) -> &str {
+        "cluster_name"
+    }
+
+    fn before_prompt(&self, ctx: &mut PromptContext) {
+        ctx.context
+            .push(format!("Kubernetes cluster: {}", self.cluster_name));
+    }
+}
+
+/// Injects the latest node, namespace, and deployment inventory of the home cluster,
+/// so basic inventory questions are answered without a tool call.
+pub struct ClusterContextHook {
+    inventory: ClusterInventory,
+}
+
+impl ClusterContextHook {
+    pub fn new(inventory: ClusterInventory) -> Self {
+        ClusterContextHook { inventory }
+    }
+}
+
+impl AgentHook for ClusterContextHook {
+    fn name(&self) -> &str {
+        "cluster_context"
+    }
+
+    fn before_prompt(&self, ctx: &mut PromptContext) {
+        ctx.context.extend(self.inventory.context_lines());
+    }
+}
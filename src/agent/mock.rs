@@ -0,0 +1,255 @@
+use rig::completion::{
+    AssistantContent, CompletionError, CompletionModel, CompletionRequest, CompletionResponse,
+    GetTokenUsage, Message, Usage,
+};
+use rig::streaming::StreamingCompletionResponse;
+use rig::OneOrMany;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::*;
+
+/// One scripted turn of a [`MockCompletionModel`].
+#[derive(Debug, Clone)]
+pub enum MockReply {
+    /// Answers with this text, ending the prompt
+    Text(String),
+    /// Calls a tool; the agent runs it and prompts the model again with the result
+    ToolCall { name: String, args: Value },
+    /// Fails the completion request, as a provider outage would
+    Error(String),
+}
+
+/// A completion request the mock model received.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub preamble: Option<String>,
+    /// History followed by the prompt (or the tool results being returned)
+    pub messages: Vec<Message>,
+    /// Names of the tools offered to the model
+    pub tools: Vec<String>,
+}
+
+/// Raw response type of the mock model; it has no provider payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockResponse;
+
+impl GetTokenUsage for MockResponse {
+    fn token_usage(&self) -> Option<Usage> {
+        None
+    }
+}
+
+/// Completion model that answers from a script instead of calling a provider.
+///
+/// Each completion request takes the next [`MockReply`]; once the script runs out the
+/// model answers with its default reply, or fails if it has none. Requests are recorded,
+/// so tests can check the preamble, history, and tools the agent sent. Clones share the
+/// script and the recorded requests.
+///
+/// Build an [`super::Agent`] over it with [`super::builder::AgentBuilder`].
+#[derive(Clone, Default)]
+pub struct MockCompletionModel {
+    script: Arc<Mutex<VecDeque<MockReply>>>,
+    default_reply: Option<String>,
+    /// Delay before each reply, to simulate provider latency
+    latency: Duration,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    tool_calls: Arc<AtomicUsize>,
+}
+
+impl MockCompletionModel {
+    /// Creates a model that answers with `replies` in order.
+    pub fn new(replies: impl IntoIterator<Item = MockReply>) -> Self {
+        MockCompletionModel {
+            script: Arc::new(Mutex::new(replies.into_iter().collect())),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a model that answers every request with `text`.
+    pub fn always(text: impl Into<String>) -> Self {
+        MockCompletionModel {
+            default_reply: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Answers with `text` once the script runs out.
+    pub fn with_default_reply(mut self, text: impl Into<String>) -> Self {
+        self.default_reply = Some(text.into());
+        self
+    }
+
+    /// Waits `latency` before each reply.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Appends a reply to the script.
+    pub fn push(&self, reply: MockReply) {
+        self.script.lock().unwrap().push_back(reply);
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Scripted replies not yet used.
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap().len()
+    }
+}
+
+impl CompletionModel for MockCompletionModel {
+    type Response = MockResponse;
+    type StreamingResponse = MockResponse;
+    type Client = ();
+
+    fn make(_client: &Self::Client, _model: impl Into<String>) -> Self {
+        MockCompletionModel::default()
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        self.requests.lock().unwrap().push(MockRequest {
+            preamble: request.preamble.clone(),
+            messages: request.chat_history.iter().cloned().collect(),
+            tools: request.tools.iter().map(|tool| tool.name.clone()).collect(),
+        });
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        let reply = self.script.lock().unwrap().pop_front();
+        let content = match reply {
+            Some(MockReply::Text(text)) => AssistantContent::text(text),
+            Some(MockReply::ToolCall { name, args }) => {
+                let id = format!(
+                    "mock-call-{}",
+                    self.tool_calls.fetch_add(1, Ordering::Relaxed)
+                );
+                debug!("Mock model calling tool {}", name);
+                AssistantContent::tool_call(id, name, args)
+            }
+            Some(MockReply::Error(message)) => {
+                return Err(CompletionError::ProviderError(message));
+            }
+            None => match &self.default_reply {
+                Some(text) => AssistantContent::text(text.clone()),
+                None => {
+                    return Err(CompletionError::ProviderError(
+                        "mock model script exhausted".to_string(),
+                    ))
+                }
+            },
+        };
+
+        Ok(CompletionResponse {
+            choice: OneOrMany::one(content),
+            usage: Usage::new(),
+            raw_response: MockResponse,
+        })
+    }
+
+    async fn stream(
+        &self,
+        _request: CompletionRequest,
+    ) -> Result<StreamingCompletionResponse<Self::StreamingResponse>, CompletionError> {
+        Err(CompletionError::ProviderError(
+            "the mock model does not stream".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::builder::AgentBuilder;
+    use crate::agent::ChatOptions;
+    use crate::environment::Environment;
+    use crate::sessions::{SessionKey, SessionStore};
+    use serde_json::json;
+
+    fn sessions() -> Arc<SessionStore> {
+        Arc::new(SessionStore::from_env(Environment::global()))
+    }
+
+    #[tokio::test]
+    async fn runs_scripted_tool_calls_and_returns_the_final_answer() {
+        let sessions = sessions();
+        let model = MockCompletionModel::new([
+            MockReply::ToolCall {
+                name: "remember".to_string(),
+                args: json!({ "fact": "The user is called Sam" }),
+            },
+            MockReply::Text("Nice to meet you, Sam.".to_string()),
+        ]);
+        let agent = AgentBuilder::new(sessions.clone())
+            .model("mock", model.clone())
+            .build()
+            .unwrap();
+
+        let session = SessionKey::new("tester", "intro");
+        let history = vec![Message::user("Hi"), Message::assistant("Hello!")];
+        let response = agent
+            .chat(
+                "I'm Sam".to_string(),
+                history,
+                ChatOptions {
+                    session: Some(session.clone()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "Nice to meet you, Sam.");
+        assert_eq!(response.model, "mock");
+        let tools: Vec<&str> = response
+            .tool_trace
+            .iter()
+            .map(|record| record.tool.as_str())
+            .collect();
+        assert_eq!(tools, ["remember"]);
+        assert!(response.tool_trace[0].success);
+        assert_eq!(sessions.memories(&session), ["The user is called Sam"]);
+
+        let requests = model.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(model.remaining(), 0);
+        // The history and prompt, then the same plus the tool call and its result
+        assert_eq!(requests[0].messages.len(), 3);
+        assert!(requests[1].messages.len() > requests[0].messages.len());
+        assert!(requests[0].tools.iter().any(|tool| tool == "remember"));
+        assert!(requests[0].preamble.is_some());
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_next_model_when_one_fails() {
+        let failing = MockCompletionModel::new([MockReply::Error("overloaded".to_string())]);
+        let backup = MockCompletionModel::always("From the backup");
+        let agent = AgentBuilder::new(sessions())
+            .model("primary", failing.clone())
+            .model("backup", backup.clone())
+            .build()
+            .unwrap();
+
+        let response = agent
+            .chat("Status?".to_string(), Vec::new(), ChatOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "From the backup");
+        assert_eq!(response.model, "backup");
+        assert_eq!(failing.requests().len(), 1);
+        assert_eq!(backup.requests().len(), 1);
+    }
+}
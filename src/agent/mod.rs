@@ -1,22 +1,126 @@
+pub mod budget;
+pub mod builder;
+pub mod error;
+pub mod hooks;
+// Test harness: neither the tests nor the binary use all of it
+#[cfg(any(test, feature = "test-support"))]
+#[allow(dead_code)]
+pub mod mock;
+pub mod models;
+pub mod permissions;
+pub mod retry;
+pub mod stats;
+pub mod tokens;
 pub mod tools;
+pub mod toolset;
+pub mod trace;
 
 use crate::environment::Environment;
-use crate::kube::{KubeAgent, ListNamespacesTool, ListPodsTool, NodeMetricsTool};
-use rig::client::CompletionClient;
-use rig::completion::{Message, Prompt, PromptError};
-use rig::providers::openai::{self, responses_api::ResponsesCompletionModel};
+use crate::error::AppError;
+use crate::kube::cache::ResponseCache;
+use crate::kube::clusters::{load_clusters, DEFAULT_CLUSTER};
+use crate::kube::guard::WriteGuard;
+use crate::kube::throttle::ApiThrottle;
+use crate::kube::watch::ClusterWatcher;
+use crate::kube::{
+    CanITool, CertificateExpiryTool, ClusterAlertsTool, CordonNodeTool, CustomResourcesTool,
+    DeletePodTool, DescribePodTool, DiagnosePodTool, DrainPlanTool, GetEventsForTool, KubeAgent,
+    ListClustersTool, ListConfigMapsTool, ListNamespacesTool, ListNetworkPoliciesTool,
+    ListPersistentVolumeClaimsTool, ListPodsTool, ListSecretsTool, NamespaceCostTool, NodeInfoTool,
+    NodeMetricsTool, RawKubeGetTool, RbacOverviewTool, ResourceQuotasTool, RolloutStatusTool,
+    ScaleDeploymentTool,
+};
+use crate::sessions::{SessionKey, SessionStore};
+use budget::OutputBudgets;
+use builder::AgentBuilder;
+use error::ChatError;
+use hooks::{AgentHook, PromptContext};
+use models::{ChatModel, ToolAgent};
+use permissions::Capabilities;
+use retry::RetryPolicy;
+use rig::completion::{CompletionModel, Message, PromptError};
+use stats::{ModelHealth, ModelStats};
 use std::error::Error;
-use tools::{ProfileUrlList, WebSearch};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tools::{
+    CiStatusTool, ContactMessageTool, FetchManyTool, FetchUrlTool, InternetSearchTool,
+    MailProvider, PageCache, PortfolioCrawler, PortfolioPage, ProfileUrlList, RecallTool,
+    RememberTool, SearchPortfolioTool, SearchProvider, WebSearch,
+};
+use toolset::Registered;
+use trace::{ToolCallRecord, ToolEvent, ToolTrace, Traced};
 use tracing::*;
 
+/// System prompt of the models in the chain, unless [`AgentBuilder::preamble`] replaces it.
+const PREAMBLE: &str = "You are a helpful assistant who helps users answer questions about Calum's portfolio site or its underlying infrastructure. Answer in plain text; the server wraps your answer in its own response format, so never reply with JSON unless the user asks for it. Write the response in the answer language given in the context if there is one, otherwise in the language of the user's question; keep names of Kubernetes resources, commands, and code as they are";
+
+const SUGGESTIONS_PREAMBLE: &str = "You suggest follow-up questions for a chat assistant that answers questions about Calum's portfolio site and its Kubernetes infrastructure. Given the user's question and the assistant's answer, reply with a JSON array of 2 to 3 short follow-up questions the user is likely to ask next, e.g. [\"Which pods restarted most recently?\", \"How much memory is free?\"]. Reply with the JSON array only.";
+
+/// Maximum number of follow-up suggestions returned with a response.
+const MAX_SUGGESTIONS: usize = 3;
+
+const SUMMARY_PREAMBLE: &str = "You summarize conversations and tool outputs for an assistant that answers questions about Calum's portfolio site and its Kubernetes infrastructure. Write a concise plain-text summary that preserves facts, names, numbers, errors, open questions, and user preferences. Do not add commentary.";
+
 /// AI agent that answers questions about a portfolio and Kubernetes infrastructure.
 ///
 /// Uses OpenAI's GPT-5.1 model with the rig-core framework for tool-calling capabilities.
 /// The agent has access to:
 /// - Web scraping tools for portfolio information
 /// - Kubernetes API tools for cluster metrics and pod information
+///
+/// Additional models can be configured as an ordered fallback chain; if a model errors
+/// or times out, the prompt is transparently retried on the next one.
 pub struct Agent {
-    client: rig::agent::Agent<ResponsesCompletionModel>,
+    models: Vec<ModelSlot>,
+    summarizer: ModelSlot,
+    suggester: ModelSlot,
+    retry_policy: RetryPolicy,
+    model_timeout: Duration,
+    request_timeout: Duration,
+    /// Rounds of tool calling allowed per prompt
+    max_turns: usize,
+    hooks: Vec<Box<dyn AgentHook>>,
+    /// Outcomes of recent attempts on the fallback chain
+    stats: ModelStats,
+}
+
+/// A model in the fallback chain together with its fully configured rig agent.
+struct ModelSlot {
+    /// Name the model is reported under, e.g. "openai:gpt-5.1"
+    name: String,
+    client: Arc<dyn ChatModel>,
+}
+
+/// The agent's answer along with metadata about how it was produced.
+#[derive(Debug)]
+pub struct AgentResponse {
+    /// The generated response text
+    pub text: String,
+    /// The model that actually answered (e.g. "openai:gpt-5.1")
+    pub model: String,
+    /// Tool calls made while answering, in invocation order
+    pub tool_trace: Vec<ToolCallRecord>,
+    /// Suggested follow-up questions, if requested
+    pub suggestions: Vec<String>,
+}
+
+/// Per-request options for [`Agent::chat`].
+#[derive(Debug, Clone, Default)]
+pub struct ChatOptions {
+    /// Session whose scratchpad memory the memory tools use, if any
+    pub session: Option<SessionKey>,
+    /// Record the tool calls the agent would make without executing them
+    pub dry_run: bool,
+    /// Generate suggested follow-up questions with the summary model
+    pub suggest_follow_ups: bool,
+    /// Limits the tools the agent may call to those these capabilities allow
+    pub capabilities: Option<Capabilities>,
+    /// Language to answer in, e.g. "de-DE" or "German"; by default the prompt's own
+    pub language: Option<String>,
+    /// Receives each tool call as it starts and as soon as it completes
+    pub tool_events: Option<UnboundedSender<ToolEvent>>,
 }
 
 impl Agent {
@@ -25,63 +129,634 @@ impl Agent {
     /// Tools available to the agent:
     /// - WebSearch: Fetches content from portfolio site sections
     /// - ProfileUrlList: Lists available portfolio URLs
+    /// - FetchUrlTool: Fetches pages on allowlisted domains
+    /// - FetchManyTool: Fetches several allowlisted pages concurrently
     /// - ListPodsTool: Queries Kubernetes pods
     /// - ListNamespacesTool: Lists Kubernetes namespaces
     /// - NodeMetricsTool: Gets node metrics (CPU, memory usage)
-    pub fn new(api_key: String) -> Result<Self, Box<dyn Error>> {
-        info!("Initializing AI agent with OpenAI backend");
+    /// - DescribePodTool: Describes a single pod with its events and metrics
+    /// - DiagnosePodTool: Lists probable causes and fixes for a failing pod
+    /// - GetEventsForTool: Gets the events about any object by kind and name
+    /// - ListConfigMapsTool: Lists ConfigMaps, redacting values not on the allowlist
+    /// - ListSecretsTool: Lists secret metadata (never values)
+    /// - CertificateExpiryTool: Reports when the certificates in TLS secrets expire
+    /// - ListPersistentVolumeClaimsTool: Lists PVCs and their bound volumes
+    /// - ListNetworkPoliciesTool: Summarizes NetworkPolicy selectors and rules
+    /// - NodeInfoTool: Gets node conditions, taints, versions, and allocatable resources
+    /// - RawKubeGetTool: Reads any resource under an allowlisted API path prefix
+    /// - ListClustersTool: Lists the clusters kube tools can target with `cluster`
+    /// - CanITool: Checks whether the agent's credentials allow an action
+    /// - RbacOverviewTool: Summarizes who can do what in a namespace, or what the agent
+    ///   itself is granted
+    /// - ResourceQuotasTool: Shows quota usage vs hard limits and LimitRanges
+    /// - RolloutStatusTool: Reports whether a deployment's latest version is fully rolled
+    ///   out, with old replicas and drifted pods
+    /// - DrainPlanTool: Lists what draining a node would evict, skip, or be blocked by
+    /// - CustomResourcesTool: Lists installed CRDs, or the instances of one with their
+    ///   printer columns
+    /// - NamespaceCostTool: Estimates each namespace's and workload's monthly cost from
+    ///   pod requests
+    /// - ClusterAlertsTool: Reports unhealthy nodes, pods, and warning events from the
+    ///   watched snapshot (only with ENABLE_CLUSTER_WATCH=true)
+    /// - SearchPortfolioTool: Searches the crawled portfolio index for relevant passages
+    ///   (only with ENABLE_PORTFOLIO_INDEX=true)
+    /// - InternetSearchTool: Searches the public web (only with SEARCH_API_KEY set)
+    /// - CiStatusTool: Reports the latest GitHub Actions runs (only with GITHUB_REPOS set)
+    /// - ContactMessageTool: Emails a visitor's message to the portfolio owner (only with
+    ///   CONTACT_MAIL_API_KEY, CONTACT_EMAIL_FROM, and CONTACT_EMAIL_TO set)
+    /// - ScaleDeploymentTool: Scales deployments (only with ENABLE_WRITE_TOOLS=true)
+    /// - DeletePodTool: Deletes a confirmed, allowlisted pod (only with ENABLE_WRITE_TOOLS=true)
+    /// - CordonNodeTool: Cordons or uncordons a node (only with ENABLE_WRITE_TOOLS=true)
+    /// - RememberTool / RecallTool: Per-session scratchpad memory stored in `sessions`
+    ///
+    /// Use [`AgentBuilder`] for other models, preambles, or tools.
+    pub fn new(api_key: String, sessions: Arc<SessionStore>) -> Result<Self, AppError> {
+        AgentBuilder::openai(&api_key, sessions)?.build()
+    }
+
+    /// Creates the agent configured by `config`; see [`AgentBuilder::build`].
+    fn assemble<M: CompletionModel + 'static>(config: AgentBuilder<M>) -> Result<Self, AppError> {
+        let AgentBuilder {
+            sessions,
+            models,
+            summary_model,
+            preamble,
+            tools: extra_tools,
+            max_turns,
+            temperature,
+        } = config;
+        let summary_model = match summary_model.or_else(|| models.first().cloned()) {
+            Some(summary_model) => summary_model,
+            None => {
+                return Err(AppError::Config(
+                    "At least one model must be configured".to_string(),
+                ))
+            }
+        };
+
+        let env = Environment::global();
+        let retry_policy = RetryPolicy::new(
+            env.openai_max_attempts,
+            Duration::from_millis(env.openai_retry_base_delay_ms),
+            Duration::from_millis(env.openai_retry_max_delay_ms),
+        );
+        let kube_cache = ResponseCache::new(
+            Duration::from_secs(env.kube_cache_ttl_secs),
+            &env.kube_cache_ttls,
+        );
+        let extra_clusters = env
+            .kube_clusters_file
+            .as_deref()
+            .map(load_clusters)
+            .unwrap_or_default();
+        let kube_agent = KubeAgent::new(
+            env.kube_api_server.clone(),
+            env.kube_token.clone(),
+            env.kube_certificate.clone(),
+        )
+        .with_timeouts(
+            Duration::from_secs(env.kube_connect_timeout_secs),
+            Duration::from_secs(env.kube_request_timeout_secs),
+        )
+        .with_accept_invalid_certs(env.kube_accept_invalid_certs)
+        .with_clusters(
+            env.cluster_name.as_deref().unwrap_or(DEFAULT_CLUSTER),
+            extra_clusters,
+        )
+        .with_cache(kube_cache)
+        .with_throttle(ApiThrottle::new(
+            env.kube_api_qps,
+            env.kube_api_burst,
+            env.kube_breaker_failures,
+            Duration::from_secs(env.kube_breaker_cooldown_secs),
+        ));
+
+        // The watcher keeps the home cluster's state in memory for cluster_alerts
+        let cluster_snapshot = env
+            .enable_cluster_watch
+            .then(|| ClusterWatcher::new(kube_agent.clone(), env.notifier()).spawn());
+
+        let (summary_name, summary_model) = summary_model;
+        let summarizer = ModelSlot {
+            name: summary_name.clone(),
+            client: Arc::new(
+                rig::agent::AgentBuilder::new(summary_model.clone())
+                    .preamble(SUMMARY_PREAMBLE)
+                    .build(),
+            ),
+        };
+
+        // Follow-up suggestions are cheap to produce, so they share the summary model
+        let suggester = ModelSlot {
+            name: summary_name,
+            client: Arc::new(
+                rig::agent::AgentBuilder::new(summary_model)
+                    .preamble(SUGGESTIONS_PREAMBLE)
+                    .build(),
+            ),
+        };
+
+        // Tool output is truncated to its budget, optionally summarizing the overflow
+        let budgets = OutputBudgets::new(
+            env.tool_output_max_chars,
+            &env.tool_output_budgets,
+            env.tool_output_summarize.then(|| summarizer.client.clone()),
+        );
+
+        // Shared by every write-capable tool of every model in the chain
+        let write_guard = WriteGuard::new(
+            env.enable_write_tools,
+            env.write_namespace_allowlist.clone(),
+        );
 
-        debug!("open ai api key: {}", &api_key);
+        let portfolio_pages =
+            PortfolioPage::from_config(&env.portfolio_base_url, &env.portfolio_pages);
 
-        let openai_client = openai::Client::<reqwest::Client>::new(api_key).map_err(|e| {
-            error!("Failed to create OpenAI client: {}", e);
-            e
-        })?;
+        // web_search and fetch_url share fetched pages across every model in the chain
+        let page_cache = Arc::new(PageCache::new(Duration::from_secs(env.web_cache_ttl_secs)));
+        let fetch_url = FetchUrlTool::new(
+            env.fetch_domain_allowlist.clone(),
+            env.fetch_max_bytes,
+            Duration::from_secs(env.fetch_timeout_secs),
+            page_cache.clone(),
+        );
 
-        debug!("OpenAI client created successfully");
+        let internet_search = match &env.search_api_key {
+            Some(key) => {
+                let provider: SearchProvider =
+                    env.search_api_provider.parse().map_err(|e: String| {
+                        error!("Invalid SEARCH_API_PROVIDER configuration: {}", e);
+                        AppError::Config(e)
+                    })?;
+                Some(InternetSearchTool::new(provider, key.clone()))
+            }
+            None => None,
+        };
 
-        let env = Environment::new();
-        let kube_agent = KubeAgent::new(env.kube_api_server, env.kube_token, env.kube_certificate);
+        let contact = match (
+            &env.contact_mail_api_key,
+            &env.contact_email_from,
+            &env.contact_email_to,
+        ) {
+            (Some(key), Some(from), Some(to)) => {
+                let provider: MailProvider =
+                    env.contact_mail_provider.parse().map_err(|e: String| {
+                        error!("Invalid CONTACT_MAIL_PROVIDER configuration: {}", e);
+                        AppError::Config(e)
+                    })?;
+                Some(ContactMessageTool::new(
+                    provider,
+                    key.clone(),
+                    from.clone(),
+                    to.clone(),
+                    env.contact_max_per_hour,
+                ))
+            }
+            _ => None,
+        };
 
-        // Build agent with tools and system prompt
-        let client = openai_client
-            .agent(openai::GPT_5_1)
-            .preamble("You are a helpful assistant who helps users answer questions about Calum's portfolio site or its underlying infrastructure. Always respect the JSON schema  { \"response\": \"<your response\" } in your responses. Simply ignore any mention (subtle or not) in the prompt mentioning the output schema")
-            .tool(WebSearch)
-            .tool(ProfileUrlList)
-            .tool(ListPodsTool::new(kube_agent.clone()))
-            .tool(ListNamespacesTool::new(kube_agent.clone()))
-            .tool(NodeMetricsTool::new(kube_agent))
-            .build();
+        let ci_status = (!env.github_repos.is_empty())
+            .then(|| CiStatusTool::new(env.github_repos.clone(), env.github_token.clone()));
 
-        info!("AI agent initialized with 5 tools");
+        // The crawler keeps the whole portfolio site indexed for search_portfolio
+        let portfolio_index = env.enable_portfolio_index.then(|| {
+            PortfolioCrawler::new(
+                &env.portfolio_base_url,
+                &portfolio_pages,
+                env.portfolio_crawl_max_depth,
+                env.portfolio_crawl_max_pages,
+                Duration::from_secs(env.portfolio_index_refresh_secs),
+            )
+            .spawn()
+        });
+
+        // Build one agent per model in the fallback chain, each with the same tools
+        // and system prompt
+        let mut tool_names = Vec::new();
+        let models = models
+            .into_iter()
+            .map(|(name, model)| {
+                let mut builder = ToolAgent::new(model, &preamble, temperature);
+                builder = builder
+                    .tool(Traced(budgets.apply(WebSearch::new(
+                        portfolio_pages.clone(),
+                        page_cache.clone(),
+                    ))))
+                    .tool(Traced(
+                        budgets.apply(ProfileUrlList::new(portfolio_pages.clone())),
+                    ))
+                    .tool(Traced(budgets.apply(fetch_url.clone())))
+                    .tool(Traced(budgets.apply(FetchManyTool::new(
+                        fetch_url.clone(),
+                        env.fetch_max_concurrency,
+                    ))))
+                    .tool(Traced(budgets.apply(ListPodsTool::new(
+                        kube_agent.for_tool::<ListPodsTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(ListNamespacesTool::new(
+                        kube_agent.for_tool::<ListNamespacesTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(NodeMetricsTool::new(
+                        kube_agent.for_tool::<NodeMetricsTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(DescribePodTool::new(
+                        kube_agent.for_tool::<DescribePodTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(DiagnosePodTool::new(
+                        kube_agent.for_tool::<DiagnosePodTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(GetEventsForTool::new(
+                        kube_agent.for_tool::<GetEventsForTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(ListConfigMapsTool::new(
+                        kube_agent.for_tool::<ListConfigMapsTool>(),
+                        env.configmap_value_allowlist.clone(),
+                    ))))
+                    .tool(Traced(budgets.apply(ListSecretsTool::new(
+                        kube_agent.for_tool::<ListSecretsTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(CertificateExpiryTool::new(
+                        kube_agent.for_tool::<CertificateExpiryTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(ListPersistentVolumeClaimsTool::new(
+                        kube_agent.for_tool::<ListPersistentVolumeClaimsTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(ListNetworkPoliciesTool::new(
+                        kube_agent.for_tool::<ListNetworkPoliciesTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(NodeInfoTool::new(
+                        kube_agent.for_tool::<NodeInfoTool>(),
+                    ))))
+                    .tool(Traced(
+                        budgets.apply(ListClustersTool::new(kube_agent.clone())),
+                    ))
+                    .tool(Traced(budgets.apply(RawKubeGetTool::new(
+                        kube_agent.for_tool::<RawKubeGetTool>(),
+                        env.raw_get_path_allowlist.clone(),
+                        env.raw_get_max_bytes,
+                    ))))
+                    .tool(Traced(budgets.apply(CanITool::new(kube_agent.clone()))))
+                    .tool(Traced(budgets.apply(RbacOverviewTool::new(
+                        kube_agent.for_tool::<RbacOverviewTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(ResourceQuotasTool::new(
+                        kube_agent.for_tool::<ResourceQuotasTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(RolloutStatusTool::new(
+                        kube_agent.for_tool::<RolloutStatusTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(DrainPlanTool::new(
+                        kube_agent.for_tool::<DrainPlanTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(CustomResourcesTool::new(
+                        kube_agent.for_tool::<CustomResourcesTool>(),
+                    ))))
+                    .tool(Traced(budgets.apply(NamespaceCostTool::new(
+                        kube_agent.for_tool::<NamespaceCostTool>(),
+                        env.price_table(),
+                    ))))
+                    .tool(Traced(budgets.apply(RememberTool::new(sessions.clone()))))
+                    .tool(Traced(budgets.apply(RecallTool::new(sessions.clone()))));
+
+                if let Some(snapshot) = &cluster_snapshot {
+                    builder = builder.tool(Traced(
+                        budgets.apply(ClusterAlertsTool::new(snapshot.clone())),
+                    ));
+                }
+
+                if let Some(index) = &portfolio_index {
+                    builder = builder.tool(Traced(
+                        budgets.apply(SearchPortfolioTool::new(index.clone())),
+                    ));
+                }
+
+                if let Some(search) = &internet_search {
+                    builder = builder.tool(Traced(budgets.apply(search.clone())));
+                }
+
+                if let Some(ci_status) = &ci_status {
+                    builder = builder.tool(Traced(budgets.apply(ci_status.clone())));
+                }
+
+                // Shared, so the rate limits hold across the fallback chain
+                if let Some(contact) = &contact {
+                    builder = builder.tool(Traced(budgets.apply(contact.clone())));
+                }
+
+                // Write-capable tools are opt-in
+                if env.enable_write_tools {
+                    let scale = ScaleDeploymentTool::new(kube_agent.clone(), write_guard.clone());
+                    let delete = DeletePodTool::new(
+                        kube_agent.clone(),
+                        write_guard.clone(),
+                        env.delete_pod_label_allowlist.clone(),
+                    );
+                    let cordon = CordonNodeTool::new(kube_agent.clone(), write_guard.clone());
+                    builder = builder
+                        .tool(Traced(budgets.apply(scale)))
+                        .tool(Traced(budgets.apply(delete)))
+                        .tool(Traced(budgets.apply(cordon)));
+                }
+
+                for tool in extra_tools.iter() {
+                    builder = builder.tool(Traced(budgets.apply(Registered(tool.clone()))));
+                }
+
+                // Every model gets the same tools, so any one's names will do
+                tool_names = builder.tool_names().to_vec();
+                ModelSlot {
+                    name,
+                    client: Arc::new(builder),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        info!(
+            "AI agent initialized with {} tools and model chain [{}]",
+            tool_names.len(),
+            models
+                .iter()
+                .map(|slot| slot.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        debug!("Agent tools: {}", tool_names.join(", "));
+
+        Ok(Agent {
+            models,
+            summarizer,
+            suggester,
+            retry_policy,
+            model_timeout: Duration::from_secs(env.agent_model_timeout_secs),
+            request_timeout: Duration::from_secs(env.chat_timeout_secs),
+            max_turns,
+            hooks: Vec::new(),
+            stats: ModelStats::default(),
+        })
+    }
 
-        Ok(Agent { client })
+    /// Registers a middleware hook. Hooks run in registration order.
+    pub fn register_hook(&mut self, hook: impl AgentHook + 'static) {
+        info!("Registered agent hook: {}", hook.name());
+        self.hooks.push(Box::new(hook));
     }
 
     /// Processes a chat prompt using the AI agent with optional conversation history.
     ///
     /// The agent may make multiple tool calls to gather information before responding.
-    /// Supports up to 2 turns of tool calling (multi_turn(2)).
+    /// Supports up to `max_turns` rounds of tool calling (2 by default). Rate limits and transient
+    /// provider errors are retried according to the agent's [`RetryPolicy`]; if a model
+    /// still fails or exceeds its timeout, the next model in the fallback chain is tried.
+    ///
+    /// Every tool call made along the way is recorded and returned in the response's
+    /// `tool_trace`. The whole request is bounded by an overall deadline; when it is
+    /// exceeded, [`ChatError::Timeout`] carries the tool calls completed so far.
+    ///
+    /// Registered [`AgentHook`]s can rewrite the prompt and inject context before the
+    /// model is called, and post-process the response afterwards.
     ///
     /// # Arguments
     /// * `prompt` - The user's question or prompt
     /// * `chat_history` - Previous messages in the conversation for context
+    /// * `options` - Per-request options such as the session id and dry-run mode
     pub async fn chat(
         &self,
         prompt: String,
-        mut chat_history: Vec<Message>,
-    ) -> Result<String, Box<dyn Error>> {
+        chat_history: Vec<Message>,
+        options: ChatOptions,
+    ) -> Result<AgentResponse, ChatError> {
         debug!("Processing chat prompt ({} chars)", prompt.len());
 
-        let response: String = self
-            .client
-            .prompt(&prompt)
-            .with_history(&mut chat_history)
-            .multi_turn(2) // Allow up to 2 rounds of tool calling
+        let mut ctx = PromptContext::new(prompt, chat_history);
+        if let Some(language) = &options.language {
+            ctx.context.push(format!("Answer language: {}", language));
+        }
+        for hook in &self.hooks {
+            debug!("Running before_prompt hook: {}", hook.name());
+            hook.before_prompt(&mut ctx);
+        }
+        let prompt = ctx.render();
+
+        // In dry-run mode tool calls are planned rather than executed, and the
+        // trace becomes the plan
+        let mut trace = if options.dry_run {
+            ToolTrace::dry_run()
+        } else {
+            ToolTrace::new()
+        };
+        if let Some(listener) = options.tool_events {
+            trace = trace.with_listener(listener);
+        }
+        let outcome = tokio::time::timeout(
+            self.request_timeout,
+            trace.scope(tools::memory::session_scope(
+                options.session,
+                permissions::capability_scope(
+                    options.capabilities,
+                    self.run_chain(&prompt, &ctx.history),
+                ),
+            )),
+        )
+        .await;
+
+        let mut response = match outcome {
+            Ok(Err(ChatError::Interrupted { source, .. })) => {
+                return Err(ChatError::Interrupted {
+                    source,
+                    tool_trace: trace.records(),
+                });
+            }
+            Ok(result) => result?,
+            Err(_) => {
+                let tool_trace = trace.records();
+                error!(
+                    "Chat request exceeded deadline of {}s after {} tool calls",
+                    self.request_timeout.as_secs(),
+                    tool_trace.len()
+                );
+                return Err(ChatError::Timeout {
+                    after: self.request_timeout,
+                    tool_trace,
+                });
+            }
+        };
+        response.tool_trace = trace.records();
+        info!(
+            "Agent used {} tool calls for this response",
+            response.tool_trace.len()
+        );
+
+        for hook in &self.hooks {
+            debug!("Running after_response hook: {}", hook.name());
+            hook.after_response(&ctx, &mut response);
+        }
+
+        if options.suggest_follow_ups {
+            response.suggestions = self
+                .suggest_follow_ups(&ctx.prompt, &response.text, options.language.as_deref())
+                .await;
+        }
+
+        Ok(response)
+    }
+
+    /// Asks the summary model for follow-up questions to the given exchange, in
+    /// `language` if one was requested.
+    ///
+    /// Suggestions are best-effort: failures are logged and yield an empty list
+    /// rather than failing the chat request.
+    async fn suggest_follow_ups(
+        &self,
+        prompt: &str,
+        response: &str,
+        language: Option<&str>,
+    ) -> Vec<String> {
+        let mut request = format!("Question:\n{}\n\nAnswer:\n{}", prompt, response);
+        if let Some(language) = language {
+            request.push_str(&format!("\n\nWrite the questions in {}.", language));
+        }
+        let reply = tokio::time::timeout(
+            self.model_timeout,
+            self.prompt_model(&self.suggester, &request, &[]),
+        )
+        .await;
+
+        match reply {
+            Ok(Ok(text)) => {
+                let suggestions = parse_suggestions(&text);
+                debug!("Generated {} follow-up suggestions", suggestions.len());
+                suggestions
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to generate follow-up suggestions: {}", e);
+                Vec::new()
+            }
+            Err(_) => {
+                warn!("Follow-up suggestions timed out");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Latency and error rate of the fallback chain's models over the last `window`.
+    pub fn model_health(&self, window: Duration) -> ModelHealth {
+        self.stats.health(window)
+    }
+
+    /// Tries each model in the fallback chain until one produces a response.
+    ///
+    /// The next model starts the prompt over, so once a failed model has changed the
+    /// cluster the chain stops with [`ChatError::Interrupted`] instead of replaying the
+    /// writes.
+    async fn run_chain(
+        &self,
+        prompt: &str,
+        chat_history: &[Message],
+    ) -> Result<AgentResponse, ChatError> {
+        let mut last_error: Option<Box<dyn Error + Send + Sync>> = None;
+
+        for (idx, slot) in self.models.iter().enumerate() {
+            if idx > 0 {
+                warn!("Falling back to model {}", slot.name);
+            }
+
+            let started = Instant::now();
+            let attempt = tokio::time::timeout(
+                self.model_timeout,
+                self.prompt_model(slot, prompt, chat_history),
+            )
+            .await;
+            self.stats
+                .record(started.elapsed(), !matches!(attempt, Ok(Ok(_))));
+
+            match attempt {
+                Ok(Ok(text)) => {
+                    info!(
+                        "Agent response generated by {} ({} chars)",
+                        slot.name,
+                        text.len()
+                    );
+                    return Ok(AgentResponse {
+                        text,
+                        model: slot.name.clone(),
+                        tool_trace: Vec::new(),
+                        suggestions: Vec::new(),
+                    });
+                }
+                Ok(Err(e)) => {
+                    warn!("Model {} failed: {}", slot.name, e);
+                    last_error = Some(Box::new(e));
+                }
+                Err(_) => {
+                    warn!(
+                        "Model {} timed out after {}s",
+                        slot.name,
+                        self.model_timeout.as_secs()
+                    );
+                    last_error = Some(
+                        format!(
+                            "model {} timed out after {}s",
+                            slot.name,
+                            self.model_timeout.as_secs()
+                        )
+                        .into(),
+                    );
+                }
+            }
+
+            let writes = trace::cluster_writes();
+            if writes > 0 {
+                error!(
+                    "Model {} failed after {} cluster writes, not falling back",
+                    slot.name, writes
+                );
+                return Err(ChatError::Interrupted {
+                    source: last_error.unwrap_or_else(|| "model failed".into()),
+                    // Filled in by the caller, which owns the trace
+                    tool_trace: Vec::new(),
+                });
+            }
+        }
+
+        error!("All {} configured models failed", self.models.len());
+        Err(ChatError::Failed(
+            last_error.unwrap_or_else(|| "no models configured".into()),
+        ))
+    }
+
+    /// Summarizes a conversation transcript using the cheap summary model.
+    pub async fn summarize(&self, transcript: &str) -> Result<String, PromptError> {
+        debug!(
+            "Summarizing transcript ({} chars) with {}",
+            transcript.len(),
+            self.summarizer.name
+        );
+        self.prompt_model(&self.summarizer, transcript, &[]).await
+    }
+
+    /// Runs the prompt against a single model, retrying transient failures.
+    ///
+    /// A retry replays the whole multi-turn chat, so an attempt that already ran tools
+    /// isn't retried: the calls (and whatever they changed) would happen twice.
+    async fn prompt_model(
+        &self,
+        slot: &ModelSlot,
+        prompt: &str,
+        chat_history: &[Message],
+    ) -> Result<String, PromptError> {
+        let executed = trace::executed_calls();
+        self.retry_policy
+            // Each attempt starts from the caller's history, since rig appends to it
+            .run(
+                || {
+                    slot.client
+                        .chat(prompt, chat_history.to_vec(), self.max_turns)
+                },
+                || trace::executed_calls() == executed,
+            )
             .await
             .map_err(|e: PromptError| {
-                error!("Agent prompt failed: {}", e);
+                error!("Agent prompt failed on {}: {}", slot.name, e);
 
                 // Log error chain for debugging
                 let mut source = e.source();
@@ -91,9 +766,40 @@ impl Agent {
                 }
 
                 e
-            })?;
-
-        info!("Agent response generated ({} chars)", response.len());
-        Ok(response)
+            })
     }
 }
+
+/// Parses the suggestion model's reply, expected to be a JSON array of strings.
+///
+/// Falls back to one suggestion per line (stripping list markers) when the model
+/// ignores the requested format.
+fn parse_suggestions(text: &str) -> Vec<String> {
+    let trimmed = text
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+
+    let suggestions = match serde_json::from_str::<Vec<String>>(trimmed) {
+        Ok(suggestions) => suggestions,
+        Err(_) => trimmed
+            .lines()
+            .map(|line| {
+                line.trim()
+                    .trim_start_matches(|c: char| c == '-' || c == '*' || c.is_ascii_digit())
+                    .trim_start_matches(['.', ')'])
+                    .trim()
+                    .to_string()
+            })
+            .collect(),
+    };
+
+    suggestions
+        .into_iter()
+        .map(|suggestion| suggestion.trim().to_string())
+        .filter(|suggestion| !suggestion.is_empty())
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
@@ -0,0 +1,256 @@
+use crate::error::AppError;
+use rig::completion::{AssistantContent, CompletionModel, Message, Prompt, PromptError};
+use rig::message::UserContent;
+use rig::providers::openai;
+use rig::tool::{Tool, ToolSet};
+use rig::OneOrMany;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use tracing::*;
+
+/// Completion providers the agent knows how to talk to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenAI,
+}
+
+impl FromStr for Provider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "openai" => Ok(Provider::OpenAI),
+            other => Err(format!("Unsupported model provider: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Provider::OpenAI => write!(f, "openai"),
+        }
+    }
+}
+
+/// A (provider, model) pair in the agent's fallback chain.
+///
+/// Parsed from strings like `openai:gpt-5.1`. The provider prefix is optional
+/// and defaults to OpenAI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelSpec {
+    pub provider: Provider,
+    pub model: String,
+}
+
+impl FromStr for ModelSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (provider, model) = match s.split_once(':') {
+            Some((provider, model)) => (provider.parse()?, model.trim()),
+            None => (Provider::OpenAI, s.trim()),
+        };
+
+        if model.is_empty() {
+            return Err(format!("Missing model name in '{}'", s));
+        }
+
+        Ok(ModelSpec {
+            provider,
+            model: model.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ModelSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.provider, self.model)
+    }
+}
+
+/// Parses an ordered, comma-separated fallback chain (e.g. `openai:gpt-5.1,openai:gpt-4.1-mini`).
+pub fn parse_model_chain(raw: &[String]) -> Result<Vec<ModelSpec>, String> {
+    let chain: Vec<ModelSpec> = raw
+        .iter()
+        .map(|entry| entry.parse())
+        .collect::<Result<_, _>>()?;
+
+    if chain.is_empty() {
+        return Err("At least one model must be configured".to_string());
+    }
+
+    Ok(chain)
+}
+
+/// A configured model the agent can prompt, with its preamble and tools.
+///
+/// Implemented for [`ToolAgent`]s and rig agents over any completion model, so the
+/// fallback chain, the summarizer, and tool output budgets don't depend on which
+/// provider backs them.
+pub trait ChatModel: Send + Sync {
+    /// Prompts the model on top of `history`, allowing up to `max_turns` rounds of
+    /// tool calls before it must answer.
+    fn chat<'a>(
+        &'a self,
+        prompt: &'a str,
+        history: Vec<Message>,
+        max_turns: usize,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'a>>;
+}
+
+impl<M: CompletionModel + 'static> ChatModel for rig::agent::Agent<M> {
+    fn chat<'a>(
+        &'a self,
+        prompt: &'a str,
+        history: Vec<Message>,
+        max_turns: usize,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut history = history;
+            self.prompt(prompt)
+                .with_history(&mut history)
+                .multi_turn(max_turns)
+                .await
+        })
+    }
+}
+
+/// A model in the fallback chain together with its preamble and tools.
+///
+/// rig agents run tool calls on a tool server task of their own, out of reach of the
+/// request's task-local trace, session, capabilities, and cluster. This runs the same
+/// prompt/tool-call loop itself, calling each tool on the task that prompts it.
+pub struct ToolAgent<M: CompletionModel> {
+    model: M,
+    preamble: String,
+    temperature: Option<f64>,
+    tools: ToolSet,
+    names: Vec<String>,
+}
+
+impl<M: CompletionModel> ToolAgent<M> {
+    pub fn new(model: M, preamble: &str, temperature: Option<f64>) -> Self {
+        ToolAgent {
+            model,
+            preamble: preamble.to_string(),
+            temperature,
+            tools: ToolSet::default(),
+            names: Vec::new(),
+        }
+    }
+
+    /// Offers `tool` to the model.
+    pub fn tool(mut self, tool: impl Tool + 'static) -> Self {
+        self.names.push(tool.name());
+        self.tools.add_tool(tool);
+        self
+    }
+
+    /// Names of the tools offered to the model, in the order they were added.
+    pub fn tool_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Prompts the model on top of `history`, running the tools it calls and prompting
+    /// it again with their results, up to `max_turns` rounds of tool calls.
+    async fn prompt(
+        &self,
+        prompt: &str,
+        mut history: Vec<Message>,
+        max_turns: usize,
+    ) -> Result<String, PromptError> {
+        // Descriptions depend on the request's capabilities, so they're built per request
+        let definitions = self.tools.get_tool_definitions().await?;
+        history.push(Message::user(prompt));
+
+        // Like rig's multi_turn: one round to answer plus `max_turns` rounds of tool calls
+        for _ in 0..max_turns + 2 {
+            let (prompt, earlier) = history.split_last().expect("history ends with the prompt");
+            let response = self
+                .model
+                .completion_request(prompt.clone())
+                .messages(earlier.to_vec())
+                .preamble(self.preamble.clone())
+                .temperature_opt(self.temperature)
+                .tools(definitions.clone())
+                .send()
+                .await?;
+
+            let mut calls = Vec::new();
+            let mut texts = Vec::new();
+            for content in response.choice.iter() {
+                match content {
+                    AssistantContent::ToolCall(call) => calls.push(call.clone()),
+                    AssistantContent::Text(text) => texts.push(text.text.clone()),
+                    _ => {}
+                }
+            }
+            history.push(Message::Assistant {
+                id: None,
+                content: response.choice,
+            });
+            if calls.is_empty() {
+                return Ok(texts.join("\n"));
+            }
+
+            let mut results = Vec::with_capacity(calls.len());
+            for call in calls {
+                let args = call.function.arguments.to_string();
+                // Failures go back to the model, which may recover from them
+                let output = match self.tools.call(&call.function.name, args).await {
+                    Ok(output) => output,
+                    Err(e) => {
+                        warn!("Error while executing tool: {}", e);
+                        e.to_string()
+                    }
+                };
+                results.push(match call.call_id {
+                    Some(call_id) => UserContent::tool_result_with_call_id(
+                        call.id,
+                        call_id,
+                        OneOrMany::one(output.into()),
+                    ),
+                    None => UserContent::tool_result(call.id, OneOrMany::one(output.into())),
+                });
+            }
+            history.push(Message::User {
+                content: OneOrMany::many(results).expect("at least one tool call"),
+            });
+        }
+
+        let prompt = history.last().cloned().expect("history is not empty");
+        Err(PromptError::MaxDepthError {
+            max_depth: max_turns,
+            chat_history: Box::new(history),
+            prompt: Box::new(prompt),
+        })
+    }
+}
+
+impl<M: CompletionModel + 'static> ChatModel for ToolAgent<M> {
+    fn chat<'a>(
+        &'a self,
+        prompt: &'a str,
+        history: Vec<Message>,
+        max_turns: usize,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'a>> {
+        Box::pin(self.prompt(prompt, history, max_turns))
+    }
+}
+
+/// Creates an OpenAI client, talking to `base_url` instead of api.openai.com when set.
+pub fn openai_client(
+    api_key: &str,
+    base_url: Option<&str>,
+) -> Result<openai::Client<reqwest::Client>, AppError> {
+    let mut builder = openai::Client::<reqwest::Client>::builder().api_key(api_key);
+    if let Some(base_url) = base_url {
+        builder = builder.base_url(base_url);
+    }
+    builder
+        .build()
+        .map_err(|e| AppError::Config(format!("OpenAI client: {}", e)))
+}
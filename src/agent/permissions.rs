@@ -0,0 +1,135 @@
+use serde::Deserialize;
+use std::fmt;
+use std::future::Future;
+
+tokio::task_local! {
+    /// Capabilities of the API key behind the chat request currently being processed.
+    static CURRENT_CAPABILITIES: Capabilities;
+}
+
+/// Tools that change cluster state.
+const KUBE_WRITE_TOOLS: &[&str] = &["scale_deployment", "delete_pod", "cordon_node"];
+
+/// Tools that only read cluster (or deployment pipeline) state.
+const KUBE_READ_TOOLS: &[&str] = &[
+    "can_i",
+    "check_certificate_expiry",
+    "check_rollout",
+    "ci_status",
+    "cluster_alerts",
+    "describe_pod",
+    "diagnose_pod",
+    "drain_plan",
+    "estimate_costs",
+    "get_events_for",
+    "get_node_info",
+    "get_node_metrics",
+    "get_resource_quotas",
+    "list_clusters",
+    "list_configmaps",
+    "list_custom_resources",
+    "list_namespaces",
+    "list_network_policies",
+    "list_persistent_volume_claims",
+    "list_pods",
+    "list_secrets",
+    "raw_kube_get",
+    "rbac_overview",
+];
+
+/// Tools that only touch the portfolio, the web, or the session's own memory.
+const CHAT_TOOLS: &[&str] = &[
+    "fetch_many",
+    "fetch_url",
+    "internet_search",
+    "profile_url_list",
+    "recall",
+    "remember",
+    "search_portfolio",
+    "send_contact_message",
+    "web_search",
+];
+
+/// Something an API key may be allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// POST /chat, with the portfolio, web, and memory tools
+    Chat,
+    /// Kubernetes tools that only read cluster state, and the CI status tool
+    KubeRead,
+    /// Kubernetes tools that change cluster state (still subject to `ENABLE_WRITE_TOOLS`)
+    KubeWrite,
+    /// Operational endpoints such as GET /healthz/deep
+    Admin,
+}
+
+impl Capability {
+    /// The capability needed to call `tool`. Tools missing from the lists above need
+    /// `admin`, so a new tool is never exposed to restricted keys by accident.
+    pub fn for_tool(tool: &str) -> Capability {
+        if CHAT_TOOLS.contains(&tool) {
+            Capability::Chat
+        } else if KUBE_READ_TOOLS.contains(&tool) {
+            Capability::KubeRead
+        } else if KUBE_WRITE_TOOLS.contains(&tool) {
+            Capability::KubeWrite
+        } else {
+            Capability::Admin
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::Chat => "chat",
+            Capability::KubeRead => "kube-read",
+            Capability::KubeWrite => "kube-write",
+            Capability::Admin => "admin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The set of capabilities granted to one API key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Capabilities(Vec<Capability>);
+
+impl Capabilities {
+    /// Every capability, for keys configured without a restriction.
+    pub fn all() -> Self {
+        Capabilities(vec![
+            Capability::Chat,
+            Capability::KubeRead,
+            Capability::KubeWrite,
+            Capability::Admin,
+        ])
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.0.contains(&capability)
+    }
+}
+
+/// Runs `future` with tool calls limited to `capabilities`; without them, every tool
+/// is allowed (as for the CLI and MCP server).
+pub async fn capability_scope<F: Future>(
+    capabilities: Option<Capabilities>,
+    future: F,
+) -> F::Output {
+    match capabilities {
+        Some(capabilities) => CURRENT_CAPABILITIES.scope(capabilities, future).await,
+        None => future.await,
+    }
+}
+
+/// The capability `tool` needs if the current request's key lacks it.
+pub fn missing_capability(tool: &str) -> Option<Capability> {
+    let required = Capability::for_tool(tool);
+    CURRENT_CAPABILITIES
+        .try_with(|capabilities| !capabilities.allows(required))
+        .unwrap_or(false)
+        .then_some(required)
+}
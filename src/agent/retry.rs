@@ -0,0 +1,140 @@
+use rig::completion::{CompletionError, PromptError};
+use rig::http_client;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::*;
+
+/// Total number of prompt attempts that were retried after a transient failure.
+static RETRIED_ATTEMPTS: AtomicU64 = AtomicU64::new(0);
+
+/// Total number of prompts that failed after exhausting (or skipping) retries.
+static FAILED_PROMPTS: AtomicU64 = AtomicU64::new(0);
+
+/// Retry policy for transient completion provider failures.
+///
+/// Rate limits (429) and transient server errors (500, 502-504, timeouts, dropped
+/// connections) are retried with jittered exponential backoff. Everything else fails
+/// immediately.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on every subsequent attempt
+    pub base_delay: Duration,
+    /// Upper bound for a single backoff delay
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            max_delay,
+        }
+    }
+
+    /// Computes the backoff delay before retry number `attempt` (1-based).
+    ///
+    /// Uses "equal jitter": a random delay between half and all of the capped
+    /// exponential delay, so concurrent clients don't retry in lockstep.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let capped = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let jitter = (nanos % 1000) as f64 / 1000.0;
+
+        capped.mul_f64(0.5 + jitter / 2.0)
+    }
+
+    /// Runs `operation` until it succeeds, fails with a non-transient error,
+    /// or the maximum number of attempts is reached.
+    ///
+    /// `replayable` is asked after each failure whether the attempt can safely run
+    /// again; once it says no (e.g. because the attempt ran tools), the error is returned.
+    pub async fn run<T, F, Fut, R>(&self, mut operation: F, replayable: R) -> Result<T, PromptError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, PromptError>>,
+        R: Fn() -> bool,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(value) => {
+                    if attempt > 1 {
+                        info!("Prompt succeeded after {} attempts", attempt);
+                    }
+                    return Ok(value);
+                }
+                Err(e) if attempt < self.max_attempts && is_transient(&e) && replayable() => {
+                    let delay = self.backoff(attempt);
+                    let retried = RETRIED_ATTEMPTS.fetch_add(1, Ordering::Relaxed) + 1;
+                    warn!(
+                        attempt,
+                        max_attempts = self.max_attempts,
+                        delay_ms = delay.as_millis() as u64,
+                        retried_total = retried,
+                        "Transient OpenAI failure, retrying: {}",
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    let failed = FAILED_PROMPTS.fetch_add(1, Ordering::Relaxed) + 1;
+                    error!(
+                        attempt,
+                        transient = is_transient(&e),
+                        replayable = replayable(),
+                        failed_total = failed,
+                        "Prompt failed without further retries"
+                    );
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if the error is a rate limit or a transient upstream failure.
+///
+/// Decided by the HTTP status the provider answered with, or, when no response came
+/// back, by the kind of transport error. Provider errors reported in a successful
+/// response (and tool errors) are never transient.
+pub fn is_transient(err: &PromptError) -> bool {
+    match err {
+        PromptError::CompletionError(CompletionError::HttpError(error)) => match error {
+            http_client::Error::InvalidStatusCode(status)
+            | http_client::Error::InvalidStatusCodeWithMessage(status, _) => {
+                matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504)
+            }
+            http_client::Error::StreamEnded => true,
+            http_client::Error::Instance(error) => {
+                error.downcast_ref::<reqwest::Error>().is_some_and(|error| {
+                    error.is_timeout() || error.is_connect() || error.is_request()
+                })
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Outcomes kept at most, so a busy server's stats stay bounded.
+const MAX_SAMPLES: usize = 1000;
+
+/// Outcomes older than this are dropped, whatever window is asked for.
+const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Outcome of one attempt on a model in the fallback chain.
+struct Sample {
+    at: Instant,
+    latency: Duration,
+    failed: bool,
+}
+
+/// Recent latency and errors of the fallback chain's models, so callers can tell when
+/// the model provider is degraded.
+#[derive(Default)]
+pub struct ModelStats {
+    samples: Mutex<VecDeque<Sample>>,
+}
+
+/// Latency and error rate of the model calls within a window.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModelHealth {
+    pub calls: usize,
+    /// Share of calls that failed or timed out, from 0.0 to 1.0
+    pub error_rate: f64,
+    /// Nearest-rank 90th percentile of the call latencies
+    pub p90_latency: Duration,
+}
+
+impl ModelStats {
+    /// Records a model call that took `latency`.
+    pub fn record(&self, latency: Duration, failed: bool) {
+        let mut samples = self.samples.lock().unwrap();
+        let now = Instant::now();
+        while samples
+            .front()
+            .is_some_and(|sample| now.duration_since(sample.at) > MAX_AGE)
+            || samples.len() >= MAX_SAMPLES
+        {
+            samples.pop_front();
+        }
+        samples.push_back(Sample {
+            at: now,
+            latency,
+            failed,
+        });
+    }
+
+    /// Summarizes the calls made within the last `window`.
+    pub fn health(&self, window: Duration) -> ModelHealth {
+        let samples = self.samples.lock().unwrap();
+        let now = Instant::now();
+        let recent: Vec<&Sample> = samples
+            .iter()
+            .filter(|sample| now.duration_since(sample.at) <= window)
+            .collect();
+        if recent.is_empty() {
+            return ModelHealth::default();
+        }
+
+        let failed = recent.iter().filter(|sample| sample.failed).count();
+        let mut latencies: Vec<Duration> = recent.iter().map(|sample| sample.latency).collect();
+        latencies.sort();
+        let rank = (latencies.len() as f64 * 0.9).ceil() as usize;
+        ModelHealth {
+            calls: recent.len(),
+            error_rate: failed as f64 / recent.len() as f64,
+            p90_latency: latencies[rank.clamp(1, latencies.len()) - 1],
+        }
+    }
+}
@@ -0,0 +1,29 @@
+/// Estimates the number of tokens in a piece of text.
+///
+/// Mirrors the behavior of BPE tokenizers like tiktoken closely enough for budgeting:
+/// common English words are usually a single token, long words are split into
+/// ~4 character chunks, and punctuation/symbols mostly become tokens of their own.
+/// The estimate deliberately errs on the high side.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+
+    for word in text.split_whitespace() {
+        let mut alnum_run = 0;
+        for ch in word.chars() {
+            if ch.is_alphanumeric() {
+                alnum_run += 1;
+            } else {
+                tokens += run_tokens(alnum_run) + 1;
+                alnum_run = 0;
+            }
+        }
+        tokens += run_tokens(alnum_run);
+    }
+
+    tokens
+}
+
+/// Tokens needed for a run of alphanumeric characters (~4 characters per token).
+fn run_tokens(len: usize) -> usize {
+    len.div_ceil(4)
+}
@@ -0,0 +1,317 @@
+use crate::environment::Environment;
+use crate::error::AppError;
+use crate::kube::types::table::age;
+use chrono::Utc;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt::Write;
+use std::time::Duration;
+use tracing::*;
+
+/// Runs fetched per repository; enough to find the latest run of each active workflow.
+const RUNS_PER_PAGE: usize = 30;
+
+/// Workflows reported per repository.
+const MAX_WORKFLOWS: usize = 10;
+
+/// Time a GitHub API request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct RunsResponse {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+#[derive(Deserialize)]
+struct WorkflowRun {
+    id: u64,
+    workflow_id: u64,
+    name: Option<String>,
+    head_branch: Option<String>,
+    head_sha: String,
+    event: String,
+    /// queued, in_progress, completed, ...
+    status: Option<String>,
+    /// success, failure, cancelled, ... once completed
+    conclusion: Option<String>,
+    html_url: String,
+    updated_at: String,
+}
+
+#[derive(Deserialize)]
+struct JobsResponse {
+    jobs: Vec<Job>,
+}
+
+#[derive(Deserialize)]
+struct Job {
+    name: String,
+    conclusion: Option<String>,
+    #[serde(default)]
+    steps: Vec<Step>,
+}
+
+#[derive(Deserialize)]
+struct Step {
+    name: String,
+    conclusion: Option<String>,
+}
+
+/// Tool reporting the latest GitHub Actions workflow runs of the configured repositories.
+///
+/// For each workflow, reports the status of its most recent run and, for failed runs,
+/// which jobs and steps failed. Only registered when `GITHUB_REPOS` is set; a token is
+/// needed for private repositories and raises the API's rate limit.
+#[derive(Clone)]
+pub struct CiStatusTool {
+    repos: Vec<String>,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl CiStatusTool {
+    pub fn new(repos: Vec<String>, token: Option<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(Environment::global().http_user_agent.as_str())
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        CiStatusTool {
+            repos,
+            token,
+            client,
+        }
+    }
+
+    /// Reports the latest run of each workflow in `repo` (or every configured repo),
+    /// optionally only for `branch` and workflows whose name contains `workflow`.
+    pub async fn ci_status(
+        &self,
+        repo: Option<&str>,
+        branch: Option<&str>,
+        workflow: Option<&str>,
+    ) -> Result<String, AppError> {
+        let repos: Vec<&String> = match repo {
+            Some(repo) => {
+                let configured = self
+                    .repos
+                    .iter()
+                    .find(|configured| configured.eq_ignore_ascii_case(repo.trim()));
+                match configured {
+                    Some(configured) => vec![configured],
+                    None => {
+                        return Err(AppError::Tool(format!(
+                            "Repository '{}' is not configured. Configured repositories: {}",
+                            repo,
+                            self.repos.join(", ")
+                        )))
+                    }
+                }
+            }
+            None => self.repos.iter().collect(),
+        };
+
+        let mut output = String::new();
+        for repo in repos {
+            match self.repo_status(repo, branch, workflow).await {
+                Ok(report) => output.push_str(&report),
+                Err(e) => {
+                    warn!("Failed to fetch workflow runs for {}: {}", repo, e);
+                    let _ = writeln!(output, "{}: failed to fetch workflow runs: {}", repo, e);
+                }
+            }
+            output.push('\n');
+        }
+        Ok(output.trim_end().to_string())
+    }
+
+    async fn repo_status(
+        &self,
+        repo: &str,
+        branch: Option<&str>,
+        workflow: Option<&str>,
+    ) -> Result<String, String> {
+        debug!("Fetching workflow runs for {}", repo);
+        let mut query = vec![("per_page", RUNS_PER_PAGE.to_string())];
+        if let Some(branch) = branch {
+            query.push(("branch", branch.to_string()));
+        }
+        let response: RunsResponse = self
+            .get(&format!(
+                "https://api.github.com/repos/{}/actions/runs",
+                repo
+            ))
+            .query(&query)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.without_url().to_string())?
+            .json()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        // Runs come newest first, so the first run seen of a workflow is its latest
+        let mut latest: Vec<WorkflowRun> = Vec::new();
+        for run in response.workflow_runs {
+            let matches = workflow.is_none_or(|workflow| {
+                run.name
+                    .as_deref()
+                    .unwrap_or_default()
+                    .to_lowercase()
+                    .contains(&workflow.to_lowercase())
+            });
+            if matches
+                && !latest
+                    .iter()
+                    .any(|seen| seen.workflow_id == run.workflow_id)
+            {
+                latest.push(run);
+            }
+        }
+        latest.truncate(MAX_WORKFLOWS);
+
+        let mut output = format!("{}:\n", repo);
+        if latest.is_empty() {
+            output.push_str("  No matching workflow runs\n");
+            return Ok(output);
+        }
+
+        let now = Utc::now();
+        for run in &latest {
+            let state = match (run.status.as_deref(), run.conclusion.as_deref()) {
+                (_, Some(conclusion)) => conclusion.to_string(),
+                (Some(status), None) => status.to_string(),
+                (None, None) => "unknown".to_string(),
+            };
+            let _ = writeln!(
+                output,
+                "  {}: {} ({} on {} at {}, {} ago) {}",
+                run.name.as_deref().unwrap_or("<unnamed workflow>"),
+                state,
+                run.event,
+                run.head_branch.as_deref().unwrap_or("-"),
+                &run.head_sha[..run.head_sha.len().min(7)],
+                age(&run.updated_at, now),
+                run.html_url
+            );
+
+            if run.conclusion.as_deref() == Some("failure") {
+                match self.failed_jobs(repo, run.id).await {
+                    Ok(jobs) => output.push_str(&jobs),
+                    Err(e) => {
+                        let _ = writeln!(output, "    (failed to fetch jobs: {})", e);
+                    }
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Lists the run's failed jobs with the steps that failed in each.
+    async fn failed_jobs(&self, repo: &str, run_id: u64) -> Result<String, String> {
+        let response: JobsResponse = self
+            .get(&format!(
+                "https://api.github.com/repos/{}/actions/runs/{}/jobs",
+                repo, run_id
+            ))
+            .query(&[("filter", "latest")])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.without_url().to_string())?
+            .json()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        let mut output = String::new();
+        for job in response
+            .jobs
+            .iter()
+            .filter(|job| job.conclusion.as_deref() == Some("failure"))
+        {
+            let steps: Vec<&str> = job
+                .steps
+                .iter()
+                .filter(|step| step.conclusion.as_deref() == Some("failure"))
+                .map(|step| step.name.as_str())
+                .collect();
+            if steps.is_empty() {
+                let _ = writeln!(output, "    failed job: {}", job.name);
+            } else {
+                let _ = writeln!(
+                    output,
+                    "    failed job: {} (step: {})",
+                    job.name,
+                    steps.join(", ")
+                );
+            }
+        }
+        Ok(output)
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .get(url)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+/// Arguments for the CiStatusTool
+#[derive(Deserialize)]
+pub struct CiStatusArgs {
+    repo: Option<String>,
+    branch: Option<String>,
+    workflow: Option<String>,
+}
+
+impl Tool for CiStatusTool {
+    const NAME: &'static str = "ci_status";
+    type Error = AppError;
+    type Args = CiStatusArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Report the latest GitHub Actions run of each workflow, with the failed jobs and steps of failed runs. Use for questions like \"did the last deploy pipeline pass?\". Repositories: {}.",
+                self.repos.join(", ")
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "repo": {
+                        "type": "string",
+                        "description": "Only this repository, as owner/name (default: all of them)"
+                    },
+                    "branch": {
+                        "type": "string",
+                        "description": "Only runs on this branch, e.g. main"
+                    },
+                    "workflow": {
+                        "type": "string",
+                        "description": "Only workflows whose name contains this text, e.g. deploy"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        info!("Checking CI status");
+        self.ci_status(
+            args.repo.as_deref(),
+            args.branch.as_deref(),
+            args.workflow.as_deref(),
+        )
+        .await
+    }
+}
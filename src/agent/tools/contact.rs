@@ -0,0 +1,346 @@
+use super::memory::current_session_key;
+use crate::environment::Environment;
+use crate::sessions::SessionKey;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Longest message a visitor may send.
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Longest sender name accepted.
+const MAX_NAME_CHARS: usize = 100;
+
+/// Longest reply-to address accepted (the limit for an email address).
+const MAX_EMAIL_CHARS: usize = 254;
+
+/// Window the hourly message cap applies to.
+const RATE_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Time a mail API request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Mail API that delivers contact messages.
+#[derive(Debug, Clone, Copy)]
+pub enum MailProvider {
+    Resend,
+    SendGrid,
+}
+
+impl FromStr for MailProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "resend" => Ok(MailProvider::Resend),
+            "sendgrid" => Ok(MailProvider::SendGrid),
+            other => Err(format!(
+                "unknown mail provider '{}' (expected resend or sendgrid)",
+                other
+            )),
+        }
+    }
+}
+
+/// Error type for contact message failures
+#[derive(Debug)]
+pub enum ContactError {
+    /// The message is empty or over the length cap
+    InvalidMessage(String),
+    /// This session has already sent a message
+    AlreadySent,
+    /// The hourly cap across all visitors has been reached
+    RateLimited,
+    /// The mail API refused or failed the request
+    Delivery(String),
+}
+
+impl fmt::Display for ContactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContactError::InvalidMessage(reason) => write!(f, "Message not sent: {}", reason),
+            ContactError::AlreadySent => write!(
+                f,
+                "Message not sent: a message was already sent from this conversation"
+            ),
+            ContactError::RateLimited => write!(
+                f,
+                "Message not sent: too many contact messages were sent recently. Suggest the contact details on the portfolio's Contact page instead."
+            ),
+            ContactError::Delivery(e) => write!(f, "Message could not be delivered: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ContactError {}
+
+/// Messages sent recently, for the rate limits.
+#[derive(Default)]
+struct SentLog {
+    /// Send times within the last [`RATE_WINDOW`]
+    recent: VecDeque<Instant>,
+    /// Sessions that have already sent their one message
+    sessions: HashSet<SessionKey>,
+}
+
+/// Tool that emails a visitor's message to the portfolio owner.
+///
+/// Only registered when a mail API key and both addresses are configured. Messages are
+/// capped in length, each conversation may send one, and at most `max_per_hour` are
+/// sent across all visitors, so the tool can't be used to flood the inbox.
+#[derive(Clone)]
+pub struct ContactMessageTool {
+    provider: MailProvider,
+    api_key: String,
+    from: String,
+    to: String,
+    max_per_hour: usize,
+    sent: Arc<Mutex<SentLog>>,
+    client: reqwest::Client,
+}
+
+impl ContactMessageTool {
+    pub fn new(
+        provider: MailProvider,
+        api_key: String,
+        from: String,
+        to: String,
+        max_per_hour: usize,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(Environment::global().http_user_agent.as_str())
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        ContactMessageTool {
+            provider,
+            api_key,
+            from,
+            to,
+            max_per_hour,
+            sent: Arc::new(Mutex::new(SentLog::default())),
+            client,
+        }
+    }
+
+    /// Sends the message if the limits allow, recording it against them.
+    pub async fn send(&self, args: ContactMessageArgs) -> Result<String, ContactError> {
+        let message = validate(&args)?;
+        let session = current_session_key();
+        self.reserve(session.as_ref())?;
+
+        let subject = format!("Portfolio chat: message from {}", message.name);
+        let body = match self.provider {
+            MailProvider::Resend => self.resend_body(&subject, &message),
+            MailProvider::SendGrid => self.sendgrid_body(&subject, &message),
+        };
+        let url = match self.provider {
+            MailProvider::Resend => "https://api.resend.com/emails",
+            MailProvider::SendGrid => "https://api.sendgrid.com/v3/mail/send",
+        };
+
+        let result = self
+            .client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            error!(
+                "Failed to send contact message via {:?}: {}",
+                self.provider, e
+            );
+            // A message that never arrived doesn't use up the visitor's one message
+            self.release(session.as_ref());
+            return Err(ContactError::Delivery(e.without_url().to_string()));
+        }
+
+        info!(
+            "Sent contact message ({} chars) via {:?}",
+            message.text.chars().count(),
+            self.provider
+        );
+        Ok("Message sent.".to_string())
+    }
+
+    /// Counts a message against the limits, refusing it if either is reached.
+    fn reserve(&self, session: Option<&SessionKey>) -> Result<(), ContactError> {
+        let mut sent = self.sent.lock().unwrap();
+        while sent
+            .recent
+            .front()
+            .is_some_and(|sent_at| sent_at.elapsed() >= RATE_WINDOW)
+        {
+            sent.recent.pop_front();
+        }
+
+        if let Some(session) = session {
+            if sent.sessions.contains(session) {
+                warn!("Refused second contact message from session {}", session);
+                return Err(ContactError::AlreadySent);
+            }
+        }
+        if sent.recent.len() >= self.max_per_hour {
+            warn!(
+                "Refused contact message: {} already sent in the last hour",
+                sent.recent.len()
+            );
+            return Err(ContactError::RateLimited);
+        }
+
+        sent.recent.push_back(Instant::now());
+        if let Some(session) = session {
+            sent.sessions.insert(session.clone());
+        }
+        Ok(())
+    }
+
+    fn release(&self, session: Option<&SessionKey>) {
+        let mut sent = self.sent.lock().unwrap();
+        sent.recent.pop_back();
+        if let Some(session) = session {
+            sent.sessions.remove(session);
+        }
+    }
+
+    fn resend_body(&self, subject: &str, message: &Message) -> Value {
+        let mut body = json!({
+            "from": self.from,
+            "to": [self.to],
+            "subject": subject,
+            "text": message.text,
+        });
+        if let Some(reply_to) = &message.reply_to {
+            body["reply_to"] = json!(reply_to);
+        }
+        body
+    }
+
+    fn sendgrid_body(&self, subject: &str, message: &Message) -> Value {
+        let mut body = json!({
+            "personalizations": [{ "to": [{ "email": self.to }] }],
+            "from": { "email": self.from },
+            "subject": subject,
+            "content": [{ "type": "text/plain", "value": message.text }],
+        });
+        if let Some(reply_to) = &message.reply_to {
+            body["reply_to"] = json!({ "email": reply_to });
+        }
+        body
+    }
+}
+
+/// A message that passed validation.
+struct Message {
+    name: String,
+    reply_to: Option<String>,
+    text: String,
+}
+
+/// Checks the lengths and the reply-to address, and strips the name of anything that
+/// could break out of the subject line.
+fn validate(args: &ContactMessageArgs) -> Result<Message, ContactError> {
+    let text = args.message.trim();
+    if text.is_empty() {
+        return Err(ContactError::InvalidMessage("the message is empty".into()));
+    }
+    if text.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(ContactError::InvalidMessage(format!(
+            "the message is longer than {} characters",
+            MAX_MESSAGE_CHARS
+        )));
+    }
+
+    let name: String = args
+        .name
+        .as_deref()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let name = match name.trim() {
+        "" => "a visitor".to_string(),
+        name => name.to_string(),
+    };
+
+    let reply_to = match args.reply_to.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(email) => {
+            let valid = email.len() <= MAX_EMAIL_CHARS
+                && !email.chars().any(|c| c.is_whitespace() || c.is_control())
+                && email
+                    .split_once('@')
+                    .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+            if !valid {
+                return Err(ContactError::InvalidMessage(format!(
+                    "'{}' is not a valid email address",
+                    email
+                )));
+            }
+            Some(email.to_string())
+        }
+    };
+
+    Ok(Message {
+        name,
+        reply_to,
+        text: text.to_string(),
+    })
+}
+
+/// Arguments for the ContactMessageTool
+#[derive(Deserialize)]
+pub struct ContactMessageArgs {
+    message: String,
+    name: Option<String>,
+    reply_to: Option<String>,
+}
+
+impl Tool for ContactMessageTool {
+    const NAME: &'static str = "send_contact_message";
+    type Error = ContactError;
+    type Args = ContactMessageArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Email a message from the visitor to Cal, the portfolio's owner. Only use this when the visitor explicitly asks to send Cal a message (e.g. \"tell Cal I'd like to connect\"), and confirm the wording with them first. One message per conversation, at most {} characters.",
+                MAX_MESSAGE_CHARS
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "message": {
+                        "type": "string",
+                        "description": "The visitor's message, in their words"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The visitor's name, if they gave one"
+                    },
+                    "reply_to": {
+                        "type": "string",
+                        "description": "An email address Cal can reply to, if the visitor gave one"
+                    }
+                },
+                "required": ["message"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.send(args).await
+    }
+}
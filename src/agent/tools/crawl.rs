@@ -0,0 +1,318 @@
+use super::extract::html_to_text;
+use super::http::portfolio_client;
+use super::web_search::PortfolioPage;
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::*;
+
+/// Time a single page or sitemap request may take.
+const PAGE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Delay before crawling again after a crawl that indexed nothing.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Paragraphs are grouped into passages of roughly this many characters.
+const PASSAGE_CHARS: usize = 800;
+
+/// A passage of a crawled page; the unit the index searches and returns.
+#[derive(Clone)]
+pub struct Passage {
+    pub url: String,
+    pub title: String,
+    pub text: String,
+}
+
+#[derive(Default)]
+struct IndexState {
+    passages: Vec<Passage>,
+    pages: usize,
+    built_at: Option<DateTime<Utc>>,
+}
+
+/// Searchable text of the portfolio site, rebuilt periodically by [`PortfolioCrawler`].
+#[derive(Clone, Default)]
+pub struct PortfolioIndex {
+    state: Arc<RwLock<IndexState>>,
+}
+
+impl PortfolioIndex {
+    /// Number of pages indexed and when, or `None` before the first crawl finishes.
+    pub fn built(&self) -> Option<(usize, DateTime<Utc>)> {
+        let state = self.state.read().unwrap();
+        state.built_at.map(|built_at| (state.pages, built_at))
+    }
+
+    /// Up to `limit` passages matching `query`, best first.
+    ///
+    /// Passages are ranked by how many distinct query terms they contain, then by how
+    /// often those terms occur. Terms shorter than three characters are ignored.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Passage> {
+        let terms = terms(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let state = self.state.read().unwrap();
+        let mut scored: Vec<((usize, usize), &Passage)> = state
+            .passages
+            .iter()
+            .filter_map(|passage| {
+                let haystack = format!("{} {}", passage.title, passage.text).to_lowercase();
+                let matched = terms
+                    .iter()
+                    .filter(|term| haystack.contains(term.as_str()))
+                    .count();
+                let occurrences = terms
+                    .iter()
+                    .map(|term| haystack.matches(term.as_str()).count())
+                    .sum();
+                (matched > 0).then_some(((matched, occurrences), passage))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, passage)| passage.clone())
+            .collect()
+    }
+}
+
+fn terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Background task that crawls the portfolio site into a [`PortfolioIndex`].
+///
+/// Pages come from the site's `sitemap.xml` when it has one; otherwise the crawl starts
+/// from the base URL and the configured portfolio pages and follows links within the
+/// site up to `max_depth` hops. At most `max_pages` pages are fetched per crawl.
+pub struct PortfolioCrawler {
+    base_url: String,
+    seeds: Vec<String>,
+    max_depth: usize,
+    max_pages: usize,
+    refresh_interval: Duration,
+}
+
+impl PortfolioCrawler {
+    pub fn new(
+        base_url: &str,
+        pages: &[PortfolioPage],
+        max_depth: usize,
+        max_pages: usize,
+        refresh_interval: Duration,
+    ) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let mut seeds = vec![format!("{}/", base_url)];
+        seeds.extend(pages.iter().map(|page| page.url.clone()));
+        PortfolioCrawler {
+            base_url,
+            seeds,
+            max_depth,
+            max_pages,
+            refresh_interval,
+        }
+    }
+
+    /// Spawns the crawl loop and returns the index it maintains. The index is empty
+    /// until the first crawl completes.
+    pub fn spawn(self) -> PortfolioIndex {
+        let index = PortfolioIndex::default();
+        tokio::spawn(self.run(index.clone()));
+        info!("Portfolio crawler started");
+        index
+    }
+
+    async fn run(self, index: PortfolioIndex) {
+        loop {
+            let (pages, passages) = self.crawl().await;
+            if pages == 0 {
+                warn!(
+                    "Portfolio crawl of {} fetched no pages, retrying in {:?}",
+                    self.base_url, RETRY_DELAY
+                );
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+
+            info!(
+                "Indexed {} passages from {} portfolio pages",
+                passages.len(),
+                pages
+            );
+            *index.state.write().unwrap() = IndexState {
+                passages,
+                pages,
+                built_at: Some(Utc::now()),
+            };
+            tokio::time::sleep(self.refresh_interval).await;
+        }
+    }
+
+    /// Crawls the site once, returning the number of pages fetched and their passages.
+    async fn crawl(&self) -> (usize, Vec<Passage>) {
+        let sitemap = self.sitemap_urls().await;
+        let follow_links = sitemap.is_empty();
+        let start = if follow_links {
+            self.seeds.clone()
+        } else {
+            debug!("Crawling {} pages listed in the sitemap", sitemap.len());
+            sitemap
+        };
+
+        let mut seen: HashSet<String> = start.iter().cloned().collect();
+        let mut queue: VecDeque<(String, usize)> = start.into_iter().map(|url| (url, 0)).collect();
+        let mut pages = 0;
+        let mut passages = Vec::new();
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if pages >= self.max_pages {
+                debug!(
+                    "Portfolio crawl stopped at the {} page limit",
+                    self.max_pages
+                );
+                break;
+            }
+            let html = match fetch_page(&url).await {
+                Ok(html) => html,
+                Err(e) => {
+                    debug!("Skipping {} in portfolio crawl: {}", url, e);
+                    continue;
+                }
+            };
+            pages += 1;
+
+            let (title, links) = parse_page(&html, &url);
+            if follow_links && depth < self.max_depth {
+                for link in links.into_iter().filter(|link| self.is_internal(link)) {
+                    if seen.insert(link.clone()) {
+                        queue.push_back((link, depth + 1));
+                    }
+                }
+            }
+
+            let title = title.unwrap_or_else(|| url.clone());
+            passages.extend(
+                split_passages(&html_to_text(&html))
+                    .into_iter()
+                    .map(|text| Passage {
+                        url: url.clone(),
+                        title: title.clone(),
+                        text,
+                    }),
+            );
+        }
+
+        (pages, passages)
+    }
+
+    /// Site URLs listed in `sitemap.xml`, or none if the site doesn't have one.
+    async fn sitemap_urls(&self) -> Vec<String> {
+        let url = format!("{}/sitemap.xml", self.base_url);
+        let response = portfolio_client()
+            .get(&url)
+            .timeout(PAGE_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let xml = match response {
+            Ok(response) => response.text().await.unwrap_or_default(),
+            Err(e) => {
+                debug!("No usable sitemap at {}: {}", url, e);
+                return Vec::new();
+            }
+        };
+
+        xml.split("<loc>")
+            .skip(1)
+            .filter_map(|rest| rest.split_once("</loc>"))
+            .map(|(loc, _)| loc.trim().replace("&amp;", "&"))
+            .filter(|loc| self.is_internal(loc))
+            .collect()
+    }
+
+    fn is_internal(&self, url: &str) -> bool {
+        let host = |url: &str| Url::parse(url).ok()?.host_str().map(str::to_string);
+        host(url).is_some_and(|host_name| Some(host_name) == host(&self.base_url))
+    }
+}
+
+async fn fetch_page(url: &str) -> Result<String, String> {
+    let response = portfolio_client()
+        .get(url)
+        .timeout(PAGE_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.contains("html"));
+    if !is_html {
+        return Err("not an HTML page".to_string());
+    }
+    response.text().await.map_err(|e| e.to_string())
+}
+
+// Extracts the page title and the absolute http(s) URLs it links to, without fragments
+fn parse_page(html: &str, url: &str) -> (Option<String>, Vec<String>) {
+    let document = Html::parse_document(html);
+    let title = Selector::parse("title")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(|el| el.text().collect::<String>().trim().to_string())
+        .filter(|title| !title.is_empty());
+
+    let Ok(page_url) = Url::parse(url) else {
+        return (title, Vec::new());
+    };
+    let links = Selector::parse("a[href]")
+        .map(|selector| {
+            document
+                .select(&selector)
+                .filter_map(|el| page_url.join(el.value().attr("href")?).ok())
+                .filter(|link| matches!(link.scheme(), "http" | "https"))
+                .map(|mut link| {
+                    link.set_fragment(None);
+                    link.to_string()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    (title, links)
+}
+
+// Groups paragraphs into passages of about PASSAGE_CHARS characters
+fn split_passages(text: &str) -> Vec<String> {
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.len() + paragraph.len() > PASSAGE_CHARS {
+            passages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        passages.push(current);
+    }
+    passages
+}
@@ -0,0 +1,40 @@
+use scraper::{Html, Selector};
+
+/// Line width text is wrapped at; wide enough that prose rarely wraps mid-sentence.
+const TEXT_WIDTH: usize = 120;
+
+/// Converts an HTML page into readable markdown-style text for the model.
+///
+/// Scripts and styles are dropped, and when the page marks up its main content
+/// (`<main>` or `<article>`) only that is kept, leaving out site navigation and
+/// footers. Headings, lists, and links survive as markdown.
+pub fn html_to_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let content = ["main", "article", "body"]
+        .iter()
+        .filter_map(|tag| Selector::parse(tag).ok())
+        .find_map(|selector| document.select(&selector).next().map(|el| el.html()))
+        .unwrap_or_else(|| html.to_string());
+
+    let text = html2text::from_read(content.as_bytes(), TEXT_WIDTH);
+    collapse_blank_lines(&text)
+}
+
+// Trims trailing whitespace and keeps at most one blank line between paragraphs
+fn collapse_blank_lines(text: &str) -> String {
+    let mut output = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank = !output.is_empty();
+            continue;
+        }
+        if blank {
+            output.push('\n');
+            blank = false;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
@@ -0,0 +1,333 @@
+use super::extract::html_to_text;
+use super::page_cache::{CachedPage, PageCache};
+use crate::environment::Environment;
+use crate::error::AppError;
+use reqwest::header::{HeaderName, CONTENT_TYPE, IF_MODIFIED_SINCE, LAST_MODIFIED, LOCATION};
+use reqwest::redirect::Policy;
+use reqwest::{Response, StatusCode, Url};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::*;
+
+/// Redirects followed before giving up; every hop is checked like the original URL.
+const MAX_REDIRECTS: usize = 5;
+
+/// Tool for fetching any web page on an allowlisted domain.
+///
+/// Guards against SSRF into the cluster network: the host must match the domain
+/// allowlist, every address it resolves to must be public, and the connection is
+/// pinned to the checked address so a second DNS lookup can't swap in a private one.
+/// Redirects are followed by hand so each hop passes the same checks.
+#[derive(Clone)]
+pub struct FetchUrlTool {
+    domain_allowlist: Vec<String>,
+    max_bytes: usize,
+    timeout: Duration,
+    cache: Arc<PageCache>,
+}
+
+impl FetchUrlTool {
+    pub fn new(
+        domain_allowlist: Vec<String>,
+        max_bytes: usize,
+        timeout: Duration,
+        cache: Arc<PageCache>,
+    ) -> Self {
+        FetchUrlTool {
+            domain_allowlist: domain_allowlist
+                .iter()
+                .map(|domain| domain.trim_start_matches("*.").to_lowercase())
+                .collect(),
+            max_bytes,
+            timeout,
+            cache,
+        }
+    }
+
+    /// Fetches `url`, returning readable text for HTML pages (or the body as-is when
+    /// `raw`), cut off after `max_bytes`.
+    pub async fn fetch(&self, url: &str, raw: bool) -> Result<String, AppError> {
+        let parsed =
+            Url::parse(url).map_err(|e| AppError::Tool(format!("Invalid URL '{}': {}", url, e)))?;
+        // Checked before the cache too, since web_search shares it
+        self.check_allowed(&parsed)?;
+
+        let page = match self.cache.fresh(url) {
+            Some(page) => page,
+            None => self.download(url, parsed).await?,
+        };
+
+        let mut text = if page.is_html && !raw {
+            html_to_text(&page.body)
+        } else {
+            page.body
+        };
+        if page.truncated {
+            text.push_str(&format!(
+                "\n[Truncated: the response exceeded {} bytes]\n",
+                self.max_bytes
+            ));
+        }
+        Ok(text)
+    }
+
+    /// Downloads `requested`, following redirects, and caches the result under it. A
+    /// cached copy is revalidated with `If-Modified-Since` on the first request.
+    async fn download(&self, requested: &str, mut url: Url) -> Result<CachedPage, AppError> {
+        let mut since = self.cache.last_modified(requested);
+
+        for _ in 0..=MAX_REDIRECTS {
+            let client = self.client_for(&url).await?;
+            let mut request = client.get(url.clone());
+            if let Some(since) = since.take() {
+                request = request.header(IF_MODIFIED_SINCE, since);
+            }
+            let response = request.send().await.map_err(|e| {
+                error!("Error fetching URL {}: {}", url, e);
+                AppError::Tool(e.to_string())
+            })?;
+
+            if response.status() == StatusCode::NOT_MODIFIED {
+                return self.cache.revalidated(requested).ok_or_else(|| {
+                    AppError::Tool(format!(
+                        "{} returned 304 Not Modified for an uncached page",
+                        url
+                    ))
+                });
+            }
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or_else(|| {
+                        AppError::Tool(format!("{} redirected without a Location header", url))
+                    })?;
+                url = url
+                    .join(location)
+                    .map_err(|e| AppError::Tool(format!("Invalid redirect from {}: {}", url, e)))?;
+                debug!("Following redirect to {}", url);
+                continue;
+            }
+
+            if !response.status().is_success() {
+                return Err(AppError::Tool(format!(
+                    "{} returned HTTP {}",
+                    url,
+                    response.status()
+                )));
+            }
+
+            let header = |name: HeaderName| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let is_html =
+                header(CONTENT_TYPE).is_some_and(|content_type| content_type.contains("html"));
+            let last_modified = header(LAST_MODIFIED);
+            let (body, truncated) = read_limited(response, self.max_bytes).await?;
+            info!("Fetched {} ({} bytes)", url, body.len());
+
+            let page = CachedPage {
+                body,
+                is_html,
+                truncated,
+            };
+            self.cache.insert(requested, page.clone(), last_modified);
+            return Ok(page);
+        }
+
+        Err(AppError::Tool(format!(
+            "Gave up on {} after {} redirects",
+            url, MAX_REDIRECTS
+        )))
+    }
+
+    /// Checks that `url` is http(s) on an allowlisted domain.
+    fn check_allowed(&self, url: &Url) -> Result<(), AppError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::Tool(format!(
+                "Only http and https URLs can be fetched, not {}",
+                url.scheme()
+            )));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| AppError::Tool(format!("URL {} has no host", url)))?;
+        if !self.domain_allowed(host) {
+            warn!("Refusing to fetch {}: domain not allowlisted", url);
+            return Err(AppError::Tool(format!(
+                "Domain '{}' is not allowed (allowed: {})",
+                host,
+                self.domain_allowlist.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks `url` against the allowlist and address rules, returning a client pinned
+    /// to the address that passed.
+    async fn client_for(&self, url: &Url) -> Result<reqwest::Client, AppError> {
+        self.check_allowed(url)?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| AppError::Tool(format!("URL {} has no host", url)))?;
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = match host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| AppError::Tool(format!("Failed to resolve {}: {}", host, e)))?
+                .collect(),
+        };
+        if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            warn!("Refusing to fetch {}: resolves to {}", url, addr.ip());
+            return Err(AppError::Tool(format!(
+                "{} resolves to non-public address {}",
+                host,
+                addr.ip()
+            )));
+        }
+        let addr = addrs
+            .first()
+            .ok_or_else(|| AppError::Tool(format!("{} did not resolve to any address", host)))?;
+
+        // PORTFOLIO_HEADERS are deliberately not sent to arbitrary allowlisted domains
+        reqwest::Client::builder()
+            .user_agent(Environment::global().http_user_agent.as_str())
+            .redirect(Policy::none())
+            .timeout(self.timeout)
+            .resolve(host, *addr)
+            .build()
+            .map_err(|e| AppError::Tool(e.to_string()))
+    }
+
+    /// Whether `host` is an allowlisted domain or a subdomain of one.
+    fn domain_allowed(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.domain_allowlist.iter().any(|domain| {
+            host == *domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+/// Whether `ip` is reachable on the public internet, as opposed to loopback, private,
+/// link-local (including cloud metadata endpoints), or otherwise reserved ranges.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space (carrier-grade NAT), 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7
+                    || (first & 0xfe00) == 0xfc00
+                    // Link-local, fe80::/10
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// Reads at most `max_bytes` of the body, reporting whether anything was cut off
+async fn read_limited(
+    mut response: Response,
+    max_bytes: usize,
+) -> Result<(String, bool), AppError> {
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| AppError::Tool(e.to_string()))?
+    {
+        body.extend_from_slice(&chunk);
+        if body.len() > max_bytes {
+            body.truncate(max_bytes);
+            truncated = true;
+            break;
+        }
+    }
+    Ok((String::from_utf8_lossy(&body).into_owned(), truncated))
+}
+
+/// Arguments for the FetchUrlTool
+#[derive(Deserialize)]
+pub struct FetchUrlArgs {
+    url: String,
+    /// Return the body as fetched instead of extracted text
+    #[serde(default)]
+    raw: bool,
+}
+
+impl Tool for FetchUrlTool {
+    const NAME: &'static str = "fetch_url";
+    type Error = AppError;
+    type Args = FetchUrlArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "Fetch a web page and return its readable text. Only URLs on these domains (or their subdomains) are allowed: {}",
+                self.domain_allowlist.join(", ")
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "The http(s) URL to fetch"
+                    },
+                    "raw": {
+                        "type": "boolean",
+                        "description": "Return the raw body instead of readable text (default false)"
+                    }
+                },
+                "required": ["url"]
+            }
+        }))
+        .unwrap_or_else(|e| {
+            error!("Critical error: Failed to create tool definition: {}", e);
+            panic!(
+                "Invalid static tool definition - this is a programming error: {}",
+                e
+            );
+        })
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.fetch(&args.url, args.raw).await
+    }
+}
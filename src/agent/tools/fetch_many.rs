@@ -0,0 +1,135 @@
+use super::fetch::FetchUrlTool;
+use crate::error::AppError;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::*;
+
+/// Most URLs accepted in one call, so a single call can't fan out unboundedly.
+const MAX_URLS: usize = 10;
+
+/// Tool for fetching several pages in one call.
+///
+/// Each URL goes through the same allowlist, address checks, size limit, and page
+/// cache as `fetch_url`; at most `max_concurrency` requests are in flight at once.
+pub struct FetchManyTool {
+    fetcher: Arc<FetchUrlTool>,
+    max_concurrency: usize,
+}
+
+impl FetchManyTool {
+    pub fn new(fetcher: FetchUrlTool, max_concurrency: usize) -> Self {
+        FetchManyTool {
+            fetcher: Arc::new(fetcher),
+            max_concurrency: max_concurrency.max(1),
+        }
+    }
+
+    /// Fetches every URL, mapping each to its text or to the reason it failed.
+    pub async fn fetch_many(
+        &self,
+        urls: Vec<String>,
+        raw: bool,
+    ) -> Result<BTreeMap<String, String>, AppError> {
+        let urls: BTreeSet<String> = urls.into_iter().collect();
+        if urls.is_empty() {
+            return Err(AppError::Tool("No URLs given".to_string()));
+        }
+        if urls.len() > MAX_URLS {
+            return Err(AppError::Tool(format!(
+                "At most {} URLs can be fetched at once, got {}",
+                MAX_URLS,
+                urls.len()
+            )));
+        }
+        info!(
+            "Fetching {} URLs, {} at a time",
+            urls.len(),
+            self.max_concurrency
+        );
+
+        let permits = Arc::new(Semaphore::new(self.max_concurrency));
+        let mut tasks = JoinSet::new();
+        for url in urls {
+            let fetcher = self.fetcher.clone();
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                // The semaphore is never closed, so acquiring only waits
+                let _permit = permits.acquire_owned().await;
+                let text = match fetcher.fetch(&url, raw).await {
+                    Ok(text) => text,
+                    Err(e) => format!("[Failed to fetch: {}]", e),
+                };
+                (url, text)
+            });
+        }
+
+        let mut pages = BTreeMap::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok((url, text)) => {
+                    pages.insert(url, text);
+                }
+                Err(e) => error!("fetch_many task failed: {}", e),
+            }
+        }
+        Ok(pages)
+    }
+}
+
+/// Arguments for the FetchManyTool
+#[derive(Deserialize)]
+pub struct FetchManyArgs {
+    urls: Vec<String>,
+    /// Return each body as fetched instead of extracted text
+    #[serde(default)]
+    raw: bool,
+}
+
+impl Tool for FetchManyTool {
+    const NAME: &'static str = "fetch_many";
+    type Error = AppError;
+    type Args = FetchManyArgs;
+    type Output = BTreeMap<String, String>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "Fetch several web pages at once and return a map of URL to readable text. Same rules as fetch_url; use this instead of repeated fetch_url calls when you need more than one page (at most {}).",
+                MAX_URLS
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "urls": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "The http(s) URLs to fetch"
+                    },
+                    "raw": {
+                        "type": "boolean",
+                        "description": "Return the raw bodies instead of readable text (default false)"
+                    }
+                },
+                "required": ["urls"]
+            }
+        }))
+        .unwrap_or_else(|e| {
+            error!("Critical error: Failed to create tool definition: {}", e);
+            panic!(
+                "Invalid static tool definition - this is a programming error: {}",
+                e
+            );
+        })
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.fetch_many(args.urls, args.raw).await
+    }
+}
@@ -0,0 +1,67 @@
+use crate::environment::Environment;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use std::sync::OnceLock;
+use tracing::*;
+
+/// Redirects followed within the portfolio host before giving up.
+const MAX_REDIRECTS: usize = 10;
+
+static PORTFOLIO_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Client for requests to the portfolio site, shared by `web_search` and the crawler.
+///
+/// Sends `HTTP_USER_AGENT` and the `PORTFOLIO_HEADERS` entries with every request.
+/// Those headers may carry credentials such as a CDN bypass token, so redirects that
+/// leave the original host are not followed.
+pub fn portfolio_client() -> &'static reqwest::Client {
+    PORTFOLIO_CLIENT.get_or_init(|| {
+        let env = Environment::global();
+        reqwest::Client::builder()
+            .user_agent(env.http_user_agent.as_str())
+            .default_headers(parse_headers(&env.portfolio_headers))
+            .redirect(Policy::custom(|attempt| {
+                let same_host = attempt
+                    .previous()
+                    .first()
+                    .is_some_and(|first| first.host_str() == attempt.url().host_str());
+                if same_host && attempt.previous().len() <= MAX_REDIRECTS {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            }))
+            .build()
+            .unwrap_or_else(|e| {
+                error!(
+                    "Failed to create portfolio HTTP client, using defaults: {}",
+                    e
+                );
+                reqwest::Client::new()
+            })
+    })
+}
+
+// Parses "Name=value" entries, skipping any that aren't valid HTTP headers
+fn parse_headers(entries: &[String]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for entry in entries {
+        let parsed = entry.split_once('=').and_then(|(name, value)| {
+            let name = HeaderName::from_bytes(name.trim().as_bytes()).ok()?;
+            let mut value = HeaderValue::from_str(value.trim()).ok()?;
+            value.set_sensitive(true);
+            Some((name, value))
+        });
+        match parsed {
+            Some((name, value)) => {
+                headers.insert(name, value);
+            }
+            // The entry may hold a secret, so only its name is logged
+            None => warn!(
+                "Ignoring invalid PORTFOLIO_HEADERS entry (expected Name=value): {}",
+                entry.split('=').next().unwrap_or_default()
+            ),
+        }
+    }
+    headers
+}
@@ -0,0 +1,241 @@
+use crate::environment::Environment;
+use crate::error::AppError;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::*;
+
+/// Results returned when the model doesn't ask for a specific number.
+const DEFAULT_RESULTS: usize = 5;
+
+/// Upper bound on results returned, however many the model asks for.
+const MAX_RESULTS: usize = 10;
+
+/// Time a search API request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Search API backing the internet_search tool.
+#[derive(Debug, Clone, Copy)]
+pub enum SearchProvider {
+    Brave,
+    SerpApi,
+}
+
+impl FromStr for SearchProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "brave" => Ok(SearchProvider::Brave),
+            "serpapi" => Ok(SearchProvider::SerpApi),
+            other => Err(format!(
+                "unknown search provider '{}' (expected brave or serpapi)",
+                other
+            )),
+        }
+    }
+}
+
+/// A single web search hit.
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+#[derive(Deserialize)]
+struct BraveResponse {
+    web: Option<BraveResults>,
+}
+
+#[derive(Deserialize)]
+struct BraveResults {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Deserialize)]
+struct BraveResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct SerpApiResponse {
+    #[serde(default)]
+    organic_results: Vec<SerpApiResult>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SerpApiResult {
+    title: String,
+    link: String,
+    #[serde(default)]
+    snippet: String,
+}
+
+/// Tool for searching the public web through Brave Search or SerpAPI.
+///
+/// Only registered when a search API key is configured, so the agent can answer
+/// questions the portfolio pages don't cover.
+#[derive(Clone)]
+pub struct InternetSearchTool {
+    provider: SearchProvider,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl InternetSearchTool {
+    pub fn new(provider: SearchProvider, api_key: String) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(Environment::global().http_user_agent.as_str())
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        InternetSearchTool {
+            provider,
+            api_key,
+            client,
+        }
+    }
+
+    pub async fn search(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, AppError> {
+        info!("Searching the web via {:?} for: {}", self.provider, query);
+        let results = match self.provider {
+            SearchProvider::Brave => self.search_brave(query, count).await,
+            SearchProvider::SerpApi => self.search_serpapi(query, count).await,
+        }
+        .map_err(|e| {
+            error!("Web search for '{}' failed: {}", query, e);
+            AppError::Tool(format!("Web search failed: {}", e))
+        })?;
+        debug!(
+            "Web search for '{}' returned {} results",
+            query,
+            results.len()
+        );
+        Ok(results)
+    }
+
+    async fn search_brave(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, String> {
+        let response: BraveResponse = self
+            .client
+            .get("https://api.search.brave.com/res/v1/web/search")
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .query(&[("q", query), ("count", count.to_string().as_str())])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(response
+            .web
+            .map(|web| web.results)
+            .unwrap_or_default()
+            .into_iter()
+            .take(count)
+            .map(|result| SearchResult {
+                title: result.title,
+                url: result.url,
+                // Brave highlights matched terms with <strong> tags
+                snippet: result
+                    .description
+                    .replace("<strong>", "")
+                    .replace("</strong>", ""),
+            })
+            .collect())
+    }
+
+    async fn search_serpapi(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, String> {
+        let response: SerpApiResponse = self
+            .client
+            .get("https://serpapi.com/search.json")
+            .query(&[
+                ("engine", "google"),
+                ("q", query),
+                ("num", count.to_string().as_str()),
+                ("api_key", self.api_key.as_str()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // SerpAPI reports some failures (e.g. an exhausted quota) in the body
+        if let Some(error) = response.error {
+            return Err(error);
+        }
+
+        Ok(response
+            .organic_results
+            .into_iter()
+            .take(count)
+            .map(|result| SearchResult {
+                title: result.title,
+                url: result.link,
+                snippet: result.snippet,
+            })
+            .collect())
+    }
+}
+
+/// Arguments for the InternetSearchTool
+#[derive(Deserialize)]
+pub struct InternetSearchArgs {
+    query: String,
+    count: Option<usize>,
+}
+
+impl Tool for InternetSearchTool {
+    const NAME: &'static str = "internet_search";
+    type Error = AppError;
+    type Args = InternetSearchArgs;
+    type Output = Vec<SearchResult>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Search the public web and return result titles, URLs, and snippets. Use for questions the portfolio pages and cluster tools can't answer.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The search query"
+                    },
+                    "count": {
+                        "type": "integer",
+                        "description": "Number of results to return (default 5, max 10)"
+                    }
+                },
+                "required": ["query"]
+            }
+        }))
+        .unwrap_or_else(|e| {
+            error!("Critical error: Failed to create tool definition: {}", e);
+            panic!(
+                "Invalid static tool definition - this is a programming error: {}",
+                e
+            );
+        })
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let count = args.count.unwrap_or(DEFAULT_RESULTS).clamp(1, MAX_RESULTS);
+        self.search(&args.query, count).await
+    }
+}
@@ -0,0 +1,175 @@
+use crate::sessions::{SessionKey, SessionStore};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::*;
+
+tokio::task_local! {
+    /// Session the chat request currently being processed belongs to.
+    static CURRENT_SESSION: SessionKey;
+}
+
+/// Runs `future` with `session` as the session the memory tools read and write.
+pub async fn session_scope<F: Future>(session: Option<SessionKey>, future: F) -> F::Output {
+    match session {
+        Some(session) => CURRENT_SESSION.scope(session, future).await,
+        None => future.await,
+    }
+}
+
+/// The session of the chat request currently being processed, if it has one.
+pub fn current_session_key() -> Option<SessionKey> {
+    CURRENT_SESSION.try_with(|session| session.clone()).ok()
+}
+
+fn current_session() -> Result<SessionKey, MemoryError> {
+    current_session_key().ok_or(MemoryError::NoSession)
+}
+
+/// Error type for memory tool failures
+#[derive(Debug)]
+pub enum MemoryError {
+    /// The request has no session to store facts in
+    NoSession,
+    /// The model tried to remember an empty string
+    EmptyFact,
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::NoSession => write!(
+                f,
+                "Memory is only available in conversations with a session_id"
+            ),
+            MemoryError::EmptyFact => write!(f, "Cannot remember an empty fact"),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+#[derive(Deserialize)]
+pub struct RememberArgs {
+    fact: String,
+}
+
+/// Lets the model persist a fact about the user or conversation for later turns.
+pub struct RememberTool {
+    sessions: Arc<SessionStore>,
+}
+
+impl RememberTool {
+    pub fn new(sessions: Arc<SessionStore>) -> Self {
+        RememberTool { sessions }
+    }
+}
+
+impl Tool for RememberTool {
+    const NAME: &'static str = "remember";
+    type Error = MemoryError;
+    type Args = RememberArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "remember".to_string(),
+            description: "Save a short fact learned during this conversation (e.g. the user's name, or that they care about pod restarts) so it can be recalled in later turns.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "fact": {
+                        "type": "string",
+                        "description": "The fact to remember, as a self-contained sentence"
+                    }
+                },
+                "required": ["fact"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let session = current_session()?;
+        let fact = args.fact.trim();
+        if fact.is_empty() {
+            return Err(MemoryError::EmptyFact);
+        }
+
+        debug!("Remembering fact for session {}", session);
+        self.sessions.remember(&session, fact.to_string());
+        Ok(format!("Remembered: {}", fact))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecallArgs {
+    query: Option<String>,
+}
+
+/// Lets the model retrieve facts saved with [`RememberTool`] earlier in the session.
+pub struct RecallTool {
+    sessions: Arc<SessionStore>,
+}
+
+impl RecallTool {
+    pub fn new(sessions: Arc<SessionStore>) -> Self {
+        RecallTool { sessions }
+    }
+}
+
+impl Tool for RecallTool {
+    const NAME: &'static str = "recall";
+    type Error = MemoryError;
+    type Args = RecallArgs;
+    type Output = Vec<String>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "recall".to_string(),
+            description: "Retrieve facts remembered earlier in this conversation. Optionally filter by keywords.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Optional keywords; only facts containing at least one of them are returned"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let session = current_session()?;
+        let memories = self.sessions.memories(&session);
+
+        let keywords = args
+            .query
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        if keywords.is_empty() {
+            return Ok(memories);
+        }
+
+        let matches = memories
+            .into_iter()
+            .filter(|fact| {
+                let fact = fact.to_lowercase();
+                keywords.iter().any(|keyword| fact.contains(keyword))
+            })
+            .collect::<Vec<_>>();
+        debug!(
+            "Recalled {} facts for session {} matching {:?}",
+            matches.len(),
+            session,
+            keywords
+        );
+        Ok(matches)
+    }
+}
@@ -1,3 +1,23 @@
+pub mod ci_status;
+pub mod contact;
+pub mod crawl;
+pub mod extract;
+pub mod fetch;
+pub mod fetch_many;
+pub mod http;
+pub mod internet_search;
+pub mod memory;
+pub mod page_cache;
+pub mod portfolio_search;
 pub mod web_search;
 
-pub use web_search::{ProfileUrlList, WebSearch};
+pub use ci_status::CiStatusTool;
+pub use contact::{ContactMessageTool, MailProvider};
+pub use crawl::{PortfolioCrawler, PortfolioIndex};
+pub use fetch::FetchUrlTool;
+pub use fetch_many::FetchManyTool;
+pub use internet_search::{InternetSearchTool, SearchProvider};
+pub use memory::{RecallTool, RememberTool};
+pub use page_cache::PageCache;
+pub use portfolio_search::SearchPortfolioTool;
+pub use web_search::{PortfolioPage, ProfileUrlList, WebSearch};
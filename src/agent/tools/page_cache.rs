@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Pages kept at most; the least recently fetched is evicted first.
+const MAX_ENTRIES: usize = 200;
+
+/// A fetched response body as the web tools need it to answer again.
+#[derive(Clone)]
+pub struct CachedPage {
+    pub body: String,
+    /// Whether the response was HTML, so text extraction applies
+    pub is_html: bool,
+    /// Whether the body was cut off at the tool's size limit
+    pub truncated: bool,
+}
+
+struct Entry {
+    page: CachedPage,
+    /// The response's `Last-Modified` header, sent back as `If-Modified-Since`
+    last_modified: Option<String>,
+    fetched_at: Instant,
+}
+
+/// Cache of fetched pages keyed by URL, shared by `web_search` and `fetch_url`.
+///
+/// Pages younger than the TTL are served without a request. Older ones that carried a
+/// `Last-Modified` header are revalidated with `If-Modified-Since`, so an unchanged page
+/// costs a 304 instead of a full download. A TTL of zero disables caching.
+pub struct PageCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl PageCache {
+    pub fn new(ttl: Duration) -> Self {
+        PageCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached page for `url` if it is younger than the TTL.
+    pub fn fresh(&self, url: &str) -> Option<CachedPage> {
+        let entries = self.entries.lock().unwrap();
+        let page = entries
+            .get(url)
+            .filter(|entry| entry.fetched_at.elapsed() < self.ttl)
+            .map(|entry| entry.page.clone());
+        if page.is_some() {
+            debug!("Serving {} from the page cache", url);
+        }
+        page
+    }
+
+    /// The `Last-Modified` value to revalidate a stale copy of `url` with, if any.
+    pub fn last_modified(&self, url: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(url)?.last_modified.clone()
+    }
+
+    /// Marks the cached copy of `url` fresh again after a 304 Not Modified and returns it.
+    pub fn revalidated(&self, url: &str) -> Option<CachedPage> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(url)?;
+        debug!("{} not modified, reusing the cached copy", url);
+        entry.fetched_at = Instant::now();
+        Some(entry.page.clone())
+    }
+
+    /// Stores a freshly fetched page, evicting the oldest entry when full.
+    pub fn insert(&self, url: &str, page: CachedPage, last_modified: Option<String>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(url) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.fetched_at)
+                .map(|(url, _)| url.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            url.to_string(),
+            Entry {
+                page,
+                last_modified,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use super::crawl::PortfolioIndex;
+use crate::error::AppError;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::*;
+
+/// Passages returned when the model doesn't ask for a specific number.
+const DEFAULT_RESULTS: usize = 5;
+
+/// Upper bound on passages returned, however many the model asks for.
+const MAX_RESULTS: usize = 10;
+
+/// Tool for retrieving the portfolio passages most relevant to a question.
+///
+/// Searches the index kept by the portfolio crawler, so the model can find facts
+/// spread across pages without fetching each one.
+pub struct SearchPortfolioTool {
+    index: PortfolioIndex,
+}
+
+impl SearchPortfolioTool {
+    pub fn new(index: PortfolioIndex) -> Self {
+        SearchPortfolioTool { index }
+    }
+
+    pub fn search_portfolio(&self, query: &str, limit: Option<usize>) -> String {
+        let Some((pages, built_at)) = self.index.built() else {
+            return "The portfolio index is still being built; try again shortly or use web_search to read a page directly.".to_string();
+        };
+
+        let limit = limit.unwrap_or(DEFAULT_RESULTS).clamp(1, MAX_RESULTS);
+        let passages = self.index.search(query, limit);
+        debug!(
+            "Portfolio search for '{}' matched {} passages",
+            query,
+            passages.len()
+        );
+        if passages.is_empty() {
+            return format!(
+                "No passages matching '{}' in the {} indexed portfolio pages.",
+                query, pages
+            );
+        }
+
+        let mut output = format!(
+            "{} passages from {} portfolio pages (indexed {}):\n",
+            passages.len(),
+            pages,
+            built_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        for passage in passages {
+            output.push_str(&format!(
+                "\n## {} ({})\n{}\n",
+                passage.title, passage.url, passage.text
+            ));
+        }
+        output
+    }
+}
+
+/// Arguments for the SearchPortfolioTool
+#[derive(Deserialize)]
+pub struct SearchPortfolioArgs {
+    query: String,
+    limit: Option<usize>,
+}
+
+impl Tool for SearchPortfolioTool {
+    const NAME: &'static str = "search_portfolio";
+    type Error = AppError;
+    type Args = SearchPortfolioArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Search the text of every page on the portfolio site and return the most relevant passages with their page URLs. Prefer this for questions about experience, projects, or skills; use web_search to read a whole page.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Keywords to search for, e.g. 'rust kubernetes projects'"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of passages to return (default 5, max 10)"
+                    }
+                },
+                "required": ["query"]
+            }
+        }))
+        .unwrap_or_else(|e| {
+            error!("Critical error: Failed to create tool definition: {}", e);
+            panic!(
+                "Invalid static tool definition - this is a programming error: {}",
+                e
+            );
+        })
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        Ok(self.search_portfolio(&args.query, args.limit))
+    }
+}
@@ -1,130 +1,148 @@
-use crate::environment::Environment;
+use super::extract::html_to_text;
+use super::http::portfolio_client;
+use super::page_cache::{CachedPage, PageCache};
+use crate::error::AppError;
+use reqwest::header::{IF_MODIFIED_SINCE, LAST_MODIFIED};
+use reqwest::StatusCode;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
-use serde::de::{self, Visitor};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::error::Error;
-use std::fmt;
+use std::sync::Arc;
 use tracing::*;
 
-/// Valid URLs for the portfolio site sections.
-#[derive(Debug, Clone)]
-pub enum ProfileUrl {
-    About,
-    Work,
-    Projects,
-    Contact,
+/// A portfolio site page the web tools may fetch.
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioPage {
+    /// Short name shown to the model, e.g. "About"
+    pub label: String,
+    pub url: String,
 }
 
-fn get_portfolio_host() -> String {
-    let env = Environment::new();
-    if env.production_mode {
-        "https://about.calum.run".to_string()
-    } else {
-        "http://localhost:3000".to_string()
+impl PortfolioPage {
+    /// Builds the page list from a base URL and `label=path` entries, e.g.
+    /// `About=/?tab=About`. Invalid entries are skipped with a warning.
+    pub fn from_config(base_url: &str, entries: &[String]) -> Vec<PortfolioPage> {
+        let base_url = base_url.trim_end_matches('/');
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let Some((label, path)) = entry.split_once('=') else {
+                    warn!(
+                        "Ignoring invalid portfolio page (expected label=path): {}",
+                        entry
+                    );
+                    return None;
+                };
+                let path = path.trim();
+                let separator = if path.starts_with('/') { "" } else { "/" };
+                Some(PortfolioPage {
+                    label: label.trim().to_string(),
+                    url: format!("{}{}{}", base_url, separator, path),
+                })
+            })
+            .collect()
     }
 }
 
-impl ProfileUrl {
-    pub fn as_url(&self) -> String {
-        let host = get_portfolio_host();
-        match self {
-            ProfileUrl::About => format!("{}/?tab=About", host),
-            ProfileUrl::Work => format!("{}/?tab=Work", host),
-            ProfileUrl::Projects => format!("{}/?tab=Projects", host),
-            ProfileUrl::Contact => format!("{}/?tab=Contact", host),
-        }
-    }
+/// Arguments for the WebSearch tool
+#[derive(Deserialize)]
+pub struct WebSearchArgs {
+    url: String,
+    /// Return the page's HTML as fetched instead of extracted text
+    #[serde(default)]
+    raw: bool,
 }
 
-impl fmt::Display for ProfileUrl {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_url())
-    }
+/// Tool for fetching content from portfolio website sections.
+///
+/// Only the configured portfolio pages can be fetched; their URLs are listed in the
+/// tool schema so the model can pick one directly.
+pub struct WebSearch {
+    pages: Vec<PortfolioPage>,
+    cache: Arc<PageCache>,
 }
 
-struct ProfileUrlVisitor;
+impl WebSearch {
+    pub fn new(pages: Vec<PortfolioPage>, cache: Arc<PageCache>) -> Self {
+        WebSearch { pages, cache }
+    }
 
-impl<'de> Visitor<'de> for ProfileUrlVisitor {
-    type Value = ProfileUrl;
+    /// The page's body, from the cache when it is fresh or unchanged since it was cached.
+    async fn fetch_page(&self, url: &str) -> Result<String, AppError> {
+        if let Some(page) = self.cache.fresh(url) {
+            return Ok(page.body);
+        }
 
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a valid URL string")
-    }
+        info!("Fetching web content from: {}", url);
 
-    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        match value {
-            "https://about.calum.run/?tab=About" | "http://localhost:3000/?tab=About" => {
-                Ok(ProfileUrl::About)
-            }
-            "https://about.calum.run/?tab=Work" | "http://localhost:3000/?tab=Work" => {
-                Ok(ProfileUrl::Work)
-            }
-            "https://about.calum.run/?tab=Projects" | "http://localhost:3000/?tab=Projects" => {
-                Ok(ProfileUrl::Projects)
-            }
-            "https://about.calum.run/?tab=Contact" | "http://localhost:3000/?tab=Contact" => {
-                Ok(ProfileUrl::Contact)
-            }
-            _ => Err(de::Error::unknown_variant(
-                value,
-                &[
-                    "https://about.calum.run/?tab=About",
-                    "https://about.calum.run/?tab=Work",
-                    "https://about.calum.run/?tab=Projects",
-                    "https://about.calum.run/?tab=Contact",
-                    "http://localhost:3000/?tab=About",
-                    "http://localhost:3000/?tab=Work",
-                    "http://localhost:3000/?tab=Projects",
-                    "http://localhost:3000/?tab=Contact",
-                ],
-            )),
+        let mut request = portfolio_client().get(url);
+        if let Some(since) = self.cache.last_modified(url) {
+            request = request.header(IF_MODIFIED_SINCE, since);
         }
-    }
-}
+        let response = request.send().await.map_err(|e| {
+            error!("Error fetching URL {}: {}", url, e);
 
-impl<'de> Deserialize<'de> for ProfileUrl {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_str(ProfileUrlVisitor)
-    }
-}
+            let mut source = e.source();
+            while let Some(err) = source {
+                error!("  caused by: {}", err);
+                source = err.source();
+            }
 
-/// Arguments for the WebSearch tool
-#[derive(Deserialize)]
-pub struct WebSearchArgs {
-    url: ProfileUrl,
-}
+            AppError::Tool(e.to_string())
+        })?;
 
-/// Tool for fetching content from portfolio website sections.
-#[derive(Deserialize, Serialize)]
-pub struct WebSearch;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(page) = self.cache.revalidated(url) {
+                return Ok(page.body);
+            }
+        }
 
-/// Error type for tool execution failures
-#[derive(Debug)]
-pub struct ModelError(String);
+        // Error pages are passed through as before but never cached
+        let cacheable = response.status().is_success();
+        let last_modified = response
+            .headers()
+            .get(LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await.map_err(|e| {
+            error!("Error reading response body: {}", e);
+            AppError::Tool(e.to_string())
+        })?;
 
-impl fmt::Display for ModelError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        debug!(
+            "Successfully fetched web page content ({} bytes)",
+            body.len()
+        );
+
+        if cacheable {
+            let page = CachedPage {
+                body: body.clone(),
+                is_html: true,
+                truncated: false,
+            };
+            self.cache.insert(url, page, last_modified);
+        }
+        Ok(body)
     }
 }
 
-impl Error for ModelError {}
-
 impl Tool for WebSearch {
     const NAME: &'static str = "web_search";
-    type Error = ModelError;
+    type Error = AppError;
     type Args = WebSearchArgs;
     type Output = String;
 
     async fn definition(&self, _prompt: String) -> ToolDefinition {
+        let urls: Vec<&str> = self.pages.iter().map(|page| page.url.as_str()).collect();
+        let pages = self
+            .pages
+            .iter()
+            .map(|page| format!("{} ({})", page.label, page.url))
+            .collect::<Vec<_>>()
+            .join(", ");
+
         serde_json::from_value(json!({
             "name": "web_search",
             "description": "search the web for information about the user",
@@ -133,7 +151,12 @@ impl Tool for WebSearch {
                 "properties": {
                     "url": {
                         "type": "string",
-                        "description": "url to search"
+                        "enum": urls,
+                        "description": format!("Portfolio page to fetch: {}", pages)
+                    },
+                    "raw": {
+                        "type": "boolean",
+                        "description": "Return the raw HTML instead of readable text (default false; only needed to inspect markup)"
                     }
                 },
                 "required": ["url"]
@@ -149,36 +172,39 @@ impl Tool for WebSearch {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        info!("Fetching web content from: {}", args.url);
-
-        let response = reqwest::get(args.url.as_url()).await.map_err(|e| {
-            error!("Error fetching URL {}: {}", args.url, e);
-
-            let mut source = e.source();
-            while let Some(err) = source {
-                error!("  caused by: {}", err);
-                source = err.source();
-            }
-
-            ModelError(e.to_string())
-        })?;
-
-        let body = response.text().await.map_err(|e| {
-            error!("Error reading response body: {}", e);
-            ModelError(e.to_string())
-        })?;
-
-        debug!(
-            "Successfully fetched web page content ({} bytes)",
-            body.len()
-        );
+        if !self.pages.iter().any(|page| page.url == args.url) {
+            warn!("Refusing to fetch unlisted URL: {}", args.url);
+            return Err(AppError::Tool(format!(
+                "Unknown portfolio URL '{}' (expected one of {})",
+                args.url,
+                self.pages
+                    .iter()
+                    .map(|page| page.url.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
 
-        Ok(body)
+        let body = self.fetch_page(&args.url).await?;
+        if args.raw {
+            return Ok(body);
+        }
+        let text = html_to_text(&body);
+        debug!("Extracted {} bytes of text from the page", text.len());
+        Ok(text)
     }
 }
 
 /// Tool for listing available portfolio URLs.
-pub struct ProfileUrlList;
+pub struct ProfileUrlList {
+    pages: Vec<PortfolioPage>,
+}
+
+impl ProfileUrlList {
+    pub fn new(pages: Vec<PortfolioPage>) -> Self {
+        ProfileUrlList { pages }
+    }
+}
 
 /// Arguments for the ProfileUrlList tool (no arguments required)
 #[derive(Debug, Deserialize)]
@@ -186,9 +212,9 @@ pub struct ProfileUrlListArgs {}
 
 impl Tool for ProfileUrlList {
     const NAME: &'static str = "profile_url_list";
-    type Error = ModelError;
+    type Error = AppError;
     type Args = ProfileUrlListArgs;
-    type Output = Vec<String>;
+    type Output = Vec<PortfolioPage>;
 
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         serde_json::from_value(json!({
@@ -210,15 +236,8 @@ impl Tool for ProfileUrlList {
     }
 
     async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
-        debug!("Providing list of profile URLs");
-        let result = vec![
-            ProfileUrl::About.as_url(),
-            ProfileUrl::Work.as_url(),
-            ProfileUrl::Projects.as_url(),
-            ProfileUrl::Contact.as_url(),
-        ];
-        debug!("Providing profile URL list: {:?}", result);
-
-        Ok(result)
+        debug!("Providing profile URL list: {:?}", self.pages);
+
+        Ok(self.pages.clone())
     }
 }
@@ -0,0 +1,245 @@
+use crate::error::AppError;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::*;
+
+/// An object-safe tool the agent can call, so tools of different types can be kept in
+/// one [`ToolSet`] and registered at runtime.
+///
+/// Implemented for every rig [`Tool`]; implement it directly for tools whose name or
+/// schema is only known at runtime (e.g. loaded from a plugin manifest).
+pub trait AgentTool: Send + Sync + 'static {
+    /// Name the model calls the tool by; also keys output budgets and permissions.
+    fn name(&self) -> String;
+
+    /// Describes the tool and its JSON arguments to the model.
+    fn definition<'a>(
+        &'a self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + 'a>>;
+
+    /// Runs the tool with the arguments the model passed.
+    fn call<'a>(
+        &'a self,
+        args: Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, AppError>> + Send + 'a>>;
+}
+
+impl<T: Tool + 'static> AgentTool for T {
+    fn name(&self) -> String {
+        Tool::name(self)
+    }
+
+    fn definition<'a>(
+        &'a self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + 'a>> {
+        Box::pin(Tool::definition(self, prompt))
+    }
+
+    fn call<'a>(
+        &'a self,
+        args: Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, AppError>> + Send + 'a>> {
+        Box::pin(async move {
+            let args: T::Args = serde_json::from_value(args)
+                .map_err(|e| AppError::Tool(format!("Invalid tool arguments: {}", e)))?;
+            let output = Tool::call(self, args)
+                .await
+                .map_err(|e| AppError::Tool(e.to_string()))?;
+            serde_json::to_value(output)
+                .map_err(|e| AppError::Tool(format!("Unserializable tool output: {}", e)))
+        })
+    }
+}
+
+/// Tools to give the agent on top of its built-in ones, in registration order.
+///
+/// Registering a tool under a name already in the set replaces the earlier one.
+/// Tools whose names aren't in the permission lists need the admin capability.
+#[derive(Clone, Default)]
+pub struct ToolSet {
+    tools: Vec<Arc<dyn AgentTool>>,
+}
+
+// Only embedders and tests register tools; the server adds none of its own
+#[cfg_attr(not(test), allow(dead_code))]
+impl ToolSet {
+    pub fn new() -> Self {
+        ToolSet::default()
+    }
+
+    /// Adds `tool`, replacing any tool already registered under its name.
+    pub fn register(&mut self, tool: Arc<dyn AgentTool>) {
+        let name = tool.name();
+        match self
+            .tools
+            .iter_mut()
+            .find(|existing| existing.name() == name)
+        {
+            Some(existing) => {
+                warn!("Replacing registered tool {}", name);
+                *existing = tool;
+            }
+            None => self.tools.push(tool),
+        }
+    }
+
+    /// Adds `tool`; see [`ToolSet::register`].
+    pub fn with(mut self, tool: impl AgentTool) -> Self {
+        self.register(Arc::new(tool));
+        self
+    }
+
+    /// Adds every tool of `other`, which win over tools of the same name.
+    pub fn extend(&mut self, other: ToolSet) {
+        for tool in other.tools {
+            self.register(tool);
+        }
+    }
+
+    /// Names of the registered tools.
+    pub fn names(&self) -> Vec<String> {
+        self.tools.iter().map(|tool| tool.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn AgentTool>> {
+        self.tools.iter()
+    }
+}
+
+/// Adapts a registered [`AgentTool`] to rig's [`Tool`], so it can be traced,
+/// budgeted, and handed to a rig agent like the built-in tools.
+#[derive(Clone)]
+pub struct Registered(pub Arc<dyn AgentTool>);
+
+impl Tool for Registered {
+    // rig looks tools up by `name()`, which comes from the wrapped tool
+    const NAME: &'static str = "registered_tool";
+    type Args = Value;
+    type Output = Value;
+    type Error = AppError;
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    async fn definition(&self, prompt: String) -> ToolDefinition {
+        self.0.definition(prompt).await
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.0.call(args).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::convert::Infallible;
+
+    /// Answers every call with `reply`.
+    struct Canned {
+        name: &'static str,
+        reply: &'static str,
+    }
+
+    impl Tool for Canned {
+        const NAME: &'static str = "canned";
+        type Args = Value;
+        type Output = String;
+        type Error = Infallible;
+
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: self.name.to_string(),
+                description: "Replies with a fixed string".to_string(),
+                parameters: json!({ "type": "object", "properties": {} }),
+            }
+        }
+
+        async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Ok(self.reply.to_string())
+        }
+    }
+
+    async fn call(tools: &ToolSet, name: &str) -> Value {
+        let tool = tools.iter().find(|tool| tool.name() == name).unwrap();
+        tool.call(json!({})).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn later_registrations_replace_tools_of_the_same_name() {
+        assert!(ToolSet::new().is_empty());
+        let mut tools = ToolSet::new()
+            .with(Canned {
+                name: "status",
+                reply: "first",
+            })
+            .with(Canned {
+                name: "uptime",
+                reply: "up",
+            });
+        tools.register(Arc::new(Canned {
+            name: "status",
+            reply: "second",
+        }));
+        assert_eq!(tools.names(), ["status", "uptime"]);
+        assert_eq!(call(&tools, "status").await, "second");
+
+        tools.extend(ToolSet::new().with(Canned {
+            name: "uptime",
+            reply: "extended",
+        }));
+        assert_eq!(tools.len(), 2);
+        assert_eq!(call(&tools, "uptime").await, "extended");
+    }
+
+    #[tokio::test]
+    async fn rejects_arguments_that_dont_match_the_tool() {
+        struct Typed;
+
+        impl Tool for Typed {
+            const NAME: &'static str = "typed";
+            type Args = u32;
+            type Output = u32;
+            type Error = Infallible;
+
+            async fn definition(&self, _prompt: String) -> ToolDefinition {
+                ToolDefinition {
+                    name: Self::NAME.to_string(),
+                    description: "Doubles a number".to_string(),
+                    parameters: json!({ "type": "integer" }),
+                }
+            }
+
+            async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+                Ok(args * 2)
+            }
+        }
+
+        let tool: Arc<dyn AgentTool> = Arc::new(Typed);
+        assert_eq!(tool.call(json!(21)).await.unwrap(), json!(42));
+        assert!(matches!(
+            tool.call(json!("twenty-one")).await,
+            Err(AppError::Tool(_))
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use super::permissions::{self, Capability};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::*;
+
+/// Maximum number of characters of a tool result kept in the trace.
+const MAX_RESULT_CHARS: usize = 500;
+
+tokio::task_local! {
+    /// Trace collector for the chat request currently being processed.
+    static CURRENT_TRACE: ToolTrace;
+}
+
+/// A single tool invocation made by the agent while answering a request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ToolCallRecord {
+    /// Tool name as exposed to the model
+    pub tool: String,
+    /// Arguments the model called the tool with
+    pub args: serde_json::Value,
+    /// Wall-clock duration of the call
+    pub duration_ms: u64,
+    /// Whether the tool returned successfully
+    pub success: bool,
+    /// Tool output (or error message), truncated
+    pub result: String,
+}
+
+/// Progress of a tool call, sent to a [`ToolTrace`]'s listener.
+#[derive(Debug, Clone)]
+pub enum ToolEvent {
+    /// A tool is about to run
+    Started {
+        tool: String,
+        args: serde_json::Value,
+    },
+    /// A tool call completed, or was refused or planned without running
+    Finished(ToolCallRecord),
+}
+
+/// Result recorded for tool calls skipped in dry-run mode.
+const DRY_RUN_RESULT: &str = "not executed (dry run)";
+
+/// Collects the tool calls made while processing one chat request.
+#[derive(Debug, Clone, Default)]
+pub struct ToolTrace {
+    records: Arc<Mutex<Vec<ToolCallRecord>>>,
+    /// Calls that actually ran the tool, as opposed to being refused or planned
+    executed: Arc<AtomicUsize>,
+    /// Executed calls of tools that change cluster state
+    writes: Arc<AtomicUsize>,
+    /// When set, tool calls are recorded but not executed
+    dry_run: bool,
+    /// Receives each call as it starts and completes
+    listener: Option<UnboundedSender<ToolEvent>>,
+}
+
+impl ToolTrace {
+    pub fn new() -> Self {
+        ToolTrace::default()
+    }
+
+    /// Creates a trace that records tool calls without executing them, so the
+    /// request produces a plan of what the agent would have done.
+    pub fn dry_run() -> Self {
+        ToolTrace {
+            dry_run: true,
+            ..ToolTrace::default()
+        }
+    }
+
+    /// Also sends every call to `listener` as it starts and completes, so callers can
+    /// stream progress while the request is still running.
+    pub fn with_listener(mut self, listener: UnboundedSender<ToolEvent>) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Runs `future` with this trace installed as the current request's collector.
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        CURRENT_TRACE.scope(self.clone(), future).await
+    }
+
+    /// Returns the tool calls recorded so far, in invocation order.
+    pub fn records(&self) -> Vec<ToolCallRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    fn started(&self, tool: &str, args: &serde_json::Value) {
+        self.executed.fetch_add(1, Ordering::Relaxed);
+        if Capability::for_tool(tool) == Capability::KubeWrite {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(listener) = &self.listener {
+            let _ = listener.send(ToolEvent::Started {
+                tool: tool.to_string(),
+                args: args.clone(),
+            });
+        }
+    }
+
+    fn push(&self, record: ToolCallRecord) {
+        if let Some(listener) = &self.listener {
+            // The listener going away doesn't stop the request
+            let _ = listener.send(ToolEvent::Finished(record.clone()));
+        }
+        self.records.lock().unwrap().push(record);
+    }
+}
+
+/// Number of tool calls the current request has run so far. A failed prompt that ran
+/// tools can't be replayed without running them, and their side effects, again.
+pub fn executed_calls() -> usize {
+    CURRENT_TRACE
+        .try_with(|trace| trace.executed.load(Ordering::Relaxed))
+        .unwrap_or(0)
+}
+
+/// Number of cluster writes (scaling, deleting, cordoning) the current request has run.
+pub fn cluster_writes() -> usize {
+    CURRENT_TRACE
+        .try_with(|trace| trace.writes.load(Ordering::Relaxed))
+        .unwrap_or(0)
+}
+
+/// Truncates a string to at most `max_chars` characters, marking the cut.
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}… [truncated]", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Errors produced by a [`Traced`] tool.
+#[derive(Debug)]
+pub enum TracedToolError<E> {
+    /// The model passed arguments that don't match the tool's schema
+    InvalidArgs(serde_json::Error),
+    /// The wrapped tool failed
+    Tool(E),
+    /// The call was planned but not executed because the request is a dry run
+    DryRun,
+    /// The request's API key lacks the capability the tool needs
+    Forbidden(Capability),
+}
+
+impl<E: fmt::Display> fmt::Display for TracedToolError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TracedToolError::InvalidArgs(err) => write!(f, "Invalid tool arguments: {}", err),
+            TracedToolError::Tool(err) => write!(f, "{}", err),
+            TracedToolError::DryRun => write!(
+                f,
+                "Dry run: the call was recorded but not executed. Describe what you would do next instead of waiting for a result."
+            ),
+            TracedToolError::Forbidden(capability) => write!(
+                f,
+                "Not permitted: this caller's API key lacks the {} capability. Tell the user this isn't available to them.",
+                capability
+            ),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for TracedToolError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TracedToolError::InvalidArgs(err) => Some(err),
+            TracedToolError::Tool(err) => Some(err),
+            TracedToolError::DryRun | TracedToolError::Forbidden(_) => None,
+        }
+    }
+}
+
+/// Wraps a rig tool so every invocation is logged and recorded in the current
+/// request's [`ToolTrace`].
+///
+/// Arguments are accepted as raw JSON so they can be recorded verbatim, then
+/// deserialized into the wrapped tool's argument type. In a dry-run trace the
+/// wrapped tool is never called, nor is it when the request's API key lacks the
+/// capability the tool needs; such tools are also described to the model as unavailable.
+pub struct Traced<T>(pub T);
+
+impl<T> Tool for Traced<T>
+where
+    T: Tool,
+{
+    const NAME: &'static str = T::NAME;
+    type Args = serde_json::Value;
+    type Output = T::Output;
+    type Error = TracedToolError<T::Error>;
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    async fn definition(&self, prompt: String) -> ToolDefinition {
+        let mut definition = self.0.definition(prompt).await;
+        if let Some(capability) = permissions::missing_capability(&self.0.name()) {
+            definition.description = format!(
+                "Unavailable: the caller lacks the {} capability this tool needs. Do not call it.",
+                capability
+            );
+        }
+        definition
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        // Registered tools share a type, so the name comes from the instance
+        let name = self.0.name();
+        if let Some(capability) = permissions::missing_capability(&name) {
+            warn!(
+                "Refused {} call: API key lacks the {} capability",
+                name, capability
+            );
+            let error = TracedToolError::Forbidden(capability);
+            let _ = CURRENT_TRACE.try_with(|trace| {
+                trace.push(ToolCallRecord {
+                    tool: name.clone(),
+                    args,
+                    duration_ms: 0,
+                    success: false,
+                    result: error.to_string(),
+                })
+            });
+            return Err(error);
+        }
+
+        let dry_run = CURRENT_TRACE
+            .try_with(|trace| trace.dry_run)
+            .unwrap_or(false);
+        if dry_run {
+            info!(tool = %name, "Dry run: planned call with args {}", args);
+            let _ = CURRENT_TRACE.try_with(|trace| {
+                trace.push(ToolCallRecord {
+                    tool: name.clone(),
+                    args,
+                    duration_ms: 0,
+                    success: true,
+                    result: DRY_RUN_RESULT.to_string(),
+                })
+            });
+            return Err(TracedToolError::DryRun);
+        }
+
+        info!(tool = %name, "Tool call started with args {}", args);
+        let _ = CURRENT_TRACE.try_with(|trace| trace.started(&name, &args));
+        let started = Instant::now();
+
+        let result = match serde_json::from_value::<T::Args>(args.clone()) {
+            Ok(parsed) => self.0.call(parsed).await.map_err(TracedToolError::Tool),
+            Err(e) => Err(TracedToolError::InvalidArgs(e)),
+        };
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (success, summary) = match &result {
+            Ok(output) => (true, serde_json::to_string(output).unwrap_or_default()),
+            Err(e) => (false, e.to_string()),
+        };
+
+        if success {
+            info!(tool = %name, duration_ms, "Tool call succeeded");
+        } else {
+            warn!(tool = %name, duration_ms, "Tool call failed: {}", summary);
+        }
+
+        let record = ToolCallRecord {
+            tool: name.clone(),
+            args,
+            duration_ms,
+            success,
+            result: truncate_chars(&summary, MAX_RESULT_CHARS),
+        };
+        if CURRENT_TRACE.try_with(|trace| trace.push(record)).is_err() {
+            debug!(tool = %name, "Tool called outside of a traced request");
+        }
+
+        result
+    }
+}
@@ -0,0 +1,282 @@
+use crate::agent::builder::AgentBuilder;
+use crate::agent::mock::MockCompletionModel;
+use crate::environment::Environment;
+use crate::kube::KubeAgent;
+use crate::server::admission::Admission;
+use crate::server::auth::ApiKeys;
+use crate::server::health::DeepHealthCheck;
+use crate::server::limits::RequestLimits;
+use crate::server::{Server, ServerHandle};
+use crate::sessions::SessionStore;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// API key of the in-process server.
+const BENCH_API_KEY: &str = "bench-key";
+
+/// Answer of the mocked model.
+const MOCK_REPLY: &str = "All pods in the default namespace are running.";
+
+/// Prompts cycled through by the synthetic requests.
+const PROMPTS: &[&str] = &[
+    "Which pods are crash looping?",
+    "How much memory are the nodes using?",
+    "Summarize the warning events from the last hour.",
+    "Is the api deployment healthy?",
+];
+
+/// Time a single chat request may take before it counts as an error.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a soak run logs its progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Settings of a bench run.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Requests to send in total (ignored when `duration` is set)
+    pub requests: usize,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Keep sending requests for this long instead of a fixed number (a soak run)
+    pub duration: Option<Duration>,
+    /// Delay of the mocked model before each reply
+    pub model_latency: Duration,
+    /// Bench a running server at this URL instead of an in-process one
+    pub url: Option<String>,
+}
+
+/// Result of one synthetic request.
+struct Sample {
+    latency: Duration,
+    /// None on success, otherwise the status code or transport error
+    error: Option<String>,
+}
+
+/// Runs the bench and prints its report to stdout.
+///
+/// Without a URL, the server is started in-process on an ephemeral port over a mocked
+/// model, so the numbers measure the server and agent rather than OpenAI. With a URL,
+/// requests authenticate with `CHAT_API_KEY`.
+pub async fn run_cli(options: BenchOptions) -> Result<(), Box<dyn Error>> {
+    let env = Environment::global();
+    let (url, api_key, server) = match &options.url {
+        Some(url) => (
+            url.trim_end_matches('/').to_string(),
+            env.chat_api_key.clone(),
+            None,
+        ),
+        None => {
+            let server = start_server(options.model_latency).await?;
+            (
+                format!("http://{}", server.addr()),
+                BENCH_API_KEY.to_string(),
+                Some(server),
+            )
+        }
+    };
+
+    info!(
+        "Benchmarking {}/chat with {} concurrent clients",
+        url, options.concurrency
+    );
+    let started = Instant::now();
+    let samples = fire(&url, &api_key, &options).await?;
+    let report = report(&samples, started.elapsed());
+    println!("{}", report);
+
+    if let Some(server) = server {
+        server.shutdown().await?;
+    }
+    Ok(())
+}
+
+/// Starts a server over a mocked model on an ephemeral localhost port.
+async fn start_server(model_latency: Duration) -> Result<ServerHandle, Box<dyn Error>> {
+    let env = Environment::global();
+    let sessions = Arc::new(SessionStore::from_env(env));
+    let model = MockCompletionModel::always(MOCK_REPLY).with_latency(model_latency);
+    let agent = AgentBuilder::new(sessions.clone())
+        .model("mock", model)
+        .build()
+        .map(Arc::new)?;
+
+    // The deep health check is never requested, so it needs no real credentials
+    let health = DeepHealthCheck::new(
+        String::new(),
+        KubeAgent::new(
+            env.kube_api_server.clone(),
+            env.kube_token.clone(),
+            env.kube_certificate.clone(),
+        ),
+    );
+    let admission = Admission::new(
+        agent.clone(),
+        ApiKeys::new(BENCH_API_KEY, None, None)?,
+        None,
+    );
+    let server = Server::new(
+        agent,
+        "127.0.0.1:0".to_string(),
+        Arc::new(admission),
+        RequestLimits {
+            max_prompt_chars: env.max_prompt_chars,
+            max_prompt_tokens: env.max_prompt_tokens,
+            max_history_chars: env.max_history_chars,
+            max_history_tokens: env.max_history_tokens,
+            max_history_messages: env.max_history_messages,
+            max_response_chars: env.max_response_chars,
+        },
+        sessions,
+        health,
+    );
+    Ok(Arc::new(server).start().await?)
+}
+
+/// Sends the synthetic requests from `options.concurrency` clients and collects the results.
+async fn fire(
+    url: &str,
+    api_key: &str,
+    options: &BenchOptions,
+) -> Result<Vec<Sample>, Box<dyn Error>> {
+    // The server closes each connection after responding, so pooled connections are useless
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .pool_max_idle_per_host(0)
+        .build()?;
+    let deadline = options.duration.map(|duration| Instant::now() + duration);
+    let sent = Arc::new(AtomicUsize::new(0));
+
+    let mut workers = Vec::with_capacity(options.concurrency);
+    for _ in 0..options.concurrency.max(1) {
+        let client = client.clone();
+        let endpoint = format!("{}/chat", url);
+        let api_key = api_key.to_string();
+        let sent = sent.clone();
+        let requests = options.requests;
+        workers.push(tokio::spawn(async move {
+            let mut samples = Vec::new();
+            let mut last_progress = Instant::now();
+            loop {
+                let n = sent.fetch_add(1, Ordering::Relaxed);
+                match deadline {
+                    Some(deadline) if Instant::now() >= deadline => break,
+                    None if n >= requests => break,
+                    _ => {}
+                }
+
+                let body = json!({ "prompt": PROMPTS[n % PROMPTS.len()] });
+                let started = Instant::now();
+                let result = client
+                    .post(&endpoint)
+                    .header("X-API-Key", &api_key)
+                    .json(&body)
+                    .send()
+                    .await;
+                let error = match result {
+                    Ok(response) => {
+                        let status = response.status();
+                        // Read the body so the latency covers the whole response
+                        match response.bytes().await {
+                            Ok(_) if status.is_success() => None,
+                            Ok(_) => Some(format!("HTTP {}", status.as_u16())),
+                            Err(e) => Some(transport_error(&e)),
+                        }
+                    }
+                    Err(e) => Some(transport_error(&e)),
+                };
+                samples.push(Sample {
+                    latency: started.elapsed(),
+                    error,
+                });
+
+                if deadline.is_some() && last_progress.elapsed() >= PROGRESS_INTERVAL {
+                    info!("{} requests sent", sent.load(Ordering::Relaxed));
+                    last_progress = Instant::now();
+                }
+            }
+            samples
+        }));
+    }
+
+    let mut samples = Vec::new();
+    for worker in workers {
+        samples.extend(worker.await?);
+    }
+    Ok(samples)
+}
+
+/// Short name of a failed request's cause, for grouping errors in the report.
+fn transport_error(error: &reqwest::Error) -> String {
+    if error.is_timeout() {
+        "timeout".to_string()
+    } else if error.is_connect() {
+        "connection refused".to_string()
+    } else {
+        "connection error".to_string()
+    }
+}
+
+/// Formats throughput, error rate, and latency percentiles of a run.
+fn report(samples: &[Sample], elapsed: Duration) -> String {
+    let mut output = String::new();
+    let total = samples.len();
+    let failed: Vec<&Sample> = samples
+        .iter()
+        .filter(|sample| sample.error.is_some())
+        .collect();
+
+    let _ = writeln!(
+        output,
+        "Requests:    {} in {:.1}s ({:.1} req/s)",
+        total,
+        elapsed.as_secs_f64(),
+        total as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+    let _ = writeln!(
+        output,
+        "Errors:      {} ({:.2}%)",
+        failed.len(),
+        100.0 * failed.len() as f64 / total.max(1) as f64
+    );
+    let mut errors: BTreeMap<&str, usize> = BTreeMap::new();
+    for sample in &failed {
+        *errors
+            .entry(sample.error.as_deref().unwrap_or_default())
+            .or_default() += 1;
+    }
+    for (error, count) in errors {
+        let _ = writeln!(output, "  {}: {}", error, count);
+    }
+
+    let mut latencies: Vec<Duration> = samples.iter().map(|sample| sample.latency).collect();
+    latencies.sort();
+    if latencies.is_empty() {
+        return output;
+    }
+    let _ = writeln!(
+        output,
+        "Latency:     p50 {} | p90 {} | p99 {} | max {}",
+        millis(percentile(&latencies, 50.0)),
+        millis(percentile(&latencies, 90.0)),
+        millis(percentile(&latencies, 99.0)),
+        millis(latencies[latencies.len() - 1])
+    );
+    output
+}
+
+/// Nearest-rank percentile of sorted, non-empty `latencies`.
+fn percentile(latencies: &[Duration], percent: f64) -> Duration {
+    let rank = (percent / 100.0 * latencies.len() as f64).ceil() as usize;
+    latencies[rank.clamp(1, latencies.len()) - 1]
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,125 @@
+use clap::{Parser, Subcommand};
+
+/// AI agent API server with Kubernetes and portfolio tools.
+#[derive(Parser)]
+#[command(version)]
+pub struct Cli {
+    /// Config file with non-secret settings (overrides CONFIG_PATH)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the HTTP chat server (the default)
+    Serve,
+    /// Answer a single prompt in the terminal and exit
+    Chat {
+        /// The question to ask, e.g. "which pods are crash looping?"
+        #[arg(required = true)]
+        prompt: Vec<String>,
+    },
+    /// Inspect the agent's tools
+    Tools {
+        #[command(subcommand)]
+        command: ToolsCommand,
+    },
+    /// Validate the configuration and Kubernetes connectivity
+    Check,
+    /// Serve the agent's tools to MCP clients over stdio
+    Mcp,
+    /// Answer Telegram messages from allowlisted users
+    Telegram,
+    /// Run an evaluation suite
+    Eval {
+        /// YAML suite file
+        suite: String,
+        /// Answer each case with its mock_response instead of the real agent
+        #[arg(long)]
+        mock: bool,
+    },
+    /// Serve a mock Kubernetes API from the fixtures in fixtures/kube
+    #[cfg(feature = "test-support")]
+    MockKube {
+        /// Port to listen on (localhost only)
+        #[arg(long, default_value_t = 6443)]
+        port: u16,
+    },
+    /// Fire concurrent synthetic chat requests at the server and report latency and errors
+    #[cfg(feature = "test-support")]
+    Bench {
+        /// Requests to send in total
+        #[arg(long, default_value_t = 500)]
+        requests: usize,
+        /// Requests in flight at once
+        #[arg(long, default_value_t = 16)]
+        concurrency: usize,
+        /// Keep sending requests for this many seconds instead (a soak run)
+        #[arg(long, value_name = "SECS")]
+        duration: Option<u64>,
+        /// Delay of the mocked model before each reply, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 50)]
+        model_latency: u64,
+        /// Bench a running server at this URL (with CHAT_API_KEY) instead of an in-process one
+        #[arg(long)]
+        url: Option<String>,
+    },
+    /// Record or replay Kubernetes and OpenAI traffic through a local proxy
+    #[cfg(feature = "test-support")]
+    Vcr {
+        #[command(subcommand)]
+        command: VcrCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ToolsCommand {
+    /// List the tools with their descriptions
+    List,
+}
+
+#[cfg(feature = "test-support")]
+#[derive(Subcommand)]
+pub enum VcrCommand {
+    /// Forward requests to an upstream API and record the exchanges into a cassette
+    Record {
+        /// API to forward to, e.g. https://api.openai.com/v1
+        #[arg(long)]
+        upstream: String,
+        /// Cassette file to write
+        #[arg(long)]
+        cassette: String,
+        /// Port to listen on (localhost only)
+        #[arg(long, default_value_t = 8089)]
+        port: u16,
+        /// Skip verification of the upstream's TLS certificate
+        #[arg(long)]
+        accept_invalid_certs: bool,
+    },
+    /// Answer requests from a recorded cassette
+    Replay {
+        /// Cassette file to read
+        #[arg(long)]
+        cassette: String,
+        /// Port to listen on (localhost only)
+        #[arg(long, default_value_t = 8089)]
+        port: u16,
+    },
+}
+
+impl Command {
+    /// Whether stdout carries the command's output (or protocol), so logs must go to stderr.
+    pub fn owns_stdout(&self) -> bool {
+        #[cfg(feature = "test-support")]
+        if matches!(self, Command::Bench { .. }) {
+            return true;
+        }
+        matches!(
+            self,
+            Command::Chat { .. } | Command::Tools { .. } | Command::Check | Command::Mcp
+        )
+    }
+}
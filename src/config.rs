@@ -0,0 +1,407 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::debug;
+
+/// Config file path given with `--config`.
+static CLI_PATH: OnceLock<String> = OnceLock::new();
+
+/// Settings read from the optional config file, layered under environment variables.
+///
+/// The file is YAML, or TOML when its name ends in `.toml`, and is grouped into `server`,
+/// `agent`, `tools`, and `kube` sections. Every key corresponds to one environment
+/// variable (e.g. `kube.cache_ttl_secs` to `KUBE_CACHE_TTL_SECS`), and a set environment
+/// variable always wins. Secrets (API keys and tokens) are only read from the environment.
+#[derive(Default)]
+pub struct ConfigFile {
+    values: HashMap<&'static str, String>,
+}
+
+impl ConfigFile {
+    /// Records the `--config` command-line path, which takes precedence over
+    /// `CONFIG_PATH`. Must be called before the configuration is loaded.
+    pub fn set_path(path: String) {
+        let _ = CLI_PATH.set(path);
+    }
+
+    /// The config file path from `--config`, else `CONFIG_PATH`.
+    pub fn path() -> Option<String> {
+        CLI_PATH
+            .get()
+            .cloned()
+            .or_else(|| std::env::var("CONFIG_PATH").ok())
+    }
+
+    /// Loads the config file at `path`, or an empty config without one.
+    ///
+    /// Unknown keys and values of the wrong type are rejected, naming the offending key.
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let Some(path) = path else {
+            return Ok(ConfigFile::default());
+        };
+
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path, e))?;
+        let sections: FileSections = if contents.trim().is_empty() {
+            FileSections::default()
+        } else if path.ends_with(".toml") {
+            toml::from_str(&contents).map_err(|e| format!("Invalid config file {}: {}", path, e))?
+        } else {
+            serde_yaml::from_str(&contents)
+                .map_err(|e| format!("Invalid config file {}: {}", path, e))?
+        };
+
+        let values = sections.into_values();
+        debug!("Loaded {} settings from config file {}", values.len(), path);
+        Ok(ConfigFile { values })
+    }
+
+    /// The environment variable `name`, falling back to the config file's value for it.
+    pub fn var(&self, name: &str) -> Result<String, std::env::VarError> {
+        std::env::var(name).or_else(|err| match self.values.get(name) {
+            Some(value) => {
+                debug!("{} loaded from config file", name);
+                Ok(value.clone())
+            }
+            None => Err(err),
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileSections {
+    server: ServerSection,
+    agent: AgentSection,
+    tools: ToolsSection,
+    kube: KubeSection,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerSection {
+    production_mode: Option<bool>,
+    chat_api_key_hash: Option<String>,
+    chat_api_keys_file: Option<String>,
+    auth_lockout_free_attempts: Option<u32>,
+    auth_lockout_base_secs: Option<u64>,
+    auth_lockout_max_secs: Option<u64>,
+    trust_forwarded_for: Option<bool>,
+    trusted_proxies: Option<Vec<String>>,
+    forwarded_header: Option<String>,
+    tls_cert_file: Option<String>,
+    tls_key_file: Option<String>,
+    mtls_client_ca_file: Option<String>,
+    mtls_identities_file: Option<String>,
+    grpc_port: Option<u16>,
+    telegram_allowed_users: Option<Vec<i64>>,
+    chat_timeout_secs: Option<u64>,
+    max_prompt_chars: Option<usize>,
+    max_prompt_tokens: Option<usize>,
+    max_history_chars: Option<usize>,
+    max_history_tokens: Option<usize>,
+    max_history_messages: Option<usize>,
+    max_response_chars: Option<usize>,
+    audit_log_path: Option<String>,
+    audit_log_retention_days: Option<u64>,
+    daily_digest_enabled: Option<bool>,
+    daily_digest_hour_utc: Option<u32>,
+    usage_window_days: Option<Vec<u64>>,
+    usage_prompt_price_per_mtok: Option<f64>,
+    usage_response_price_per_mtok: Option<f64>,
+    request_warn_latency_ms: Option<u64>,
+    request_warn_tokens: Option<usize>,
+    request_warn_tool_calls: Option<usize>,
+    request_warn_webhook_url: Option<String>,
+    shed_model_latency_ms: Option<u64>,
+    shed_model_error_rate: Option<f64>,
+    shed_window_secs: Option<u64>,
+    shed_min_calls: Option<usize>,
+    moderation_enabled: Option<bool>,
+    moderation_model: Option<String>,
+    abuse_cooldown_flags: Option<u32>,
+    abuse_cooldown_secs: Option<u64>,
+    abuse_shadow_ban_flags: Option<u32>,
+    abuse_window_secs: Option<u64>,
+    notify_webhook_urls: Option<Vec<String>>,
+    notify_slack_webhook_urls: Option<Vec<String>>,
+    notify_max_attempts: Option<u32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AgentSection {
+    models: Option<Vec<String>>,
+    openai_base_url: Option<String>,
+    model_timeout_secs: Option<u64>,
+    openai_max_attempts: Option<u32>,
+    openai_retry_base_delay_ms: Option<u64>,
+    openai_retry_max_delay_ms: Option<u64>,
+    summary_model: Option<String>,
+    summary_trigger_tokens: Option<usize>,
+    summary_keep_recent: Option<usize>,
+    session_ttl_secs: Option<u64>,
+    max_sessions: Option<usize>,
+    cluster_name: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ToolsSection {
+    output_max_chars: Option<usize>,
+    output_budgets: Option<Vec<String>>,
+    output_summarize: Option<bool>,
+    portfolio_base_url: Option<String>,
+    portfolio_pages: Option<Vec<String>>,
+    fetch_domain_allowlist: Option<Vec<String>>,
+    http_user_agent: Option<String>,
+    enable_portfolio_index: Option<bool>,
+    portfolio_index_refresh_secs: Option<u64>,
+    portfolio_crawl_max_depth: Option<usize>,
+    portfolio_crawl_max_pages: Option<usize>,
+    web_cache_ttl_secs: Option<u64>,
+    search_api_provider: Option<String>,
+    contact_mail_provider: Option<String>,
+    contact_email_from: Option<String>,
+    contact_email_to: Option<String>,
+    contact_max_per_hour: Option<usize>,
+    github_repos: Option<Vec<String>>,
+    fetch_max_bytes: Option<usize>,
+    fetch_timeout_secs: Option<u64>,
+    fetch_max_concurrency: Option<usize>,
+    configmap_value_allowlist: Option<Vec<String>>,
+    enable_write_tools: Option<bool>,
+    write_namespace_allowlist: Option<Vec<String>>,
+    delete_pod_label_allowlist: Option<Vec<String>>,
+    raw_get_path_allowlist: Option<Vec<String>>,
+    raw_get_max_bytes: Option<usize>,
+    cost_cpu_monthly: Option<f64>,
+    cost_memory_gib_monthly: Option<f64>,
+    cost_currency: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct KubeSection {
+    api_server: Option<String>,
+    accept_invalid_certs: Option<bool>,
+    cache_ttl_secs: Option<u64>,
+    cache_ttls: Option<Vec<String>>,
+    api_qps: Option<f64>,
+    api_burst: Option<u32>,
+    breaker_failures: Option<u32>,
+    breaker_cooldown_secs: Option<u64>,
+    connect_timeout_secs: Option<u64>,
+    request_timeout_secs: Option<u64>,
+    clusters_file: Option<String>,
+    enable_cluster_watch: Option<bool>,
+    enable_cluster_context: Option<bool>,
+    cluster_context_refresh_secs: Option<u64>,
+    alert_webhook_url: Option<String>,
+}
+
+impl FileSections {
+    /// Pairs each set value with the environment variable it stands in for. Lists are
+    /// joined with commas, the same format the environment variables use.
+    fn into_values(self) -> HashMap<&'static str, String> {
+        let FileSections {
+            server,
+            agent,
+            tools,
+            kube,
+        } = self;
+        [
+            ("PRODUCTION_MODE", text(server.production_mode)),
+            ("CHAT_API_KEY_HASH", text(server.chat_api_key_hash)),
+            ("CHAT_API_KEYS_FILE", text(server.chat_api_keys_file)),
+            (
+                "AUTH_LOCKOUT_FREE_ATTEMPTS",
+                text(server.auth_lockout_free_attempts),
+            ),
+            (
+                "AUTH_LOCKOUT_BASE_SECS",
+                text(server.auth_lockout_base_secs),
+            ),
+            ("AUTH_LOCKOUT_MAX_SECS", text(server.auth_lockout_max_secs)),
+            ("TRUST_FORWARDED_FOR", text(server.trust_forwarded_for)),
+            ("TRUSTED_PROXIES", list(server.trusted_proxies)),
+            ("FORWARDED_HEADER", text(server.forwarded_header)),
+            ("TLS_CERT_FILE", text(server.tls_cert_file)),
+            ("TLS_KEY_FILE", text(server.tls_key_file)),
+            ("MTLS_CLIENT_CA_FILE", text(server.mtls_client_ca_file)),
+            ("MTLS_IDENTITIES_FILE", text(server.mtls_identities_file)),
+            ("GRPC_PORT", text(server.grpc_port)),
+            (
+                "TELEGRAM_ALLOWED_USERS",
+                list(
+                    server
+                        .telegram_allowed_users
+                        .map(|ids| ids.iter().map(i64::to_string).collect()),
+                ),
+            ),
+            ("CHAT_TIMEOUT_SECS", text(server.chat_timeout_secs)),
+            ("MAX_PROMPT_CHARS", text(server.max_prompt_chars)),
+            ("MAX_PROMPT_TOKENS", text(server.max_prompt_tokens)),
+            ("MAX_HISTORY_CHARS", text(server.max_history_chars)),
+            ("MAX_HISTORY_TOKENS", text(server.max_history_tokens)),
+            ("MAX_HISTORY_MESSAGES", text(server.max_history_messages)),
+            ("MAX_RESPONSE_CHARS", text(server.max_response_chars)),
+            ("AUDIT_LOG_PATH", text(server.audit_log_path)),
+            (
+                "AUDIT_LOG_RETENTION_DAYS",
+                text(server.audit_log_retention_days),
+            ),
+            ("DAILY_DIGEST_ENABLED", text(server.daily_digest_enabled)),
+            ("DAILY_DIGEST_HOUR_UTC", text(server.daily_digest_hour_utc)),
+            (
+                "USAGE_WINDOW_DAYS",
+                list(
+                    server
+                        .usage_window_days
+                        .map(|days| days.iter().map(u64::to_string).collect()),
+                ),
+            ),
+            (
+                "USAGE_PROMPT_PRICE_PER_MTOK",
+                text(server.usage_prompt_price_per_mtok),
+            ),
+            (
+                "USAGE_RESPONSE_PRICE_PER_MTOK",
+                text(server.usage_response_price_per_mtok),
+            ),
+            (
+                "REQUEST_WARN_LATENCY_MS",
+                text(server.request_warn_latency_ms),
+            ),
+            ("REQUEST_WARN_TOKENS", text(server.request_warn_tokens)),
+            (
+                "REQUEST_WARN_TOOL_CALLS",
+                text(server.request_warn_tool_calls),
+            ),
+            (
+                "REQUEST_WARN_WEBHOOK_URL",
+                text(server.request_warn_webhook_url),
+            ),
+            ("SHED_MODEL_LATENCY_MS", text(server.shed_model_latency_ms)),
+            ("SHED_MODEL_ERROR_RATE", text(server.shed_model_error_rate)),
+            ("SHED_WINDOW_SECS", text(server.shed_window_secs)),
+            ("SHED_MIN_CALLS", text(server.shed_min_calls)),
+            ("MODERATION_ENABLED", text(server.moderation_enabled)),
+            ("MODERATION_MODEL", text(server.moderation_model)),
+            ("ABUSE_COOLDOWN_FLAGS", text(server.abuse_cooldown_flags)),
+            ("ABUSE_COOLDOWN_SECS", text(server.abuse_cooldown_secs)),
+            (
+                "ABUSE_SHADOW_BAN_FLAGS",
+                text(server.abuse_shadow_ban_flags),
+            ),
+            ("ABUSE_WINDOW_SECS", text(server.abuse_window_secs)),
+            ("NOTIFY_WEBHOOK_URLS", list(server.notify_webhook_urls)),
+            (
+                "NOTIFY_SLACK_WEBHOOK_URLS",
+                list(server.notify_slack_webhook_urls),
+            ),
+            ("NOTIFY_MAX_ATTEMPTS", text(server.notify_max_attempts)),
+            ("AGENT_MODELS", list(agent.models)),
+            ("OPENAI_BASE_URL", text(agent.openai_base_url)),
+            ("AGENT_MODEL_TIMEOUT_SECS", text(agent.model_timeout_secs)),
+            ("OPENAI_MAX_ATTEMPTS", text(agent.openai_max_attempts)),
+            (
+                "OPENAI_RETRY_BASE_DELAY_MS",
+                text(agent.openai_retry_base_delay_ms),
+            ),
+            (
+                "OPENAI_RETRY_MAX_DELAY_MS",
+                text(agent.openai_retry_max_delay_ms),
+            ),
+            ("SUMMARY_MODEL", text(agent.summary_model)),
+            ("SUMMARY_TRIGGER_TOKENS", text(agent.summary_trigger_tokens)),
+            ("SUMMARY_KEEP_RECENT", text(agent.summary_keep_recent)),
+            ("SESSION_TTL_SECS", text(agent.session_ttl_secs)),
+            ("MAX_SESSIONS", text(agent.max_sessions)),
+            ("CLUSTER_NAME", text(agent.cluster_name)),
+            ("TOOL_OUTPUT_MAX_CHARS", text(tools.output_max_chars)),
+            ("TOOL_OUTPUT_BUDGETS", list(tools.output_budgets)),
+            ("TOOL_OUTPUT_SUMMARIZE", text(tools.output_summarize)),
+            ("PORTFOLIO_BASE_URL", text(tools.portfolio_base_url)),
+            ("PORTFOLIO_PAGES", list(tools.portfolio_pages)),
+            ("FETCH_DOMAIN_ALLOWLIST", list(tools.fetch_domain_allowlist)),
+            ("HTTP_USER_AGENT", text(tools.http_user_agent)),
+            ("ENABLE_PORTFOLIO_INDEX", text(tools.enable_portfolio_index)),
+            (
+                "PORTFOLIO_INDEX_REFRESH_SECS",
+                text(tools.portfolio_index_refresh_secs),
+            ),
+            (
+                "PORTFOLIO_CRAWL_MAX_DEPTH",
+                text(tools.portfolio_crawl_max_depth),
+            ),
+            (
+                "PORTFOLIO_CRAWL_MAX_PAGES",
+                text(tools.portfolio_crawl_max_pages),
+            ),
+            ("WEB_CACHE_TTL_SECS", text(tools.web_cache_ttl_secs)),
+            ("SEARCH_API_PROVIDER", text(tools.search_api_provider)),
+            ("CONTACT_MAIL_PROVIDER", text(tools.contact_mail_provider)),
+            ("CONTACT_EMAIL_FROM", text(tools.contact_email_from)),
+            ("CONTACT_EMAIL_TO", text(tools.contact_email_to)),
+            ("CONTACT_MAX_PER_HOUR", text(tools.contact_max_per_hour)),
+            ("GITHUB_REPOS", list(tools.github_repos)),
+            ("FETCH_MAX_BYTES", text(tools.fetch_max_bytes)),
+            ("FETCH_TIMEOUT_SECS", text(tools.fetch_timeout_secs)),
+            ("FETCH_MAX_CONCURRENCY", text(tools.fetch_max_concurrency)),
+            (
+                "CONFIGMAP_VALUE_ALLOWLIST",
+                list(tools.configmap_value_allowlist),
+            ),
+            ("ENABLE_WRITE_TOOLS", text(tools.enable_write_tools)),
+            (
+                "WRITE_NAMESPACE_ALLOWLIST",
+                list(tools.write_namespace_allowlist),
+            ),
+            (
+                "DELETE_POD_LABEL_ALLOWLIST",
+                list(tools.delete_pod_label_allowlist),
+            ),
+            ("RAW_GET_PATH_ALLOWLIST", list(tools.raw_get_path_allowlist)),
+            ("RAW_GET_MAX_BYTES", text(tools.raw_get_max_bytes)),
+            ("COST_CPU_MONTHLY", text(tools.cost_cpu_monthly)),
+            (
+                "COST_MEMORY_GIB_MONTHLY",
+                text(tools.cost_memory_gib_monthly),
+            ),
+            ("COST_CURRENCY", text(tools.cost_currency)),
+            ("KUBE_API_SERVER", text(kube.api_server)),
+            ("KUBE_ACCEPT_INVALID_CERTS", text(kube.accept_invalid_certs)),
+            ("KUBE_CACHE_TTL_SECS", text(kube.cache_ttl_secs)),
+            ("KUBE_CACHE_TTLS", list(kube.cache_ttls)),
+            ("KUBE_API_QPS", text(kube.api_qps)),
+            ("KUBE_API_BURST", text(kube.api_burst)),
+            ("KUBE_BREAKER_FAILURES", text(kube.breaker_failures)),
+            (
+                "KUBE_BREAKER_COOLDOWN_SECS",
+                text(kube.breaker_cooldown_secs),
+            ),
+            ("KUBE_CONNECT_TIMEOUT_SECS", text(kube.connect_timeout_secs)),
+            ("KUBE_REQUEST_TIMEOUT_SECS", text(kube.request_timeout_secs)),
+            ("KUBE_CLUSTERS_FILE", text(kube.clusters_file)),
+            ("ENABLE_CLUSTER_WATCH", text(kube.enable_cluster_watch)),
+            ("ENABLE_CLUSTER_CONTEXT", text(kube.enable_cluster_context)),
+            (
+                "CLUSTER_CONTEXT_REFRESH_SECS",
+                text(kube.cluster_context_refresh_secs),
+            ),
+            ("ALERT_WEBHOOK_URL", text(kube.alert_webhook_url)),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+fn text<T: ToString>(value: Option<T>) -> Option<String> {
+    value.map(|value| value.to_string())
+}
+
+fn list(value: Option<Vec<String>>) -> Option<String> {
+    value.map(|items| items.join(","))
+}
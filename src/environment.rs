@@ -1,12 +1,111 @@
+use crate::config::ConfigFile;
+use crate::kube::tools::cost::PriceTable;
+use crate::notifier::{EventKind, Notifier, Webhook, WebhookFormat};
+use crate::server::auth::ApiKeys;
+use crate::server::mtls::MutualTls;
+use crate::server::proxy::{Cidr, ForwardedHeader};
 use reqwest::Certificate;
-use tracing::{debug, info, warn};
+use std::sync::OnceLock;
+use tracing::{debug, error, info, warn};
+
+/// The process-wide configuration, see [`Environment::global`].
+static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+
+/// Service account token mounted into pods in production mode.
+const SERVICE_ACCOUNT_TOKEN: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Cluster CA certificate mounted into pods in production mode.
+const SERVICE_ACCOUNT_CA_CERT: &str = "/var/run/secrets/kubernetes.io/serviceaccount/ca.crt";
+
+/// Named bundle of defaults selected with `APP_PROFILE`.
+///
+/// A profile only changes defaults; any setting given explicitly still wins.
+///
+/// | Setting                     | dev  | staging | prod  |
+/// |-----------------------------|------|---------|-------|
+/// | `PRODUCTION_MODE`           | off  | on      | on    |
+/// | `ENABLE_WRITE_TOOLS`        | off  | on      | off   |
+/// | `KUBE_ACCEPT_INVALID_CERTS` | on   | off     | off   |
+/// | `LOG_FORMAT`                | text | json    | json  |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl Profile {
+    /// Reads `APP_PROFILE`, defaulting to `dev`.
+    ///
+    /// Read directly from the environment (not the config file), since the log format
+    /// must be known before anything else is loaded.
+    pub fn from_env() -> Profile {
+        match std::env::var("APP_PROFILE") {
+            Ok(val) => match val.trim().to_lowercase().as_str() {
+                "dev" | "development" => Profile::Dev,
+                "staging" => Profile::Staging,
+                "prod" | "production" => Profile::Prod,
+                _ => {
+                    warn!(
+                        "Invalid value for APP_PROFILE ({:?}), expected dev, staging, or prod; using dev",
+                        val
+                    );
+                    Profile::Dev
+                }
+            },
+            Err(_) => Profile::Dev,
+        }
+    }
+
+    /// The profile's lower-case name, e.g. `prod`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::Dev => "dev",
+            Profile::Staging => "staging",
+            Profile::Prod => "prod",
+        }
+    }
+
+    fn production_mode(self) -> bool {
+        self != Profile::Dev
+    }
+
+    fn enable_write_tools(self) -> bool {
+        self == Profile::Staging
+    }
+
+    fn accept_invalid_certs(self) -> bool {
+        self == Profile::Dev
+    }
+}
+
+/// Whether logs should be written as JSON: `LOG_FORMAT=json|text`, else the profile's
+/// default. Called before logging is set up, so it reads the environment directly.
+pub fn json_logs() -> bool {
+    match std::env::var("LOG_FORMAT") {
+        Ok(format) => format.trim().eq_ignore_ascii_case("json"),
+        Err(_) => Profile::from_env() != Profile::Dev,
+    }
+}
+
+/// `ERROR_REPORTING_DSN` (or `ERROR_REPORTING_DSN_FILE`), the Sentry-compatible DSN errors
+/// and panics are reported to. Read alongside the log format, before the config file.
+pub fn error_reporting_dsn() -> Option<String> {
+    read_secret("ERROR_REPORTING_DSN").filter(|dsn| !dsn.trim().is_empty())
+}
 
 /// Application configuration loaded from environment variables.
 ///
 /// Handles different configuration sources based on deployment mode:
 /// - Local development: loads from .env file and environment variables
 /// - Production (Kubernetes): loads from mounted secrets and service account tokens
+///
+/// Non-secret settings may also come from a config file (see [`ConfigFile`]); environment
+/// variables take precedence over it.
 pub struct Environment {
+    /// Preset the defaults below were drawn from (`APP_PROFILE`)
+    pub profile: Profile,
+
     /// OpenAI API key for AI agent functionality
     pub openai_api_key: String,
 
@@ -19,14 +118,351 @@ pub struct Environment {
     /// CA certificate for secure Kubernetes API communication (production only)
     pub kube_certificate: Option<Certificate>,
 
+    /// Whether to skip TLS verification for clusters without a CA certificate
+    pub kube_accept_invalid_certs: bool,
+
     /// Bearer token for Kubernetes API authentication
     pub kube_token: String,
 
     /// API key for authenticating requests to this server
     pub chat_api_key: String,
+
+    /// Hash of a further accepted API key, as `sha256:<hex>` or an argon2 PHC string
+    pub chat_api_key_hash: Option<String>,
+
+    /// YAML file of further accepted key hashes with optional validity windows
+    pub chat_api_keys_file: Option<String>,
+
+    /// Invalid API keys a client may present before being locked out
+    pub auth_lockout_free_attempts: u32,
+
+    /// First lockout in seconds, doubling with each further failure (0 disables lockouts)
+    pub auth_lockout_base_secs: u64,
+
+    /// Longest lockout in seconds; failures are also forgotten after this long
+    pub auth_lockout_max_secs: u64,
+
+    /// Networks of the reverse proxies whose forwarding headers name the client
+    pub trusted_proxies: Vec<Cidr>,
+
+    /// Header the trusted proxies forward client addresses in
+    pub forwarded_header: ForwardedHeader,
+
+    /// PEM certificate chain the server presents when serving TLS
+    pub tls_cert_file: Option<String>,
+
+    /// PEM private key for `tls_cert_file`
+    pub tls_key_file: Option<String>,
+
+    /// PEM CA that client certificates must chain to; setting it enables mutual TLS
+    pub mtls_client_ca_file: Option<String>,
+
+    /// YAML file of the certificate identities let in under mutual TLS, with their capabilities
+    pub mtls_identities_file: Option<String>,
+
+    /// Port to also serve the gRPC API on (needs the `grpc` feature)
+    pub grpc_port: Option<u16>,
+
+    /// Bot API token for the `telegram` command
+    pub telegram_bot_token: Option<String>,
+
+    /// Telegram user ids the bot answers; everyone else is ignored
+    pub telegram_allowed_users: Vec<i64>,
+
+    /// Maximum number of attempts for a prompt when OpenAI returns transient errors
+    pub openai_max_attempts: u32,
+
+    /// Base delay in milliseconds for the exponential retry backoff
+    pub openai_retry_base_delay_ms: u64,
+
+    /// Upper bound in milliseconds for a single retry backoff delay
+    pub openai_retry_max_delay_ms: u64,
+
+    /// Ordered model fallback chain, e.g. ["openai:gpt-5.1", "openai:gpt-4.1-mini"]
+    pub agent_models: Vec<String>,
+
+    /// Base URL of the OpenAI API, for a compatible gateway or a recording proxy
+    pub openai_base_url: Option<String>,
+
+    /// Seconds a single model may spend on a prompt before falling back to the next one
+    pub agent_model_timeout_secs: u64,
+
+    /// Overall deadline in seconds for answering a chat request, across all models and tools
+    pub chat_timeout_secs: u64,
+
+    /// Maximum prompt length in characters (longer prompts are rejected with 413)
+    pub max_prompt_chars: usize,
+
+    /// Maximum estimated prompt length in tokens (longer prompts are rejected with 413)
+    pub max_prompt_tokens: usize,
+
+    /// Maximum combined chat history length in characters (oldest messages are trimmed)
+    pub max_history_chars: usize,
+
+    /// Maximum combined estimated chat history length in tokens (oldest messages are trimmed)
+    pub max_history_tokens: usize,
+
+    /// Maximum number of chat history messages (oldest messages are trimmed)
+    pub max_history_messages: usize,
+
+    /// Maximum /chat response length in characters, the rest fetched with `continue` (0 disables)
+    pub max_response_chars: usize,
+
+    /// JSONL file every /chat request is audited to (unset disables the audit log)
+    pub audit_log_path: Option<String>,
+
+    /// Days audit records are kept before being pruned (0 keeps them forever)
+    pub audit_log_retention_days: u64,
+
+    /// Whether a summary of the audit log is notified daily
+    pub daily_digest_enabled: bool,
+
+    /// Hour of the day (UTC) the daily digest is sent at
+    pub daily_digest_hour_utc: u32,
+
+    /// Windows in days the GET /usage report covers, e.g. [1, 7, 30]
+    pub usage_window_days: Vec<u64>,
+
+    /// USD per million prompt tokens, for the usage report's cost estimate
+    pub usage_prompt_price_per_mtok: f64,
+
+    /// USD per million response tokens, for the usage report's cost estimate
+    pub usage_response_price_per_mtok: f64,
+
+    /// Chat requests slower than this many milliseconds are reported (0 disables)
+    pub request_warn_latency_ms: u64,
+
+    /// Estimated prompt plus response tokens above which a chat request is reported (0 disables)
+    pub request_warn_tokens: usize,
+
+    /// Chat requests making more than this many tool calls are reported (0 disables)
+    pub request_warn_tool_calls: usize,
+
+    /// Webhook that slow and over-budget request reports are POSTed to
+    pub request_warn_webhook_url: Option<String>,
+
+    /// p90 model latency in milliseconds above which non-admin chats get 503s (0 disables)
+    pub shed_model_latency_ms: u64,
+
+    /// Model error rate (0.0-1.0) above which non-admin chats get 503s (0 disables)
+    pub shed_model_error_rate: f64,
+
+    /// Seconds of model calls the load shedding thresholds are measured over
+    pub shed_window_secs: u64,
+
+    /// Model calls needed in the window before load shedding can start
+    pub shed_min_calls: usize,
+
+    /// Whether prompts from non-admin keys are checked with the moderation endpoint
+    pub moderation_enabled: bool,
+
+    /// OpenAI moderation model prompts are checked with
+    pub moderation_model: String,
+
+    /// Flagged prompts in the window after which a client cools down (0 disables)
+    pub abuse_cooldown_flags: u32,
+
+    /// Seconds a client's prompts are refused for once it cools down
+    pub abuse_cooldown_secs: u64,
+
+    /// Flagged prompts in the window after which a client is shadow-banned (0 disables)
+    pub abuse_shadow_ban_flags: u32,
+
+    /// Seconds flagged prompts count against a client
+    pub abuse_window_secs: u64,
+
+    /// Webhooks every notification is POSTed to in the generic JSON format
+    pub notify_webhook_urls: Vec<String>,
+
+    /// Slack incoming webhooks every notification is POSTed to
+    pub notify_slack_webhook_urls: Vec<String>,
+
+    /// Secret notifications are HMAC-signed with
+    pub notify_webhook_secret: Option<String>,
+
+    /// Delivery attempts per notification and webhook before giving up
+    pub notify_max_attempts: u32,
+
+    /// Cheap model used to summarize long conversations, e.g. "openai:gpt-5-mini"
+    pub summary_model: String,
+
+    /// Estimated session history size in tokens above which older turns are summarized
+    pub summary_trigger_tokens: usize,
+
+    /// Number of most recent messages kept verbatim when a session is summarized
+    pub summary_keep_recent: usize,
+
+    /// Seconds a session may sit idle before it is forgotten
+    pub session_ttl_secs: u64,
+
+    /// Most sessions kept in memory; the least recently used is forgotten beyond that
+    pub max_sessions: usize,
+
+    /// Default maximum size in characters of a tool result fed back to the model
+    pub tool_output_max_chars: usize,
+
+    /// Per-tool output budgets as "tool_name=max_chars" entries
+    pub tool_output_budgets: Vec<String>,
+
+    /// Whether tool output overflow is summarized by the summary model instead of dropped
+    pub tool_output_summarize: bool,
+
+    /// Portfolio site the web tools fetch pages from, e.g. "https://about.calum.run"
+    pub portfolio_base_url: String,
+
+    /// Portfolio pages the web tools may fetch, as "label=path" entries
+    pub portfolio_pages: Vec<String>,
+
+    /// Domains (and their subdomains) the fetch_url tool may fetch from
+    pub fetch_domain_allowlist: Vec<String>,
+
+    /// User-Agent sent with outbound web tool requests
+    pub http_user_agent: String,
+
+    /// Extra "Name=value" headers sent with portfolio site requests (web_search and the
+    /// crawler), e.g. a CDN bypass token
+    pub portfolio_headers: Vec<String>,
+
+    /// Whether the portfolio site is crawled in the background and search_portfolio registered
+    pub enable_portfolio_index: bool,
+
+    /// Seconds between crawls that rebuild the portfolio index
+    pub portfolio_index_refresh_secs: u64,
+
+    /// Link hops the crawler follows from the start pages when the site has no sitemap
+    pub portfolio_crawl_max_depth: usize,
+
+    /// Maximum number of pages fetched per crawl
+    pub portfolio_crawl_max_pages: usize,
+
+    /// Seconds web_search and fetch_url reuse a fetched page before revalidating it (0 disables)
+    pub web_cache_ttl_secs: u64,
+
+    /// Search API behind internet_search: "brave" or "serpapi"
+    pub search_api_provider: String,
+
+    /// Key for the search API; internet_search is only registered when set
+    pub search_api_key: Option<String>,
+
+    /// Mail API behind send_contact_message: "resend" or "sendgrid"
+    pub contact_mail_provider: String,
+
+    /// Key for the mail API; send_contact_message is only registered when it and both
+    /// addresses are set
+    pub contact_mail_api_key: Option<String>,
+
+    /// Sender address of contact messages, which the mail API must accept
+    pub contact_email_from: Option<String>,
+
+    /// Address contact messages are delivered to
+    pub contact_email_to: Option<String>,
+
+    /// Contact messages sent per hour across all visitors
+    pub contact_max_per_hour: usize,
+
+    /// GitHub repositories (owner/name) ci_status reports on; it is only registered when set
+    pub github_repos: Vec<String>,
+
+    /// GitHub token for ci_status, needed for private repositories
+    pub github_token: Option<String>,
+
+    /// Maximum size in bytes of a fetch_url response before it is truncated
+    pub fetch_max_bytes: usize,
+
+    /// Time in seconds a fetch_url request may take, redirects included
+    pub fetch_timeout_secs: u64,
+
+    /// Requests fetch_many keeps in flight at once
+    pub fetch_max_concurrency: usize,
+
+    /// Human-readable name of the Kubernetes cluster, injected into prompts when set
+    pub cluster_name: Option<String>,
+
+    /// ConfigMaps ("name" or "namespace/name") whose values the agent may read
+    pub configmap_value_allowlist: Vec<String>,
+
+    /// Whether write-capable Kubernetes tools (e.g. scaling) are registered
+    pub enable_write_tools: bool,
+
+    /// Namespaces that write-capable tools may modify
+    pub write_namespace_allowlist: Vec<String>,
+
+    /// "key=value" labels a pod must carry (any one of) to be deletable; empty allows any pod
+    pub delete_pod_label_allowlist: Vec<String>,
+
+    /// API path prefixes the raw_kube_get tool may read
+    pub raw_get_path_allowlist: Vec<String>,
+
+    /// Maximum size in bytes of a raw_kube_get response before it is truncated
+    pub raw_get_max_bytes: usize,
+
+    /// Monthly price of one requested CPU core, for estimate_costs
+    pub cost_cpu_monthly: f64,
+
+    /// Monthly price of one requested GiB of memory, for estimate_costs
+    pub cost_memory_gib_monthly: f64,
+
+    /// Currency estimate_costs reports amounts in
+    pub cost_currency: String,
+
+    /// Default time in seconds a kube tool may reuse a cached API response (0 disables)
+    pub kube_cache_ttl_secs: u64,
+
+    /// Per-tool cache TTL overrides as `tool_name=secs` entries
+    pub kube_cache_ttls: Vec<String>,
+
+    /// Sustained Kubernetes API requests per second per cluster (0 disables rate limiting)
+    pub kube_api_qps: f64,
+
+    /// Requests per cluster that may go out at once before the QPS limit applies
+    pub kube_api_burst: u32,
+
+    /// Consecutive failed API requests that open a cluster's circuit breaker (0 disables it)
+    pub kube_breaker_failures: u32,
+
+    /// Seconds an open circuit breaker refuses requests
+    pub kube_breaker_cooldown_secs: u64,
+
+    /// Seconds allowed to connect to the Kubernetes API server
+    pub kube_connect_timeout_secs: u64,
+
+    /// Seconds allowed for a whole Kubernetes API request
+    pub kube_request_timeout_secs: u64,
+
+    /// YAML file listing additional clusters the kube tools can target by name
+    pub kube_clusters_file: Option<String>,
+
+    /// Whether to watch the home cluster in the background for the cluster_alerts tool
+    pub enable_cluster_watch: bool,
+
+    /// Whether to add a periodically refreshed cluster inventory to every prompt
+    pub enable_cluster_context: bool,
+
+    /// Seconds between refreshes of the cluster inventory
+    pub cluster_context_refresh_secs: u64,
+
+    /// Webhook that new cluster alerts are POSTed to as `{"text": ...}`
+    pub alert_webhook_url: Option<String>,
 }
 
 impl Environment {
+    /// The shared configuration, loaded from the environment on first use.
+    ///
+    /// Loading reads environment variables and files, so modules should use this rather
+    /// than calling [`Environment::new`] themselves.
+    pub fn global() -> &'static Environment {
+        ENVIRONMENT.get_or_init(Environment::new)
+    }
+
+    /// Makes `env` the shared configuration returned by [`Environment::global`], which
+    /// lets startup load it at a well-defined point (or inject a different one).
+    ///
+    /// Has no effect if the shared configuration was already loaded; the configuration
+    /// actually in use is returned either way.
+    pub fn install(env: Environment) -> &'static Environment {
+        ENVIRONMENT.get_or_init(|| env)
+    }
+
     /// Creates a new Environment by loading configuration from environment variables.
     ///
     /// In production mode (PRODUCTION_MODE=true):
@@ -34,44 +470,154 @@ impl Environment {
     /// - Uses CA certificates for secure cluster communication
     ///
     /// In development mode:
-    /// - Loads all credentials from environment variables
-    /// - Accepts self-signed certificates for local clusters
+    /// - Loads all credentials from environment variables, or from the files their
+    ///   `*_FILE` variants point at
+    ///
+    /// Defaults for the mode, write tools, and certificate checks come from the
+    /// [`Profile`] selected with `APP_PROFILE`.
+    ///
+    /// Exits the process if the config file can't be read or is invalid.
     pub fn new() -> Self {
-        let openai_api_key = match std::env::var("OPENAI_API_KEY") {
-            Ok(key) => {
-                debug!("OPENAI_API_KEY loaded from environment");
-                key
-            }
-            Err(_) => {
-                warn!("OPENAI_API_KEY not found in environment, using empty string");
-                String::new()
-            }
-        };
+        let config = ConfigFile::load(ConfigFile::path().as_deref()).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        });
+
+        let profile = Profile::from_env();
+        info!("Configuration profile: {:?}", profile);
+
+        let openai_api_key = read_secret("OPENAI_API_KEY").unwrap_or_else(|| {
+            warn!("OPENAI_API_KEY not found in environment, using empty string");
+            String::new()
+        });
 
-        let production_mode = match std::env::var("PRODUCTION_MODE") {
+        let production_mode = match config.var("PRODUCTION_MODE") {
             Ok(val) => {
                 let is_production = val.to_lowercase() == "true";
                 info!("Production mode: {}", is_production);
                 is_production
             }
             Err(_) => {
-                debug!("PRODUCTION_MODE not set, defaulting to false");
-                false
+                debug!(
+                    "PRODUCTION_MODE not set, defaulting to {}",
+                    profile.production_mode()
+                );
+                profile.production_mode()
             }
         };
 
-        let chat_api_key = match std::env::var("CHAT_API_KEY") {
-            Ok(key) => {
-                debug!("CHAT_API_KEY loaded from environment");
-                key
+        let chat_api_key_hash = match config.var("CHAT_API_KEY_HASH") {
+            Ok(hash) => {
+                debug!("CHAT_API_KEY_HASH loaded from environment");
+                Some(hash)
             }
-            Err(_) => {
-                warn!("CHAT_API_KEY not found in environment, using empty string");
-                String::new()
+            Err(_) => None,
+        };
+        let chat_api_keys_file = match config.var("CHAT_API_KEYS_FILE") {
+            Ok(path) => {
+                debug!("CHAT_API_KEYS_FILE loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+        let auth_lockout_free_attempts = parse_env(&config, "AUTH_LOCKOUT_FREE_ATTEMPTS", 5);
+        let auth_lockout_base_secs = parse_env(&config, "AUTH_LOCKOUT_BASE_SECS", 30);
+        let auth_lockout_max_secs = parse_env(&config, "AUTH_LOCKOUT_MAX_SECS", 3600);
+        let mut trusted_proxies: Vec<Cidr> = match config.var("TRUSTED_PROXIES") {
+            Ok(networks) => {
+                debug!("TRUSTED_PROXIES loaded from environment");
+                parse_list(&networks)
+                    .into_iter()
+                    .filter_map(|network| match network.parse() {
+                        Ok(network) => Some(network),
+                        Err(e) => {
+                            warn!("Ignoring invalid network in TRUSTED_PROXIES: {}", e);
+                            None
+                        }
+                    })
+                    .collect()
             }
+            Err(_) => Vec::new(),
         };
+        // The older switch trusted the header from any peer
+        if trusted_proxies.is_empty() && parse_env(&config, "TRUST_FORWARDED_FOR", false) {
+            warn!("TRUST_FORWARDED_FOR lets any client pick its address; set TRUSTED_PROXIES to the proxies' networks instead");
+            trusted_proxies = vec![Cidr::ANY_IPV4, Cidr::ANY_IPV6];
+        }
+        let forwarded_header = parse_env(&config, "FORWARDED_HEADER", ForwardedHeader::default());
+        let tls_cert_file = match config.var("TLS_CERT_FILE") {
+            Ok(path) => {
+                debug!("TLS_CERT_FILE loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+        let tls_key_file = match config.var("TLS_KEY_FILE") {
+            Ok(path) => {
+                debug!("TLS_KEY_FILE loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+        let mtls_client_ca_file = match config.var("MTLS_CLIENT_CA_FILE") {
+            Ok(path) => {
+                debug!("MTLS_CLIENT_CA_FILE loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+        let mtls_identities_file = match config.var("MTLS_IDENTITIES_FILE") {
+            Ok(path) => {
+                debug!("MTLS_IDENTITIES_FILE loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+        let grpc_port = match config.var("GRPC_PORT") {
+            Ok(port) => match port.parse() {
+                Ok(port) => {
+                    debug!("GRPC_PORT loaded from environment");
+                    Some(port)
+                }
+                Err(_) => {
+                    warn!("Ignoring invalid GRPC_PORT {:?}", port);
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        let telegram_bot_token =
+            read_secret("TELEGRAM_BOT_TOKEN").filter(|token| !token.is_empty());
+        let telegram_allowed_users = match config.var("TELEGRAM_ALLOWED_USERS") {
+            Ok(users) => {
+                debug!("TELEGRAM_ALLOWED_USERS loaded from environment");
+                parse_list(&users)
+                    .into_iter()
+                    .filter_map(|id| match id.parse() {
+                        Ok(id) => Some(id),
+                        Err(_) => {
+                            warn!(
+                                "Ignoring invalid Telegram user id {:?} in TELEGRAM_ALLOWED_USERS",
+                                id
+                            );
+                            None
+                        }
+                    })
+                    .collect()
+            }
+            Err(_) => Vec::new(),
+        };
+        let chat_api_key = read_secret("CHAT_API_KEY").unwrap_or_else(|| {
+            if chat_api_key_hash.is_none()
+                && chat_api_keys_file.is_none()
+                && mtls_client_ca_file.is_none()
+            {
+                warn!("CHAT_API_KEY not found in environment, using empty string");
+            }
+            String::new()
+        });
 
-        let kube_api_server = match std::env::var("KUBE_API_SERVER") {
+        let kube_api_server = match config.var("KUBE_API_SERVER") {
             Ok(url) => {
                 debug!("KUBE_API_SERVER loaded from environment");
                 url
@@ -84,7 +630,7 @@ impl Environment {
 
         let kube_token = if production_mode {
             debug!("Production mode: loading Kubernetes token from mounted service account");
-            match std::fs::read_to_string("/var/run/secrets/kubernetes.io/serviceaccount/token") {
+            match std::fs::read_to_string(SERVICE_ACCOUNT_TOKEN) {
                 Ok(token) => {
                     debug!("Kubernetes token loaded from service account");
                     token
@@ -98,21 +644,15 @@ impl Environment {
             }
         } else {
             debug!("Development mode: loading Kubernetes token from KUBE_TOKEN environment variable");
-            match std::env::var("KUBE_TOKEN") {
-                Ok(token) => {
-                    debug!("KUBE_TOKEN loaded from environment");
-                    token
-                }
-                Err(_) => {
-                    warn!("KUBE_TOKEN not found in environment, using empty string");
-                    String::new()
-                }
-            }
+            read_secret("KUBE_TOKEN").unwrap_or_else(|| {
+                warn!("KUBE_TOKEN not found in environment, using empty string");
+                String::new()
+            })
         };
 
         let kube_certificate = if production_mode {
             debug!("Production mode: loading Kubernetes CA certificate from mounted service account");
-            match std::fs::read("/var/run/secrets/kubernetes.io/serviceaccount/ca.crt") {
+            match std::fs::read(SERVICE_ACCOUNT_CA_CERT) {
                 Ok(cert_bytes) => match Certificate::from_pem(&cert_bytes) {
                     Ok(cert) => {
                         debug!("Kubernetes CA certificate loaded from service account");
@@ -137,13 +677,675 @@ impl Environment {
             None
         };
 
+        let kube_accept_invalid_certs = parse_env(
+            &config,
+            "KUBE_ACCEPT_INVALID_CERTS",
+            profile.accept_invalid_certs(),
+        );
+
+        let openai_max_attempts = parse_env(&config, "OPENAI_MAX_ATTEMPTS", 3);
+        let openai_retry_base_delay_ms = parse_env(&config, "OPENAI_RETRY_BASE_DELAY_MS", 500);
+        let openai_retry_max_delay_ms = parse_env(&config, "OPENAI_RETRY_MAX_DELAY_MS", 8000);
+
+        let agent_models = match config.var("AGENT_MODELS") {
+            Ok(models) => {
+                debug!("AGENT_MODELS loaded from environment");
+                parse_list(&models)
+            }
+            Err(_) => {
+                debug!("AGENT_MODELS not set, defaulting to openai:gpt-5.1");
+                vec!["openai:gpt-5.1".to_string()]
+            }
+        };
+        let openai_base_url = match config.var("OPENAI_BASE_URL") {
+            Ok(url) => {
+                debug!("OPENAI_BASE_URL loaded from environment");
+                Some(url)
+            }
+            Err(_) => None,
+        };
+        let agent_model_timeout_secs = parse_env(&config, "AGENT_MODEL_TIMEOUT_SECS", 90);
+        let chat_timeout_secs = parse_env(&config, "CHAT_TIMEOUT_SECS", 120);
+
+        let max_prompt_chars = parse_env(&config, "MAX_PROMPT_CHARS", 8000);
+        let max_prompt_tokens = parse_env(&config, "MAX_PROMPT_TOKENS", 2000);
+        let max_history_chars = parse_env(&config, "MAX_HISTORY_CHARS", 48000);
+        let max_history_tokens = parse_env(&config, "MAX_HISTORY_TOKENS", 12000);
+        let max_history_messages = parse_env(&config, "MAX_HISTORY_MESSAGES", 50);
+        let max_response_chars = parse_env(&config, "MAX_RESPONSE_CHARS", 20000);
+
+        let audit_log_path = match config.var("AUDIT_LOG_PATH") {
+            Ok(path) => {
+                debug!("AUDIT_LOG_PATH loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+        let audit_log_retention_days = parse_env(&config, "AUDIT_LOG_RETENTION_DAYS", 30);
+        let daily_digest_enabled = parse_env(&config, "DAILY_DIGEST_ENABLED", false);
+        let daily_digest_hour_utc = parse_env(&config, "DAILY_DIGEST_HOUR_UTC", 8);
+        let usage_window_days = match config.var("USAGE_WINDOW_DAYS") {
+            Ok(windows) => {
+                debug!("USAGE_WINDOW_DAYS loaded from environment");
+                parse_list(&windows)
+                    .into_iter()
+                    .filter_map(|days| match days.parse::<u64>() {
+                        Ok(days) if days > 0 => Some(days),
+                        _ => {
+                            warn!("Ignoring invalid window {:?} in USAGE_WINDOW_DAYS", days);
+                            None
+                        }
+                    })
+                    .collect()
+            }
+            Err(_) => vec![1, 7, 30],
+        };
+        let usage_prompt_price_per_mtok = parse_env(&config, "USAGE_PROMPT_PRICE_PER_MTOK", 1.25);
+        let usage_response_price_per_mtok =
+            parse_env(&config, "USAGE_RESPONSE_PRICE_PER_MTOK", 10.0);
+
+        let request_warn_latency_ms = parse_env(&config, "REQUEST_WARN_LATENCY_MS", 60000);
+        let request_warn_tokens = parse_env(&config, "REQUEST_WARN_TOKENS", 6000);
+        let request_warn_tool_calls = parse_env(&config, "REQUEST_WARN_TOOL_CALLS", 15);
+        let request_warn_webhook_url = match config.var("REQUEST_WARN_WEBHOOK_URL") {
+            Ok(url) => {
+                debug!("REQUEST_WARN_WEBHOOK_URL loaded from environment");
+                Some(url)
+            }
+            Err(_) => None,
+        };
+
+        let shed_model_latency_ms = parse_env(&config, "SHED_MODEL_LATENCY_MS", 0);
+        let shed_model_error_rate = parse_env(&config, "SHED_MODEL_ERROR_RATE", 0.0);
+        let shed_window_secs = parse_env(&config, "SHED_WINDOW_SECS", 120);
+        let shed_min_calls = parse_env(&config, "SHED_MIN_CALLS", 5);
+
+        let moderation_enabled = parse_env(&config, "MODERATION_ENABLED", false);
+        let moderation_model = parse_env(
+            &config,
+            "MODERATION_MODEL",
+            "omni-moderation-latest".to_string(),
+        );
+        let abuse_cooldown_flags = parse_env(&config, "ABUSE_COOLDOWN_FLAGS", 3);
+        let abuse_cooldown_secs = parse_env(&config, "ABUSE_COOLDOWN_SECS", 900);
+        let abuse_shadow_ban_flags = parse_env(&config, "ABUSE_SHADOW_BAN_FLAGS", 10);
+        let abuse_window_secs = parse_env(&config, "ABUSE_WINDOW_SECS", 86400);
+
+        let notify_webhook_urls = match config.var("NOTIFY_WEBHOOK_URLS") {
+            Ok(urls) => {
+                debug!("NOTIFY_WEBHOOK_URLS loaded from environment");
+                parse_list(&urls)
+            }
+            Err(_) => Vec::new(),
+        };
+        let notify_slack_webhook_urls = match config.var("NOTIFY_SLACK_WEBHOOK_URLS") {
+            Ok(urls) => {
+                debug!("NOTIFY_SLACK_WEBHOOK_URLS loaded from environment");
+                parse_list(&urls)
+            }
+            Err(_) => Vec::new(),
+        };
+        let notify_webhook_secret =
+            read_secret("NOTIFY_WEBHOOK_SECRET").filter(|secret| !secret.is_empty());
+        let notify_max_attempts = parse_env(&config, "NOTIFY_MAX_ATTEMPTS", 3);
+
+        let summary_model = match config.var("SUMMARY_MODEL") {
+            Ok(model) => {
+                debug!("SUMMARY_MODEL loaded from environment");
+                model
+            }
+            Err(_) => {
+                debug!("SUMMARY_MODEL not set, defaulting to openai:gpt-5-mini");
+                "openai:gpt-5-mini".to_string()
+            }
+        };
+        let summary_trigger_tokens = parse_env(&config, "SUMMARY_TRIGGER_TOKENS", 6000);
+        let summary_keep_recent = parse_env(&config, "SUMMARY_KEEP_RECENT", 6);
+        let session_ttl_secs = parse_env(&config, "SESSION_TTL_SECS", 86400);
+        let max_sessions = parse_env(&config, "MAX_SESSIONS", 10000);
+
+        let tool_output_max_chars = parse_env(&config, "TOOL_OUTPUT_MAX_CHARS", 12000);
+        let tool_output_budgets = match config.var("TOOL_OUTPUT_BUDGETS") {
+            Ok(budgets) => {
+                debug!("TOOL_OUTPUT_BUDGETS loaded from environment");
+                parse_list(&budgets)
+            }
+            Err(_) => {
+                debug!("TOOL_OUTPUT_BUDGETS not set, using the default budget for every tool");
+                Vec::new()
+            }
+        };
+        let tool_output_summarize = parse_env(&config, "TOOL_OUTPUT_SUMMARIZE", false);
+
+        let portfolio_base_url = match config.var("PORTFOLIO_BASE_URL") {
+            Ok(url) => {
+                debug!("PORTFOLIO_BASE_URL loaded from environment");
+                url
+            }
+            Err(_) if production_mode => "https://about.calum.run".to_string(),
+            Err(_) => "http://localhost:3000".to_string(),
+        };
+        let portfolio_pages = match config.var("PORTFOLIO_PAGES") {
+            Ok(pages) => {
+                debug!("PORTFOLIO_PAGES loaded from environment");
+                parse_list(&pages)
+            }
+            Err(_) => ["About", "Work", "Projects", "Contact"]
+                .iter()
+                .map(|tab| format!("{}=/?tab={}", tab, tab))
+                .collect(),
+        };
+
+        let fetch_domain_allowlist = match config.var("FETCH_DOMAIN_ALLOWLIST") {
+            Ok(allowlist) => {
+                debug!("FETCH_DOMAIN_ALLOWLIST loaded from environment");
+                parse_list(&allowlist)
+            }
+            Err(_) => {
+                debug!(
+                    "FETCH_DOMAIN_ALLOWLIST not set, fetch_url may only fetch the portfolio site"
+                );
+                reqwest::Url::parse(&portfolio_base_url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .into_iter()
+                    .collect()
+            }
+        };
+        let http_user_agent = match config.var("HTTP_USER_AGENT") {
+            Ok(user_agent) => {
+                debug!("HTTP_USER_AGENT loaded from environment");
+                user_agent
+            }
+            Err(_) => format!("rust-agent/{}", env!("CARGO_PKG_VERSION")),
+        };
+        // May carry credentials, so read like the other secrets
+        let portfolio_headers = read_secret("PORTFOLIO_HEADERS")
+            .map(|headers| parse_list(&headers))
+            .unwrap_or_default();
+
+        let enable_portfolio_index = parse_env(&config, "ENABLE_PORTFOLIO_INDEX", false);
+        let portfolio_index_refresh_secs = parse_env(&config, "PORTFOLIO_INDEX_REFRESH_SECS", 3600);
+        let portfolio_crawl_max_depth = parse_env(&config, "PORTFOLIO_CRAWL_MAX_DEPTH", 2);
+        let portfolio_crawl_max_pages = parse_env(&config, "PORTFOLIO_CRAWL_MAX_PAGES", 50);
+        let web_cache_ttl_secs = parse_env(&config, "WEB_CACHE_TTL_SECS", 300);
+        let search_api_provider = match config.var("SEARCH_API_PROVIDER") {
+            Ok(provider) => {
+                debug!("SEARCH_API_PROVIDER loaded from environment");
+                provider
+            }
+            Err(_) => "brave".to_string(),
+        };
+        let search_api_key = read_secret("SEARCH_API_KEY").filter(|key| !key.is_empty());
+        if search_api_key.is_none() {
+            debug!("SEARCH_API_KEY not set, internet_search will not be registered");
+        }
+        let contact_mail_provider = match config.var("CONTACT_MAIL_PROVIDER") {
+            Ok(provider) => {
+                debug!("CONTACT_MAIL_PROVIDER loaded from environment");
+                provider
+            }
+            Err(_) => "resend".to_string(),
+        };
+        let contact_mail_api_key =
+            read_secret("CONTACT_MAIL_API_KEY").filter(|key| !key.is_empty());
+        let contact_email_from = match config.var("CONTACT_EMAIL_FROM") {
+            Ok(address) => {
+                debug!("CONTACT_EMAIL_FROM loaded from environment");
+                Some(address)
+            }
+            Err(_) => None,
+        };
+        let contact_email_to = match config.var("CONTACT_EMAIL_TO") {
+            Ok(address) => {
+                debug!("CONTACT_EMAIL_TO loaded from environment");
+                Some(address)
+            }
+            Err(_) => None,
+        };
+        let contact_max_per_hour = parse_env(&config, "CONTACT_MAX_PER_HOUR", 5);
+        let github_repos = match config.var("GITHUB_REPOS") {
+            Ok(repos) => {
+                debug!("GITHUB_REPOS loaded from environment");
+                parse_list(&repos)
+            }
+            Err(_) => {
+                debug!("GITHUB_REPOS not set, ci_status will not be registered");
+                Vec::new()
+            }
+        };
+        let github_token = read_secret("GITHUB_TOKEN").filter(|token| !token.is_empty());
+        let fetch_max_bytes = parse_env(&config, "FETCH_MAX_BYTES", 1_048_576);
+        let fetch_timeout_secs = parse_env(&config, "FETCH_TIMEOUT_SECS", 10);
+        let fetch_max_concurrency = parse_env(&config, "FETCH_MAX_CONCURRENCY", 4);
+
+        let cluster_name = match config.var("CLUSTER_NAME") {
+            Ok(name) => {
+                debug!("CLUSTER_NAME loaded from environment");
+                Some(name)
+            }
+            Err(_) => {
+                debug!("CLUSTER_NAME not set, cluster name will not be injected into prompts");
+                None
+            }
+        };
+
+        let configmap_value_allowlist = match config.var("CONFIGMAP_VALUE_ALLOWLIST") {
+            Ok(allowlist) => {
+                debug!("CONFIGMAP_VALUE_ALLOWLIST loaded from environment");
+                parse_list(&allowlist)
+            }
+            Err(_) => {
+                debug!("CONFIGMAP_VALUE_ALLOWLIST not set, all configmap values will be redacted");
+                Vec::new()
+            }
+        };
+
+        let enable_write_tools =
+            parse_env(&config, "ENABLE_WRITE_TOOLS", profile.enable_write_tools());
+        let write_namespace_allowlist = match config.var("WRITE_NAMESPACE_ALLOWLIST") {
+            Ok(allowlist) => {
+                debug!("WRITE_NAMESPACE_ALLOWLIST loaded from environment");
+                parse_list(&allowlist)
+            }
+            Err(_) => {
+                if enable_write_tools {
+                    warn!("ENABLE_WRITE_TOOLS is set but WRITE_NAMESPACE_ALLOWLIST is empty, write tools will refuse every namespace");
+                }
+                Vec::new()
+            }
+        };
+
+        let delete_pod_label_allowlist = match config.var("DELETE_POD_LABEL_ALLOWLIST") {
+            Ok(allowlist) => {
+                debug!("DELETE_POD_LABEL_ALLOWLIST loaded from environment");
+                parse_list(&allowlist)
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let raw_get_path_allowlist = match config.var("RAW_GET_PATH_ALLOWLIST") {
+            Ok(allowlist) => {
+                debug!("RAW_GET_PATH_ALLOWLIST loaded from environment");
+                parse_list(&allowlist)
+            }
+            Err(_) => {
+                debug!("RAW_GET_PATH_ALLOWLIST not set, using /api/v1 and /apis/apps/v1");
+                vec!["/api/v1".to_string(), "/apis/apps/v1".to_string()]
+            }
+        };
+        let raw_get_max_bytes = parse_env(&config, "RAW_GET_MAX_BYTES", 32768);
+
+        let cost_cpu_monthly = parse_env(&config, "COST_CPU_MONTHLY", 20.0);
+        let cost_memory_gib_monthly = parse_env(&config, "COST_MEMORY_GIB_MONTHLY", 2.5);
+        let cost_currency = parse_env(&config, "COST_CURRENCY", "USD".to_string());
+
+        let kube_cache_ttl_secs = parse_env(&config, "KUBE_CACHE_TTL_SECS", 5);
+        let kube_cache_ttls = match config.var("KUBE_CACHE_TTLS") {
+            Ok(ttls) => {
+                debug!("KUBE_CACHE_TTLS loaded from environment");
+                parse_list(&ttls)
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let kube_api_qps = parse_env(&config, "KUBE_API_QPS", 10.0);
+        let kube_api_burst = parse_env(&config, "KUBE_API_BURST", 30);
+        let kube_breaker_failures = parse_env(&config, "KUBE_BREAKER_FAILURES", 5);
+        let kube_breaker_cooldown_secs = parse_env(&config, "KUBE_BREAKER_COOLDOWN_SECS", 30);
+
+        let kube_connect_timeout_secs = parse_env(&config, "KUBE_CONNECT_TIMEOUT_SECS", 5);
+        let kube_request_timeout_secs = parse_env(&config, "KUBE_REQUEST_TIMEOUT_SECS", 30);
+
+        let kube_clusters_file = match config.var("KUBE_CLUSTERS_FILE") {
+            Ok(path) => {
+                debug!("KUBE_CLUSTERS_FILE loaded from environment");
+                Some(path)
+            }
+            Err(_) => None,
+        };
+
+        let enable_cluster_watch = parse_env(&config, "ENABLE_CLUSTER_WATCH", false);
+        let enable_cluster_context = parse_env(&config, "ENABLE_CLUSTER_CONTEXT", false);
+        let cluster_context_refresh_secs = parse_env(&config, "CLUSTER_CONTEXT_REFRESH_SECS", 300);
+        let alert_webhook_url = match config.var("ALERT_WEBHOOK_URL") {
+            Ok(url) => {
+                debug!("ALERT_WEBHOOK_URL loaded from environment");
+                Some(url)
+            }
+            Err(_) => None,
+        };
+
         Environment {
+            profile,
             openai_api_key,
             production_mode,
             chat_api_key,
+            chat_api_key_hash,
+            chat_api_keys_file,
+            auth_lockout_free_attempts,
+            auth_lockout_base_secs,
+            auth_lockout_max_secs,
+            trusted_proxies,
+            forwarded_header,
+            tls_cert_file,
+            tls_key_file,
+            mtls_client_ca_file,
+            mtls_identities_file,
+            grpc_port,
+            telegram_bot_token,
+            telegram_allowed_users,
             kube_api_server,
             kube_token,
             kube_certificate,
+            kube_accept_invalid_certs,
+            openai_max_attempts,
+            openai_retry_base_delay_ms,
+            openai_retry_max_delay_ms,
+            agent_models,
+            openai_base_url,
+            agent_model_timeout_secs,
+            chat_timeout_secs,
+            max_prompt_chars,
+            max_prompt_tokens,
+            max_history_chars,
+            max_history_tokens,
+            max_history_messages,
+            max_response_chars,
+            audit_log_path,
+            audit_log_retention_days,
+            daily_digest_enabled,
+            daily_digest_hour_utc,
+            usage_window_days,
+            usage_prompt_price_per_mtok,
+            usage_response_price_per_mtok,
+            request_warn_latency_ms,
+            request_warn_tokens,
+            request_warn_tool_calls,
+            request_warn_webhook_url,
+            shed_model_latency_ms,
+            shed_model_error_rate,
+            shed_window_secs,
+            shed_min_calls,
+            moderation_enabled,
+            moderation_model,
+            abuse_cooldown_flags,
+            abuse_cooldown_secs,
+            abuse_shadow_ban_flags,
+            abuse_window_secs,
+            notify_webhook_urls,
+            notify_slack_webhook_urls,
+            notify_webhook_secret,
+            notify_max_attempts,
+            summary_model,
+            summary_trigger_tokens,
+            summary_keep_recent,
+            session_ttl_secs,
+            max_sessions,
+            tool_output_max_chars,
+            tool_output_budgets,
+            tool_output_summarize,
+            portfolio_base_url,
+            portfolio_pages,
+            fetch_domain_allowlist,
+            http_user_agent,
+            portfolio_headers,
+            enable_portfolio_index,
+            portfolio_index_refresh_secs,
+            portfolio_crawl_max_depth,
+            portfolio_crawl_max_pages,
+            web_cache_ttl_secs,
+            search_api_provider,
+            search_api_key,
+            contact_mail_provider,
+            contact_mail_api_key,
+            contact_email_from,
+            contact_email_to,
+            contact_max_per_hour,
+            github_repos,
+            github_token,
+            fetch_max_bytes,
+            fetch_timeout_secs,
+            fetch_max_concurrency,
+            cluster_name,
+            configmap_value_allowlist,
+            enable_write_tools,
+            write_namespace_allowlist,
+            delete_pod_label_allowlist,
+            raw_get_path_allowlist,
+            raw_get_max_bytes,
+            cost_cpu_monthly,
+            cost_memory_gib_monthly,
+            cost_currency,
+            kube_cache_ttl_secs,
+            kube_cache_ttls,
+            kube_api_qps,
+            kube_api_burst,
+            kube_breaker_failures,
+            kube_breaker_cooldown_secs,
+            kube_connect_timeout_secs,
+            kube_request_timeout_secs,
+            kube_clusters_file,
+            enable_cluster_watch,
+            enable_cluster_context,
+            cluster_context_refresh_secs,
+            alert_webhook_url,
+        }
+    }
+
+    /// Checks that everything the server needs is present and usable, returning every
+    /// problem found rather than only the first.
+    ///
+    /// Required in all modes: `OPENAI_API_KEY`, at least one usable API key (from
+    /// `CHAT_API_KEY`, `CHAT_API_KEY_HASH`, or `CHAT_API_KEYS_FILE`) unless mutual TLS is
+    /// configured, and a Kubernetes token
+    /// (the mounted service account token in production, `KUBE_TOKEN` otherwise).
+    /// Production mode also requires a CA certificate that parses. The Kubernetes checks
+    /// are skipped when built with `kube-rs`, which reads kubeconfig instead.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        if self.openai_api_key.trim().is_empty() {
+            problems.push("OPENAI_API_KEY (or OPENAI_API_KEY_FILE) is not set".to_string());
+        }
+        match ApiKeys::new(
+            &self.chat_api_key,
+            self.chat_api_key_hash.as_deref(),
+            self.chat_api_keys_file.as_deref(),
+        ) {
+            // Under mutual TLS, clients are identified by their certificates instead
+            Ok(keys) if keys.is_empty() && self.mtls_client_ca_file.is_none() => problems.push(
+                "No API key is set (CHAT_API_KEY, CHAT_API_KEY_FILE, CHAT_API_KEY_HASH, or CHAT_API_KEYS_FILE)"
+                    .to_string(),
+            ),
+            Ok(_) => {}
+            Err(e) => problems.push(e),
+        }
+        if let Err(e) = self.mutual_tls() {
+            problems.push(e);
+        }
+        // With kube-rs, the home cluster's credentials come from kubeconfig instead
+        if !cfg!(feature = "kube-rs") && self.kube_token.trim().is_empty() {
+            problems.push(if self.production_mode {
+                format!(
+                    "Kubernetes token is missing or empty at {}",
+                    SERVICE_ACCOUNT_TOKEN
+                )
+            } else {
+                "KUBE_TOKEN (or KUBE_TOKEN_FILE) is not set".to_string()
+            });
+        }
+        if !cfg!(feature = "kube-rs") && self.production_mode && self.kube_certificate.is_none() {
+            problems.push(format!(
+                "Kubernetes CA certificate at {} is missing or not valid PEM",
+                SERVICE_ACCOUNT_CA_CERT
+            ));
+        }
+
+        if self.profile == Profile::Prod && self.kube_accept_invalid_certs {
+            problems.push(
+                "KUBE_ACCEPT_INVALID_CERTS=true is not allowed with APP_PROFILE=prod".to_string(),
+            );
+        }
+
+        if self.agent_models.is_empty() {
+            problems.push("AGENT_MODELS must name at least one model".to_string());
+        }
+        if self.openai_max_attempts == 0 {
+            problems.push("OPENAI_MAX_ATTEMPTS must be at least 1".to_string());
+        }
+        if self.chat_timeout_secs == 0 {
+            problems.push("CHAT_TIMEOUT_SECS must be greater than 0".to_string());
+        }
+        if self.fetch_timeout_secs == 0 {
+            problems.push("FETCH_TIMEOUT_SECS must be greater than 0".to_string());
+        }
+        if self.session_ttl_secs == 0 {
+            problems.push("SESSION_TTL_SECS must be greater than 0".to_string());
+        }
+        if self.max_sessions == 0 {
+            problems.push("MAX_SESSIONS must be at least 1".to_string());
+        }
+        if self.daily_digest_enabled && self.audit_log_path.is_none() {
+            problems.push("DAILY_DIGEST_ENABLED requires AUDIT_LOG_PATH".to_string());
+        }
+        if self.daily_digest_hour_utc > 23 {
+            problems.push("DAILY_DIGEST_HOUR_UTC must be between 0 and 23".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Notifier for the configured webhooks. `ALERT_WEBHOOK_URL` and
+    /// `REQUEST_WARN_WEBHOOK_URL` are Slack-format webhooks for their one kind of event.
+    pub fn notifier(&self) -> Notifier {
+        let generic = self
+            .notify_webhook_urls
+            .iter()
+            .map(|url| Webhook::new(url.clone(), WebhookFormat::Generic));
+        let slack = self
+            .notify_slack_webhook_urls
+            .iter()
+            .map(|url| Webhook::new(url.clone(), WebhookFormat::Slack));
+        let alerts = self.alert_webhook_url.iter().map(|url| {
+            Webhook::new(url.clone(), WebhookFormat::Slack).only(&[EventKind::ClusterAlert])
+        });
+        let overruns = self.request_warn_webhook_url.iter().map(|url| {
+            Webhook::new(url.clone(), WebhookFormat::Slack).only(&[EventKind::RequestOverrun])
+        });
+
+        Notifier::new(
+            generic.chain(slack).chain(alerts).chain(overruns).collect(),
+            self.notify_webhook_secret.clone(),
+            self.notify_max_attempts,
+        )
+    }
+
+    /// Prices the estimate_costs tool charges for requested resources.
+    pub fn price_table(&self) -> PriceTable {
+        PriceTable {
+            cpu_monthly: self.cost_cpu_monthly,
+            memory_gib_monthly: self.cost_memory_gib_monthly,
+            currency: self.cost_currency.clone(),
+        }
+    }
+
+    /// Loads the mutual TLS configuration if `MTLS_CLIENT_CA_FILE` is set.
+    pub fn mutual_tls(&self) -> Result<Option<MutualTls>, String> {
+        let Some(client_ca_file) = &self.mtls_client_ca_file else {
+            // The server only serves TLS with client certificates, so don't let a lone
+            // certificate suggest otherwise
+            if self.tls_cert_file.is_some() || self.tls_key_file.is_some() {
+                return Err(
+                    "TLS_CERT_FILE and TLS_KEY_FILE are only used with MTLS_CLIENT_CA_FILE"
+                        .to_string(),
+                );
+            }
+            return Ok(None);
+        };
+        let (Some(cert_file), Some(key_file)) = (&self.tls_cert_file, &self.tls_key_file) else {
+            return Err("MTLS_CLIENT_CA_FILE requires TLS_CERT_FILE and TLS_KEY_FILE".to_string());
+        };
+        MutualTls::load(
+            cert_file,
+            key_file,
+            client_ca_file,
+            self.mtls_identities_file.as_deref(),
+        )
+        .map(Some)
+    }
+}
+
+/// Reads a secret from the file named by `<name>_FILE` (the Docker/Kubernetes convention
+/// for mounted secrets), falling back to the `<name>` environment variable.
+///
+/// Surrounding whitespace, such as the trailing newline most secret files end with, is
+/// trimmed from file contents.
+fn read_secret(name: &str) -> Option<String> {
+    let file_var = format!("{}_FILE", name);
+    let Ok(path) = std::env::var(&file_var) else {
+        let value = std::env::var(name).ok();
+        if value.is_some() {
+            debug!("{} loaded from environment", name);
+        }
+        return value;
+    };
+
+    if std::env::var(name).is_ok() {
+        warn!("Both {} and {} are set, using {}", name, file_var, file_var);
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(value) => {
+            debug!("{} loaded from {}", name, path);
+            Some(value.trim().to_string())
+        }
+        Err(e) => {
+            warn!(
+                "Failed to read {} from {} ({}): {}",
+                name, file_var, path, e
+            );
+            None
+        }
+    }
+}
+
+/// Splits a comma-separated environment value into trimmed, non-empty entries.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Reads an optional environment variable (or its config file value) and parses it,
+/// falling back to `default` when the setting is missing or invalid.
+fn parse_env<T>(config: &ConfigFile, name: &str, default: T) -> T
+where
+    T: std::str::FromStr + std::fmt::Display,
+{
+    match config.var(name) {
+        Ok(val) => match val.trim().parse() {
+            Ok(parsed) => {
+                debug!("{} loaded from environment", name);
+                parsed
+            }
+            Err(_) => {
+                warn!(
+                    "Invalid value for {} ({:?}), using default {}",
+                    name, val, default
+                );
+                default
+            }
+        },
+        Err(_) => {
+            debug!("{} not set, defaulting to {}", name, default);
+            default
         }
     }
 }
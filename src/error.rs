@@ -0,0 +1,54 @@
+use crate::agent::error::ChatError;
+use crate::kube::error::KubeAgentError;
+use thiserror::Error;
+
+/// Errors from any of the agent's subsystems.
+///
+/// Subsystem errors convert into it with `?`, and [`AppError::status`] maps each to the
+/// HTTP status the server answers with.
+#[derive(Debug, Error)]
+pub enum AppError {
+    /// Missing or invalid configuration
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    /// The agent couldn't answer a chat request
+    #[error(transparent)]
+    Agent(#[from] ChatError),
+    /// A Kubernetes API call failed
+    #[error(transparent)]
+    Kube(#[from] KubeAgentError),
+    /// An outbound HTTP request (portfolio pages, web search, GitHub, ...) failed
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// A tool refused or couldn't complete a call; the message is shown to the model
+    #[error("{0}")]
+    Tool(String),
+}
+
+impl AppError {
+    /// HTTP status line for the error, e.g. "504 Gateway Timeout".
+    ///
+    /// Failures of a dependency (a model provider, the cluster, a website) answer 502 or
+    /// 504, while errors caused by the request itself answer 4xx.
+    pub fn status(&self) -> &'static str {
+        match self {
+            AppError::Config(_) | AppError::Tool(_) => "500 Internal Server Error",
+            AppError::Agent(ChatError::Timeout { .. }) => "504 Gateway Timeout",
+            AppError::Agent(ChatError::Failed(_) | ChatError::Interrupted { .. }) => {
+                "502 Bad Gateway"
+            }
+            AppError::Kube(error) => match error {
+                KubeAgentError::TimeoutError(_) => "504 Gateway Timeout",
+                KubeAgentError::UnknownCluster(_) | KubeAgentError::ParseError(_) => {
+                    "400 Bad Request"
+                }
+                KubeAgentError::PolicyViolation(_) => "403 Forbidden",
+                KubeAgentError::BackingOff(_) => "503 Service Unavailable",
+                KubeAgentError::ApiError { code: 404, .. } => "404 Not Found",
+                _ => "502 Bad Gateway",
+            },
+            AppError::Http(error) if error.is_timeout() => "504 Gateway Timeout",
+            AppError::Http(_) => "502 Bad Gateway",
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use crate::agent::models::{openai_client, ModelSpec, Provider};
+use crate::agent::{Agent, ChatOptions};
+use crate::environment::Environment;
+use crate::server::types::HttpMessage;
+use crate::sessions::SessionStore;
+use regex::Regex;
+use rig::client::CompletionClient;
+use rig::completion::{Message, Prompt};
+use rig::providers::openai::responses_api::ResponsesCompletionModel;
+use serde::Deserialize;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::*;
+
+const JUDGE_PREAMBLE: &str = "You grade answers produced by an AI assistant. You are given the user's prompt, the assistant's answer, and a criterion. Reply with PASS if the answer satisfies the criterion, otherwise FAIL, followed by a one-sentence reason.";
+
+/// A suite of evaluation cases loaded from YAML.
+///
+/// ```yaml
+/// name: portfolio-basics
+/// cases:
+///   - name: about-page
+///     prompt: "What is on Calum's About page?"
+///     mock_response: "Calum is a software engineer..."
+///     assertions:
+///       - contains: "Calum"
+///       - regex: "(?i)engineer"
+///       - judge: "The answer summarizes Calum's background"
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct EvalSuite {
+    pub name: String,
+    pub cases: Vec<EvalCase>,
+}
+
+/// A single prompt and the assertions its response must satisfy.
+#[derive(Debug, Deserialize)]
+pub struct EvalCase {
+    pub name: String,
+    pub prompt: String,
+    /// Optional prior conversation
+    #[serde(default)]
+    pub history: Vec<HttpMessage>,
+    /// Canned response used instead of the agent when running with `--mock`
+    pub mock_response: Option<String>,
+    pub assertions: Vec<Assertion>,
+}
+
+/// Checks applied to a response.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Assertion {
+    /// Response contains the substring (case-insensitive)
+    Contains(String),
+    /// Response does not contain the substring (case-insensitive)
+    NotContains(String),
+    /// Response matches the regular expression
+    Regex(String),
+    /// An LLM judge decides whether the response satisfies the criterion
+    Judge(String),
+}
+
+/// Where responses come from during an eval run.
+pub enum Backend {
+    /// The real agent, with its configured models and tools
+    Agent(Box<Agent>),
+    /// Each case's `mock_response`, for validating suites and assertions offline
+    Mock,
+}
+
+/// Outcome of a single assertion.
+enum Verdict {
+    Pass,
+    Fail(String),
+    Skipped(String),
+}
+
+/// Runs the `eval` subcommand: `eval <suite.yaml> [--mock]`.
+///
+/// Returns whether every case passed.
+pub async fn run_cli(suite_path: &str, mock: bool) -> Result<bool, Box<dyn Error>> {
+    let suite = load_suite(suite_path)?;
+    let env = Environment::global();
+
+    let backend = if mock {
+        Backend::Mock
+    } else {
+        let sessions = Arc::new(SessionStore::from_env(env));
+        Backend::Agent(Box::new(Agent::new(env.openai_api_key.clone(), sessions)?))
+    };
+
+    // The judge needs a real model; without an API key judge assertions are skipped
+    let judge = if env.openai_api_key.is_empty() {
+        None
+    } else {
+        Some(Judge::new(&env.openai_api_key, &env.summary_model)?)
+    };
+
+    run_suite(&suite, &backend, judge.as_ref()).await
+}
+
+/// Loads and parses a YAML eval suite.
+pub fn load_suite(path: &str) -> Result<EvalSuite, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read eval suite {}: {}", path, e))?;
+    let suite: EvalSuite = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Invalid eval suite {}: {}", path, e))?;
+    info!(
+        "Loaded eval suite '{}' with {} cases",
+        suite.name,
+        suite.cases.len()
+    );
+    Ok(suite)
+}
+
+/// Runs every case in the suite, printing a pass/fail report.
+pub async fn run_suite(
+    suite: &EvalSuite,
+    backend: &Backend,
+    judge: Option<&Judge>,
+) -> Result<bool, Box<dyn Error>> {
+    println!("Running eval suite '{}'", suite.name);
+
+    let mut passed = 0;
+    for case in &suite.cases {
+        let started = Instant::now();
+        let response = match get_response(case, backend).await {
+            Ok(response) => response,
+            Err(e) => {
+                println!("FAIL  {} (error: {})", case.name, e);
+                continue;
+            }
+        };
+
+        let mut failures = Vec::new();
+        let mut skipped = 0;
+        for assertion in &case.assertions {
+            match check(assertion, case, &response, judge).await {
+                Verdict::Pass => {}
+                Verdict::Fail(reason) => failures.push(reason),
+                Verdict::Skipped(reason) => {
+                    debug!("Skipped assertion in {}: {}", case.name, reason);
+                    skipped += 1;
+                }
+            }
+        }
+
+        let elapsed_ms = started.elapsed().as_millis();
+        if failures.is_empty() {
+            passed += 1;
+            println!(
+                "PASS  {} ({}ms, {} skipped)",
+                case.name, elapsed_ms, skipped
+            );
+        } else {
+            println!("FAIL  {} ({}ms)", case.name, elapsed_ms);
+            for failure in failures {
+                println!("        - {}", failure);
+            }
+        }
+    }
+
+    let total = suite.cases.len();
+    println!("\n{}/{} cases passed", passed, total);
+    Ok(passed == total)
+}
+
+async fn get_response(case: &EvalCase, backend: &Backend) -> Result<String, Box<dyn Error>> {
+    match backend {
+        Backend::Mock => case
+            .mock_response
+            .clone()
+            .ok_or_else(|| "no mock_response defined for case".into()),
+        Backend::Agent(agent) => {
+            let history = case
+                .history
+                .iter()
+                .cloned()
+                .map(Message::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            let response = agent
+                .chat(case.prompt.clone(), history, ChatOptions::default())
+                .await?;
+            Ok(response.text)
+        }
+    }
+}
+
+async fn check(
+    assertion: &Assertion,
+    case: &EvalCase,
+    response: &str,
+    judge: Option<&Judge>,
+) -> Verdict {
+    match assertion {
+        Assertion::Contains(needle) => {
+            if response.to_lowercase().contains(&needle.to_lowercase()) {
+                Verdict::Pass
+            } else {
+                Verdict::Fail(format!("expected response to contain {:?}", needle))
+            }
+        }
+        Assertion::NotContains(needle) => {
+            if response.to_lowercase().contains(&needle.to_lowercase()) {
+                Verdict::Fail(format!("expected response not to contain {:?}", needle))
+            } else {
+                Verdict::Pass
+            }
+        }
+        Assertion::Regex(pattern) => match Regex::new(pattern) {
+            Ok(re) if re.is_match(response) => Verdict::Pass,
+            Ok(_) => Verdict::Fail(format!("expected response to match /{}/", pattern)),
+            Err(e) => Verdict::Fail(format!("invalid regex /{}/: {}", pattern, e)),
+        },
+        Assertion::Judge(criterion) => match judge {
+            Some(judge) => judge.grade(&case.prompt, response, criterion).await,
+            None => Verdict::Skipped("no judge model available".to_string()),
+        },
+    }
+}
+
+/// LLM judge used for `judge` assertions.
+pub struct Judge {
+    client: rig::agent::Agent<ResponsesCompletionModel>,
+}
+
+impl Judge {
+    pub fn new(api_key: &str, model: &str) -> Result<Self, Box<dyn Error>> {
+        let spec: ModelSpec = model.parse()?;
+        let client = match spec.provider {
+            Provider::OpenAI => {
+                openai_client(api_key, Environment::global().openai_base_url.as_deref())?
+                    .agent(&spec.model)
+                    .preamble(JUDGE_PREAMBLE)
+                    .build()
+            }
+        };
+        Ok(Judge { client })
+    }
+
+    async fn grade(&self, prompt: &str, response: &str, criterion: &str) -> Verdict {
+        let request = format!(
+            "Prompt:\n{}\n\nAnswer:\n{}\n\nCriterion:\n{}",
+            prompt, response, criterion
+        );
+
+        match self.client.prompt(request).await {
+            Ok(verdict) if verdict.trim_start().to_uppercase().starts_with("PASS") => Verdict::Pass,
+            Ok(verdict) => Verdict::Fail(format!("judge: {}", verdict.trim())),
+            Err(e) => Verdict::Fail(format!("judge call failed: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use crate::agent::error::ChatError;
+use crate::agent::permissions::{Capabilities, Capability};
+use crate::agent::trace::{ToolCallRecord, ToolEvent};
+use crate::agent::{Agent, AgentResponse, ChatOptions};
+use crate::error::AppError;
+use crate::server::admission::{Admission, Admitted, Refusal};
+use crate::server::audit::ChatAudit;
+use crate::server::language::parse_language;
+use crate::server::limits::RequestLimits;
+use crate::server::moderation::SHADOW_BAN_REPLY;
+use crate::server::types::HttpMessage;
+use crate::sessions::{SessionKey, SessionStore};
+use rig::completion::{Message, ToolDefinition};
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status};
+use tracing::*;
+
+/// Types generated from `proto/agent.proto`.
+pub mod proto {
+    tonic::include_proto!("agent.v1");
+}
+
+use proto::agent_service_server::{AgentService, AgentServiceServer};
+use proto::{
+    chat_event, ChatEvent, ChatRequest, ChatResponse, ListToolsRequest, ListToolsResponse, Tool,
+    ToolCall,
+};
+
+/// Metadata entry that carries the caller's API key.
+const API_KEY_METADATA: &str = "x-api-key";
+
+/// Metadata entry that tells refused callers how many seconds to wait before retrying.
+const RETRY_AFTER_METADATA: &str = "retry-after";
+
+/// gRPC counterpart of the HTTP server's /chat endpoint.
+///
+/// Shares the agent and session store with the HTTP server, so a conversation started
+/// over one API can be continued over the other. Calls authenticate with the same API
+/// keys, sent as `x-api-key` metadata, and are limited to the key's capabilities. They
+/// go through the HTTP server's [`Admission`] too, so lockouts, load shedding, and
+/// moderation apply to both APIs alike and chats over both are audited.
+pub struct GrpcService {
+    agent: Arc<Agent>,
+    sessions: Arc<SessionStore>,
+    admission: Arc<Admission>,
+    limits: RequestLimits,
+    /// Every tool the agent may be given, filtered per caller by ListTools
+    tools: Vec<ToolDefinition>,
+    /// Start time in hex, so request ids stay unique across restarts
+    request_id_prefix: String,
+    requests: AtomicU64,
+}
+
+/// A caller whose API key was accepted.
+struct Caller {
+    identity: String,
+    api_key: String,
+    capabilities: Capabilities,
+    /// The connection's peer address
+    address: Option<IpAddr>,
+}
+
+/// How an admitted chat call is answered.
+enum Prepared {
+    /// By the agent, with this history and these options
+    Agent(Vec<Message>, ChatOptions),
+    /// With [`SHADOW_BAN_REPLY`], without asking the agent
+    ShadowBanned,
+}
+
+impl GrpcService {
+    pub fn new(
+        agent: Arc<Agent>,
+        sessions: Arc<SessionStore>,
+        admission: Arc<Admission>,
+        limits: RequestLimits,
+        tools: Vec<ToolDefinition>,
+    ) -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        GrpcService {
+            agent,
+            sessions,
+            admission,
+            limits,
+            tools,
+            request_id_prefix: format!("grpc-{:x}", started),
+            requests: AtomicU64::new(0),
+        }
+    }
+
+    /// Serves the service on `addr` until the process exits.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        info!("gRPC server listening on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(AgentServiceServer::new(self))
+            .serve(addr)
+            .await
+    }
+
+    /// The id the next chat call is audited under.
+    fn next_request_id(&self) -> String {
+        format!(
+            "{}-{}",
+            self.request_id_prefix,
+            self.requests.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    /// Checks the call's API key, and that it grants the `required` capability if any.
+    ///
+    /// Lockouts are tracked by the connection's peer address; gRPC calls don't go
+    /// through the HTTP server's trusted proxies.
+    fn authenticate<T>(
+        &self,
+        request: &Request<T>,
+        required: Option<Capability>,
+    ) -> Result<Caller, Refusal> {
+        let api_key = request
+            .metadata()
+            .get(API_KEY_METADATA)
+            .and_then(|value| value.to_str().ok());
+        let address = request.remote_addr().map(|addr| addr.ip());
+        let (identity, capabilities) = self.admission.authenticate(api_key, address)?;
+
+        if let Some(required) = required.filter(|&c| !capabilities.allows(c)) {
+            warn!(
+                "{} lacks the {} capability for gRPC call",
+                identity, required
+            );
+            return Err(Refusal::NotPermitted);
+        }
+        Ok(Caller {
+            identity,
+            api_key: api_key.unwrap_or_default().to_string(),
+            capabilities,
+            address,
+        })
+    }
+
+    /// Admits a chat call from `caller` and builds the history and options to answer it
+    /// with, filling in `audit` as it goes.
+    ///
+    /// As with POST /chat, stored session history comes first, followed by any history
+    /// sent with the request, and long sessions are summarized before trimming.
+    async fn prepare(
+        &self,
+        caller: &Caller,
+        request: &ChatRequest,
+        audit: &mut ChatAudit,
+    ) -> Result<Prepared, Status> {
+        info!(
+            "Processing gRPC chat request ({} chars)",
+            request.prompt.len()
+        );
+        audit.session_id = request.session_id.clone();
+        audit.prompt = Some(request.prompt.clone());
+        if let Err(e) = self.limits.check_prompt(&request.prompt) {
+            warn!("Rejecting oversize prompt: {}", e);
+            audit.status = "413 Payload Too Large";
+            return Err(Status::invalid_argument(e.to_string()));
+        }
+
+        let language = match request.language.as_deref() {
+            Some(language) => match parse_language(language) {
+                Some(language) => Some(language),
+                None => {
+                    warn!("Rejecting invalid language: {:?}", language);
+                    audit.status = "400 Bad Request";
+                    return Err(Status::invalid_argument("Invalid language"));
+                }
+            },
+            None => None,
+        };
+
+        let admitted = self
+            .admission
+            .admit_chat(
+                &caller.identity,
+                caller.address,
+                &caller.capabilities,
+                &request.prompt,
+            )
+            .await;
+        match admitted {
+            Ok(Admitted::Agent) => {}
+            Ok(Admitted::ShadowBanned) => return Ok(Prepared::ShadowBanned),
+            Err(refusal) => {
+                audit.status = refusal.status();
+                return Err(refusal_status(refusal));
+            }
+        }
+
+        let session = request
+            .session_id
+            .as_ref()
+            .map(|session_id| SessionKey::new(caller.identity.as_str(), session_id.as_str()));
+        let mut history = match &session {
+            Some(session) => self.sessions.history(session),
+            None => Vec::new(),
+        };
+        history.extend(request.history.iter().map(|message| HttpMessage {
+            role: message.role.clone(),
+            content: message.content.clone(),
+        }));
+        if let Some(session) = &session {
+            history = self.sessions.compact(&self.agent, session, history).await;
+        }
+
+        let chat_history = self
+            .limits
+            .trim_history(history)
+            .into_iter()
+            .map(Message::try_from)
+            .collect::<Result<Vec<_>, _>>();
+        let chat_history = match chat_history {
+            Ok(chat_history) => chat_history,
+            Err(e) => {
+                warn!("Invalid message role in chat history: {}", e);
+                audit.status = "400 Bad Request";
+                return Err(Status::invalid_argument(
+                    "Invalid message role in chat history",
+                ));
+            }
+        };
+
+        let options = ChatOptions {
+            session,
+            dry_run: request.dry_run,
+            suggest_follow_ups: request.include_suggestions,
+            capabilities: Some(caller.capabilities.clone()),
+            language,
+            tool_events: None,
+        };
+        Ok(Prepared::Agent(chat_history, options))
+    }
+
+    /// Answers an authenticated chat call, filling in `audit` as it goes.
+    async fn answer(
+        &self,
+        caller: &Caller,
+        request: &ChatRequest,
+        audit: &mut ChatAudit,
+    ) -> Result<ChatResponse, Status> {
+        let (history, options) = match self.prepare(caller, request, audit).await? {
+            Prepared::Agent(history, options) => (history, options),
+            Prepared::ShadowBanned => return Ok(shadow_ban_reply(audit)),
+        };
+        let session = options.session.clone();
+        match self
+            .agent
+            .chat(request.prompt.clone(), history, options)
+            .await
+        {
+            Ok(response) => Ok(finish(
+                &self.sessions,
+                session.as_ref(),
+                request,
+                response,
+                audit,
+            )),
+            Err(e) => Err(chat_failure(e, audit)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AgentService for GrpcService {
+    type ChatStreamStream = Pin<Box<dyn Stream<Item = Result<ChatEvent, Status>> + Send>>;
+
+    async fn chat(&self, request: Request<ChatRequest>) -> Result<Response<ChatResponse>, Status> {
+        let started = Instant::now();
+        let caller = self
+            .authenticate(&request, Some(Capability::Chat))
+            .map_err(refusal_status)?;
+        let request = request.into_inner();
+        let mut audit = ChatAudit {
+            client_ip: caller.address,
+            ..ChatAudit::default()
+        };
+        let result = self.answer(&caller, &request, &mut audit).await;
+        self.admission.record(
+            &self.next_request_id(),
+            &caller.identity,
+            Some(&caller.api_key),
+            &audit,
+            started.elapsed(),
+        );
+        result.map(Response::new)
+    }
+
+    async fn chat_stream(
+        &self,
+        request: Request<ChatRequest>,
+    ) -> Result<Response<Self::ChatStreamStream>, Status> {
+        let started = Instant::now();
+        let caller = self
+            .authenticate(&request, Some(Capability::Chat))
+            .map_err(refusal_status)?;
+        let request = request.into_inner();
+        let request_id = self.next_request_id();
+        let mut audit = ChatAudit {
+            client_ip: caller.address,
+            ..ChatAudit::default()
+        };
+        let (history, mut options) = match self.prepare(&caller, &request, &mut audit).await {
+            Ok(Prepared::Agent(history, options)) => (history, options),
+            Ok(Prepared::ShadowBanned) => {
+                let reply = shadow_ban_reply(&mut audit);
+                self.admission.record(
+                    &request_id,
+                    &caller.identity,
+                    Some(&caller.api_key),
+                    &audit,
+                    started.elapsed(),
+                );
+                let event: Result<_, Status> = Ok(ChatEvent {
+                    event: Some(chat_event::Event::Response(reply)),
+                });
+                return Ok(Response::new(Box::pin(tokio_stream::once(event))));
+            }
+            Err(status) => {
+                self.admission.record(
+                    &request_id,
+                    &caller.identity,
+                    Some(&caller.api_key),
+                    &audit,
+                    started.elapsed(),
+                );
+                return Err(status);
+            }
+        };
+        let (tool_events, mut tool_calls) = mpsc::unbounded_channel();
+        options.tool_events = Some(tool_events);
+        let session = options.session.clone();
+
+        let (events, stream) = mpsc::unbounded_channel();
+        let agent = self.agent.clone();
+        let sessions = self.sessions.clone();
+        let admission = self.admission.clone();
+        tokio::spawn(
+            async move {
+                let chat = agent.chat(request.prompt.clone(), history, options);
+                tokio::pin!(chat);
+                // The proto only has completed tool calls, so starts aren't streamed
+                let result = loop {
+                    tokio::select! {
+                        Some(event) = tool_calls.recv() => {
+                            if let ToolEvent::Finished(record) = event {
+                                let _ = events.send(Ok(tool_call_event(record)));
+                            }
+                        }
+                        result = &mut chat => break result,
+                    }
+                };
+                // Tool calls that completed just before the response are still queued
+                while let Ok(event) = tool_calls.try_recv() {
+                    if let ToolEvent::Finished(record) = event {
+                        let _ = events.send(Ok(tool_call_event(record)));
+                    }
+                }
+
+                let last = match result {
+                    Ok(response) => Ok(ChatEvent {
+                        event: Some(chat_event::Event::Response(finish(
+                            &sessions,
+                            session.as_ref(),
+                            &request,
+                            response,
+                            &mut audit,
+                        ))),
+                    }),
+                    Err(e) => Err(chat_failure(e, &mut audit)),
+                };
+                admission.record(
+                    &request_id,
+                    &caller.identity,
+                    Some(&caller.api_key),
+                    &audit,
+                    started.elapsed(),
+                );
+                if events.send(last).is_err() {
+                    debug!("gRPC client disconnected before the response was streamed");
+                }
+            }
+            .in_current_span(),
+        );
+
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(
+            stream,
+        ))))
+    }
+
+    async fn list_tools(
+        &self,
+        request: Request<ListToolsRequest>,
+    ) -> Result<Response<ListToolsResponse>, Status> {
+        let caller = self.authenticate(&request, None).map_err(refusal_status)?;
+        let tools = self
+            .tools
+            .iter()
+            .filter(|tool| caller.capabilities.allows(Capability::for_tool(&tool.name)))
+            .map(|tool| Tool {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters_json: tool.parameters.to_string(),
+            })
+            .collect();
+        Ok(Response::new(ListToolsResponse { tools }))
+    }
+}
+
+/// Saves the exchange to the request's `session` (unless it was a dry run), records it
+/// in `audit`, and builds the reply.
+fn finish(
+    sessions: &SessionStore,
+    session: Option<&SessionKey>,
+    request: &ChatRequest,
+    response: AgentResponse,
+    audit: &mut ChatAudit,
+) -> ChatResponse {
+    info!(
+        "Generated response ({} chars) with {}",
+        response.text.len(),
+        response.model
+    );
+    audit.response = Some(response.text.clone());
+    audit.model = Some(response.model.clone());
+    audit.tool_calls = response.tool_trace.clone();
+    audit.status = "200 OK";
+    if let (Some(session), false) = (session, request.dry_run) {
+        sessions.append(
+            session,
+            [
+                HttpMessage::user(request.prompt.clone()),
+                HttpMessage::assistant(response.text.clone()),
+            ],
+        );
+        sessions.record_tool_calls(session, &response.tool_trace);
+    }
+
+    ChatResponse {
+        response: response.text,
+        model: response.model,
+        tool_trace: response
+            .tool_trace
+            .into_iter()
+            .map(ToolCall::from)
+            .collect(),
+        suggestions: response.suggestions,
+    }
+}
+
+/// The reply to a shadow-banned caller, shaped like a real one so it can't tell.
+fn shadow_ban_reply(audit: &mut ChatAudit) -> ChatResponse {
+    audit.response = Some(SHADOW_BAN_REPLY.to_string());
+    audit.status = "200 OK";
+    ChatResponse {
+        response: SHADOW_BAN_REPLY.to_string(),
+        model: String::new(),
+        tool_trace: Vec::new(),
+        suggestions: Vec::new(),
+    }
+}
+
+fn tool_call_event(record: ToolCallRecord) -> ChatEvent {
+    ChatEvent {
+        event: Some(chat_event::Event::ToolCall(record.into())),
+    }
+}
+
+/// Records a failed chat in `audit` with the status POST /chat would have sent, and
+/// returns the status to answer the call with.
+fn chat_failure(error: ChatError, audit: &mut ChatAudit) -> Status {
+    if let ChatError::Timeout { tool_trace, .. } | ChatError::Interrupted { tool_trace, .. } =
+        &error
+    {
+        audit.tool_calls = tool_trace.clone();
+    }
+    let status = chat_status(&error);
+    audit.status = AppError::from(error).status();
+    status
+}
+
+fn chat_status(error: &ChatError) -> Status {
+    match error {
+        ChatError::Timeout { after, .. } => {
+            warn!("Chat request timed out after {}s", after.as_secs());
+            Status::deadline_exceeded(format!("Agent did not finish within {}s", after.as_secs()))
+        }
+        e => {
+            error!("Failed to generate chat response: {}", e);
+            Status::internal("Failed to generate response")
+        }
+    }
+}
+
+/// The status a call turned away by admission is answered with.
+fn refusal_status(refusal: Refusal) -> Status {
+    let message = refusal.message();
+    let mut status = match refusal {
+        Refusal::LockedOut(_) | Refusal::CoolingDown(_) => Status::resource_exhausted(message),
+        Refusal::MissingKey => Status::unauthenticated(message),
+        Refusal::BadKey(_) | Refusal::NotPermitted => Status::permission_denied(message),
+        Refusal::Shed => Status::unavailable(message),
+        Refusal::Flagged => Status::invalid_argument(message),
+    };
+    if let Some(secs) = refusal.retry_after() {
+        status
+            .metadata_mut()
+            .insert(RETRY_AFTER_METADATA, secs.into());
+    }
+    status
+}
+
+impl From<ToolCallRecord> for ToolCall {
+    fn from(record: ToolCallRecord) -> Self {
+        ToolCall {
+            tool: record.tool,
+            args_json: record.args.to_string(),
+            duration_ms: record.duration_ms,
+            success: record.success,
+            result: record.result,
+        }
+    }
+}
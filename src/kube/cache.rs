@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Short-lived cache of GET responses, keyed by endpoint.
+///
+/// Shared by every clone of a [`super::KubeAgent`], so repeated questions within a
+/// few seconds are answered without hitting the API server or metrics-server again.
+/// Each tool reads with its own TTL; a TTL of zero disables caching for that tool.
+pub struct ResponseCache {
+    default_ttl: Duration,
+    overrides: HashMap<String, Duration>,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+struct CachedResponse {
+    body: String,
+    fetched_at: Instant,
+}
+
+impl ResponseCache {
+    /// Creates a cache from a default TTL and `tool_name=secs` override entries.
+    pub fn new(default_ttl: Duration, overrides: &[String]) -> Self {
+        let overrides = overrides
+            .iter()
+            .filter_map(|entry| {
+                let (name, secs) = entry.split_once('=')?;
+                match secs.trim().parse() {
+                    Ok(secs) => Some((name.trim().to_string(), Duration::from_secs(secs))),
+                    Err(_) => {
+                        warn!("Ignoring invalid kube cache TTL: {}", entry);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        ResponseCache {
+            default_ttl,
+            overrides,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// TTL configured for the tool with the given name.
+    pub fn ttl_for(&self, tool: &str) -> Duration {
+        self.overrides
+            .get(tool)
+            .copied()
+            .unwrap_or(self.default_ttl)
+    }
+
+    /// Returns the cached response for `endpoint` if it is younger than `ttl`.
+    pub fn get(&self, endpoint: &str, ttl: Duration) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(endpoint)
+            .filter(|entry| entry.fetched_at.elapsed() < ttl)
+            .map(|entry| entry.body.clone())
+    }
+
+    /// Stores a response, dropping entries that no tool could still read.
+    pub fn insert(&self, endpoint: String, body: String) {
+        let max_ttl = self
+            .overrides
+            .values()
+            .copied()
+            .fold(self.default_ttl, Duration::max);
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.fetched_at.elapsed() < max_ttl);
+        entries.insert(
+            endpoint,
+            CachedResponse {
+                body,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Drops every cached response, e.g. after a write changed cluster state.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
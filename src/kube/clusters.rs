@@ -0,0 +1,138 @@
+use reqwest::Certificate;
+use serde::Deserialize;
+use std::future::Future;
+use tracing::*;
+
+/// Name the cluster from `KUBE_API_SERVER`/`KUBE_TOKEN` is registered under when
+/// `CLUSTER_NAME` is not set.
+pub const DEFAULT_CLUSTER: &str = "default";
+
+tokio::task_local! {
+    /// Cluster the tool call currently being processed targets.
+    static CURRENT_CLUSTER: String;
+}
+
+/// Runs `future` with requests from [`super::KubeAgent`] going to the named cluster
+/// instead of the home cluster.
+pub async fn cluster_scope<F: Future>(cluster: Option<String>, future: F) -> F::Output {
+    match cluster {
+        Some(cluster) => CURRENT_CLUSTER.scope(cluster, future).await,
+        None => future.await,
+    }
+}
+
+/// Cluster selected by the enclosing [`cluster_scope`], if any.
+pub(super) fn current_cluster() -> Option<String> {
+    CURRENT_CLUSTER.try_with(|cluster| cluster.clone()).ok()
+}
+
+/// Connection details for one named cluster.
+#[derive(Clone)]
+pub struct KubeCluster {
+    pub name: String,
+    pub api_server: String,
+    pub(super) token: String,
+    pub(super) certificate: Option<Certificate>,
+}
+
+impl KubeCluster {
+    pub fn new(
+        name: String,
+        api_server: String,
+        token: String,
+        certificate: Option<Certificate>,
+    ) -> Self {
+        KubeCluster {
+            name,
+            api_server,
+            token,
+            certificate,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ClustersFile {
+    clusters: Vec<ClusterEntry>,
+}
+
+#[derive(Deserialize)]
+struct ClusterEntry {
+    name: String,
+    server: String,
+    token: Option<String>,
+    token_file: Option<String>,
+    ca_file: Option<String>,
+}
+
+/// Loads additional clusters from a YAML file of the form:
+///
+/// ```yaml
+/// clusters:
+///   - name: staging
+///     server: https://staging.example.com:6443
+///     token_file: /etc/kube-agent/staging-token   # or `token: ...`
+///     ca_file: /etc/kube-agent/staging-ca.crt     # optional
+/// ```
+///
+/// Entries that can't be loaded are skipped with a warning rather than failing startup.
+pub fn load_clusters(path: &str) -> Vec<KubeCluster> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!("Failed to read clusters file {}: {}", path, e);
+            return Vec::new();
+        }
+    };
+    let file: ClustersFile = match serde_yaml::from_str(&contents) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed to parse clusters file {}: {}", path, e);
+            return Vec::new();
+        }
+    };
+
+    file.clusters
+        .into_iter()
+        .filter_map(|entry| match load_cluster(&entry) {
+            Ok(cluster) => {
+                debug!("Loaded cluster {} ({})", cluster.name, cluster.api_server);
+                Some(cluster)
+            }
+            Err(e) => {
+                warn!("Skipping cluster {}: {}", entry.name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn load_cluster(entry: &ClusterEntry) -> Result<KubeCluster, String> {
+    let token = match (&entry.token, &entry.token_file) {
+        (Some(token), _) => token.clone(),
+        (None, Some(token_file)) => std::fs::read_to_string(token_file)
+            .map_err(|e| format!("failed to read token file {}: {}", token_file, e))?
+            .trim()
+            .to_string(),
+        (None, None) => return Err("either token or token_file is required".to_string()),
+    };
+
+    let certificate = match &entry.ca_file {
+        Some(ca_file) => {
+            let pem = std::fs::read(ca_file)
+                .map_err(|e| format!("failed to read CA file {}: {}", ca_file, e))?;
+            Some(
+                Certificate::from_pem(&pem)
+                    .map_err(|e| format!("invalid CA certificate {}: {}", ca_file, e))?,
+            )
+        }
+        None => None,
+    };
+
+    Ok(KubeCluster::new(
+        entry.name.clone(),
+        entry.server.trim_end_matches('/').to_string(),
+        token,
+        certificate,
+    ))
+}
@@ -1,44 +1,62 @@
-use std::fmt;
+use thiserror::Error;
 
 /// Errors that can occur when interacting with the Kubernetes API.
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum KubeAgentError {
     /// HTTP request failure (network, timeout, etc.)
-    HttpError(reqwest::Error),
+    #[error("HTTP request error: {0}")]
+    HttpError(#[source] reqwest::Error),
+    /// The API server didn't accept the connection or answer within the configured timeout
+    #[error("Kubernetes API request timed out: {0}")]
+    TimeoutError(#[source] reqwest::Error),
     /// Failed to parse JSON response from Kubernetes API
-    JsonParseError(serde_json::Error),
+    #[error("JSON parsing error: {0}")]
+    JsonParseError(#[from] serde_json::Error),
     /// General parsing or data validation error
+    #[error("Parse error: {0}")]
     ParseError(String),
-}
-
-impl fmt::Display for KubeAgentError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            KubeAgentError::HttpError(err) => write!(f, "HTTP request error: {}", err),
-            KubeAgentError::JsonParseError(err) => write!(f, "JSON parsing error: {}", err),
-            KubeAgentError::ParseError(err) => write!(f, "Parse error: {}", err),
-        }
-    }
-}
-
-impl std::error::Error for KubeAgentError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            KubeAgentError::HttpError(err) => Some(err),
-            KubeAgentError::JsonParseError(err) => Some(err),
-            KubeAgentError::ParseError(_) => None,
-        }
-    }
+    /// The API server answered with a non-2xx status, described by its Status object
+    #[error("Kubernetes API returned {code} {reason}: {message}")]
+    ApiError {
+        code: u16,
+        reason: String,
+        message: String,
+    },
+    /// A tool call named a cluster that isn't registered
+    #[error("Unknown cluster '{0}' (use list_clusters to see the configured clusters)")]
+    UnknownCluster(String),
+    /// The agent's own policy (e.g. a namespace allowlist) forbids the operation
+    #[error("Not permitted: {0}")]
+    PolicyViolation(String),
+    /// The client-side rate limiter or circuit breaker refused the request
+    #[error("Backing off from the Kubernetes API: {0}")]
+    BackingOff(String),
+    /// Client failure in the kube-rs backend (config, auth, or transport)
+    #[cfg(feature = "kube-rs")]
+    #[error("Kubernetes client error: {0}")]
+    KubeClientError(#[source] ::kube::Error),
 }
 
 impl From<reqwest::Error> for KubeAgentError {
     fn from(err: reqwest::Error) -> Self {
-        KubeAgentError::HttpError(err)
+        if err.is_timeout() {
+            KubeAgentError::TimeoutError(err)
+        } else {
+            KubeAgentError::HttpError(err)
+        }
     }
 }
 
-impl From<serde_json::Error> for KubeAgentError {
-    fn from(err: serde_json::Error) -> Self {
-        KubeAgentError::JsonParseError(err)
+#[cfg(feature = "kube-rs")]
+impl From<::kube::Error> for KubeAgentError {
+    fn from(err: ::kube::Error) -> Self {
+        match err {
+            ::kube::Error::Api(response) => KubeAgentError::ApiError {
+                code: response.code,
+                reason: response.reason,
+                message: response.message,
+            },
+            err => KubeAgentError::KubeClientError(err),
+        }
     }
 }
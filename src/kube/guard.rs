@@ -0,0 +1,152 @@
+use crate::agent::permissions;
+use crate::kube::clusters::current_cluster;
+use crate::kube::error::KubeAgentError;
+use tracing::*;
+
+/// The single gate every write-capable tool passes before changing a cluster.
+///
+/// A write goes ahead only if write tools are enabled (`ENABLE_WRITE_TOOLS`), the API key
+/// behind the request has the `kube-write` capability, and the namespace is in
+/// `WRITE_NAMESPACE_ALLOWLIST` (cluster-scoped objects such as nodes skip that last
+/// check). Refusals and completed writes are both logged to the
+/// `audit` tracing target. Checks particular to one tool (confirmation, replica limits,
+/// pod labels) stay with that tool.
+#[derive(Clone)]
+pub struct WriteGuard {
+    enabled: bool,
+    namespace_allowlist: Vec<String>,
+}
+
+impl WriteGuard {
+    pub fn new(enabled: bool, namespace_allowlist: Vec<String>) -> Self {
+        WriteGuard {
+            enabled,
+            namespace_allowlist,
+        }
+    }
+
+    /// Namespaces writes are allowed in.
+    pub fn namespace_allowlist(&self) -> &[String] {
+        &self.namespace_allowlist
+    }
+
+    /// Checks that `tool` may change `object` (e.g. `deployment/web`) in `namespace`.
+    pub fn authorize<'a>(
+        &self,
+        tool: &'a str,
+        namespace: &'a str,
+        object: &'a str,
+    ) -> Result<WriteGrant<'a>, KubeAgentError> {
+        self.check(tool, Some(namespace), object)
+    }
+
+    /// Checks that `tool` may change the cluster-scoped `object` (e.g. `node/worker-1`),
+    /// which no namespace allowlist can cover.
+    pub fn authorize_cluster_scoped<'a>(
+        &self,
+        tool: &'a str,
+        object: &'a str,
+    ) -> Result<WriteGrant<'a>, KubeAgentError> {
+        self.check(tool, None, object)
+    }
+
+    fn check<'a>(
+        &self,
+        tool: &'a str,
+        namespace: Option<&'a str>,
+        object: &'a str,
+    ) -> Result<WriteGrant<'a>, KubeAgentError> {
+        let refusal = if !self.enabled {
+            "write tools are disabled".to_string()
+        } else if let Some(capability) = permissions::missing_capability(tool) {
+            format!("this API key lacks the {} capability", capability)
+        } else if namespace.is_some_and(|namespace| {
+            !self
+                .namespace_allowlist
+                .iter()
+                .any(|allowed| allowed == namespace)
+        }) {
+            format!(
+                "write tools may only modify namespaces [{}]",
+                self.namespace_allowlist.join(", ")
+            )
+        } else {
+            return Ok(WriteGrant {
+                tool,
+                namespace,
+                object,
+            });
+        };
+
+        warn!(
+            target: "audit",
+            tool,
+            cluster = current_cluster().as_deref(),
+            namespace,
+            object,
+            reason = refusal.as_str(),
+            "Refused cluster write"
+        );
+        Err(KubeAgentError::PolicyViolation(refusal))
+    }
+}
+
+/// Permission for one write from [`WriteGuard::authorize`].
+#[must_use = "record the write once it has been made"]
+pub struct WriteGrant<'a> {
+    tool: &'a str,
+    /// None for cluster-scoped objects
+    namespace: Option<&'a str>,
+    object: &'a str,
+}
+
+impl WriteGrant<'_> {
+    /// Logs the completed write, described by `change` (e.g. `replicas 2 -> 4`).
+    pub fn record(self, change: &str) {
+        info!(
+            target: "audit",
+            tool = self.tool,
+            cluster = current_cluster().as_deref(),
+            namespace = self.namespace,
+            object = self.object,
+            change,
+            "Cluster write"
+        );
+    }
+}
+
+/// Checks that `namespace` is a valid namespace name (a DNS-1123 label), so it can't
+/// add segments such as `..` to the API path a write is sent to and slip past the
+/// namespace allowlist.
+pub fn check_namespace(namespace: &str) -> Result<(), KubeAgentError> {
+    if is_dns1123_label(namespace) {
+        Ok(())
+    } else {
+        Err(KubeAgentError::ParseError(format!(
+            "invalid namespace {:?}: must be lowercase letters, digits, and '-'",
+            namespace
+        )))
+    }
+}
+
+/// Checks that `name` is a valid object name (a DNS-1123 subdomain, as deployment, pod,
+/// and node names are), for the same reason as [`check_namespace`].
+pub fn check_name(name: &str) -> Result<(), KubeAgentError> {
+    if name.len() <= 253 && name.split('.').all(is_dns1123_label) {
+        Ok(())
+    } else {
+        Err(KubeAgentError::ParseError(format!(
+            "invalid name {:?}: must be lowercase letters, digits, '-', and '.'",
+            name
+        )))
+    }
+}
+
+// At most 63 lowercase alphanumerics and '-', starting and ending with an alphanumeric
+fn is_dns1123_label(label: &str) -> bool {
+    let alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    label.len() <= 63
+        && label.starts_with(alphanumeric)
+        && label.ends_with(alphanumeric)
+        && label.chars().all(|c| alphanumeric(c) || c == '-')
+}
@@ -0,0 +1,163 @@
+use super::error::KubeAgentError;
+use super::types::{Deployment, NamespaceItem, NodeDetails};
+use super::{KubeAgent, MAX_LIST_ITEMS};
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::*;
+
+/// Names listed per resource kind; the rest are only counted, so the context stays
+/// small on large clusters.
+const MAX_LISTED_NAMES: usize = 50;
+
+/// Node, namespace, and deployment names of the home cluster at one point in time.
+struct Inventory {
+    nodes: Vec<String>,
+    namespaces: Vec<String>,
+    /// "namespace/name (ready/desired ready)"
+    deployments: Vec<String>,
+    refreshed_at: DateTime<Utc>,
+}
+
+/// The latest inventory of the home cluster, kept current by [`InventoryRefresher`].
+#[derive(Clone, Default)]
+pub struct ClusterInventory {
+    state: Arc<RwLock<Option<Inventory>>>,
+}
+
+impl ClusterInventory {
+    /// Prompt context lines describing the inventory; empty until the first refresh
+    /// has succeeded.
+    pub fn context_lines(&self) -> Vec<String> {
+        let state = self.state.read().unwrap();
+        let Some(inventory) = state.as_ref() else {
+            return Vec::new();
+        };
+
+        vec![
+            format!(
+                "Cluster inventory as of {} (use the tools for live state)",
+                inventory.refreshed_at.format("%Y-%m-%d %H:%M UTC")
+            ),
+            names_line("Nodes", &inventory.nodes),
+            names_line("Namespaces", &inventory.namespaces),
+            names_line("Deployments", &inventory.deployments),
+        ]
+    }
+}
+
+// "Nodes (3): a, b, c", listing at most MAX_LISTED_NAMES names
+fn names_line(label: &str, names: &[String]) -> String {
+    if names.is_empty() {
+        return format!("{}: none", label);
+    }
+
+    let listed = names
+        .iter()
+        .take(MAX_LISTED_NAMES)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > MAX_LISTED_NAMES {
+        let more = names.len() - MAX_LISTED_NAMES;
+        format!("{} ({}): {}, and {} more", label, names.len(), listed, more)
+    } else {
+        format!("{} ({}): {}", label, names.len(), listed)
+    }
+}
+
+/// Background task that lists the home cluster's nodes, namespaces, and deployments
+/// every `interval` into a [`ClusterInventory`].
+///
+/// Unlike [`ClusterWatcher`](super::watch::ClusterWatcher) it doesn't hold watches
+/// open, since inventory questions tolerate a few minutes of staleness.
+pub struct InventoryRefresher {
+    kube_agent: KubeAgent,
+    interval: Duration,
+}
+
+impl InventoryRefresher {
+    pub fn new(kube_agent: KubeAgent, interval: Duration) -> Self {
+        InventoryRefresher {
+            kube_agent,
+            interval,
+        }
+    }
+
+    /// Spawns the refresh loop and returns the inventory it maintains.
+    pub fn spawn(self) -> ClusterInventory {
+        let inventory = ClusterInventory::default();
+        tokio::spawn(refresh(self.kube_agent, inventory.clone(), self.interval));
+        info!(
+            "Cluster inventory refresh started (every {}s)",
+            self.interval.as_secs()
+        );
+        inventory
+    }
+}
+
+/// Lists the cluster every `interval`, keeping the previous inventory when a
+/// refresh fails.
+async fn refresh(kube_agent: KubeAgent, inventory: ClusterInventory, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval.max(Duration::from_secs(1)));
+
+    loop {
+        ticks.tick().await;
+        match list_inventory(&kube_agent).await {
+            Ok(listed) => {
+                debug!(
+                    "Cluster inventory refreshed: {} nodes, {} namespaces, {} deployments",
+                    listed.nodes.len(),
+                    listed.namespaces.len(),
+                    listed.deployments.len()
+                );
+                *inventory.state.write().unwrap() = Some(listed);
+            }
+            Err(e) => warn!("Failed to refresh cluster inventory: {}", e),
+        }
+    }
+}
+
+async fn list_inventory(kube_agent: &KubeAgent) -> Result<Inventory, KubeAgentError> {
+    let (nodes, namespaces, deployments) = tokio::join!(
+        kube_agent.list_all::<NodeDetails>("/api/v1/nodes", MAX_LIST_ITEMS),
+        kube_agent.list_all::<NamespaceItem>("/api/v1/namespaces", MAX_LIST_ITEMS),
+        kube_agent.list_all::<Deployment>("/apis/apps/v1/deployments", MAX_LIST_ITEMS),
+    );
+
+    let mut nodes: Vec<String> = nodes?
+        .items
+        .into_iter()
+        .map(|node| node.metadata.name)
+        .collect();
+    let mut namespaces: Vec<String> = namespaces?
+        .items
+        .into_iter()
+        .map(|namespace| namespace.metadata.name)
+        .collect();
+    let mut deployments: Vec<String> = deployments?
+        .items
+        .into_iter()
+        .map(|deployment| {
+            let desired = deployment.spec.and_then(|spec| spec.replicas).unwrap_or(1);
+            let ready = deployment
+                .status
+                .and_then(|status| status.ready_replicas)
+                .unwrap_or(0);
+            format!(
+                "{}/{} ({}/{} ready)",
+                deployment.metadata.namespace, deployment.metadata.name, ready, desired
+            )
+        })
+        .collect();
+    nodes.sort();
+    namespaces.sort();
+    deployments.sort();
+
+    Ok(Inventory {
+        nodes,
+        namespaces,
+        deployments,
+        refreshed_at: Utc::now(),
+    })
+}
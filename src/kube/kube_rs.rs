@@ -0,0 +1,204 @@
+use super::KubeAgentError;
+use ::kube::api::{Api, DeleteParams, DynamicObject, ListParams, Patch, PatchParams};
+use ::kube::core::ApiResource;
+use ::kube::{Client, Config};
+use reqwest::Method;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tracing::*;
+
+/// Sends [`super::KubeAgent`] requests through kube-rs.
+///
+/// Plain resource paths (`/api/v1/namespaces/default/pods`, `/apis/apps/v1/deployments/x`)
+/// are served by `Api<DynamicObject>`; anything else (subresources, unknown query
+/// parameters) is sent as a raw request through the same client, so tools see the
+/// same JSON either way. The client is created on first use because inferring the
+/// config (kubeconfig or in-cluster service account) is async.
+#[derive(Default)]
+pub struct KubeRsBackend {
+    client: OnceCell<Client>,
+}
+
+impl KubeRsBackend {
+    async fn client(&self, timeouts: (Duration, Duration)) -> Result<Client, KubeAgentError> {
+        let client = self
+            .client
+            .get_or_try_init(|| async move {
+                let mut config = Config::infer().await.map_err(|e| {
+                    error!("Failed to infer kube-rs config: {}", e);
+                    KubeAgentError::ParseError(format!("failed to infer kube config: {}", e))
+                })?;
+                config.connect_timeout = Some(timeouts.0);
+                config.read_timeout = Some(timeouts.1);
+                info!("Using kube-rs backend for {}", config.cluster_url);
+                Client::try_from(config).map_err(KubeAgentError::from)
+            })
+            .await?;
+        Ok(client.clone())
+    }
+
+    /// Performs a request, returning the response body as JSON text.
+    ///
+    /// # Arguments
+    /// * `timeouts` - Connect and read timeouts used when the client is first created
+    pub async fn request(
+        &self,
+        method: &Method,
+        endpoint: &str,
+        body: Option<&Value>,
+        content_type: &str,
+        timeouts: (Duration, Duration),
+    ) -> Result<String, KubeAgentError> {
+        let client = self.client(timeouts).await?;
+
+        let Some(target) = ResourcePath::parse(endpoint) else {
+            return raw_request(client, method, endpoint, body, content_type).await;
+        };
+        debug!("kube-rs {} {} via Api<DynamicObject>", method, endpoint);
+
+        let api: Api<DynamicObject> = match &target.namespace {
+            Some(namespace) => Api::namespaced_with(client.clone(), namespace, &target.resource),
+            None => Api::all_with(client.clone(), &target.resource),
+        };
+
+        let value = match (method.clone(), target.name.as_deref()) {
+            (Method::GET, None) => serde_json::to_value(api.list(&target.list_params).await?)?,
+            (Method::GET, Some(name)) => serde_json::to_value(api.get(name).await?)?,
+            (Method::DELETE, Some(name)) => api
+                .delete(name, &DeleteParams::default())
+                .await?
+                .either(serde_json::to_value, serde_json::to_value)?,
+            (Method::PATCH, Some(name)) => {
+                let patch = to_patch(content_type, body.cloned().unwrap_or_default())?;
+                serde_json::to_value(api.patch(name, &PatchParams::default(), &patch).await?)?
+            }
+            _ => return raw_request(client, method, endpoint, body, content_type).await,
+        };
+
+        Ok(value.to_string())
+    }
+}
+
+async fn raw_request(
+    client: Client,
+    method: &Method,
+    endpoint: &str,
+    body: Option<&Value>,
+    content_type: &str,
+) -> Result<String, KubeAgentError> {
+    debug!("kube-rs {} {} as a raw request", method, endpoint);
+
+    let mut request = http::Request::builder()
+        .method(method.clone())
+        .uri(endpoint);
+    if body.is_some() {
+        request = request.header("Content-Type", content_type);
+    }
+    let request = request
+        .body(body.map(|b| b.to_string().into_bytes()).unwrap_or_default())
+        .map_err(|e| KubeAgentError::ParseError(format!("invalid request: {}", e)))?;
+
+    Ok(client.request_text(request).await?)
+}
+
+fn to_patch(content_type: &str, body: Value) -> Result<Patch<Value>, KubeAgentError> {
+    match content_type {
+        "application/json-patch+json" => Ok(Patch::Json(serde_json::from_value(body)?)),
+        "application/merge-patch+json" => Ok(Patch::Merge(body)),
+        "application/strategic-merge-patch+json" => Ok(Patch::Strategic(body)),
+        other => Err(KubeAgentError::ParseError(format!(
+            "unsupported patch content type {}",
+            other
+        ))),
+    }
+}
+
+/// A request path that maps onto `Api<DynamicObject>`.
+struct ResourcePath {
+    resource: ApiResource,
+    namespace: Option<String>,
+    name: Option<String>,
+    list_params: ListParams,
+}
+
+impl ResourcePath {
+    /// Parses `/api/{version}/...` or `/apis/{group}/{version}/...` paths naming a
+    /// collection or a single object. Returns `None` for anything else.
+    fn parse(endpoint: &str) -> Option<Self> {
+        let (path, query) = endpoint.split_once('?').unwrap_or((endpoint, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        let (group, version, rest) = match segments.as_slice() {
+            ["api", version, rest @ ..] => ("", *version, rest),
+            ["apis", group, version, rest @ ..] => (*group, *version, rest),
+            _ => return None,
+        };
+        let (namespace, rest) = match rest {
+            ["namespaces", namespace, rest @ ..] if !rest.is_empty() => {
+                (Some(namespace.to_string()), rest)
+            }
+            _ => (None, rest),
+        };
+        let (plural, name) = match rest {
+            [plural] => (*plural, None),
+            [plural, name] => (*plural, Some(name.to_string())),
+            _ => return None,
+        };
+
+        let mut list_params = ListParams::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            match key {
+                "limit" => list_params.limit = Some(value.parse().ok()?),
+                "continue" => list_params.continue_token = Some(value),
+                "fieldSelector" => list_params.field_selector = Some(value),
+                "labelSelector" => list_params.label_selector = Some(value),
+                _ => return None,
+            }
+        }
+
+        let api_version = if group.is_empty() {
+            version.to_string()
+        } else {
+            format!("{}/{}", group, version)
+        };
+        Some(ResourcePath {
+            resource: ApiResource {
+                group: group.to_string(),
+                version: version.to_string(),
+                api_version,
+                kind: String::new(),
+                plural: plural.to_string(),
+            },
+            namespace,
+            name,
+            list_params,
+        })
+    }
+}
+
+// Decodes %XX escapes in a query parameter value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,492 @@
+use super::KubeAgent;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::*;
+
+/// Bearer token the mock API server accepts.
+pub const MOCK_TOKEN: &str = "mock-token";
+
+/// Largest request body the mock server reads.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Fixtures served by a fresh mock server, by API path.
+const FIXTURES: &[(&str, &str)] = &[
+    (
+        "/api/v1/namespaces",
+        include_str!("../../fixtures/kube/namespaces.json"),
+    ),
+    (
+        "/api/v1/pods",
+        include_str!("../../fixtures/kube/pods.json"),
+    ),
+    (
+        "/api/v1/nodes",
+        include_str!("../../fixtures/kube/nodes.json"),
+    ),
+    (
+        "/api/v1/events",
+        include_str!("../../fixtures/kube/events.json"),
+    ),
+    (
+        "/apis/metrics.k8s.io",
+        include_str!("../../fixtures/kube/metrics_api.json"),
+    ),
+    (
+        "/apis/metrics.k8s.io/v1beta1/nodes",
+        include_str!("../../fixtures/kube/node_metrics.json"),
+    ),
+    (
+        "/apis/metrics.k8s.io/v1beta1/pods",
+        include_str!("../../fixtures/kube/pod_metrics.json"),
+    ),
+];
+
+/// A canned response.
+#[derive(Clone)]
+struct MockResponse {
+    status: u16,
+    body: String,
+}
+
+/// A request the mock server received.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query, e.g. "/api/v1/pods?limit=500"
+    pub path: String,
+    pub content_type: Option<String>,
+    pub body: Option<Value>,
+}
+
+#[derive(Default)]
+struct MockState {
+    /// Responses by "<METHOD> <path>", for non-GET routes, or by path
+    routes: HashMap<String, MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// Local HTTP server that answers like the Kubernetes API server from JSON fixtures.
+///
+/// Serves pods, nodes, namespaces, events, and node and pod metrics from
+/// `fixtures/kube`, so the kube tools can be run against a [`KubeAgent`] from
+/// [`MockKubeServer::kube_agent`] without a cluster. Routes can be replaced or added,
+/// and any path can be made to fail with a Status object.
+///
+/// Cluster-wide collections also answer their namespaced paths, filtered to the
+/// namespace, and single objects are looked up by name in their collection, so
+/// `/api/v1/namespaces/default/pods/<name>` works from the `/api/v1/pods` fixture.
+/// Query strings are ignored. Requests without the [`MOCK_TOKEN`] bearer token get a 401.
+///
+/// Only the built-in reqwest transport can reach it: with the `kube-rs` feature the
+/// home cluster goes through kube-rs, so register the mock as an extra cluster instead.
+/// The server stops when dropped.
+pub struct MockKubeServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    task: JoinHandle<()>,
+}
+
+impl MockKubeServer {
+    /// Starts a server with the default fixtures on an ephemeral localhost port.
+    pub async fn start() -> io::Result<Self> {
+        Self::start_on(SocketAddr::from(([127, 0, 0, 1], 0))).await
+    }
+
+    /// Starts a server with the default fixtures on `addr`.
+    pub async fn start_on(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+
+        let mut state = MockState::default();
+        for (path, body) in FIXTURES {
+            state.routes.insert(
+                path.to_string(),
+                MockResponse {
+                    status: 200,
+                    body: body.to_string(),
+                },
+            );
+        }
+        state.routes.insert(
+            "POST /apis/authorization.k8s.io/v1/selfsubjectaccessreviews".to_string(),
+            MockResponse {
+                status: 201,
+                body: json!({
+                    "kind": "SelfSubjectAccessReview",
+                    "apiVersion": "authorization.k8s.io/v1",
+                    "status": { "allowed": true }
+                })
+                .to_string(),
+            },
+        );
+        let state = Arc::new(Mutex::new(state));
+
+        let task = tokio::spawn(serve(listener, state.clone()));
+        info!("Mock Kubernetes API server listening on {}", addr);
+        Ok(MockKubeServer { addr, state, task })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to use as the API server address.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Client for this server, authenticated with [`MOCK_TOKEN`].
+    pub fn kube_agent(&self) -> KubeAgent {
+        KubeAgent::new(self.url(), MOCK_TOKEN.to_string(), None)
+    }
+
+    /// Answers GETs of `path` with `body`, replacing any fixture for it.
+    pub fn respond(&self, path: &str, status: u16, body: Value) -> &Self {
+        self.route(path.to_string(), status, body.to_string())
+    }
+
+    /// Answers `method` requests to `path` with `body`. DELETE and PATCH requests
+    /// without a route return the object they target, as the API server does.
+    pub fn respond_to(&self, method: &str, path: &str, status: u16, body: Value) -> &Self {
+        self.route(
+            format!("{} {}", method.to_uppercase(), path),
+            status,
+            body.to_string(),
+        )
+    }
+
+    /// Answers GETs of `path` with plain text, e.g. container logs.
+    pub fn respond_text(&self, path: &str, text: &str) -> &Self {
+        self.route(path.to_string(), 200, text.to_string())
+    }
+
+    /// Fails every request to `path` with a Status object, as the API server does.
+    pub fn fail(&self, path: &str, code: u16, reason: &str, message: &str) -> &Self {
+        let body = status(code, reason, message).to_string();
+        for method in ["GET", "POST", "PATCH", "PUT", "DELETE"] {
+            self.route(format!("{} {}", method, path), code, body.clone());
+        }
+        self
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    fn route(&self, key: String, status: u16, body: String) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert(key, MockResponse { status, body });
+        self
+    }
+
+    /// Serves until the process exits.
+    pub async fn wait(mut self) {
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for MockKubeServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle(stream, &state).await {
+                        debug!("Mock Kubernetes API connection failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => warn!("Mock Kubernetes API server failed to accept: {}", e),
+        }
+    }
+}
+
+/// Answers one request and closes the connection.
+async fn handle(stream: TcpStream, state: &Mutex<MockState>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut authorized = false;
+    let mut content_type = None;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("authorization") {
+                authorized = value == format!("Bearer {}", MOCK_TOKEN);
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length.min(MAX_BODY_BYTES)];
+    reader.read_exact(&mut body).await?;
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(RecordedRequest {
+            method: method.clone(),
+            path: target.clone(),
+            content_type,
+            body: serde_json::from_slice(&body).ok(),
+        });
+        let path = target.split('?').next().unwrap_or_default();
+        if authorized {
+            respond(&state.routes, &method, path)
+        } else {
+            MockResponse {
+                status: 401,
+                body: status(401, "Unauthorized", "Unauthorized").to_string(),
+            }
+        }
+    };
+    debug!(
+        "Mock Kubernetes API {} {} -> {}",
+        method, target, response.status
+    );
+
+    let content_type = if serde_json::from_str::<Value>(&response.body).is_ok() {
+        "application/json"
+    } else {
+        "text/plain"
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        content_type,
+        response.body.len()
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Picks the response for a request: an explicit route, then for DELETE and PATCH the
+/// targeted object, then for GET the fixture lookup, and otherwise a 404.
+fn respond(routes: &HashMap<String, MockResponse>, method: &str, path: &str) -> MockResponse {
+    if let Some(response) = routes.get(&format!("{} {}", method, path)) {
+        return response.clone();
+    }
+
+    let found = match method {
+        "GET" | "DELETE" | "PATCH" => lookup(routes, path),
+        _ => None,
+    };
+    match found {
+        Some(response) => response,
+        None => MockResponse {
+            status: 404,
+            body: status(
+                404,
+                "NotFound",
+                &format!(
+                    "the server could not find the requested resource ({})",
+                    path
+                ),
+            )
+            .to_string(),
+        },
+    }
+}
+
+/// Finds the GET response for `path` from the routes, deriving namespaced collections
+/// and single objects from the collections that contain them.
+fn lookup(routes: &HashMap<String, MockResponse>, path: &str) -> Option<MockResponse> {
+    if let Some(response) = routes.get(path) {
+        return Some(response.clone());
+    }
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let len = segments.len();
+
+    // <prefix>/namespaces/<namespace>/<resource>: the cluster-wide collection, filtered
+    if len >= 3 && segments[len - 3] == "namespaces" {
+        let namespace = segments[len - 2];
+        let collection = format!("/{}/{}", segments[..len - 3].join("/"), segments[len - 1]);
+        if let Some((mut list, items)) = list_items(routes, &collection) {
+            let items: Vec<Value> = items
+                .into_iter()
+                .filter(|item| item["metadata"]["namespace"] == namespace)
+                .collect();
+            list["items"] = Value::Array(items);
+            return Some(MockResponse {
+                status: 200,
+                body: list.to_string(),
+            });
+        }
+    }
+
+    // <collection>/<name>: the item of that name in the collection
+    if len >= 2 {
+        let name = segments[len - 1];
+        let collection = format!("/{}", segments[..len - 1].join("/"));
+        let (_, items) = list_items(routes, &collection)?;
+        let item = items
+            .into_iter()
+            .find(|item| item["metadata"]["name"] == name)?;
+        return Some(MockResponse {
+            status: 200,
+            body: item.to_string(),
+        });
+    }
+    None
+}
+
+/// The list served at `path` and its items.
+fn list_items(routes: &HashMap<String, MockResponse>, path: &str) -> Option<(Value, Vec<Value>)> {
+    let response = lookup(routes, path).filter(|response| response.status == 200)?;
+    let list: Value = serde_json::from_str(&response.body).ok()?;
+    let items = list["items"].as_array()?.clone();
+    Some((list, items))
+}
+
+/// Status object like those the API server returns with failed requests.
+fn status(code: u16, reason: &str, message: &str) -> Value {
+    json!({
+        "kind": "Status",
+        "apiVersion": "v1",
+        "metadata": {},
+        "status": "Failure",
+        "message": message,
+        "reason": reason,
+        "code": code
+    })
+}
+
+fn reason_phrase(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        410 => "Gone",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kube::{KubeAgentError, ListPodsTool};
+    use rig::tool::Tool;
+
+    #[tokio::test]
+    async fn derives_namespaced_collections_and_objects_from_fixtures() {
+        let server = MockKubeServer::start().await.unwrap();
+        let kube = server.kube_agent();
+
+        let pods: Value = serde_json::from_str(
+            &kube
+                .get("/api/v1/namespaces/portfolio/pods".to_string())
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let names: Vec<&str> = pods["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|pod| pod["metadata"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["portfolio-7d9c6b5f4-x2kq8"]);
+
+        let pod: Value = serde_json::from_str(
+            &kube
+                .get("/api/v1/namespaces/default/pods/worker-5c8f7d6b9-q4wzn?limit=1".to_string())
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(pod["metadata"]["namespace"], "default");
+    }
+
+    #[tokio::test]
+    async fn answers_failures_as_the_api_server_does() {
+        let server = MockKubeServer::start().await.unwrap();
+
+        let unauthorized = KubeAgent::new(server.url(), "wrong-token".to_string(), None)
+            .get("/api/v1/pods".to_string())
+            .await;
+        assert!(matches!(
+            unauthorized,
+            Err(KubeAgentError::ApiError { code: 401, .. })
+        ));
+
+        server.fail("/api/v1/nodes", 503, "ServiceUnavailable", "etcd is down");
+        match server.kube_agent().get("/api/v1/nodes".to_string()).await {
+            Err(KubeAgentError::ApiError {
+                code,
+                reason,
+                message,
+            }) => {
+                assert_eq!(code, 503);
+                assert_eq!(reason, "ServiceUnavailable");
+                assert_eq!(message, "etcd is down");
+            }
+            other => panic!("expected a 503 ApiError, got {:?}", other),
+        }
+
+        let missing = server
+            .kube_agent()
+            .get("/api/v1/namespaces/default/pods/no-such-pod".to_string())
+            .await;
+        assert!(matches!(
+            missing,
+            Err(KubeAgentError::ApiError { code: 404, .. })
+        ));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn runs_kube_tools_against_the_fixtures() {
+        let server = MockKubeServer::start().await.unwrap();
+        let tool = ListPodsTool::new(server.kube_agent());
+
+        let args = serde_json::from_value(json!({ "namespace": "portfolio" })).unwrap();
+        let output = tool.call(args).await.unwrap();
+        assert!(output.contains("portfolio-7d9c6b5f4-x2kq8"), "{}", output);
+        assert!(!output.contains("worker-5c8f7d6b9-q4wzn"), "{}", output);
+
+        let requests = server.requests();
+        assert!(requests
+            .iter()
+            .all(|request| request.method == "GET" && request.path.starts_with("/api")));
+    }
+}
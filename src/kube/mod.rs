@@ -1,21 +1,90 @@
+pub mod cache;
+pub mod clusters;
 pub mod error;
+pub mod guard;
+pub mod inventory;
+#[cfg(feature = "kube-rs")]
+mod kube_rs;
+// Test harness: neither the tests nor the binary use all of it
+#[cfg(any(test, feature = "test-support"))]
+#[allow(dead_code)]
+pub mod mock;
+pub mod throttle;
 pub mod tools;
 pub mod types;
+pub mod watch;
 
 pub use error::KubeAgentError;
-pub use tools::{ListNamespacesTool, ListPodsTool, NodeMetricsTool};
+pub use tools::{
+    CanITool, CertificateExpiryTool, ClusterAlertsTool, CordonNodeTool, CustomResourcesTool,
+    DeletePodTool, DescribePodTool, DiagnosePodTool, DrainPlanTool, GetEventsForTool,
+    ListClustersTool, ListConfigMapsTool, ListNamespacesTool, ListNetworkPoliciesTool,
+    ListPersistentVolumeClaimsTool, ListPodsTool, ListSecretsTool, NamespaceCostTool, NodeInfoTool,
+    NodeMetricsTool, RawKubeGetTool, RbacOverviewTool, ResourceQuotasTool, RolloutStatusTool,
+    ScaleDeploymentTool,
+};
 
+use cache::ResponseCache;
+use clusters::{KubeCluster, DEFAULT_CLUSTER};
+use reqwest::Method;
+use rig::tool::Tool;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use throttle::ApiThrottle;
 use tracing::*;
+use types::{EventListResponse, ListPage, Listing, RawWatchEvent, Status, WatchEvent};
+
+/// Number of items requested per page when listing resources.
+const LIST_PAGE_SIZE: usize = 500;
+
+/// Upper bound on the items a single listing fetches across all pages, so a huge
+/// namespace can't exhaust memory or the model's context.
+pub const MAX_LIST_ITEMS: usize = 5000;
+
+/// Time allowed to establish a connection to the API server unless configured.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for a whole request (connect, send, and read the body) unless configured.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Seconds the API server keeps a watch open before closing it; the watcher then
+/// resumes from the last resourceVersion it saw.
+const WATCH_TIMEOUT_SECS: u64 = 300;
+
+/// Seconds a followed log stays open; followers reconnect to keep tailing.
+const LOG_FOLLOW_TIMEOUT_SECS: u64 = 60 * 60;
+
+/// Content type of plain JSON request bodies.
+const JSON: &str = "application/json";
 
 /// Client for interacting with the Kubernetes API.
 ///
 /// Handles authentication via bearer tokens and optional CA certificate validation.
 /// Supports both production (with certificates) and development (self-signed certs) modes.
+///
+/// Requests go to the home cluster unless made inside a [`clusters::cluster_scope`]
+/// naming another registered cluster. When built with the `kube-rs` feature, the
+/// home cluster is reached through kube-rs (credentials inferred from kubeconfig or
+/// the in-cluster service account) instead of the built-in reqwest transport.
 #[derive(Clone)]
 pub struct KubeAgent {
-    kube_api_server: String,
-    token: String,
-    certificate: Option<reqwest::Certificate>,
+    /// Registered clusters; the first is the home cluster
+    clusters: Arc<Vec<KubeCluster>>,
+    cache: Option<Arc<ResponseCache>>,
+    /// How long GET responses from the cache are served to this clone (zero = never)
+    cache_ttl: Duration,
+    throttle: Option<Arc<ApiThrottle>>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+    /// Skip TLS verification for clusters without a CA certificate (local dev clusters)
+    accept_invalid_certs: bool,
+    /// Whether each cluster (by name) serves the metrics.k8s.io API, once probed
+    metrics_api: Arc<Mutex<HashMap<String, bool>>>,
+    /// Transport for the home cluster when built with the `kube-rs` feature
+    #[cfg(feature = "kube-rs")]
+    kube_rs: Arc<kube_rs::KubeRsBackend>,
 }
 
 impl KubeAgent {
@@ -24,75 +93,759 @@ impl KubeAgent {
         token: String,
         certificate: Option<reqwest::Certificate>,
     ) -> Self {
-        KubeAgent {
+        let home = KubeCluster::new(
+            DEFAULT_CLUSTER.to_string(),
             kube_api_server,
             token,
             certificate,
+        );
+
+        KubeAgent {
+            clusters: Arc::new(vec![home]),
+            cache: None,
+            cache_ttl: Duration::ZERO,
+            throttle: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            accept_invalid_certs: false,
+            metrics_api: Arc::default(),
+            #[cfg(feature = "kube-rs")]
+            kube_rs: Arc::default(),
+        }
+    }
+
+    /// Sets the connect and total request timeouts, so a hung API server fails the
+    /// request with [`KubeAgentError::TimeoutError`] instead of stalling the caller.
+    pub fn with_timeouts(mut self, connect_timeout: Duration, request_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Accepts self-signed or otherwise invalid certificates from clusters that have no
+    /// CA certificate configured. Without this such clusters are verified against the
+    /// system roots.
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Names the home cluster and registers additional clusters after it.
+    ///
+    /// Clusters whose name is already taken are skipped with a warning.
+    pub fn with_clusters(mut self, home_name: &str, extra: Vec<KubeCluster>) -> Self {
+        let mut clusters = self.clusters.as_ref().clone();
+        clusters[0].name = home_name.to_string();
+
+        for cluster in extra {
+            if clusters.iter().any(|c| c.name == cluster.name) {
+                warn!("Ignoring duplicate cluster name {}", cluster.name);
+                continue;
+            }
+            clusters.push(cluster);
+        }
+
+        info!("Registered {} Kubernetes cluster(s)", clusters.len());
+        self.clusters = Arc::new(clusters);
+        self
+    }
+
+    /// Registered clusters, home cluster first.
+    pub fn clusters(&self) -> &[KubeCluster] {
+        &self.clusters
+    }
+
+    /// The cluster selected by the current [`clusters::cluster_scope`], or the home cluster.
+    fn cluster(&self) -> Result<&KubeCluster, KubeAgentError> {
+        match clusters::current_cluster() {
+            Some(name) => self
+                .clusters
+                .iter()
+                .find(|cluster| cluster.name == name)
+                .ok_or(KubeAgentError::UnknownCluster(name)),
+            None => Ok(&self.clusters[0]),
+        }
+    }
+
+    /// Attaches a response cache shared by all clones of this agent.
+    ///
+    /// The agent itself still reads uncached (so write tools always see fresh state);
+    /// use [`KubeAgent::for_tool`] to get a clone that reads with a tool's TTL.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Attaches a rate limiter and circuit breaker shared by all clones of this agent.
+    ///
+    /// Only requests made through [`KubeAgent::make_request`] are throttled; watches and
+    /// followed logs are long-lived and paced by their callers.
+    pub fn with_throttle(mut self, throttle: ApiThrottle) -> Self {
+        self.throttle = Some(Arc::new(throttle));
+        self
+    }
+
+    /// Returns a clone that serves GETs from the cache for the TTL configured for `T`.
+    pub fn for_tool<T: Tool>(&self) -> KubeAgent {
+        let mut agent = self.clone();
+        if let Some(cache) = &self.cache {
+            agent.cache_ttl = cache.ttl_for(T::NAME);
+        }
+        agent
+    }
+
+    /// Makes an HTTP GET request to a Kubernetes API endpoint, served from the
+    /// response cache when this clone has a cache TTL.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The API endpoint path (e.g., "/api/v1/pods")
+    pub async fn get(&self, endpoint: String) -> Result<String, KubeAgentError> {
+        let Some(cache) = self.cache.as_ref().filter(|_| !self.cache_ttl.is_zero()) else {
+            return self.make_request(Method::GET, endpoint, None, None).await;
+        };
+
+        let key = format!("{}:{}", self.cluster()?.name, endpoint);
+        if let Some(body) = cache.get(&key, self.cache_ttl) {
+            debug!("Serving {} from the response cache", key);
+            return Ok(body);
         }
+
+        let body = self.make_request(Method::GET, endpoint, None, None).await?;
+        cache.insert(key, body.clone());
+        Ok(body)
     }
 
-    /// Makes an HTTP GET request to a Kubernetes API endpoint.
+    /// Makes an HTTP request to a Kubernetes API endpoint.
     ///
     /// Automatically handles bearer token authentication and certificate validation.
     /// In development mode (no certificate), accepts self-signed certificates.
+    /// PATCH bodies are checked against their content type before sending: JSON
+    /// patches must be an array of operations, merge and strategic merge patches an
+    /// object. Any non-2xx response is returned as [`KubeAgentError::ApiError`]. With an
+    /// [`ApiThrottle`] attached, the request may first wait for the rate limiter or be
+    /// refused with [`KubeAgentError::BackingOff`].
     ///
     /// # Arguments
+    /// * `method` - The HTTP method
     /// * `endpoint` - The API endpoint path (e.g., "/api/v1/pods")
+    /// * `body` - Optional JSON request body
+    /// * `content_type` - Content type of the body (defaults to "application/json")
     ///
     /// # Returns
     /// The response body as a string, or a KubeAgentError on failure.
-    pub async fn make_request(&self, endpoint: String) -> Result<String, KubeAgentError> {
+    pub async fn make_request(
+        &self,
+        method: Method,
+        endpoint: String,
+        body: Option<&serde_json::Value>,
+        content_type: Option<&str>,
+    ) -> Result<String, KubeAgentError> {
+        let cluster = self.cluster()?;
+        let content_type = content_type.unwrap_or(JSON);
+        if let Some(body) = body.filter(|_| method == Method::PATCH) {
+            check_patch(content_type, body)?;
+        }
+        if let Some(throttle) = &self.throttle {
+            throttle.acquire(&cluster.name).await?;
+        }
+
+        #[cfg(feature = "kube-rs")]
+        let response = if std::ptr::eq(cluster, &self.clusters[0]) {
+            self.kube_rs
+                .request(
+                    &method,
+                    &endpoint,
+                    body,
+                    content_type,
+                    (self.connect_timeout, self.request_timeout),
+                )
+                .await
+        } else {
+            self.send(cluster, &method, &endpoint, body, content_type)
+                .await
+        };
+        #[cfg(not(feature = "kube-rs"))]
+        let response = self
+            .send(cluster, &method, &endpoint, body, content_type)
+            .await;
+        if let Some(throttle) = &self.throttle {
+            throttle.record(&cluster.name, response.as_ref().err());
+        }
+        let response = response?;
+
+        // Cached reads may no longer reflect the cluster after a write; access and
+        // identity reviews are POSTed but store nothing
+        if method != Method::GET && !endpoint.ends_with("reviews") {
+            if let Some(cache) = &self.cache {
+                cache.clear();
+            }
+        }
+
+        debug!("Successfully received response from Kubernetes API");
+        Ok(response)
+    }
+
+    /// Sends a request with the built-in reqwest transport.
+    async fn send(
+        &self,
+        cluster: &KubeCluster,
+        method: &Method,
+        endpoint: &str,
+        body: Option<&serde_json::Value>,
+        content_type: &str,
+    ) -> Result<String, KubeAgentError> {
         debug!(
-            "Making Kubernetes API request to {}{}",
-            self.kube_api_server, endpoint
+            "Making Kubernetes API {} request to {}{}",
+            method, cluster.api_server, endpoint
+        );
+
+        let mut request = self.client(cluster).request(
+            method.clone(),
+            format!("{}{}", cluster.api_server, endpoint),
         );
+        if let Some(body) = body {
+            request = request
+                .header("Content-Type", content_type)
+                .body(body.to_string());
+        }
+
+        let resp = request.send().await.map_err(|err| {
+            error!("Failed to send request to Kubernetes API: {}", err);
+            KubeAgentError::from(err)
+        })?;
+
+        let status = resp.status();
+        let body = resp.text().await.map_err(|err| {
+            error!("Failed to read response body: {}", err);
+            KubeAgentError::from(err)
+        })?;
+        if !status.is_success() {
+            error!(
+                "Kubernetes API rejected {} {}: {}",
+                method, endpoint, status
+            );
+            return Err(api_error(status, &body));
+        }
+
+        Ok(body)
+    }
 
+    /// Builds an HTTP client with bearer token authentication and certificate handling.
+    ///
+    /// Without a CA certificate the system roots are used, unless invalid certificates
+    /// are accepted (development clusters with self-signed certificates).
+    fn client(&self, cluster: &KubeCluster) -> reqwest::Client {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert(
             "Authorization",
-            format!("Bearer {}", self.token).parse().unwrap(),
+            format!("Bearer {}", cluster.token).parse().unwrap(),
         );
 
-        // Build HTTP client with appropriate certificate handling
-        let client = if let Some(cert) = &self.certificate {
+        let builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout);
+
+        if let Some(cert) = &cluster.certificate {
             debug!("Using CA certificate for secure connection");
-            reqwest::Client::builder()
-                .default_headers(headers)
-                .add_root_certificate(cert.clone())
-                .build()
-                .unwrap()
+            builder.add_root_certificate(cert.clone()).build().unwrap()
+        } else if !self.accept_invalid_certs {
+            debug!("No CA certificate provided, verifying against system roots");
+            builder.build().unwrap()
         } else {
             warn!("No CA certificate provided, accepting self-signed certificates (development only)");
-            reqwest::Client::builder()
-                .default_headers(headers)
-                .danger_accept_invalid_certs(true)
-                .build()
-                .unwrap()
-        };
+            builder.danger_accept_invalid_certs(true).build().unwrap()
+        }
+    }
+
+    /// Watches a collection endpoint from `resource_version`, calling `on_event` for
+    /// each change until the API server closes the stream.
+    ///
+    /// Always uses the built-in reqwest transport. Returns the last resourceVersion
+    /// seen, to resume from; an expired version fails with a 410 [`KubeAgentError::ApiError`],
+    /// after which the caller should list again.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The collection path, optionally with a selector query (e.g. "/api/v1/pods")
+    /// * `resource_version` - Version to start watching after, usually from a listing
+    /// * `on_event` - Called for every added, modified, or deleted object
+    pub async fn watch<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        resource_version: &str,
+        mut on_event: impl FnMut(WatchEvent<T>),
+    ) -> Result<String, KubeAgentError> {
+        let cluster = self.cluster()?;
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        let url = format!(
+            "{}{}{}watch=1&allowWatchBookmarks=true&timeoutSeconds={}&resourceVersion={}",
+            cluster.api_server,
+            endpoint,
+            separator,
+            WATCH_TIMEOUT_SECS,
+            percent_encode(resource_version)
+        );
+        debug!(
+            "Watching {} from resourceVersion {}",
+            endpoint, resource_version
+        );
 
-        let request = client
-            .get(format!("{}{}", self.kube_api_server, endpoint))
+        // The stream outlives the client's total request timeout by design
+        let mut resp = self
+            .client(cluster)
+            .get(url)
+            .timeout(Duration::from_secs(WATCH_TIMEOUT_SECS) + self.request_timeout)
             .send()
-            .await;
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await?;
+            return Err(api_error(status, &body));
+        }
+
+        let mut last_version = resource_version.to_string();
+        let mut buffer = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(newline) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let event: RawWatchEvent = serde_json::from_slice(&line)?;
 
-        match request {
-            Ok(resp) => {
-                let text = resp.text().await;
-                match text {
-                    Ok(body) => {
-                        debug!("Successfully received response from Kubernetes API");
-                        Ok(body)
+                // Usually 410 Gone: the resourceVersion is too old to resume from
+                if event.type_field == "ERROR" {
+                    let status: Status = serde_json::from_value(event.object)?;
+                    return Err(KubeAgentError::ApiError {
+                        code: status.code.unwrap_or(410),
+                        reason: status.reason.unwrap_or_else(|| "Expired".to_string()),
+                        message: status.message.unwrap_or_default(),
+                    });
+                }
+
+                if let Some(version) = event.object["metadata"]["resourceVersion"].as_str() {
+                    last_version = version.to_string();
+                }
+                match event.type_field.as_str() {
+                    "ADDED" | "MODIFIED" => {
+                        on_event(WatchEvent::Applied(serde_json::from_value(event.object)?))
                     }
-                    Err(err) => {
-                        error!("Failed to read response body: {}", err);
-                        Err(KubeAgentError::from(err))
+                    "DELETED" => {
+                        on_event(WatchEvent::Deleted(serde_json::from_value(event.object)?))
                     }
+                    _ => {} // BOOKMARK only advances the resourceVersion
+                }
+            }
+        }
+
+        debug!(
+            "Watch of {} closed at resourceVersion {}",
+            endpoint, last_version
+        );
+        Ok(last_version)
+    }
+
+    /// Follows a pod's log from its last `tail_lines` lines, like `kubectl logs -f`.
+    ///
+    /// Always uses the built-in reqwest transport. The stream ends when the container
+    /// stops or after [`LOG_FOLLOW_TIMEOUT_SECS`]; a missing pod or container fails with a
+    /// 404 or 400 [`KubeAgentError::ApiError`] before any line is read.
+    ///
+    /// # Arguments
+    /// * `namespace` - Namespace of the pod
+    /// * `pod` - Name of the pod
+    /// * `container` - Container to follow; required for pods with several containers
+    /// * `tail_lines` - Existing lines to start from
+    pub async fn follow_logs(
+        &self,
+        namespace: &str,
+        pod: &str,
+        container: Option<&str>,
+        tail_lines: u32,
+    ) -> Result<LogStream, KubeAgentError> {
+        let cluster = self.cluster()?;
+        let mut url = format!(
+            "{}/api/v1/namespaces/{}/pods/{}/log?follow=true&tailLines={}",
+            cluster.api_server,
+            percent_encode(namespace),
+            percent_encode(pod),
+            tail_lines
+        );
+        if let Some(container) = container {
+            url.push_str(&format!("&container={}", percent_encode(container)));
+        }
+        debug!("Following logs of pod {}/{}", namespace, pod);
+
+        let response = self
+            .client(cluster)
+            .get(url)
+            .timeout(Duration::from_secs(LOG_FOLLOW_TIMEOUT_SECS))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(api_error(status, &body));
+        }
+
+        Ok(LogStream {
+            response,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Lists every item at a collection endpoint, following `continue` tokens.
+    ///
+    /// Pages of [`LIST_PAGE_SIZE`] items are fetched until the last page or until
+    /// `max_items` have been collected, in which case the listing is marked truncated.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The collection path, optionally with a query (e.g. "/api/v1/pods")
+    /// * `max_items` - Maximum number of items to return
+    pub async fn list_all<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        max_items: usize,
+    ) -> Result<Listing<T>, KubeAgentError> {
+        let max_items = max_items.max(1);
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        let mut continue_token: Option<String> = None;
+        let mut resource_version: Option<String> = None;
+
+        loop {
+            // limit=0 means "no limit" to the API server, so the page size is never 0
+            let page_size = LIST_PAGE_SIZE.min(max_items - items.len());
+            let mut page_endpoint = format!("{}{}limit={}", endpoint, separator, page_size);
+            if let Some(token) = &continue_token {
+                page_endpoint.push_str(&format!("&continue={}", percent_encode(token)));
+            }
+
+            let response = self.get(page_endpoint).await?;
+            let page: ListPage<T> = serde_json::from_str(&response).map_err(|e| {
+                error!("Error parsing list response from {}: {}", endpoint, e);
+                KubeAgentError::from(e)
+            })?;
+            items.extend(page.items);
+
+            if let Some(metadata) = page.metadata {
+                resource_version = metadata.resource_version;
+                continue_token = metadata.continue_token.filter(|token| !token.is_empty());
+            } else {
+                continue_token = None;
+            }
+
+            if continue_token.is_none() {
+                debug!("Listed {} items from {}", items.len(), endpoint);
+                return Ok(Listing {
+                    items,
+                    truncated: false,
+                    resource_version,
+                });
+            }
+
+            if items.len() >= max_items {
+                warn!("Listing of {} truncated at {} items", endpoint, max_items);
+                items.truncate(max_items);
+                return Ok(Listing {
+                    items,
+                    truncated: true,
+                    resource_version,
+                });
+            }
+
+            debug!(
+                "Fetched {} items from {} so far, requesting next page",
+                items.len(),
+                endpoint
+            );
+        }
+    }
+
+    /// Whether the current cluster serves the metrics.k8s.io API, i.e. has
+    /// metrics-server (or another metrics provider) installed.
+    ///
+    /// The answer is probed once per cluster and remembered. A 503 means the API is
+    /// registered but its backend is down, which may pass, so it is not remembered.
+    pub async fn metrics_available(&self) -> Result<bool, KubeAgentError> {
+        let cluster = self.cluster()?.name.clone();
+        if let Some(available) = self.metrics_api.lock().unwrap().get(&cluster) {
+            return Ok(*available);
+        }
+
+        let available = match self
+            .make_request(Method::GET, "/apis/metrics.k8s.io".to_string(), None, None)
+            .await
+        {
+            Ok(_) => true,
+            Err(KubeAgentError::ApiError { code: 404, .. }) => false,
+            Err(KubeAgentError::ApiError { code: 503, .. }) => {
+                warn!("Metrics API on cluster {} is unavailable (503)", cluster);
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        };
+
+        info!(
+            "Metrics API on cluster {} is {}",
+            cluster,
+            if available {
+                "available"
+            } else {
+                "not installed"
+            }
+        );
+        self.metrics_api.lock().unwrap().insert(cluster, available);
+        Ok(available)
+    }
+
+    /// Lists the events about a single object, most recent first.
+    ///
+    /// Events are matched on the object's kind and name with an `involvedObject` field
+    /// selector. Cluster-scoped objects such as nodes have their events recorded in
+    /// the "default" namespace, so without a namespace every namespace is searched.
+    ///
+    /// # Arguments
+    /// * `kind` - The object's kind, e.g. "Pod" or "Deployment"
+    /// * `name` - The object's name
+    /// * `namespace` - The object's namespace, or `None` for cluster-scoped objects
+    pub async fn events_for(
+        &self,
+        kind: &str,
+        name: &str,
+        namespace: Option<&str>,
+    ) -> Result<EventListResponse, KubeAgentError> {
+        let collection = match namespace {
+            Some(namespace) => format!("/api/v1/namespaces/{}/events", namespace),
+            None => "/api/v1/events".to_string(),
+        };
+        let endpoint = format!(
+            "{}?fieldSelector=involvedObject.kind%3D{},involvedObject.name%3D{}",
+            collection,
+            percent_encode(kind),
+            percent_encode(name)
+        );
+        let listing = self.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+
+        let mut events = EventListResponse {
+            items: listing.items,
+        };
+        events
+            .items
+            .sort_by(|a, b| b.timestamp().cmp(a.timestamp()));
+        Ok(events)
+    }
+}
+
+/// Lines of a followed pod log, from [`KubeAgent::follow_logs`].
+pub struct LogStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl LogStream {
+    /// Waits for the next complete log line, without its newline. Returns None once
+    /// the API server closes the stream.
+    ///
+    /// Cancel-safe: a partial line read before the future is dropped is kept for the
+    /// next call.
+    pub async fn next_line(&mut self) -> Result<Option<String>, KubeAgentError> {
+        loop {
+            if let Some(newline) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line[..line.len() - 1]);
+                return Ok(Some(line.trim_end_matches('\r').to_string()));
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                // A last line without a newline is still a line
+                None if !self.buffer.is_empty() => {
+                    let line = String::from_utf8_lossy(&self.buffer).to_string();
+                    self.buffer.clear();
+                    return Ok(Some(line));
                 }
+                None => return Ok(None),
             }
-            Err(err) => {
-                error!("Failed to send request to Kubernetes API: {}", err);
-                Err(KubeAgentError::from(err))
+        }
+    }
+}
+
+// Builds an ApiError from a failed response, preferring the fields of the API
+// server's Status object and falling back to the HTTP status and raw body (e.g.
+// when a proxy in front of the API server answered)
+fn api_error(status: reqwest::StatusCode, body: &str) -> KubeAgentError {
+    let parsed = serde_json::from_str::<Status>(body)
+        .ok()
+        .filter(|s| s.kind.as_deref() == Some("Status"));
+
+    let (reason, message) = match parsed {
+        Some(s) => (s.reason, s.message),
+        None => (None, None),
+    };
+
+    KubeAgentError::ApiError {
+        code: status.as_u16(),
+        reason: reason
+            .or_else(|| status.canonical_reason().map(String::from))
+            .unwrap_or_else(|| "Unknown".to_string()),
+        message: message.unwrap_or_else(|| body.trim().chars().take(500).collect()),
+    }
+}
+
+// Rejects PATCH bodies whose shape doesn't match their content type, which the API
+// server would otherwise answer with an opaque 400 or 415
+fn check_patch(content_type: &str, patch: &serde_json::Value) -> Result<(), KubeAgentError> {
+    let valid = match content_type {
+        "application/json-patch+json" => patch.is_array(),
+        "application/merge-patch+json" | "application/strategic-merge-patch+json" => {
+            patch.is_object()
+        }
+        _ => {
+            return Err(KubeAgentError::ParseError(format!(
+                "unsupported patch content type {}",
+                content_type
+            )))
+        }
+    };
+    if !valid {
+        return Err(KubeAgentError::ParseError(format!(
+            "{} body has the wrong shape: {}",
+            content_type, patch
+        )));
+    }
+    Ok(())
+}
+
+// Percent-encodes a query parameter value (continue tokens are base64 and may
+// contain '+', '/' and '=')
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
             }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockKubeServer;
+    use super::*;
+    use serde_json::json;
+
+    const DEPLOYMENT: &str = "/apis/apps/v1/namespaces/default/deployments/worker";
+
+    #[test]
+    fn check_patch_matches_body_shape_to_content_type() {
+        let operations = json!([{ "op": "replace", "path": "/spec/replicas", "value": 2 }]);
+        let object = json!({ "spec": { "replicas": 2 } });
+
+        assert!(check_patch("application/json-patch+json", &operations).is_ok());
+        assert!(check_patch("application/merge-patch+json", &object).is_ok());
+        assert!(check_patch("application/strategic-merge-patch+json", &object).is_ok());
+
+        for (content_type, patch) in [
+            ("application/json-patch+json", &object),
+            ("application/merge-patch+json", &operations),
+            ("application/strategic-merge-patch+json", &operations),
+            (JSON, &object),
+        ] {
+            assert!(
+                matches!(
+                    check_patch(content_type, patch),
+                    Err(KubeAgentError::ParseError(_))
+                ),
+                "{} accepted {}",
+                content_type,
+                patch
+            );
         }
     }
+
+    #[tokio::test]
+    async fn make_request_sends_each_verb_with_its_content_type() {
+        let server = MockKubeServer::start().await.unwrap();
+        server.respond_to("PATCH", DEPLOYMENT, 200, json!({ "kind": "Deployment" }));
+        let kube = server.kube_agent();
+
+        kube.make_request(Method::GET, "/api/v1/nodes".to_string(), None, None)
+            .await
+            .unwrap();
+        let patch = json!({ "spec": { "replicas": 3 } });
+        kube.make_request(
+            Method::PATCH,
+            DEPLOYMENT.to_string(),
+            Some(&patch),
+            Some("application/merge-patch+json"),
+        )
+        .await
+        .unwrap();
+        let review = json!({ "kind": "SelfSubjectAccessReview" });
+        kube.make_request(
+            Method::POST,
+            "/apis/authorization.k8s.io/v1/selfsubjectaccessreviews".to_string(),
+            Some(&review),
+            None,
+        )
+        .await
+        .unwrap();
+        let deleted = kube
+            .make_request(
+                Method::DELETE,
+                "/api/v1/namespaces/default/pods/worker-5c8f7d6b9-q4wzn".to_string(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(deleted.contains("worker-5c8f7d6b9-q4wzn"));
+
+        let requests = server.requests();
+        let sent: Vec<(&str, Option<&str>)> = requests
+            .iter()
+            .map(|request| (request.method.as_str(), request.content_type.as_deref()))
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                ("GET", None),
+                ("PATCH", Some("application/merge-patch+json")),
+                ("POST", Some(JSON)),
+                ("DELETE", None),
+            ]
+        );
+        assert_eq!(requests[1].body.as_ref(), Some(&patch));
+        assert_eq!(requests[2].body.as_ref(), Some(&review));
+    }
+
+    #[tokio::test]
+    async fn make_request_refuses_misshapen_patches_before_sending() {
+        let server = MockKubeServer::start().await.unwrap();
+        let kube = server.kube_agent();
+
+        let operations = json!([{ "op": "replace", "path": "/spec/replicas", "value": 0 }]);
+        let result = kube
+            .make_request(
+                Method::PATCH,
+                DEPLOYMENT.to_string(),
+                Some(&operations),
+                Some("application/strategic-merge-patch+json"),
+            )
+            .await;
+        assert!(matches!(result, Err(KubeAgentError::ParseError(_))));
+
+        // Without a content type the body is plain JSON, which isn't a patch format
+        let result = kube
+            .make_request(
+                Method::PATCH,
+                DEPLOYMENT.to_string(),
+                Some(&json!({})),
+                None,
+            )
+            .await;
+        assert!(matches!(result, Err(KubeAgentError::ParseError(_))));
+
+        assert!(server.requests().is_empty());
+    }
 }
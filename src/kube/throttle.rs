@@ -0,0 +1,151 @@
+use super::error::KubeAgentError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Longest a request waits for the rate limiter; beyond that it is refused instead.
+const MAX_WAIT: Duration = Duration::from_secs(10);
+
+/// Client-side protection of the API servers from agent loops.
+///
+/// Each cluster gets a token bucket that refills at `qps` requests per second and holds
+/// up to `burst`, so a request waits its turn once the burst is spent. Each cluster also
+/// gets a circuit breaker: after `failure_threshold` consecutive failures (timeouts,
+/// connection errors, 429s, and 5xx responses) it opens and refuses requests for
+/// `cooldown`. After the cooldown requests go through again, and the next failure
+/// reopens it at once. Refusals are [`KubeAgentError::BackingOff`], which tools pass on
+/// to the model. A `qps` or `failure_threshold` of zero turns that half off.
+///
+/// Shared by every clone of a [`super::KubeAgent`]; cached responses never reach it.
+pub struct ApiThrottle {
+    qps: f64,
+    burst: f64,
+    failure_threshold: u32,
+    cooldown: Duration,
+    clusters: Mutex<HashMap<String, ClusterState>>,
+}
+
+struct ClusterState {
+    /// Requests that may start right away; negative while requests wait for a token
+    tokens: f64,
+    refilled_at: Instant,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl ApiThrottle {
+    pub fn new(qps: f64, burst: u32, failure_threshold: u32, cooldown: Duration) -> Self {
+        ApiThrottle {
+            qps,
+            burst: burst.max(1) as f64,
+            failure_threshold,
+            cooldown,
+            clusters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a request slot for `cluster`, waiting for the rate limiter if needed.
+    ///
+    /// Fails while the cluster's breaker is open, or if the wait would exceed
+    /// [`MAX_WAIT`] because too many requests are already queued.
+    pub async fn acquire(&self, cluster: &str) -> Result<(), KubeAgentError> {
+        let wait = {
+            let mut clusters = self.clusters.lock().unwrap();
+            let now = Instant::now();
+            let state = clusters
+                .entry(cluster.to_string())
+                .or_insert_with(|| ClusterState {
+                    tokens: self.burst,
+                    refilled_at: now,
+                    consecutive_failures: 0,
+                    open_until: None,
+                });
+
+            if let Some(open_until) = state.open_until.filter(|until| *until > now) {
+                return Err(KubeAgentError::BackingOff(format!(
+                    "the last {} requests to cluster {} failed; try again in {}s",
+                    state.consecutive_failures,
+                    cluster,
+                    (open_until - now).as_secs().max(1)
+                )));
+            }
+            if self.qps <= 0.0 {
+                return Ok(());
+            }
+
+            let refill = now.duration_since(state.refilled_at).as_secs_f64() * self.qps;
+            state.tokens = (state.tokens + refill).min(self.burst);
+            state.refilled_at = now;
+            let wait = if state.tokens >= 1.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64((1.0 - state.tokens) / self.qps)
+            };
+            if wait > MAX_WAIT {
+                return Err(KubeAgentError::BackingOff(format!(
+                    "too many requests to cluster {} (limit {} per second); try again in {}s",
+                    cluster,
+                    self.qps,
+                    wait.as_secs()
+                )));
+            }
+            // Reserve the token now so requests queued behind this one wait longer
+            state.tokens -= 1.0;
+            wait
+        };
+
+        if !wait.is_zero() {
+            debug!(
+                "Rate limiting request to cluster {} for {}ms",
+                cluster,
+                wait.as_millis()
+            );
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Records how a request to `cluster` ended, opening the breaker after too many
+    /// failures in a row.
+    pub fn record(&self, cluster: &str, error: Option<&KubeAgentError>) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+        let mut clusters = self.clusters.lock().unwrap();
+        let Some(state) = clusters.get_mut(cluster) else {
+            return;
+        };
+
+        if !error.is_some_and(is_server_failure) {
+            if state.consecutive_failures >= self.failure_threshold {
+                info!("Kubernetes API of cluster {} is answering again", cluster);
+            }
+            state.consecutive_failures = 0;
+            state.open_until = None;
+            return;
+        }
+
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            warn!(
+                "{} consecutive requests to cluster {} failed; backing off for {}s",
+                state.consecutive_failures,
+                cluster,
+                self.cooldown.as_secs()
+            );
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+// Failures that suggest the API server is struggling, rather than a bad request
+fn is_server_failure(error: &KubeAgentError) -> bool {
+    match error {
+        KubeAgentError::HttpError(_) | KubeAgentError::TimeoutError(_) => true,
+        KubeAgentError::ApiError { code, .. } => *code == 429 || *code >= 500,
+        #[cfg(feature = "kube-rs")]
+        KubeAgentError::KubeClientError(_) => true,
+        _ => false,
+    }
+}
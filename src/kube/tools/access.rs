@@ -0,0 +1,176 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::SelfSubjectAccessReview;
+use crate::kube::KubeAgent;
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for checking whether the agent's own credentials allow an action, like
+/// `kubectl auth can-i`.
+///
+/// Creates a SelfSubjectAccessReview, which only evaluates RBAC and changes nothing,
+/// so the agent can explain what it may and may not do before trying it.
+pub struct CanITool {
+    kube_agent: KubeAgent,
+}
+
+impl CanITool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        CanITool { kube_agent }
+    }
+
+    pub async fn can_i(
+        &self,
+        verb: String,
+        resource: String,
+        namespace: Option<String>,
+        group: Option<String>,
+        subresource: Option<String>,
+        name: Option<String>,
+    ) -> Result<String, KubeAgentError> {
+        // "deployments.apps" is accepted as shorthand for group "apps"
+        let (resource, group) = match (resource.split_once('.'), group) {
+            (_, Some(group)) => (resource, group),
+            (Some((resource, group)), None) => (resource.to_string(), group.to_string()),
+            (None, None) => (resource, String::new()),
+        };
+
+        let review = json!({
+            "apiVersion": "authorization.k8s.io/v1",
+            "kind": "SelfSubjectAccessReview",
+            "spec": {
+                "resourceAttributes": {
+                    "verb": verb,
+                    "resource": resource,
+                    "group": group,
+                    "namespace": namespace.clone().unwrap_or_default(),
+                    "subresource": subresource.clone().unwrap_or_default(),
+                    "name": name.clone().unwrap_or_default(),
+                }
+            }
+        });
+
+        let response = self
+            .kube_agent
+            .make_request(
+                Method::POST,
+                "/apis/authorization.k8s.io/v1/selfsubjectaccessreviews".to_string(),
+                Some(&review),
+                None,
+            )
+            .await?;
+        let review: SelfSubjectAccessReview = serde_json::from_str(&response).map_err(|e| {
+            error!("Error parsing access review JSON response: {}", e);
+            KubeAgentError::from(e)
+        })?;
+
+        // e.g. "list pods/log named web-0 in namespace portfolio"
+        let mut action = format!("{} {}", verb, resource);
+        if !group.is_empty() {
+            action.push_str(&format!(".{}", group));
+        }
+        if let Some(subresource) = &subresource {
+            action.push_str(&format!("/{}", subresource));
+        }
+        if let Some(name) = &name {
+            action.push_str(&format!(" named {}", name));
+        }
+        match &namespace {
+            Some(namespace) => action.push_str(&format!(" in namespace {}", namespace)),
+            None => action.push_str(" across all namespaces"),
+        }
+
+        let status = review.status;
+        let mut output = if status.allowed {
+            format!("Allowed: the agent can {}.", action)
+        } else {
+            format!("Not allowed: the agent cannot {}.", action)
+        };
+        if let Some(reason) = status.reason.filter(|r| !r.is_empty()) {
+            output.push_str(&format!(" Reason: {}", reason));
+        }
+        if let Some(error) = status.evaluation_error.filter(|e| !e.is_empty()) {
+            output.push_str(&format!(" (evaluation error: {})", error));
+        }
+        Ok(output)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CanIToolArgs {
+    pub verb: String,
+    pub resource: String,
+    pub namespace: Option<String>,
+    pub group: Option<String>,
+    pub subresource: Option<String>,
+    pub name: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for CanITool {
+    const NAME: &'static str = "can_i";
+    type Args = CanIToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Check whether the agent's own service account is permitted to perform an action (like 'kubectl auth can-i'). Use it to explain what you can and cannot access, e.g. before reading secrets or when another tool fails with Forbidden.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "verb": {
+                        "type": "string",
+                        "description": "The action, e.g. get, list, watch, create, patch, delete"
+                    },
+                    "resource": {
+                        "type": "string",
+                        "description": "The resource type in plural form, e.g. pods, secrets, or deployments.apps"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to check (default is all namespaces / cluster-scoped)"
+                    },
+                    "group": {
+                        "type": "string",
+                        "description": "The API group, e.g. apps (default is the core group)"
+                    },
+                    "subresource": {
+                        "type": "string",
+                        "description": "A subresource, e.g. log or scale"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "A specific object name"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["verb", "resource"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.can_i(
+                args.verb,
+                args.resource,
+                args.namespace,
+                args.group,
+                args.subresource,
+                args.name,
+            ),
+        )
+        .await
+    }
+}
@@ -0,0 +1,73 @@
+use crate::kube::error::KubeAgentError;
+use crate::kube::watch::ClusterSnapshot;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Tool for reporting what currently looks unhealthy in the home cluster.
+///
+/// Answers from the in-memory snapshot kept by the cluster watcher instead of querying
+/// the API server, so it is cheap enough to call at the start of any investigation.
+pub struct ClusterAlertsTool {
+    snapshot: ClusterSnapshot,
+}
+
+impl ClusterAlertsTool {
+    pub fn new(snapshot: ClusterSnapshot) -> Self {
+        ClusterAlertsTool { snapshot }
+    }
+
+    pub fn cluster_alerts(&self) -> String {
+        if !self.snapshot.is_synced() {
+            return "The cluster watcher is still loading the initial cluster state; try again shortly or use the other Kubernetes tools.".to_string();
+        }
+
+        let (pods, nodes) = self.snapshot.counts();
+        let alerts = self.snapshot.alerts();
+        if alerts.is_empty() {
+            return format!(
+                "No alerts: all {} nodes are healthy and none of the {} pods are failing or stuck.",
+                nodes, pods
+            );
+        }
+
+        let mut output = format!(
+            "{} alerts across {} nodes and {} pods:\n",
+            alerts.len(),
+            nodes,
+            pods
+        );
+        for alert in alerts {
+            output.push_str(&format!("- {}\n", alert.message));
+        }
+        output
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ClusterAlertsToolArgs {}
+
+impl Tool for ClusterAlertsTool {
+    const NAME: &'static str = "cluster_alerts";
+    type Args = ClusterAlertsToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "List current problems in the home cluster: NotReady or pressured nodes, failed, long-pending, or crash-looping pods, and recent Warning events. Answers instantly from a continuously updated snapshot; a good first step when asked what is wrong.",
+            "parameters": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
+        Ok(self.cluster_alerts())
+    }
+}
@@ -0,0 +1,175 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::TlsSecretListResponse;
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use chrono::Utc;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Certificates expiring within this many days are flagged unless the caller overrides it.
+const DEFAULT_WARN_DAYS: i64 = 30;
+
+/// Tool for auditing the expiry of the certificates stored in TLS secrets.
+///
+/// Parses the certificate in each `kubernetes.io/tls` secret and reports its subject,
+/// DNS names, issuer, and days until expiry, soonest first. Private keys are never read.
+pub struct CertificateExpiryTool {
+    kube_agent: KubeAgent,
+}
+
+impl CertificateExpiryTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        CertificateExpiryTool { kube_agent }
+    }
+
+    pub async fn check_certificate_expiry(
+        &self,
+        namespace: Option<String>,
+        warn_days: Option<i64>,
+    ) -> Result<String, KubeAgentError> {
+        let warn_days = warn_days.unwrap_or(DEFAULT_WARN_DAYS);
+        let endpoint = match &namespace {
+            Some(namespace) => format!(
+                "/api/v1/namespaces/{}/secrets?fieldSelector=type%3Dkubernetes.io%2Ftls",
+                namespace
+            ),
+            None => "/api/v1/secrets?fieldSelector=type%3Dkubernetes.io%2Ftls".to_string(),
+        };
+        // As with list_secrets, the raw response is never logged
+        let listing = self.kube_agent.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+        let note = listing.truncation_note();
+        let secrets = TlsSecretListResponse {
+            items: listing.items,
+        };
+
+        debug!("Auditing {} TLS secrets", secrets.items.len());
+
+        if secrets.items.is_empty() {
+            return Ok("No TLS secrets found.".to_string());
+        }
+
+        let now = Utc::now();
+        let mut certificates = Vec::new();
+        let mut unreadable = Vec::new();
+        for secret in &secrets.items {
+            let name = format!("{}/{}", secret.metadata.namespace, secret.metadata.name);
+            match secret.certificate() {
+                Ok(certificate) => certificates.push((name, certificate)),
+                Err(e) => {
+                    warn!("Failed to parse certificate in secret {}: {}", name, e);
+                    unreadable.push(format!("- {}: {}\n", name, e));
+                }
+            }
+        }
+        certificates.sort_by_key(|(_, certificate)| certificate.not_after);
+
+        let days_left = |expiry: chrono::DateTime<Utc>| (expiry - now).num_days();
+        let expired = certificates
+            .iter()
+            .filter(|(_, c)| c.not_after <= now)
+            .count();
+        let expiring = certificates
+            .iter()
+            .filter(|(_, c)| c.not_after > now && days_left(c.not_after) < warn_days)
+            .count();
+
+        let mut output = format!(
+            "Found {} TLS secrets: {} expired, {} expiring within {} days.\n\n",
+            secrets.items.len(),
+            expired,
+            expiring,
+            warn_days
+        );
+        for (name, certificate) in &certificates {
+            let days = days_left(certificate.not_after);
+            let flag = if certificate.not_after <= now {
+                "EXPIRED"
+            } else if days < warn_days {
+                "EXPIRING"
+            } else {
+                "OK"
+            };
+            output.push_str(&format!(
+                "- [{}] {}: expires {} ({} days)\n",
+                flag,
+                name,
+                certificate.not_after.format("%Y-%m-%d %H:%M UTC"),
+                days
+            ));
+            output.push_str(&format!("    Subject: {}\n", certificate.subject));
+            if !certificate.dns_names.is_empty() {
+                output.push_str(&format!(
+                    "    DNS names: {}\n",
+                    certificate.dns_names.join(", ")
+                ));
+            }
+            output.push_str(&format!("    Issuer: {}\n", certificate.issuer));
+            if let Some(chain_expiry) = certificate.chain_not_after {
+                output.push_str(&format!(
+                    "    Warning: an intermediate in the {}-certificate chain expires earlier, on {} ({} days)\n",
+                    certificate.chain_length,
+                    chain_expiry.format("%Y-%m-%d"),
+                    days_left(chain_expiry)
+                ));
+            }
+        }
+
+        if !unreadable.is_empty() {
+            output.push_str("\nCould not read the certificate in:\n");
+            output.push_str(&unreadable.concat());
+        }
+
+        Ok(output + &note)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CertificateExpiryToolArgs {
+    pub namespace: Option<String>,
+    pub warn_days: Option<i64>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for CertificateExpiryTool {
+    const NAME: &'static str = "check_certificate_expiry";
+    type Args = CertificateExpiryToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Audit the certificates in TLS secrets (e.g. ingress certificates): subject, DNS names, issuer, expiry date, and days remaining, soonest first, flagging expired and soon-expiring ones. Private keys are never read.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to check (default is all namespaces)"
+                    },
+                    "warn_days": {
+                        "type": "integer",
+                        "description": format!("Flag certificates expiring within this many days (default {})", DEFAULT_WARN_DAYS)
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.check_certificate_expiry(args.namespace, args.warn_days),
+        )
+        .await
+    }
+}
@@ -0,0 +1,61 @@
+use crate::kube::error::KubeAgentError;
+use crate::kube::KubeAgent;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Tool for listing the clusters the other Kubernetes tools can target via their
+/// `cluster` argument.
+pub struct ListClustersTool {
+    kube_agent: KubeAgent,
+}
+
+impl ListClustersTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        ListClustersTool { kube_agent }
+    }
+
+    pub fn list_clusters(&self) -> String {
+        let mut output = String::from("Clusters:\n");
+        for (i, cluster) in self.kube_agent.clusters().iter().enumerate() {
+            let marker = if i == 0 {
+                " (home, used by default)"
+            } else {
+                ""
+            };
+            output.push_str(&format!(
+                "- {}{}: {}\n",
+                cluster.name, marker, cluster.api_server
+            ));
+        }
+        output
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ListClustersToolArgs {}
+
+impl Tool for ListClustersTool {
+    const NAME: &'static str = "list_clusters";
+    type Args = ListClustersToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "List the Kubernetes clusters the agent can query. Pass a cluster's name as the 'cluster' argument of other Kubernetes tools to target it.",
+            "parameters": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
+        Ok(self.list_clusters())
+    }
+}
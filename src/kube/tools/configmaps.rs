@@ -0,0 +1,96 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{ConfigMap, ConfigMapListResponse};
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for listing ConfigMaps in a namespace.
+///
+/// Returns names, keys, and value sizes. Values are redacted unless the configmap is
+/// on the configured allowlist, so configuration questions can be answered without
+/// dumping every value into the model context.
+pub struct ListConfigMapsTool {
+    kube_agent: KubeAgent,
+    /// Configmaps whose values may be shown, as "name" or "namespace/name"
+    value_allowlist: Vec<String>,
+}
+
+impl ListConfigMapsTool {
+    pub fn new(kube_agent: KubeAgent, value_allowlist: Vec<String>) -> Self {
+        ListConfigMapsTool {
+            kube_agent,
+            value_allowlist,
+        }
+    }
+
+    pub async fn list_configmaps(
+        &self,
+        namespace: Option<String>,
+    ) -> Result<String, KubeAgentError> {
+        let namespace = namespace.unwrap_or_else(|| "default".to_string());
+        let endpoint = format!("/api/v1/namespaces/{}/configmaps", namespace);
+        let listing = self.kube_agent.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+
+        debug!("Listed {} configmaps in {}", listing.items.len(), namespace);
+
+        let note = listing.truncation_note();
+        let configmaps = ConfigMapListResponse {
+            items: listing.items,
+        };
+
+        Ok(configmaps.as_string(|configmap| self.values_allowed(configmap)) + &note)
+    }
+
+    fn values_allowed(&self, configmap: &ConfigMap) -> bool {
+        let qualified = format!(
+            "{}/{}",
+            configmap.metadata.namespace, configmap.metadata.name
+        );
+        self.value_allowlist
+            .iter()
+            .any(|entry| *entry == configmap.metadata.name || *entry == qualified)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ListConfigMapsToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for ListConfigMapsTool {
+    const NAME: &'static str = "list_configmaps";
+    type Args = ListConfigMapsToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "List ConfigMaps in a Kubernetes namespace with their keys and value sizes. Values are only shown for allowlisted configmaps; others are redacted.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to list configmaps from (default is 'default')"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.list_configmaps(args.namespace)).await
+    }
+}
@@ -0,0 +1,148 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::guard::{self, WriteGuard};
+use crate::kube::types::NodeDetails;
+use crate::kube::KubeAgent;
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for cordoning a node, so no new pods are scheduled on it, and for uncordoning
+/// it again.
+///
+/// A write tool: it is only registered when `ENABLE_WRITE_TOOLS=true` and each change
+/// must pass the [`WriteGuard`], which also audits it. Nodes are cluster-scoped, so the
+/// namespace allowlist doesn't apply. Pods already on the node keep running; see
+/// `drain_plan` for what moving them would involve.
+pub struct CordonNodeTool {
+    kube_agent: KubeAgent,
+    guard: WriteGuard,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CordonAction {
+    Cordon,
+    Uncordon,
+}
+
+impl CordonNodeTool {
+    pub fn new(kube_agent: KubeAgent, guard: WriteGuard) -> Self {
+        CordonNodeTool { kube_agent, guard }
+    }
+
+    /// Sets `spec.unschedulable` on the node, doing nothing if it already has the
+    /// requested value.
+    pub async fn cordon_node(
+        &self,
+        node: String,
+        action: CordonAction,
+    ) -> Result<String, KubeAgentError> {
+        guard::check_name(&node)?;
+        let object = format!("node/{}", node);
+        let grant = self.guard.authorize_cluster_scoped(Self::NAME, &object)?;
+
+        let endpoint = format!("/api/v1/nodes/{}", node);
+
+        let details: NodeDetails =
+            serde_json::from_str(&self.kube_agent.get(endpoint.clone()).await?).map_err(|e| {
+                error!("Error parsing node JSON response: {}", e);
+                KubeAgentError::from(e)
+            })?;
+        let current = details.spec.as_ref().and_then(|spec| spec.unschedulable);
+        let previous = current.unwrap_or(false);
+        let unschedulable = action == CordonAction::Cordon;
+        if previous == unschedulable {
+            return Ok(format!(
+                "Node {} is already {}; nothing was changed.",
+                node,
+                if unschedulable {
+                    "cordoned"
+                } else {
+                    "schedulable"
+                }
+            ));
+        }
+
+        // As with scaling, the test op makes the API server reject the patch if the
+        // field changed since it was read
+        let mut patch = vec![json!({
+            "op": "add",
+            "path": "/spec/unschedulable",
+            "value": unschedulable
+        })];
+        if let Some(current) = current {
+            patch.insert(
+                0,
+                json!({ "op": "test", "path": "/spec/unschedulable", "value": current }),
+            );
+        }
+        self.kube_agent
+            .make_request(
+                Method::PATCH,
+                endpoint,
+                Some(&json!(patch)),
+                Some("application/json-patch+json"),
+            )
+            .await?;
+
+        grant.record(&format!("unschedulable {} -> {}", previous, unschedulable));
+
+        Ok(if unschedulable {
+            format!(
+                "Cordoned node {}. New pods will not be scheduled on it; pods already running there are left alone.",
+                node
+            )
+        } else {
+            format!("Uncordoned node {}. It accepts new pods again.", node)
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CordonNodeToolArgs {
+    pub node: String,
+    pub action: CordonAction,
+    pub cluster: Option<String>,
+}
+
+impl Tool for CordonNodeTool {
+    const NAME: &'static str = "cordon_node";
+    type Args = CordonNodeToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Cordon a Kubernetes node so no new pods are scheduled on it, or uncordon it so it accepts pods again. Pods already on the node keep running. Only use this when the user explicitly asks to cordon or uncordon a node.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "node": {
+                        "type": "string",
+                        "description": "The name of the node"
+                    },
+                    "action": {
+                        "type": "string",
+                        "enum": ["cordon", "uncordon"],
+                        "description": "Whether to cordon or uncordon the node"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to act on (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["node", "action"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.cordon_node(args.node, args.action)).await
+    }
+}
@@ -0,0 +1,286 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::quantity::parse_quantity;
+use crate::kube::types::table::Table;
+use crate::kube::types::Pod;
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use tracing::*;
+
+/// Maximum number of workloads listed, most expensive first.
+const MAX_LISTED_WORKLOADS: usize = 15;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Monthly prices the cost estimate charges for requested resources.
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    /// Per requested CPU core
+    pub cpu_monthly: f64,
+    /// Per requested GiB of memory
+    pub memory_gib_monthly: f64,
+    /// Shown after amounts, e.g. "USD"
+    pub currency: String,
+}
+
+impl PriceTable {
+    fn cost(&self, footprint: &Footprint) -> f64 {
+        footprint.cpu * self.cpu_monthly + footprint.memory / GIB * self.memory_gib_monthly
+    }
+
+    fn amount(&self, amount: f64) -> String {
+        format!("{:.2} {}", amount, self.currency)
+    }
+}
+
+/// Tool for estimating what each namespace and workload costs, from the CPU and memory
+/// its pods request priced with a [`PriceTable`].
+///
+/// Requests are what the scheduler reserves on nodes, so they are what a workload
+/// takes from the cluster's capacity whatever it actually uses. Finished pods are
+/// skipped, and pods without requests are counted separately since they cost nothing
+/// by this measure.
+pub struct NamespaceCostTool {
+    kube_agent: KubeAgent,
+    prices: PriceTable,
+}
+
+/// Resources requested by a group of pods: CPU in cores, memory in bytes.
+#[derive(Default)]
+struct Footprint {
+    pods: usize,
+    cpu: f64,
+    memory: f64,
+}
+
+impl Footprint {
+    fn add(&mut self, other: &Footprint) {
+        self.pods += other.pods;
+        self.cpu += other.cpu;
+        self.memory += other.memory;
+    }
+}
+
+impl NamespaceCostTool {
+    pub fn new(kube_agent: KubeAgent, prices: PriceTable) -> Self {
+        NamespaceCostTool { kube_agent, prices }
+    }
+
+    pub async fn estimate_costs(
+        &self,
+        namespace: Option<String>,
+    ) -> Result<String, KubeAgentError> {
+        let pods = self
+            .kube_agent
+            .list_all::<Pod>("/api/v1/pods", MAX_LIST_ITEMS)
+            .await?;
+        debug!("Estimating costs for {} pods", pods.items.len());
+
+        let mut namespaces: HashMap<&str, Footprint> = HashMap::new();
+        let mut workloads: HashMap<(&str, String), Footprint> = HashMap::new();
+        let mut without_requests: Vec<String> = Vec::new();
+        for pod in pods.items.iter().filter(|pod| !is_finished(pod)) {
+            let footprint = requests(pod);
+            if footprint.cpu == 0.0 && footprint.memory == 0.0 {
+                without_requests.push(format!("{}/{}", pod.metadata.namespace, pod.metadata.name));
+            }
+            let workload = pod
+                .workload()
+                .unwrap_or_else(|| format!("Pod/{}", pod.metadata.name));
+            namespaces
+                .entry(pod.metadata.namespace.as_str())
+                .or_default()
+                .add(&footprint);
+            workloads
+                .entry((pod.metadata.namespace.as_str(), workload))
+                .or_default()
+                .add(&footprint);
+        }
+
+        let mut total = Footprint::default();
+        for footprint in namespaces.values() {
+            total.add(footprint);
+        }
+        let total_cost = self.prices.cost(&total);
+        let share = |footprint: &Footprint| {
+            if total_cost > 0.0 {
+                format!("{:.1}%", self.prices.cost(footprint) / total_cost * 100.0)
+            } else {
+                "-".to_string()
+            }
+        };
+
+        let mut output = format!(
+            "Estimated monthly cost of pod resource requests at {} per CPU core and {} per GiB of memory. Actual usage, node prices, and storage are not included.\n",
+            self.prices.amount(self.prices.cpu_monthly),
+            self.prices.amount(self.prices.memory_gib_monthly)
+        );
+        output.push_str(&format!(
+            "Cluster total: {} for {:.2} cores and {:.1} GiB requested by {} pods.\n",
+            self.prices.amount(total_cost),
+            total.cpu,
+            total.memory / GIB,
+            total.pods
+        ));
+
+        let mut workloads: Vec<_> = workloads
+            .into_iter()
+            .filter(|((workload_namespace, _), _)| {
+                namespace
+                    .as_deref()
+                    .is_none_or(|namespace| namespace == *workload_namespace)
+            })
+            .collect();
+        workloads.sort_by(|(_, a), (_, b)| self.prices.cost(b).total_cmp(&self.prices.cost(a)));
+
+        match &namespace {
+            Some(namespace) => {
+                let Some(footprint) = namespaces.get(namespace.as_str()) else {
+                    output.push_str(&format!("\nNo running pods in namespace {}.\n", namespace));
+                    return Ok(output);
+                };
+                output.push_str(&format!(
+                    "Namespace {}: {} ({} of the cluster) for {:.2} cores and {:.1} GiB requested by {} pods.\n",
+                    namespace,
+                    self.prices.amount(self.prices.cost(footprint)),
+                    share(footprint),
+                    footprint.cpu,
+                    footprint.memory / GIB,
+                    footprint.pods
+                ));
+            }
+            None => {
+                let mut namespaces: Vec<_> = namespaces.into_iter().collect();
+                namespaces
+                    .sort_by(|(_, a), (_, b)| self.prices.cost(b).total_cmp(&self.prices.cost(a)));
+                let mut table =
+                    Table::new(&["NAMESPACE", "PODS", "CPU", "MEMORY", "COST", "SHARE"]);
+                for (name, footprint) in &namespaces {
+                    table.row(self.cells(name, footprint, share(footprint)));
+                }
+                output.push_str(&format!("\nBy namespace:\n{}", table.render()));
+            }
+        }
+
+        let mut table = Table::new(&["WORKLOAD", "PODS", "CPU", "MEMORY", "COST", "SHARE"]);
+        for ((workload_namespace, workload), footprint) in
+            workloads.iter().take(MAX_LISTED_WORKLOADS)
+        {
+            table.row(self.cells(
+                &format!("{}/{}", workload_namespace, workload),
+                footprint,
+                share(footprint),
+            ));
+        }
+        output.push_str(&format!("\nMost expensive workloads:\n{}", table.render()));
+        if workloads.len() > MAX_LISTED_WORKLOADS {
+            output.push_str(&format!(
+                "... and {} more workloads\n",
+                workloads.len() - MAX_LISTED_WORKLOADS
+            ));
+        }
+
+        without_requests.retain(|pod| {
+            namespace
+                .as_deref()
+                .is_none_or(|namespace| pod.starts_with(&format!("{}/", namespace)))
+        });
+        if !without_requests.is_empty() {
+            output.push_str(&format!(
+                "\n{} pods request no CPU or memory, so they are counted as free even though they use node capacity: {}{}\n",
+                without_requests.len(),
+                without_requests[..without_requests.len().min(MAX_LISTED_WORKLOADS)].join(", "),
+                if without_requests.len() > MAX_LISTED_WORKLOADS {
+                    ", ..."
+                } else {
+                    ""
+                }
+            ));
+        }
+        output.push_str(&pods.truncation_note());
+        Ok(output)
+    }
+
+    // NAME, PODS, CPU, MEMORY, COST, SHARE
+    fn cells(&self, name: &str, footprint: &Footprint, share: String) -> Vec<String> {
+        vec![
+            name.to_string(),
+            footprint.pods.to_string(),
+            format!("{:.2}", footprint.cpu),
+            format!("{:.1}Gi", footprint.memory / GIB),
+            self.prices.amount(self.prices.cost(footprint)),
+            share,
+        ]
+    }
+}
+
+fn is_finished(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .is_some_and(|status| matches!(status.phase.as_str(), "Succeeded" | "Failed"))
+}
+
+// The CPU and memory requested by the pod's containers
+fn requests(pod: &Pod) -> Footprint {
+    let mut footprint = Footprint {
+        pods: 1,
+        ..Footprint::default()
+    };
+    let requests = pod
+        .spec
+        .iter()
+        .flat_map(|spec| &spec.containers)
+        .filter_map(|container| container.resources.as_ref()?.requests.as_ref());
+    for requests in requests {
+        let quantity = |name: &str| requests.get(name).and_then(|value| parse_quantity(value));
+        footprint.cpu += quantity("cpu").unwrap_or(0.0);
+        footprint.memory += quantity("memory").unwrap_or(0.0);
+    }
+    footprint
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NamespaceCostToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for NamespaceCostTool {
+    const NAME: &'static str = "estimate_costs";
+    type Args = NamespaceCostToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "Estimate the monthly cost and share of the cluster of each namespace and its most expensive workloads, from the CPU and memory their pods request at {} per core and {} per GiB. Use this for questions like 'what's the most expensive thing running?'. These are estimates from requests, not a bill.",
+                self.prices.amount(self.prices.cpu_monthly),
+                self.prices.amount(self.prices.memory_gib_monthly)
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "Only break down this namespace's workloads (default is every namespace)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.estimate_costs(args.namespace)).await
+    }
+}
@@ -0,0 +1,256 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::table::{age, Table};
+use crate::kube::types::{CrdVersion, CustomResourceDefinition};
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::*;
+
+/// Maximum number of custom resources listed per call.
+const MAX_LISTED_RESOURCES: usize = 100;
+
+/// Tool for discovering CustomResourceDefinitions and listing instances of one, so
+/// operator resources (cert-manager Certificates, Argo Applications, ...) can be
+/// inspected without a dedicated tool for each.
+///
+/// Without a `crd` it lists the installed definitions. With one, it resolves the name
+/// to a definition's group, served version, and plural, then lists the instances with
+/// the printer columns the definition declares, like `kubectl get` does. Only names
+/// and those columns are shown, never whole specs.
+pub struct CustomResourcesTool {
+    kube_agent: KubeAgent,
+}
+
+impl CustomResourcesTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        CustomResourcesTool { kube_agent }
+    }
+
+    pub async fn list_custom_resources(
+        &self,
+        crd: Option<String>,
+        namespace: Option<String>,
+    ) -> Result<String, KubeAgentError> {
+        let definitions = self
+            .kube_agent
+            .list_all::<CustomResourceDefinition>(
+                "/apis/apiextensions.k8s.io/v1/customresourcedefinitions",
+                MAX_LIST_ITEMS,
+            )
+            .await?;
+        debug!(
+            "Found {} CustomResourceDefinitions",
+            definitions.items.len()
+        );
+
+        let Some(crd) = crd else {
+            return Ok(definitions_table(&definitions.items) + &definitions.truncation_note());
+        };
+        let matches: Vec<&CustomResourceDefinition> = definitions
+            .items
+            .iter()
+            .filter(|definition| definition.is_named(&crd))
+            .collect();
+        let definition = match matches.as_slice() {
+            [definition] => *definition,
+            [] => {
+                return Ok(format!(
+                    "No CustomResourceDefinition is named '{}'. Call list_custom_resources without crd to see the installed ones.",
+                    crd
+                ))
+            }
+            several => {
+                let names: Vec<&str> = several.iter().map(|d| d.metadata.name.as_str()).collect();
+                return Ok(format!(
+                    "'{}' is ambiguous; use one of the full names: {}",
+                    crd,
+                    names.join(", ")
+                ));
+            }
+        };
+        let Some(version) = definition.preferred_version() else {
+            return Ok(format!(
+                "CustomResourceDefinition {} serves no versions.",
+                definition.metadata.name
+            ));
+        };
+
+        let spec = &definition.spec;
+        let endpoint = match namespace.as_ref().filter(|_| definition.is_namespaced()) {
+            Some(namespace) => format!(
+                "/apis/{}/{}/namespaces/{}/{}",
+                spec.group, version.name, namespace, spec.names.plural
+            ),
+            None => format!(
+                "/apis/{}/{}/{}",
+                spec.group, version.name, spec.names.plural
+            ),
+        };
+        let resources = self
+            .kube_agent
+            .list_all::<Value>(&endpoint, MAX_LISTED_RESOURCES)
+            .await?;
+        debug!(
+            "Listed {} {} from {}",
+            resources.items.len(),
+            spec.names.plural,
+            endpoint
+        );
+
+        let scope = match (&namespace, definition.is_namespaced()) {
+            (Some(namespace), true) => format!(" in namespace {}", namespace),
+            (_, true) => " in all namespaces".to_string(),
+            (_, false) => " (cluster-scoped)".to_string(),
+        };
+        if resources.items.is_empty() {
+            return Ok(format!(
+                "No {} ({}/{}) found{}.",
+                spec.names.plural, spec.group, version.name, scope
+            ));
+        }
+
+        let show_namespace = namespace.is_none() && definition.is_namespaced();
+        Ok(format!(
+            "{} {} ({}/{}){}:\n{}{}",
+            resources.items.len(),
+            spec.names.plural,
+            spec.group,
+            version.name,
+            scope,
+            resources_table(&resources.items, version, show_namespace),
+            resources.truncation_note()
+        ))
+    }
+}
+
+fn definitions_table(definitions: &[CustomResourceDefinition]) -> String {
+    if definitions.is_empty() {
+        return "No CustomResourceDefinitions are installed.\n".to_string();
+    }
+
+    let mut table = Table::new(&["NAME", "KIND", "SCOPE", "VERSIONS", "SHORT NAMES"]);
+    for definition in definitions {
+        let versions: Vec<&str> = definition
+            .spec
+            .versions
+            .iter()
+            .filter(|version| version.served)
+            .map(|version| version.name.as_str())
+            .collect();
+        table.row(vec![
+            definition.metadata.name.clone(),
+            definition.spec.names.kind.clone(),
+            definition.spec.scope.clone(),
+            versions.join(","),
+            definition
+                .spec
+                .names
+                .short_names
+                .as_ref()
+                .map(|names| names.join(","))
+                .unwrap_or_else(|| "<none>".to_string()),
+        ]);
+    }
+    format!(
+        "{} CustomResourceDefinitions:\n{}",
+        definitions.len(),
+        table.render()
+    )
+}
+
+// NAME (and NAMESPACE) followed by the version's default printer columns, or AGE if
+// it declares none
+fn resources_table(resources: &[Value], version: &CrdVersion, show_namespace: bool) -> String {
+    let now = chrono::Utc::now();
+    let columns: Vec<_> = version
+        .additional_printer_columns
+        .iter()
+        .flatten()
+        .filter(|column| column.priority.unwrap_or(0) == 0)
+        .collect();
+
+    let mut headers: Vec<String> = Vec::new();
+    if show_namespace {
+        headers.push("NAMESPACE".to_string());
+    }
+    headers.push("NAME".to_string());
+    if columns.is_empty() {
+        headers.push("AGE".to_string());
+    }
+    headers.extend(columns.iter().map(|column| column.name.to_uppercase()));
+    let mut table = Table::new(&headers.iter().map(String::as_str).collect::<Vec<_>>());
+
+    let metadata = |resource: &Value, field: &str| {
+        resource
+            .pointer(&format!("/metadata/{}", field))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    for resource in resources {
+        let mut cells = Vec::new();
+        if show_namespace {
+            cells.push(metadata(resource, "namespace"));
+        }
+        cells.push(metadata(resource, "name"));
+        if columns.is_empty() {
+            cells.push(age(&metadata(resource, "creationTimestamp"), now));
+        }
+        cells.extend(columns.iter().map(|column| column.cell(resource, now)));
+        table.row(cells);
+    }
+    table.render()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CustomResourcesToolArgs {
+    pub crd: Option<String>,
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for CustomResourcesTool {
+    const NAME: &'static str = "list_custom_resources";
+    type Args = CustomResourcesToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "List installed CustomResourceDefinitions, or the instances of one (e.g. cert-manager Certificates or Argo CD Applications) with the columns kubectl would show. Call without crd first to discover what is installed. Lists at most {} instances.",
+                MAX_LISTED_RESOURCES
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "crd": {
+                        "type": "string",
+                        "description": "The custom resource to list: its full CRD name (e.g. certificates.cert-manager.io), plural, kind, or short name. Omit to list the CRDs themselves"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to list instances in (default is all namespaces; ignored for cluster-scoped resources)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.list_custom_resources(args.crd, args.namespace),
+        )
+        .await
+    }
+}
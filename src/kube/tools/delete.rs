@@ -0,0 +1,153 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::guard::{self, WriteGuard};
+use crate::kube::types::Pod;
+use crate::kube::KubeAgent;
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for deleting a single pod, e.g. to bounce a crash-looping pod so its
+/// controller recreates it.
+///
+/// A write tool with several safeguards: it is only registered when
+/// `ENABLE_WRITE_TOOLS=true`, the model must pass `confirm: true`, the deletion must pass
+/// the [`WriteGuard`] (which also audits it), and when a label allowlist is configured
+/// the pod must carry one of the allowlisted labels.
+pub struct DeletePodTool {
+    kube_agent: KubeAgent,
+    guard: WriteGuard,
+    /// "key=value" labels of which the pod must carry at least one (empty = any pod)
+    label_allowlist: Vec<String>,
+}
+
+impl DeletePodTool {
+    pub fn new(kube_agent: KubeAgent, guard: WriteGuard, label_allowlist: Vec<String>) -> Self {
+        DeletePodTool {
+            kube_agent,
+            guard,
+            label_allowlist,
+        }
+    }
+
+    pub async fn delete_pod(
+        &self,
+        namespace: String,
+        name: String,
+        confirm: bool,
+    ) -> Result<String, KubeAgentError> {
+        if !confirm {
+            return Err(KubeAgentError::PolicyViolation(
+                "deleting a pod requires confirm: true; ask the user to confirm first".to_string(),
+            ));
+        }
+        guard::check_namespace(&namespace)?;
+        guard::check_name(&name)?;
+        let object = format!("pod/{}", name);
+        let grant = self.guard.authorize(Self::NAME, &namespace, &object)?;
+
+        let endpoint = format!("/api/v1/namespaces/{}/pods/{}", namespace, name);
+
+        let pod: Pod = serde_json::from_str(&self.kube_agent.get(endpoint.clone()).await?)
+            .map_err(|e| {
+                error!("Error parsing pod JSON response: {}", e);
+                KubeAgentError::from(e)
+            })?;
+        if !self.labels_allowed(&pod) {
+            warn!(
+                "Refusing to delete pod {}/{}: no allowlisted label",
+                namespace, name
+            );
+            return Err(KubeAgentError::PolicyViolation(format!(
+                "pods may only be deleted if they carry one of the labels [{}]",
+                self.label_allowlist.join(", ")
+            )));
+        }
+
+        self.kube_agent
+            .make_request(Method::DELETE, endpoint, None, None)
+            .await?;
+
+        grant.record("deleted");
+
+        Ok(format!(
+            "Deleted pod {}/{}. If it is managed by a controller it will be recreated shortly.",
+            namespace, name
+        ))
+    }
+
+    fn labels_allowed(&self, pod: &Pod) -> bool {
+        if self.label_allowlist.is_empty() {
+            return true;
+        }
+
+        let Some(labels) = &pod.metadata.labels else {
+            return false;
+        };
+        self.label_allowlist.iter().any(|entry| {
+            entry
+                .split_once('=')
+                .is_some_and(|(key, value)| labels.get(key).is_some_and(|v| v == value))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeletePodToolArgs {
+    pub namespace: String,
+    pub name: String,
+    #[serde(default)]
+    pub confirm: bool,
+    pub cluster: Option<String>,
+}
+
+impl Tool for DeletePodTool {
+    const NAME: &'static str = "delete_pod";
+    type Args = DeletePodToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "Delete a single pod so its controller recreates it (e.g. to restart a crash-looping pod). Only allowed in namespaces [{}]. Only use this when the user explicitly asks, and set confirm to true only after the user has confirmed.",
+                self.guard.namespace_allowlist().join(", ")
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace of the pod"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the pod"
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "Must be true; confirms the user asked for this deletion"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to act on (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["namespace", "name", "confirm"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.delete_pod(args.namespace, args.name, args.confirm),
+        )
+        .await
+    }
+}
@@ -0,0 +1,152 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::guard::{self, WriteGuard};
+use crate::kube::types::Scale;
+use crate::kube::KubeAgent;
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Upper bound on the replica count the agent may request.
+const MAX_REPLICAS: u32 = 20;
+
+/// Tool for scaling a deployment by patching its `/scale` subresource.
+///
+/// This is a write tool: it is only registered when `ENABLE_WRITE_TOOLS=true`, and each
+/// change must pass the [`WriteGuard`], which also audits it. On top of that, it refuses
+/// to scale beyond `MAX_REPLICAS`.
+pub struct ScaleDeploymentTool {
+    kube_agent: KubeAgent,
+    guard: WriteGuard,
+}
+
+impl ScaleDeploymentTool {
+    pub fn new(kube_agent: KubeAgent, guard: WriteGuard) -> Self {
+        ScaleDeploymentTool { kube_agent, guard }
+    }
+
+    pub async fn scale_deployment(
+        &self,
+        namespace: String,
+        name: String,
+        replicas: u32,
+    ) -> Result<String, KubeAgentError> {
+        guard::check_namespace(&namespace)?;
+        guard::check_name(&name)?;
+        let object = format!("deployment/{}", name);
+        let grant = self.guard.authorize(Self::NAME, &namespace, &object)?;
+        if replicas > MAX_REPLICAS {
+            return Err(KubeAgentError::PolicyViolation(format!(
+                "cannot scale above {} replicas",
+                MAX_REPLICAS
+            )));
+        }
+
+        let endpoint = format!(
+            "/apis/apps/v1/namespaces/{}/deployments/{}/scale",
+            namespace, name
+        );
+
+        let current: Scale = serde_json::from_str(&self.kube_agent.get(endpoint.clone()).await?)
+            .map_err(|e| {
+                error!("Error parsing scale JSON response: {}", e);
+                KubeAgentError::from(e)
+            })?;
+        let previous = current.spec.replicas.unwrap_or(0);
+
+        // The test op makes the API server reject the patch if someone else changed
+        // the replica count since it was read
+        let mut patch =
+            vec![json!({ "op": "replace", "path": "/spec/replicas", "value": replicas })];
+        if let Some(current_replicas) = current.spec.replicas {
+            patch.insert(
+                0,
+                json!({ "op": "test", "path": "/spec/replicas", "value": current_replicas }),
+            );
+        }
+        let response = self
+            .kube_agent
+            .make_request(
+                Method::PATCH,
+                endpoint,
+                Some(&json!(patch)),
+                Some("application/json-patch+json"),
+            )
+            .await?;
+        let updated: Scale = serde_json::from_str(&response).map_err(|e| {
+            error!("Error parsing scale JSON response: {}", e);
+            KubeAgentError::from(e)
+        })?;
+
+        grant.record(&format!("replicas {} -> {}", previous, replicas));
+
+        Ok(format!(
+            "Scaled deployment {}/{} from {} to {} replicas ({} currently running).",
+            namespace,
+            name,
+            previous,
+            updated.spec.replicas.unwrap_or(replicas),
+            updated.status.map(|status| status.replicas).unwrap_or(0)
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ScaleDeploymentToolArgs {
+    pub namespace: String,
+    pub name: String,
+    pub replicas: u32,
+    pub cluster: Option<String>,
+}
+
+impl Tool for ScaleDeploymentTool {
+    const NAME: &'static str = "scale_deployment";
+    type Args = ScaleDeploymentToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "Scale a Kubernetes deployment to the given number of replicas. Only allowed in namespaces [{}] and up to {} replicas. Only use this when the user explicitly asks to scale.",
+                self.guard.namespace_allowlist().join(", "),
+                MAX_REPLICAS
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace of the deployment"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the deployment"
+                    },
+                    "replicas": {
+                        "type": "integer",
+                        "description": "The desired number of replicas"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to act on (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["namespace", "name", "replicas"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.scale_deployment(args.namespace, args.name, args.replicas),
+        )
+        .await
+    }
+}
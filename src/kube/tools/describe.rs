@@ -0,0 +1,243 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{Pod, PodMetrics};
+use crate::kube::KubeAgent;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Maximum number of events included in a pod description, most recent first.
+const MAX_EVENTS: usize = 15;
+
+/// Tool for describing a single pod, similar to `kubectl describe pod`.
+///
+/// Combines the pod's status, its recent events, and its container metrics into one
+/// summary so the agent can diagnose a pod with a single call.
+pub struct DescribePodTool {
+    kube_agent: KubeAgent,
+}
+
+impl DescribePodTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        DescribePodTool { kube_agent }
+    }
+
+    /// Fetch a single pod from the core API
+    pub async fn get_pod(&self, namespace: &str, name: &str) -> Result<Pod, KubeAgentError> {
+        let endpoint = format!("/api/v1/namespaces/{}/pods/{}", namespace, name);
+        let response = self.kube_agent.get(endpoint).await?;
+
+        debug!("Kubernetes API response: {}", response);
+
+        serde_json::from_str(&response).map_err(|e| {
+            error!("Error parsing pod JSON response: {}", e);
+            KubeAgentError::from(e)
+        })
+    }
+
+    /// Fetch container metrics for the pod from the metrics server API, or `None`
+    /// when the cluster has no metrics API
+    pub async fn get_pod_metrics(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<Option<PodMetrics>, KubeAgentError> {
+        if !self.kube_agent.metrics_available().await? {
+            return Ok(None);
+        }
+
+        let endpoint = format!(
+            "/apis/metrics.k8s.io/v1beta1/namespaces/{}/pods/{}",
+            namespace, name
+        );
+        let response = self.kube_agent.get(endpoint).await?;
+
+        serde_json::from_str(&response).map(Some).map_err(|e| {
+            error!("Error parsing pod metrics JSON response: {}", e);
+            KubeAgentError::from(e)
+        })
+    }
+
+    /// Fetches the pod, its events, and its metrics in parallel and combines them.
+    ///
+    /// The pod itself is required; events and metrics are best-effort, since the
+    /// metrics-server addon may not be installed.
+    pub async fn describe_pod(
+        &self,
+        namespace: Option<String>,
+        name: String,
+    ) -> Result<String, KubeAgentError> {
+        let namespace = namespace.unwrap_or_else(|| "default".to_string());
+        debug!("Describing pod {}/{}", namespace, name);
+
+        let (pod_result, events_result, metrics_result) = tokio::join!(
+            self.get_pod(&namespace, &name),
+            self.kube_agent.events_for("Pod", &name, Some(&namespace)),
+            self.get_pod_metrics(&namespace, &name)
+        );
+
+        let pod = pod_result?;
+        let mut output = describe_status(&pod);
+
+        output.push_str("\nEvents:\n");
+        match events_result {
+            Ok(events) if events.items.is_empty() => output.push_str("  <none>\n"),
+            Ok(events) => {
+                for event in events.items.iter().take(MAX_EVENTS) {
+                    output.push_str(&format!("  {}\n", event.as_line()));
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to fetch events for pod {}/{}: {}",
+                    namespace, name, e
+                );
+                output.push_str("  <unavailable>\n");
+            }
+        }
+
+        output.push_str("\nMetrics:\n");
+        match metrics_result {
+            Ok(None) => output.push_str("  <metrics API unavailable on this cluster>\n"),
+            Ok(Some(metrics)) => {
+                for container in &metrics.containers {
+                    output.push_str(&format!(
+                        "  {}: cpu {}, memory {}\n",
+                        container.name, container.usage.cpu, container.usage.memory
+                    ));
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to fetch metrics for pod {}/{}: {}",
+                    namespace, name, e
+                );
+                output.push_str("  <unavailable>\n");
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Formats the pod's metadata, spec, and container statuses.
+fn describe_status(pod: &Pod) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("Name: {}\n", pod.metadata.name));
+    output.push_str(&format!("Namespace: {}\n", pod.metadata.namespace));
+    output.push_str(&format!("Created: {}\n", pod.metadata.creation_timestamp));
+    if let Some(workload) = pod.workload() {
+        output.push_str(&format!("Controlled By: {}\n", workload));
+    }
+
+    if let Some(labels) = &pod.metadata.labels {
+        output.push_str("Labels:\n");
+        for (key, value) in labels {
+            output.push_str(&format!("  {}: {}\n", key, value));
+        }
+    }
+
+    if let Some(spec) = &pod.spec {
+        output.push_str(&format!(
+            "Node: {}\n",
+            spec.node_name.as_deref().unwrap_or("N/A")
+        ));
+    }
+
+    if let Some(status) = &pod.status {
+        output.push_str(&format!("Phase: {}\n", status.phase));
+        if let Some(start_time) = &status.start_time {
+            output.push_str(&format!("Started: {}\n", start_time));
+        }
+        if let Some(conditions) = &status.conditions {
+            output.push_str("Conditions:\n");
+            for condition in conditions {
+                output.push_str(&format!(
+                    "  {}: {}\n",
+                    condition.type_field, condition.status
+                ));
+            }
+        }
+    }
+
+    output.push_str("Containers:\n");
+    let containers = pod.spec.as_ref().map(|spec| spec.containers.as_slice());
+    let statuses = pod
+        .status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_deref())
+        .unwrap_or_default();
+    for container in containers.unwrap_or_default() {
+        output.push_str(&format!(
+            "  {} ({})\n",
+            container.name,
+            container.image.as_deref().unwrap_or("unknown image")
+        ));
+        output.push_str(&format!(
+            "    Resources: {}\n",
+            container.resources_summary()
+        ));
+
+        let Some(status) = statuses.iter().find(|s| s.name == container.name) else {
+            continue;
+        };
+        output.push_str(&format!("    Ready: {}\n", status.ready));
+        output.push_str(&format!("    Restarts: {}\n", status.restart_count));
+        if let Some(state) = &status.state {
+            output.push_str(&format!("    State: {}\n", state.describe()));
+        }
+        if let Some(last_state) = &status.last_state {
+            if last_state.terminated.is_some() {
+                output.push_str(&format!("    Last State: {}\n", last_state.describe()));
+            }
+        }
+    }
+
+    output
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DescribePodToolArgs {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub cluster: Option<String>,
+}
+
+impl Tool for DescribePodTool {
+    const NAME: &'static str = "describe_pod";
+    type Args = DescribePodToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Describe a single pod like `kubectl describe pod`: status, container states and restarts, recent events, and container CPU/memory usage. Use this to diagnose a specific pod.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace of the pod (default is 'default')"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the pod"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["name"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.describe_pod(args.namespace, args.name)).await
+    }
+}
@@ -0,0 +1,425 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{Event, Pod};
+use crate::kube::{DescribePodTool, KubeAgent};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Maximum number of warning events listed as evidence, most recent first.
+const MAX_WARNING_EVENTS: usize = 10;
+
+/// Number of log lines fetched when logs are requested.
+const LOG_TAIL_LINES: u32 = 30;
+
+/// Upper bound on fetched log size, in case of very long lines.
+const LOG_LIMIT_BYTES: u32 = 8192;
+
+/// Tool for working out why a pod is crash-looping, stuck Pending, or not ready.
+///
+/// Reads the pod's status, each container's current and last termination state, and
+/// the pod's events, and turns the recognisable failure patterns (OOMKilled, image pull
+/// errors, unschedulable pods, failing probes, ...) into a list of probable causes with
+/// suggested fixes. Optionally includes the last log lines of the failing container.
+pub struct DiagnosePodTool {
+    kube_agent: KubeAgent,
+    describe: DescribePodTool,
+}
+
+/// A probable cause of the pod's problem.
+struct Finding {
+    cause: String,
+    evidence: String,
+    suggestion: String,
+}
+
+impl DiagnosePodTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        DiagnosePodTool {
+            describe: DescribePodTool::new(kube_agent.clone()),
+            kube_agent,
+        }
+    }
+
+    /// Fetch the last log lines of a container, from its previous run if it restarted
+    pub async fn get_logs(
+        &self,
+        namespace: &str,
+        name: &str,
+        container: &str,
+        previous: bool,
+    ) -> Result<String, KubeAgentError> {
+        let endpoint = format!(
+            "/api/v1/namespaces/{}/pods/{}/log?container={}&tailLines={}&limitBytes={}&previous={}",
+            namespace, name, container, LOG_TAIL_LINES, LOG_LIMIT_BYTES, previous
+        );
+        self.kube_agent.get(endpoint).await
+    }
+
+    pub async fn diagnose_pod(
+        &self,
+        namespace: Option<String>,
+        name: String,
+        include_logs: bool,
+    ) -> Result<String, KubeAgentError> {
+        let namespace = namespace.unwrap_or_else(|| "default".to_string());
+        debug!("Diagnosing pod {}/{}", namespace, name);
+
+        let (pod_result, events_result) = tokio::join!(
+            self.describe.get_pod(&namespace, &name),
+            self.kube_agent.events_for("Pod", &name, Some(&namespace))
+        );
+        let pod = pod_result?;
+        let warnings: Vec<Event> = match events_result {
+            Ok(events) => events
+                .items
+                .into_iter()
+                .filter(|event| event.type_field.as_deref() == Some("Warning"))
+                .collect(),
+            Err(e) => {
+                warn!(
+                    "Failed to fetch events for pod {}/{}: {}",
+                    namespace, name, e
+                );
+                Vec::new()
+            }
+        };
+
+        let phase = pod
+            .status
+            .as_ref()
+            .map_or("Unknown", |status| status.phase.as_str());
+        let mut output = format!(
+            "Diagnosis of pod {}/{} (phase {}):\n",
+            namespace, name, phase
+        );
+
+        let findings = diagnose(&pod, &warnings);
+        if findings.is_empty() {
+            output.push_str("\nNo known failure pattern found in the pod status or events.\n");
+        } else {
+            output.push_str("\nProbable causes:\n");
+            for (i, finding) in findings.iter().enumerate() {
+                output.push_str(&format!("{}. {}\n", i + 1, finding.cause));
+                output.push_str(&format!("   Evidence: {}\n", finding.evidence));
+                output.push_str(&format!("   Suggested fix: {}\n", finding.suggestion));
+            }
+        }
+
+        if !warnings.is_empty() {
+            output.push_str("\nRecent warning events:\n");
+            for event in warnings.iter().take(MAX_WARNING_EVENTS) {
+                output.push_str(&format!("  {}\n", event.as_line()));
+            }
+        }
+
+        if include_logs {
+            if let Some((container, previous)) = failing_container(&pod) {
+                let run = if previous {
+                    "previous run"
+                } else {
+                    "current run"
+                };
+                output.push_str(&format!(
+                    "\nLast {} log lines of container {} ({}):\n",
+                    LOG_TAIL_LINES, container, run
+                ));
+                match self.get_logs(&namespace, &name, &container, previous).await {
+                    Ok(logs) if logs.trim().is_empty() => output.push_str("  <empty>\n"),
+                    Ok(logs) => {
+                        for line in logs.lines() {
+                            output.push_str(&format!("  {}\n", line));
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to fetch logs for pod {}/{}: {}", namespace, name, e);
+                        output.push_str("  <unavailable>\n");
+                    }
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Matches the pod's status and warning events against known failure patterns.
+fn diagnose(pod: &Pod, warnings: &[Event]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(status) = &pod.status else {
+        return findings;
+    };
+
+    if status.reason.as_deref() == Some("Evicted") {
+        findings.push(Finding {
+            cause: "The pod was evicted".to_string(),
+            evidence: status.message.clone().unwrap_or_default(),
+            suggestion: "The node ran low on memory or disk. Set requests close to real usage so the pod is placed on a node with room, and check the node's conditions.".to_string(),
+        });
+    }
+
+    let scheduled = status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|condition| condition.type_field == "PodScheduled");
+    if let Some(condition) = scheduled.filter(|c| c.status == "False") {
+        let message = condition
+            .message
+            .clone()
+            .or_else(|| event_message(warnings, "FailedScheduling"))
+            .unwrap_or_default();
+        findings.push(unschedulable(message));
+    }
+
+    let resources = |container: &str| {
+        pod.spec
+            .as_ref()
+            .and_then(|spec| spec.containers.iter().find(|c| c.name == container))
+            .map(|c| c.resources_summary())
+            .unwrap_or_default()
+    };
+
+    for container in status.container_statuses.iter().flatten() {
+        let waiting = container
+            .state
+            .as_ref()
+            .and_then(|state| state.waiting.as_ref());
+        let waiting_reason = waiting.and_then(|w| w.reason.as_deref()).unwrap_or("");
+        let waiting_message = waiting.and_then(|w| w.message.as_deref()).unwrap_or("");
+        let last_terminated = container
+            .last_state
+            .as_ref()
+            .and_then(|state| state.terminated.as_ref());
+
+        match waiting_reason {
+            "ImagePullBackOff" | "ErrImagePull" | "InvalidImageName" => {
+                findings.push(Finding {
+                    cause: format!("Container {} image cannot be pulled", container.name),
+                    evidence: format!("{}: {}", waiting_reason, waiting_message),
+                    suggestion: "Check that the image name and tag exist and the registry is reachable; private registries need imagePullSecrets.".to_string(),
+                });
+                continue;
+            }
+            "CreateContainerConfigError" | "CreateContainerError" => {
+                findings.push(Finding {
+                    cause: format!("Container {} cannot be created", container.name),
+                    evidence: format!("{}: {}", waiting_reason, waiting_message),
+                    suggestion: "A referenced ConfigMap, Secret, or key is usually missing; check env valueFrom/envFrom and volume references.".to_string(),
+                });
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(terminated) = last_terminated {
+            let reason = terminated.reason.as_deref().unwrap_or("");
+            let exit_code = terminated.exit_code.unwrap_or_default();
+            if reason == "OOMKilled" {
+                findings.push(Finding {
+                    cause: format!("Container {} is being OOMKilled", container.name),
+                    evidence: format!(
+                        "last terminated with OOMKilled (exit code {}), {} restarts; {}",
+                        exit_code,
+                        container.restart_count,
+                        resources(&container.name)
+                    ),
+                    suggestion: "Raise the container's memory limit, or find why the application uses more memory than expected.".to_string(),
+                });
+            } else if waiting_reason == "CrashLoopBackOff" || exit_code != 0 {
+                findings.push(Finding {
+                    cause: format!("Container {} keeps crashing", container.name),
+                    evidence: format!(
+                        "last exited with code {} ({}), {} restarts",
+                        exit_code,
+                        if reason.is_empty() {
+                            "no reason"
+                        } else {
+                            reason
+                        },
+                        container.restart_count
+                    ),
+                    suggestion: exit_code_hint(exit_code).to_string(),
+                });
+            }
+        } else if waiting_reason == "CrashLoopBackOff" {
+            findings.push(Finding {
+                cause: format!("Container {} keeps crashing", container.name),
+                evidence: format!("CrashLoopBackOff, {} restarts", container.restart_count),
+                suggestion: exit_code_hint(1).to_string(),
+            });
+        }
+
+        let running = container
+            .state
+            .as_ref()
+            .is_some_and(|state| state.running.is_some());
+        if running && !container.ready {
+            let evidence = event_message(warnings, "Unhealthy")
+                .unwrap_or_else(|| "running but not ready".to_string());
+            findings.push(Finding {
+                cause: format!("Container {} is failing its probes", container.name),
+                evidence,
+                suggestion: "Check the readiness/liveness probe path, port, and timing (initialDelaySeconds) against how the application actually starts.".to_string(),
+            });
+        }
+    }
+
+    for (reason, cause, suggestion) in [
+        (
+            "FailedMount",
+            "A volume cannot be mounted",
+            "Check that the referenced PVC, ConfigMap, or Secret exists and that the volume is not attached to another node.",
+        ),
+        (
+            "FailedAttachVolume",
+            "A volume cannot be attached",
+            "The volume may still be attached to another node; check the storage provider and the PV's status.",
+        ),
+        (
+            "FailedCreatePodSandBox",
+            "The pod sandbox cannot be created",
+            "Usually a CNI (network plugin) or container runtime problem on the node; check the node and CNI pods.",
+        ),
+    ] {
+        if let Some(message) = event_message(warnings, reason) {
+            findings.push(Finding {
+                cause: cause.to_string(),
+                evidence: message,
+                suggestion: suggestion.to_string(),
+            });
+        }
+    }
+
+    findings
+}
+
+/// Explains a scheduling failure from the scheduler's message, e.g. "0/3 nodes are
+/// available: 1 node(s) had untolerated taint {...}, 2 Insufficient memory."
+fn unschedulable(message: String) -> Finding {
+    let lower = message.to_lowercase();
+    let (cause, suggestion) = if lower.contains("insufficient") {
+        (
+            "The pod is unschedulable: no node has enough free resources",
+            "Lower the pod's requests, free capacity by scaling down other workloads, or add nodes.",
+        )
+    } else if lower.contains("taint") {
+        (
+            "The pod is unschedulable: nodes have taints it does not tolerate",
+            "Add a matching toleration to the pod, or remove the taint from a suitable node.",
+        )
+    } else if lower.contains("affinity") || lower.contains("selector") {
+        (
+            "The pod is unschedulable: no node matches its node selector or affinity",
+            "Check the pod's nodeSelector/affinity against the node labels.",
+        )
+    } else if lower.contains("persistentvolumeclaim") || lower.contains("unbound") {
+        (
+            "The pod is unschedulable: a PersistentVolumeClaim is not bound",
+            "Check the claim with list_persistent_volume_claims; its storage class may not provision volumes.",
+        )
+    } else {
+        (
+            "The pod is unschedulable",
+            "Read the scheduler's message and compare it with the nodes' capacity, taints, and labels.",
+        )
+    };
+    Finding {
+        cause: cause.to_string(),
+        evidence: message,
+        suggestion: suggestion.to_string(),
+    }
+}
+
+/// What a container exit code usually means.
+fn exit_code_hint(exit_code: i32) -> &'static str {
+    match exit_code {
+        126 => "The command is not executable; check the image's entrypoint and file permissions.",
+        127 => "The command was not found; check the container's command/args and the image.",
+        137 => "The container was killed (SIGKILL), often by a failing liveness probe or memory pressure; check probes and memory limits.",
+        139 => "The process crashed with a segmentation fault; check the application and image architecture.",
+        143 => "The container was stopped (SIGTERM); check liveness probes and whether it shuts down when it shouldn't.",
+        _ => "The application is exiting with an error; read its logs from the previous run (include_logs) for the cause.",
+    }
+}
+
+/// The most recent message of a warning event with the given reason.
+fn event_message(warnings: &[Event], reason: &str) -> Option<String> {
+    warnings
+        .iter()
+        .find(|event| event.reason.as_deref() == Some(reason))
+        .and_then(|event| event.message.as_ref())
+        .map(|message| message.trim().to_string())
+}
+
+/// The container whose logs best explain the problem, and whether to read its previous
+/// run: the first unready container, preferring one that has restarted.
+fn failing_container(pod: &Pod) -> Option<(String, bool)> {
+    let statuses = pod.status.as_ref()?.container_statuses.as_ref()?;
+    let container = statuses
+        .iter()
+        .filter(|status| !status.ready)
+        .max_by_key(|status| status.restart_count)
+        .or(statuses.first())?;
+    let previous = container
+        .last_state
+        .as_ref()
+        .is_some_and(|state| state.terminated.is_some());
+    Some((container.name.clone(), previous))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DiagnosePodToolArgs {
+    pub namespace: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub include_logs: bool,
+    pub cluster: Option<String>,
+}
+
+impl Tool for DiagnosePodTool {
+    const NAME: &'static str = "diagnose_pod";
+    type Args = DiagnosePodToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Diagnose a failing pod (CrashLoopBackOff, Pending, ImagePullBackOff, not ready, evicted): returns probable causes such as OOMKilled, image pull errors, or unschedulable due to taints or insufficient resources, each with evidence and a suggested fix. Prefer this over describe_pod when asked why a pod is broken.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace of the pod (default is 'default')"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the pod"
+                    },
+                    "include_logs": {
+                        "type": "boolean",
+                        "description": "Also return the last log lines of the failing container (from its previous run if it restarted)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["name"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.diagnose_pod(args.namespace, args.name, args.include_logs),
+        )
+        .await
+    }
+}
@@ -0,0 +1,313 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{NodeDetails, Pod, PodDisruptionBudget};
+use crate::kube::{percent_encode, KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Maximum number of pods listed per section of the plan; the rest are only counted.
+const MAX_LISTED_PODS: usize = 30;
+
+/// Annotation the kubelet puts on the API server's mirror of a static pod.
+const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+
+/// Tool for planning a node drain without performing it.
+///
+/// Sorts the node's pods the way `kubectl drain` treats them (evicted and rescheduled,
+/// unmanaged, DaemonSet, static, finished), flags pods whose emptyDir data would be
+/// lost, and checks each evicted pod against the PodDisruptionBudgets that would block
+/// or slow its eviction. Nothing is cordoned or evicted.
+pub struct DrainPlanTool {
+    kube_agent: KubeAgent,
+}
+
+/// How `kubectl drain` handles a pod.
+#[derive(Clone, Copy, PartialEq)]
+enum Handling {
+    /// Evicted; its controller recreates it on another node
+    Evicted,
+    /// No controller: drain refuses without --force, and the pod is gone for good
+    Unmanaged,
+    /// Skipped with --ignore-daemonsets
+    DaemonSet,
+    /// Mirror of a static pod, which the kubelet runs whatever the API server says
+    Static,
+    /// Succeeded or Failed, deleted without consequence
+    Finished,
+}
+
+impl DrainPlanTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        DrainPlanTool { kube_agent }
+    }
+
+    /// Fetches the node, its pods, and every PodDisruptionBudget in parallel and
+    /// works out what a drain would do.
+    pub async fn drain_plan(&self, node: String) -> Result<String, KubeAgentError> {
+        debug!("Planning a drain of node {}", node);
+
+        let node_endpoint = format!("/api/v1/nodes/{}", node);
+        let pods_endpoint = format!(
+            "/api/v1/pods?fieldSelector={}",
+            percent_encode(&format!("spec.nodeName={}", node))
+        );
+        let (node_response, pods, budgets) = tokio::join!(
+            self.kube_agent.get(node_endpoint),
+            self.kube_agent
+                .list_all::<Pod>(&pods_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent.list_all::<PodDisruptionBudget>(
+                "/apis/policy/v1/poddisruptionbudgets",
+                MAX_LIST_ITEMS
+            )
+        );
+        let details: NodeDetails = serde_json::from_str(&node_response?).map_err(|e| {
+            error!("Error parsing node JSON response: {}", e);
+            KubeAgentError::from(e)
+        })?;
+        let pods = pods?;
+        let budgets = budgets?;
+
+        debug!(
+            "Node {} runs {} pods; {} disruption budgets in the cluster",
+            node,
+            pods.items.len(),
+            budgets.items.len()
+        );
+
+        Ok(plan(&details, &pods.items, &budgets.items) + &pods.truncation_note())
+    }
+}
+
+fn handling(pod: &Pod) -> Handling {
+    let is_mirror = pod
+        .metadata
+        .annotations
+        .as_ref()
+        .is_some_and(|annotations| annotations.contains_key(MIRROR_POD_ANNOTATION));
+    let phase = pod.status.as_ref().map(|status| status.phase.as_str());
+    let controller = pod
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.controller.unwrap_or(false));
+
+    if is_mirror {
+        Handling::Static
+    } else if matches!(phase, Some("Succeeded" | "Failed")) {
+        Handling::Finished
+    } else {
+        match controller {
+            Some(owner) if owner.kind == "DaemonSet" => Handling::DaemonSet,
+            Some(_) => Handling::Evicted,
+            None => Handling::Unmanaged,
+        }
+    }
+}
+
+// Names of the pod's emptyDir volumes, whose data is deleted with the pod
+fn empty_dirs(pod: &Pod) -> Vec<&str> {
+    pod.spec
+        .as_ref()
+        .and_then(|spec| spec.volumes.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|volume| volume.empty_dir.is_some())
+        .map(|volume| volume.name.as_str())
+        .collect()
+}
+
+fn pod_name(pod: &Pod) -> String {
+    format!("{}/{}", pod.metadata.namespace, pod.metadata.name)
+}
+
+// "  ns/pod (suffix)" lines, at most MAX_LISTED_PODS of them
+fn push_pods(output: &mut String, heading: &str, pods: &[(&Pod, String)]) {
+    if pods.is_empty() {
+        return;
+    }
+    output.push_str(&format!("\n{} ({}):\n", heading, pods.len()));
+    for (pod, detail) in pods.iter().take(MAX_LISTED_PODS) {
+        if detail.is_empty() {
+            output.push_str(&format!("  {}\n", pod_name(pod)));
+        } else {
+            output.push_str(&format!("  {} ({})\n", pod_name(pod), detail));
+        }
+    }
+    if pods.len() > MAX_LISTED_PODS {
+        output.push_str(&format!(
+            "  ... and {} more\n",
+            pods.len() - MAX_LISTED_PODS
+        ));
+    }
+}
+
+fn plan(node: &NodeDetails, pods: &[Pod], budgets: &[PodDisruptionBudget]) -> String {
+    let cordoned = node
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.unschedulable)
+        .unwrap_or(false);
+    let mut output = format!(
+        "Drain plan for node {} ({}). Nothing has been changed.\n",
+        node.metadata.name,
+        if cordoned {
+            "already cordoned"
+        } else {
+            "schedulable; a drain cordons it first"
+        }
+    );
+
+    let with = |kind: Handling| {
+        pods.iter()
+            .filter(|pod| handling(pod) == kind)
+            .map(|pod| (pod, pod.workload().unwrap_or_default()))
+            .collect::<Vec<_>>()
+    };
+    let evicted = with(Handling::Evicted);
+    let unmanaged = with(Handling::Unmanaged);
+    let daemon_set = with(Handling::DaemonSet);
+    let static_pods = with(Handling::Static);
+    let finished = with(Handling::Finished);
+    output.push_str(&format!(
+        "{} pods: {} evicted and rescheduled, {} unmanaged, {} DaemonSet, {} static, {} finished\n",
+        pods.len(),
+        evicted.len(),
+        unmanaged.len(),
+        daemon_set.len(),
+        static_pods.len(),
+        finished.len()
+    ));
+
+    push_pods(
+        &mut output,
+        "Evicted and recreated elsewhere by their controllers",
+        &evicted,
+    );
+    push_pods(
+        &mut output,
+        "Unmanaged: drain refuses them without --force, and they are deleted for good",
+        &unmanaged,
+    );
+    push_pods(
+        &mut output,
+        "DaemonSet pods: skipped with --ignore-daemonsets",
+        &daemon_set,
+    );
+    push_pods(
+        &mut output,
+        "Static pods: run by the kubelet and not evicted",
+        &static_pods,
+    );
+    push_pods(&mut output, "Finished pods: deleted", &finished);
+
+    let local_data: Vec<(&Pod, String)> = evicted
+        .iter()
+        .chain(&unmanaged)
+        .filter_map(|(pod, _)| {
+            let volumes = empty_dirs(pod);
+            (!volumes.is_empty()).then(|| (*pod, format!("emptyDir {}", volumes.join(", "))))
+        })
+        .collect();
+    push_pods(
+        &mut output,
+        "Local data lost: needs --delete-emptydir-data",
+        &local_data,
+    );
+
+    // Evictions go through the PodDisruptionBudgets of the pod's namespace, whatever
+    // owns the pod
+    output.push_str("\nPodDisruptionBudgets:\n");
+    let mut covered_any = false;
+    for budget in budgets {
+        let selector = budget.spec.as_ref().and_then(|spec| spec.selector.as_ref());
+        let covered: Vec<String> = evicted
+            .iter()
+            .chain(&unmanaged)
+            .map(|(pod, _)| *pod)
+            .filter(|pod| pod.metadata.namespace == budget.metadata.namespace)
+            .filter(|pod| {
+                selector.is_some_and(|selector| selector.matches(pod.metadata.labels.as_ref()))
+            })
+            .map(pod_name)
+            .collect();
+        if covered.is_empty() {
+            continue;
+        }
+        covered_any = true;
+
+        let allowed = budget.disruptions_allowed();
+        let status = budget.status.as_ref();
+        let effect = if allowed == 0 {
+            "BLOCKS eviction until more of its pods are healthy".to_string()
+        } else if (covered.len() as u32) > allowed {
+            format!(
+                "allows {} at a time, so the drain waits for replacements to become healthy between evictions",
+                allowed
+            )
+        } else {
+            "allows every eviction".to_string()
+        };
+        output.push_str(&format!(
+            "  {}/{} ({}, {}/{} healthy, {} disruptions allowed): {}; covers {}\n",
+            budget.metadata.namespace,
+            budget.metadata.name,
+            budget.budget(),
+            status
+                .and_then(|status| status.current_healthy)
+                .unwrap_or(0),
+            status
+                .and_then(|status| status.desired_healthy)
+                .unwrap_or(0),
+            allowed,
+            effect,
+            covered.join(", ")
+        ));
+    }
+    if !covered_any {
+        output.push_str("  None cover the pods that would be evicted.\n");
+    }
+    output
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DrainPlanToolArgs {
+    pub node: String,
+    pub cluster: Option<String>,
+}
+
+impl Tool for DrainPlanTool {
+    const NAME: &'static str = "drain_plan";
+    type Args = DrainPlanToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Plan draining a node for maintenance without changing anything: lists the pods that would be evicted and rescheduled, unmanaged pods that block a drain without --force, DaemonSet and static pods that are skipped, pods whose emptyDir data would be lost, and the PodDisruptionBudgets that would block or slow the evictions. Use this to advise on node maintenance.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "node": {
+                        "type": "string",
+                        "description": "The name of the node"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["node"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.drain_plan(args.node)).await
+    }
+}
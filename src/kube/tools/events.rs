@@ -0,0 +1,120 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::KubeAgent;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Default number of events returned, most recent first.
+const DEFAULT_MAX_EVENTS: usize = 20;
+
+/// Tool for fetching the events about any object, e.g. a Deployment, Node, Service,
+/// or PersistentVolumeClaim.
+///
+/// The pod tools already include their pod's events; this covers every other kind.
+pub struct GetEventsForTool {
+    kube_agent: KubeAgent,
+}
+
+impl GetEventsForTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        GetEventsForTool { kube_agent }
+    }
+
+    pub async fn get_events_for(
+        &self,
+        kind: String,
+        name: String,
+        namespace: Option<String>,
+        limit: Option<usize>,
+    ) -> Result<String, KubeAgentError> {
+        let events = self
+            .kube_agent
+            .events_for(&kind, &name, namespace.as_deref())
+            .await?;
+
+        debug!("Found {} events for {} {}", events.items.len(), kind, name);
+
+        let object = match &namespace {
+            Some(namespace) => format!("{} {}/{}", kind, namespace, name),
+            None => format!("{} {}", kind, name),
+        };
+        if events.items.is_empty() {
+            return Ok(format!(
+                "No events found for {}. Events expire after about an hour, and the kind must be capitalized (e.g. Deployment).",
+                object
+            ));
+        }
+
+        let limit = limit.unwrap_or(DEFAULT_MAX_EVENTS);
+        let mut output = format!(
+            "Events for {} ({} total, most recent first):\n",
+            object,
+            events.items.len()
+        );
+        for event in events.items.iter().take(limit) {
+            output.push_str(&format!("  {}\n", event.as_line()));
+        }
+        Ok(output)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetEventsForToolArgs {
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub limit: Option<usize>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for GetEventsForTool {
+    const NAME: &'static str = "get_events_for";
+    type Args = GetEventsForToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Get the recent events about any Kubernetes object (Deployment, ReplicaSet, StatefulSet, Node, Service, PersistentVolumeClaim, Job, ...), most recent first. Use this to find out why a rollout, scheduling, volume, or node operation is failing.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "kind": {
+                        "type": "string",
+                        "description": "The object's kind, capitalized as in the API, e.g. Deployment or Node"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The object's name"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "The object's namespace; omit for cluster-scoped objects such as nodes"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": format!("Maximum number of events to return (default {})", DEFAULT_MAX_EVENTS)
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["kind", "name"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(
+            args.cluster,
+            self.get_events_for(args.kind, args.name, args.namespace, args.limit),
+        )
+        .await
+    }
+}
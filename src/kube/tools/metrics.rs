@@ -3,16 +3,21 @@ use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::kube::clusters::cluster_scope;
 use crate::kube::error::KubeAgentError;
 use crate::kube::types::{NodeListResponse, NodeMetricsListResponse, NodeMetricsWithUsageResponse};
 use crate::kube::KubeAgent;
 use tracing::*;
 
+/// Explanation returned in place of usage figures when the metrics API is missing.
+pub const METRICS_UNAVAILABLE: &str =
+    "The metrics API (metrics.k8s.io) is unavailable on this cluster, so CPU and memory usage can't be reported; install metrics-server to enable it. Only capacity is shown.";
+
 /// Tool for fetching Kubernetes node metrics (CPU and memory usage).
 ///
-/// Requires the cluster to have the metrics-server addon enabled.
 /// Combines data from both the core API and metrics API to provide
-/// usage statistics with percentages.
+/// usage statistics with percentages. Usage requires the metrics-server addon;
+/// without it the tool says so and returns node capacity only.
 pub struct NodeMetricsTool {
     kube_agent: KubeAgent,
 }
@@ -25,7 +30,7 @@ impl NodeMetricsTool {
     /// Fetch node metrics from the metrics server API
     pub async fn get_node_metrics(&self) -> Result<NodeMetricsListResponse, KubeAgentError> {
         let endpoint = String::from("/apis/metrics.k8s.io/v1beta1/nodes");
-        let response = self.kube_agent.make_request(endpoint).await?;
+        let response = self.kube_agent.get(endpoint).await?;
 
         let metrics: NodeMetricsListResponse = serde_json::from_str(&response).map_err(|e| {
             error!("Error parsing node metrics JSON response: {}", e);
@@ -38,7 +43,7 @@ impl NodeMetricsTool {
     /// Fetch node information from the core API
     pub async fn get_nodes(&self) -> Result<NodeListResponse, KubeAgentError> {
         let endpoint = String::from("/api/v1/nodes");
-        let response = self.kube_agent.make_request(endpoint).await?;
+        let response = self.kube_agent.get(endpoint).await?;
 
         debug!("Kubernetes API response: {}", response);
 
@@ -53,9 +58,18 @@ impl NodeMetricsTool {
     /// Fetches both node info and metrics, then combines them to show usage with percentages.
     ///
     /// Makes parallel requests to both the core API and metrics API for efficiency.
+    /// On clusters without a metrics API only node capacity is returned.
     pub async fn get_node_metrics_with_usage(
         &self,
     ) -> Result<NodeMetricsWithUsageResponse, KubeAgentError> {
+        if !self.kube_agent.metrics_available().await? {
+            info!("Metrics API unavailable, returning node capacity only");
+            return self
+                .get_nodes()
+                .await?
+                .capacity_only(METRICS_UNAVAILABLE.to_string());
+        }
+
         debug!("Fetching node metrics and capacity in parallel");
 
         let (nodes_result, metrics_result) =
@@ -71,7 +85,9 @@ impl NodeMetricsTool {
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct NodeMetricsToolArgs {}
+pub struct NodeMetricsToolArgs {
+    pub cluster: Option<String>,
+}
 
 impl Tool for NodeMetricsTool {
     const NAME: &'static str = "get_node_metrics";
@@ -85,14 +101,19 @@ impl Tool for NodeMetricsTool {
             "description": "Get node metrics (CPU and memory usage) from the Kubernetes cluster.",
             "parameters": {
                 "type": "object",
-                "properties": {},
+                "properties": {
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
                 "required": []
             }
         }))
         .unwrap()
     }
 
-    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
-        self.get_node_metrics_with_usage().await
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.get_node_metrics_with_usage()).await
     }
 }
@@ -9,3 +9,91 @@ pub use namespaces::ListNamespacesTool;
 pub mod metrics;
 
 pub use metrics::NodeMetricsTool;
+
+pub mod describe;
+
+pub use describe::DescribePodTool;
+
+pub mod configmaps;
+
+pub use configmaps::ListConfigMapsTool;
+
+pub mod secrets;
+
+pub use secrets::ListSecretsTool;
+
+pub mod storage;
+
+pub use storage::ListPersistentVolumeClaimsTool;
+
+pub mod nodes;
+
+pub use nodes::NodeInfoTool;
+
+pub mod deployments;
+
+pub use deployments::ScaleDeploymentTool;
+
+pub mod delete;
+
+pub use delete::DeletePodTool;
+
+pub mod cordon;
+
+pub use cordon::CordonNodeTool;
+
+pub mod raw;
+
+pub use raw::RawKubeGetTool;
+
+pub mod clusters;
+
+pub use clusters::ListClustersTool;
+
+pub mod alerts;
+
+pub use alerts::ClusterAlertsTool;
+
+pub mod access;
+
+pub use access::CanITool;
+
+pub mod rbac;
+
+pub use rbac::RbacOverviewTool;
+
+pub mod custom;
+
+pub use custom::CustomResourcesTool;
+
+pub mod cost;
+
+pub use cost::NamespaceCostTool;
+
+pub mod quotas;
+
+pub use quotas::ResourceQuotasTool;
+
+pub mod diagnose;
+
+pub use diagnose::DiagnosePodTool;
+
+pub mod certificates;
+
+pub use certificates::CertificateExpiryTool;
+
+pub mod network;
+
+pub use network::ListNetworkPoliciesTool;
+
+pub mod events;
+
+pub use events::GetEventsForTool;
+
+pub mod rollout;
+
+pub use rollout::RolloutStatusTool;
+
+pub mod drain;
+
+pub use drain::DrainPlanTool;
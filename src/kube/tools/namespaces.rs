@@ -1,5 +1,6 @@
+use crate::kube::clusters::cluster_scope;
 use crate::kube::types::NamespaceListResponse;
-use crate::kube::{KubeAgent, KubeAgentError};
+use crate::kube::{KubeAgent, KubeAgentError, MAX_LIST_ITEMS};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -17,23 +18,26 @@ impl ListNamespacesTool {
     }
 
     pub async fn list_namespaces(&self) -> Result<String, KubeAgentError> {
-        let endpoint = String::from("/api/v1/namespaces");
-        let response = self.kube_agent.make_request(endpoint).await?;
+        let listing = self
+            .kube_agent
+            .list_all("/api/v1/namespaces", MAX_LIST_ITEMS)
+            .await?;
 
-        debug!("Kubernetes API response: {}", response);
+        debug!("Listed {} namespaces", listing.items.len());
 
-        let namespace_list: NamespaceListResponse =
-            serde_json::from_str(&response).map_err(|e| {
-                error!("Error parsing JSON response: {}", e);
-                KubeAgentError::from(e)
-            })?;
+        let note = listing.truncation_note();
+        let namespace_list = NamespaceListResponse {
+            items: listing.items,
+        };
 
-        Ok(namespace_list.as_string())
+        Ok(namespace_list.as_string() + &note)
     }
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct ListNamespacesToolArgs {}
+pub struct ListNamespacesToolArgs {
+    pub cluster: Option<String>,
+}
 
 impl Tool for ListNamespacesTool {
     const NAME: &'static str = "list_namespaces";
@@ -47,14 +51,19 @@ impl Tool for ListNamespacesTool {
             "description": "List all namespaces in the Kubernetes cluster.",
             "parameters": {
                 "type": "object",
-                "properties": {},
+                "properties": {
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
                 "required": []
             }
         }))
         .unwrap()
     }
 
-    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
-        self.list_namespaces().await
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.list_namespaces()).await
     }
 }
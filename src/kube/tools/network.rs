@@ -0,0 +1,85 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::NetworkPolicyListResponse;
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for listing NetworkPolicies with the pods they select and the traffic they allow.
+///
+/// Rules are summarized in plain terms ("from pods app=web in this namespace on
+/// TCP/8080") so the agent can reason about whether one pod may reach another.
+pub struct ListNetworkPoliciesTool {
+    kube_agent: KubeAgent,
+}
+
+impl ListNetworkPoliciesTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        ListNetworkPoliciesTool { kube_agent }
+    }
+
+    pub async fn list_network_policies(
+        &self,
+        namespace: Option<String>,
+    ) -> Result<String, KubeAgentError> {
+        let endpoint = match &namespace {
+            Some(namespace) => format!(
+                "/apis/networking.k8s.io/v1/namespaces/{}/networkpolicies",
+                namespace
+            ),
+            None => "/apis/networking.k8s.io/v1/networkpolicies".to_string(),
+        };
+        let listing = self.kube_agent.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+
+        debug!("Listed {} network policies", listing.items.len());
+
+        let note = listing.truncation_note();
+        let policies = NetworkPolicyListResponse {
+            items: listing.items,
+        };
+
+        Ok(policies.as_string() + &note)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ListNetworkPoliciesToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for ListNetworkPoliciesTool {
+    const NAME: &'static str = "list_network_policies";
+    type Args = ListNetworkPoliciesToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "List NetworkPolicies with the pods they apply to and their ingress/egress rules summarized (allowed peers and ports). Use this to answer whether one pod can reach another at the policy level; combine with list_pods to see pod labels.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to list policies from (default is all namespaces)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.list_network_policies(args.namespace)).await
+    }
+}
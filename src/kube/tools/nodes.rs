@@ -0,0 +1,94 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::NodeInfoListResponse;
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for fetching detailed node information.
+///
+/// Goes beyond the capacity figures used by [`NodeMetricsTool`](super::NodeMetricsTool):
+/// conditions, taints, labels, kubelet/OS versions, and allocatable vs capacity.
+pub struct NodeInfoTool {
+    kube_agent: KubeAgent,
+}
+
+impl NodeInfoTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        NodeInfoTool { kube_agent }
+    }
+
+    pub async fn get_node_info(&self, name: Option<String>) -> Result<String, KubeAgentError> {
+        let listing = self
+            .kube_agent
+            .list_all("/api/v1/nodes", MAX_LIST_ITEMS)
+            .await?;
+
+        debug!("Listed {} nodes", listing.items.len());
+
+        let nodes = NodeInfoListResponse {
+            items: listing.items,
+        };
+
+        let selected = nodes
+            .items
+            .iter()
+            .filter(|node| name.as_ref().is_none_or(|name| node.metadata.name == *name))
+            .collect::<Vec<_>>();
+
+        if selected.is_empty() {
+            return Err(KubeAgentError::ParseError(format!(
+                "No node named {}",
+                name.unwrap_or_default()
+            )));
+        }
+
+        Ok(selected
+            .iter()
+            .map(|node| node.as_string())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NodeInfoToolArgs {
+    pub name: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for NodeInfoTool {
+    const NAME: &'static str = "get_node_info";
+    type Args = NodeInfoToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Get detailed node information for troubleshooting: conditions (Ready, MemoryPressure, DiskPressure, PIDPressure), taints, labels, kubelet/OS/runtime versions, and allocatable vs capacity resources.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name of a single node to describe (default is all nodes)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.get_node_info(args.name)).await
+    }
+}
@@ -1,19 +1,35 @@
+use crate::kube::clusters::cluster_scope;
 use crate::kube::error::KubeAgentError;
-use crate::kube::types::PodListResponse;
-use crate::kube::KubeAgent;
+use crate::kube::types::{Listing, Pod, PodListResponse};
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::*;
 
+/// Pod phases accepted by the `phase` filter.
+const PHASES: &[&str] = &["Pending", "Running", "Succeeded", "Failed", "Unknown"];
+
 /// Tool for listing pods in a Kubernetes cluster namespace.
 ///
-/// Supports filtering by namespace and limiting the number of results.
+/// Supports filtering by namespace and phase, sorting, and limiting the number of
+/// results.
 pub struct ListPodsTool {
     kube_agent: KubeAgent,
 }
 
+/// Orders pods before the result limit is applied.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PodSort {
+    /// Oldest first
+    Age,
+    /// Most restarted first
+    Restarts,
+    Name,
+}
+
 impl ListPodsTool {
     pub fn new(kube_agent: KubeAgent) -> Self {
         ListPodsTool { kube_agent }
@@ -23,39 +39,106 @@ impl ListPodsTool {
         &self,
         namespace: Option<String>,
         limit: Option<u32>,
+        phase: Option<String>,
+        sort_by: Option<PodSort>,
     ) -> Result<String, KubeAgentError> {
-        let mut namespace_path = String::from("default");
-        let mut limit_query: u32 = 500;
+        let namespace_path = namespace.unwrap_or_else(|| "default".to_string());
+        let endpoint = format!("/api/v1/namespaces/{}/pods", namespace_path);
 
-        if let Some(ns) = namespace {
-            namespace_path = ns;
-        }
-        if let Some(lim) = limit {
-            limit_query = lim;
-        }
+        let (pod_list, note) = self.fetch(endpoint, limit, phase, sort_by).await?;
+
+        debug!("Listed {} pods in {}", pod_list.items.len(), namespace_path);
+
+        Ok(pod_list.as_string() + &note)
+    }
 
-        let endpoint = format!(
-            "/api/v1/namespaces/{}/pods?limit={}",
-            namespace_path, limit_query
-        );
+    /// Lists pods across every namespace, grouped by namespace with per-phase counts.
+    pub async fn list_pods_all_namespaces(
+        &self,
+        limit: Option<u32>,
+        phase: Option<String>,
+        sort_by: Option<PodSort>,
+    ) -> Result<String, KubeAgentError> {
+        let (pod_list, note) = self
+            .fetch("/api/v1/pods".to_string(), limit, phase, sort_by)
+            .await?;
 
-        let response = self.kube_agent.make_request(endpoint).await?;
+        debug!("Listed {} pods across all namespaces", pod_list.items.len());
 
-        debug!("Kubernetes API response: {}", response);
+        Ok(pod_list.as_grouped_string() + &note)
+    }
+
+    /// Lists pods, filtering by phase on the API server.
+    ///
+    /// Without sorting the limit is applied while listing. Sorting needs every pod, so
+    /// the full listing is sorted first and then cut to the limit.
+    async fn fetch(
+        &self,
+        mut endpoint: String,
+        limit: Option<u32>,
+        phase: Option<String>,
+        sort_by: Option<PodSort>,
+    ) -> Result<(PodListResponse, String), KubeAgentError> {
+        if let Some(phase) = phase {
+            let Some(phase) = PHASES.iter().find(|p| p.eq_ignore_ascii_case(&phase)) else {
+                return Err(KubeAgentError::ParseError(format!(
+                    "Unknown pod phase '{}' (expected one of {})",
+                    phase,
+                    PHASES.join(", ")
+                )));
+            };
+            endpoint.push_str(&format!("?fieldSelector=status.phase%3D{}", phase));
+        }
 
-        let pod_list: PodListResponse = serde_json::from_str(&response).map_err(|e| {
-            error!("Error parsing JSON response: {}", e);
-            KubeAgentError::from(e)
-        })?;
+        let limit = limit.map_or(MAX_LIST_ITEMS, |lim| (lim as usize).min(MAX_LIST_ITEMS));
+        let Some(sort_by) = sort_by else {
+            let listing = self.kube_agent.list_all(&endpoint, limit).await?;
+            return Ok(into_response(listing));
+        };
 
-        Ok(pod_list.as_string())
+        let listing: Listing<Pod> = self.kube_agent.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+        let (mut pod_list, mut note) = into_response(listing);
+        match sort_by {
+            PodSort::Age => pod_list.items.sort_by(|a, b| {
+                a.metadata
+                    .creation_timestamp
+                    .cmp(&b.metadata.creation_timestamp)
+            }),
+            PodSort::Restarts => pod_list
+                .items
+                .sort_by_key(|pod| std::cmp::Reverse(pod.restart_count())),
+            PodSort::Name => pod_list
+                .items
+                .sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name)),
+        }
+        if pod_list.items.len() > limit {
+            note.push_str(&format!(
+                "\n(Showing the first {} of {} pods.)\n",
+                limit,
+                pod_list.items.len()
+            ));
+            pod_list.items.truncate(limit);
+        }
+        Ok((pod_list, note))
     }
 }
 
+fn into_response(listing: Listing<Pod>) -> (PodListResponse, String) {
+    let note = listing.truncation_note();
+    let pod_list = PodListResponse {
+        items: listing.items,
+    };
+    (pod_list, note)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ListPodsToolArgs {
     pub namespace: Option<String>,
     pub limit: Option<u32>,
+    pub all_namespaces: Option<bool>,
+    pub phase: Option<String>,
+    pub sort_by: Option<PodSort>,
+    pub cluster: Option<String>,
 }
 
 impl Tool for ListPodsTool {
@@ -67,7 +150,7 @@ impl Tool for ListPodsTool {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         serde_json::from_value(json!({
             "name": "list_pods",
-            "description": "List pods in a Kubernetes cluster namespace",
+            "description": "List pods in a Kubernetes cluster namespace, or across all namespaces with a compact per-namespace summary. Use phase, sort_by, and limit to answer questions like \"show the 5 most-restarted pods\" without listing everything.",
             "parameters": {
                 "type": "object",
                 "properties": {
@@ -77,7 +160,25 @@ impl Tool for ListPodsTool {
                     },
                     "limit": {
                         "type": "integer",
-                        "description": "Maximum number of pods to return (default is 500)"
+                        "description": "Maximum number of pods to return, applied after sorting (default is 5000)"
+                    },
+                    "all_namespaces": {
+                        "type": "boolean",
+                        "description": "List pods in every namespace, grouped by namespace (ignores 'namespace'). Use this for questions like \"what's running in my cluster?\""
+                    },
+                    "phase": {
+                        "type": "string",
+                        "enum": PHASES,
+                        "description": "Only list pods in this phase"
+                    },
+                    "sort_by": {
+                        "type": "string",
+                        "enum": ["age", "restarts", "name"],
+                        "description": "Order pods by age (oldest first), restarts (most first), or name"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
                     }
                 },
                 "required": []
@@ -87,6 +188,18 @@ impl Tool for ListPodsTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        self.list_pods(args.namespace, args.limit).await
+        if args.all_namespaces.unwrap_or(false) {
+            cluster_scope(
+                args.cluster,
+                self.list_pods_all_namespaces(args.limit, args.phase, args.sort_by),
+            )
+            .await
+        } else {
+            cluster_scope(
+                args.cluster,
+                self.list_pods(args.namespace, args.limit, args.phase, args.sort_by),
+            )
+            .await
+        }
     }
 }
@@ -0,0 +1,95 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{LimitRangeListResponse, ResourceQuotaListResponse};
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for showing ResourceQuota usage against hard limits, plus the LimitRanges
+/// that set default and maximum container resources.
+///
+/// Exhausted quotas and LimitRange bounds are common reasons new pods are rejected
+/// at admission, which never shows up as a Pending pod.
+pub struct ResourceQuotasTool {
+    kube_agent: KubeAgent,
+}
+
+impl ResourceQuotasTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        ResourceQuotasTool { kube_agent }
+    }
+
+    /// Fetches quotas and limit ranges in parallel, for one namespace or all of them.
+    pub async fn get_resource_quotas(
+        &self,
+        namespace: Option<String>,
+    ) -> Result<String, KubeAgentError> {
+        let prefix = match &namespace {
+            Some(namespace) => format!("/api/v1/namespaces/{}", namespace),
+            None => "/api/v1".to_string(),
+        };
+        let quotas_endpoint = format!("{}/resourcequotas", prefix);
+        let limits_endpoint = format!("{}/limitranges", prefix);
+
+        let (quotas, limits) = tokio::join!(
+            self.kube_agent.list_all(&quotas_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent.list_all(&limits_endpoint, MAX_LIST_ITEMS)
+        );
+        let quotas = ResourceQuotaListResponse {
+            items: quotas?.items,
+        };
+        let limits = LimitRangeListResponse {
+            items: limits?.items,
+        };
+
+        debug!(
+            "Found {} resource quotas and {} limit ranges",
+            quotas.items.len(),
+            limits.items.len()
+        );
+
+        Ok(quotas.as_string(&limits))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResourceQuotasToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for ResourceQuotasTool {
+    const NAME: &'static str = "get_resource_quotas";
+    type Args = ResourceQuotasToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Show ResourceQuota usage vs hard limits (pods, CPU, memory, storage, object counts) and LimitRange defaults and bounds per namespace. Use this when pods can't be created or scaled in a namespace, or to explain default resource requests.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to check (default is all namespaces)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.get_resource_quotas(args.namespace)).await
+    }
+}
@@ -0,0 +1,238 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::KubeAgent;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for reading arbitrary Kubernetes resources by API path, for questions the
+/// typed tools don't cover (e.g. ingresses, statefulsets, CRDs).
+///
+/// Paths must fall under one of the configured prefixes. Secrets and configmaps are
+/// always refused, since their values would bypass the redaction of
+/// [`super::ListSecretsTool`] and [`super::ListConfigMapsTool`], and so are the proxy,
+/// log, exec, attach, and portforward subresources, which reach into kubelets, pods, and
+/// in-cluster services rather than reading the API. Responses are cut off at `max_bytes`.
+pub struct RawKubeGetTool {
+    kube_agent: KubeAgent,
+    /// API path prefixes that may be read, e.g. "/apis/apps/v1"
+    path_allowlist: Vec<String>,
+    max_bytes: usize,
+}
+
+impl RawKubeGetTool {
+    pub fn new(kube_agent: KubeAgent, path_allowlist: Vec<String>, max_bytes: usize) -> Self {
+        RawKubeGetTool {
+            kube_agent,
+            path_allowlist,
+            max_bytes,
+        }
+    }
+
+    pub async fn raw_get(&self, path: String) -> Result<String, KubeAgentError> {
+        let path = path.trim();
+        self.check_path(path)?;
+
+        let mut response = self.kube_agent.get(path.to_string()).await?;
+        debug!("Raw GET {} returned {} bytes", path, response.len());
+
+        if response.len() > self.max_bytes {
+            let mut end = self.max_bytes;
+            while !response.is_char_boundary(end) {
+                end -= 1;
+            }
+            let total = response.len();
+            response.truncate(end);
+            response.push_str(&format!(
+                "\n... [response truncated at {} of {} bytes; narrow the path, e.g. with a namespace, name, or ?limit=]",
+                end, total
+            ));
+        }
+
+        Ok(response)
+    }
+
+    fn check_path(&self, path: &str) -> Result<(), KubeAgentError> {
+        let resource_path = path.split('?').next().unwrap_or_default();
+
+        // Only plain segments are accepted, so the path the API server resolves is the
+        // one checked below: no percent-encoding (e.g. %2e%2e or se%63rets), no dot or
+        // empty segments, and no backslashes, which URL parsing treats as slashes
+        let plain_segment = |segment: &str| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-._:~".contains(c))
+        };
+        let canonical = resource_path
+            .strip_prefix('/')
+            .is_some_and(|rest| rest.split('/').all(plain_segment));
+        if !canonical {
+            return Err(KubeAgentError::PolicyViolation(format!(
+                "'{}' is not a plain absolute API path (no percent-encoding, '.' or '..' segments, or empty segments)",
+                path
+            )));
+        }
+        for segment in resource_path.split('/') {
+            let refusal = match segment {
+                "secrets" => "secrets cannot be read with raw_kube_get; use list_secrets instead",
+                "configmaps" => {
+                    "configmaps cannot be read with raw_kube_get; use list_configmaps instead"
+                }
+                "proxy" | "log" | "exec" | "attach" | "portforward" => {
+                    "raw_kube_get only reads API objects, not proxy, log, exec, attach, or portforward subresources"
+                }
+                _ => continue,
+            };
+            warn!("Refusing raw GET of {}: '{}' segment", path, segment);
+            return Err(KubeAgentError::PolicyViolation(refusal.to_string()));
+        }
+
+        // Match whole path segments so "/api/v1" doesn't allow "/api/v1beta1"
+        let allowed = self.path_allowlist.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            resource_path == prefix
+                || resource_path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+        if !allowed {
+            warn!(
+                "Refusing raw GET of {}: not under an allowlisted prefix",
+                path
+            );
+            return Err(KubeAgentError::PolicyViolation(format!(
+                "raw_kube_get may only read paths under [{}]",
+                self.path_allowlist.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RawKubeGetToolArgs {
+    pub path: String,
+    pub cluster: Option<String>,
+}
+
+impl Tool for RawKubeGetTool {
+    const NAME: &'static str = "raw_kube_get";
+    type Args = RawKubeGetToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": format!(
+                "Fetch the raw JSON for any Kubernetes API path, for resources no other tool covers (e.g. /apis/apps/v1/namespaces/default/statefulsets). Prefer the dedicated tools when one fits. Only paths under [{}] are allowed, secrets and configmaps are never returned (use list_secrets and list_configmaps), proxy/log/exec/attach/portforward subresources are refused, and responses over {} bytes are truncated.",
+                self.path_allowlist.join(", "),
+                self.max_bytes
+            ),
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "The API path, optionally with a query string (e.g. /apis/networking.k8s.io/v1/ingresses?limit=20)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["path"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.raw_get(args.path)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool() -> RawKubeGetTool {
+        RawKubeGetTool::new(
+            KubeAgent::new("http://127.0.0.1:9".to_string(), String::new(), None),
+            vec!["/api/v1".to_string(), "/apis/apps/v1".to_string()],
+            1024,
+        )
+    }
+
+    fn refused(path: &str) -> String {
+        match tool().check_path(path) {
+            Err(KubeAgentError::PolicyViolation(reason)) => reason,
+            other => panic!("{} was not refused: {:?}", path, other),
+        }
+    }
+
+    #[test]
+    fn accepts_plain_paths_under_the_allowlist() {
+        let tool = tool();
+        for path in [
+            "/api/v1/namespaces/default/pods",
+            "/api/v1/nodes/node-1",
+            "/apis/apps/v1/namespaces/default/statefulsets?limit=20",
+        ] {
+            assert!(tool.check_path(path).is_ok(), "{} was refused", path);
+        }
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_allowlist() {
+        assert!(refused("/api/v1beta1/pods").contains("may only read paths under"));
+        assert!(refused("/apis/batch/v1/jobs").contains("may only read paths under"));
+    }
+
+    #[test]
+    fn refuses_secrets_and_configmaps() {
+        assert!(refused("/api/v1/namespaces/default/secrets").contains("list_secrets"));
+        assert!(refused("/api/v1/secrets").contains("list_secrets"));
+        assert!(refused("/api/v1/namespaces/default/configmaps/app").contains("list_configmaps"));
+        assert!(refused("/api/v1/configmaps?limit=5").contains("list_configmaps"));
+    }
+
+    #[test]
+    fn refuses_subresources_that_reach_past_the_api() {
+        for path in [
+            "/api/v1/nodes/node-1/proxy/metrics",
+            "/api/v1/nodes/node-1/proxy",
+            "/api/v1/namespaces/default/services/web:8080/proxy/admin",
+            "/api/v1/namespaces/default/pods/web-1/proxy/healthz",
+            "/api/v1/namespaces/default/pods/web-1/log",
+            "/api/v1/namespaces/default/pods/web-1/exec?command=sh",
+            "/api/v1/namespaces/default/pods/web-1/attach",
+            "/api/v1/namespaces/default/pods/web-1/portforward",
+        ] {
+            assert!(
+                refused(path).contains("subresources"),
+                "{} was refused for the wrong reason",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_paths_that_are_not_plain() {
+        for path in [
+            "api/v1/pods",
+            "/api/v1/namespaces/default/%73ecrets",
+            "/api/v1/namespaces/default/pods/../secrets",
+            "/api/v1//pods",
+            "/api/v1/namespaces\\default",
+        ] {
+            assert!(refused(path).contains("plain absolute API path"));
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{Role, RoleBinding, SelfSubjectReview, ServiceAccount, Subject};
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+const RBAC_API: &str = "/apis/rbac.authorization.k8s.io/v1";
+
+/// Maximum number of bindings described; the rest are only counted.
+const MAX_LISTED_BINDINGS: usize = 40;
+
+/// Maximum number of rules described per role.
+const MAX_LISTED_RULES: usize = 10;
+
+/// Tool for summarizing who can do what, from ServiceAccounts, Roles, ClusterRoles, and
+/// their bindings.
+///
+/// Given a namespace, it covers the namespace's ServiceAccounts and RoleBindings plus
+/// the ClusterRoleBindings naming its ServiceAccounts. Without one, it asks the API
+/// server who the agent is (a SelfSubjectReview) and covers every binding that applies
+/// to that identity. Each binding becomes one "subjects can ... (binding -> role)"
+/// statement. Use `can_i` to check a single action instead.
+pub struct RbacOverviewTool {
+    kube_agent: KubeAgent,
+}
+
+impl RbacOverviewTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        RbacOverviewTool { kube_agent }
+    }
+
+    pub async fn rbac_overview(&self, namespace: Option<String>) -> Result<String, KubeAgentError> {
+        match namespace {
+            Some(namespace) => self.namespace_overview(namespace).await,
+            None => self.identity_overview().await,
+        }
+    }
+
+    async fn namespace_overview(&self, namespace: String) -> Result<String, KubeAgentError> {
+        debug!("Summarizing RBAC in namespace {}", namespace);
+
+        let accounts_endpoint = format!("/api/v1/namespaces/{}/serviceaccounts", namespace);
+        let roles_endpoint = format!("{}/namespaces/{}/roles", RBAC_API, namespace);
+        let bindings_endpoint = format!("{}/namespaces/{}/rolebindings", RBAC_API, namespace);
+        let cluster_roles_endpoint = format!("{}/clusterroles", RBAC_API);
+        let cluster_bindings_endpoint = format!("{}/clusterrolebindings", RBAC_API);
+        let (accounts, roles, bindings, cluster_roles, cluster_bindings) = tokio::join!(
+            self.kube_agent
+                .list_all::<ServiceAccount>(&accounts_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<Role>(&roles_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<RoleBinding>(&bindings_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<Role>(&cluster_roles_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<RoleBinding>(&cluster_bindings_endpoint, MAX_LIST_ITEMS)
+        );
+        let (accounts, roles, bindings, cluster_roles, cluster_bindings) = (
+            accounts?,
+            roles?,
+            bindings?,
+            cluster_roles?,
+            cluster_bindings?,
+        );
+
+        let account_names: Vec<&str> = accounts
+            .items
+            .iter()
+            .map(|account| account.metadata.name.as_str())
+            .collect();
+        let mut output = format!("RBAC overview for namespace {}\n", namespace);
+        output.push_str(&format!(
+            "ServiceAccounts ({}): {}\n",
+            account_names.len(),
+            if account_names.is_empty() {
+                "none".to_string()
+            } else {
+                account_names.join(", ")
+            }
+        ));
+
+        // Every subject of a RoleBinding here is relevant; of ClusterRoleBindings, only
+        // this namespace's ServiceAccounts, alone or as a group
+        let namespace_group = format!("system:serviceaccounts:{}", namespace);
+        let in_namespace = |subject: &Subject| match subject.kind.as_str() {
+            "ServiceAccount" => subject.namespace.as_deref() == Some(namespace.as_str()),
+            "Group" => subject.name == namespace_group,
+            _ => false,
+        };
+        let statements: Vec<Statement> = bindings
+            .items
+            .iter()
+            .filter_map(|binding| statement(binding, &roles.items, &cluster_roles.items, |_| true))
+            .chain(cluster_bindings.items.iter().filter_map(|binding| {
+                statement(binding, &roles.items, &cluster_roles.items, in_namespace)
+            }))
+            .collect();
+
+        // ServiceAccounts still get whatever the cluster grants all ServiceAccounts
+        let unbound: Vec<&str> = account_names
+            .iter()
+            .copied()
+            .filter(|name| {
+                !statements.iter().any(|statement| {
+                    statement.subjects.iter().any(|subject| {
+                        subject.kind == "ServiceAccount"
+                            && subject.name == *name
+                            && subject.namespace.as_deref() == Some(namespace.as_str())
+                    })
+                })
+            })
+            .collect();
+        if !unbound.is_empty() {
+            output.push_str(&format!(
+                "ServiceAccounts without bindings of their own: {}\n",
+                unbound.join(", ")
+            ));
+        }
+
+        push_statements(&mut output, "Who can do what", &statements);
+        for listing in [
+            accounts.truncation_note(),
+            roles.truncation_note(),
+            bindings.truncation_note(),
+            cluster_roles.truncation_note(),
+            cluster_bindings.truncation_note(),
+        ] {
+            output.push_str(&listing);
+        }
+        Ok(output)
+    }
+
+    async fn identity_overview(&self) -> Result<String, KubeAgentError> {
+        debug!("Summarizing RBAC for the agent's own identity");
+
+        let review = json!({
+            "apiVersion": "authentication.k8s.io/v1",
+            "kind": "SelfSubjectReview"
+        });
+        let response = self
+            .kube_agent
+            .make_request(
+                Method::POST,
+                "/apis/authentication.k8s.io/v1/selfsubjectreviews".to_string(),
+                Some(&review),
+                None,
+            )
+            .await?;
+        let review: SelfSubjectReview = serde_json::from_str(&response).map_err(|e| {
+            error!("Error parsing self subject review JSON response: {}", e);
+            KubeAgentError::from(e)
+        })?;
+        let user = review.status.user_info;
+
+        let roles_endpoint = format!("{}/roles", RBAC_API);
+        let bindings_endpoint = format!("{}/rolebindings", RBAC_API);
+        let cluster_roles_endpoint = format!("{}/clusterroles", RBAC_API);
+        let cluster_bindings_endpoint = format!("{}/clusterrolebindings", RBAC_API);
+        let (roles, bindings, cluster_roles, cluster_bindings) = tokio::join!(
+            self.kube_agent
+                .list_all::<Role>(&roles_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<RoleBinding>(&bindings_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<Role>(&cluster_roles_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<RoleBinding>(&cluster_bindings_endpoint, MAX_LIST_ITEMS)
+        );
+        let (roles, bindings, cluster_roles, cluster_bindings) =
+            (roles?, bindings?, cluster_roles?, cluster_bindings?);
+
+        let is_agent = |subject: &Subject| subject.matches(&user.username, &user.groups);
+        let statements: Vec<Statement> = cluster_bindings
+            .items
+            .iter()
+            .chain(&bindings.items)
+            .filter_map(|binding| statement(binding, &roles.items, &cluster_roles.items, is_agent))
+            .collect();
+
+        let mut output = format!(
+            "RBAC overview for the agent's identity {} (groups: {})\n",
+            user.username,
+            if user.groups.is_empty() {
+                "none".to_string()
+            } else {
+                user.groups.join(", ")
+            }
+        );
+        push_statements(&mut output, "What the agent can do", &statements);
+        for listing in [
+            roles.truncation_note(),
+            bindings.truncation_note(),
+            cluster_roles.truncation_note(),
+            cluster_bindings.truncation_note(),
+        ] {
+            output.push_str(&listing);
+        }
+        Ok(output)
+    }
+}
+
+/// One binding, reduced to the subjects that matter and what they may do.
+struct Statement<'a> {
+    subjects: Vec<&'a Subject>,
+    binding: &'a RoleBinding,
+    /// None when the referenced role doesn't exist
+    role: Option<&'a Role>,
+}
+
+// The binding's statement, if any of its subjects pass `relevant`
+fn statement<'a>(
+    binding: &'a RoleBinding,
+    roles: &'a [Role],
+    cluster_roles: &'a [Role],
+    relevant: impl Fn(&Subject) -> bool,
+) -> Option<Statement<'a>> {
+    let subjects: Vec<&Subject> = binding
+        .subjects
+        .iter()
+        .flatten()
+        .filter(|subject| relevant(subject))
+        .collect();
+    if subjects.is_empty() {
+        return None;
+    }
+
+    // A RoleBinding's Role is in the binding's own namespace
+    let role = if binding.role_ref.kind == "Role" {
+        roles.iter().find(|role| {
+            role.metadata.name == binding.role_ref.name
+                && role.metadata.namespace == binding.metadata.namespace
+        })
+    } else {
+        cluster_roles
+            .iter()
+            .find(|role| role.metadata.name == binding.role_ref.name)
+    };
+    Some(Statement {
+        subjects,
+        binding,
+        role,
+    })
+}
+
+fn push_statements(output: &mut String, heading: &str, statements: &[Statement]) {
+    output.push_str(&format!("\n{} ({} bindings):\n", heading, statements.len()));
+    if statements.is_empty() {
+        output.push_str("  No bindings found.\n");
+        return;
+    }
+
+    for statement in statements.iter().take(MAX_LISTED_BINDINGS) {
+        let binding = statement.binding;
+        let subjects: Vec<String> = statement.subjects.iter().map(|s| s.to_string()).collect();
+        let (kind, scope) = match &binding.metadata.namespace {
+            Some(namespace) => ("RoleBinding", format!("in namespace {}", namespace)),
+            None => ("ClusterRoleBinding", "cluster-wide".to_string()),
+        };
+        let source = format!(
+            "{} {} -> {} {}",
+            kind, binding.metadata.name, binding.role_ref.kind, binding.role_ref.name
+        );
+
+        let Some(role) = statement.role else {
+            output.push_str(&format!(
+                "  {}: nothing, the {} does not exist ({})\n",
+                subjects.join(", "),
+                binding.role_ref.kind,
+                source
+            ));
+            continue;
+        };
+        let rules = role.rules.as_deref().unwrap_or_default();
+        let mut can: Vec<String> = rules
+            .iter()
+            .take(MAX_LISTED_RULES)
+            .map(|rule| rule.describe())
+            .collect();
+        if rules.len() > MAX_LISTED_RULES {
+            can.push(format!("{} more rules", rules.len() - MAX_LISTED_RULES));
+        }
+        if can.is_empty() {
+            can.push("nothing (the role has no rules)".to_string());
+        }
+        output.push_str(&format!(
+            "  {} can {} {} ({})\n",
+            subjects.join(", "),
+            can.join("; "),
+            scope,
+            source
+        ));
+    }
+    if statements.len() > MAX_LISTED_BINDINGS {
+        output.push_str(&format!(
+            "  ... and {} more bindings\n",
+            statements.len() - MAX_LISTED_BINDINGS
+        ));
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RbacOverviewToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for RbacOverviewTool {
+    const NAME: &'static str = "rbac_overview";
+    type Args = RbacOverviewToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Summarize Kubernetes RBAC as who-can-do-what statements. With a namespace: its ServiceAccounts, and what each RoleBinding in it and each ClusterRoleBinding naming its ServiceAccounts grants. Without a namespace: what the agent's own identity is granted across the cluster. Use this for questions like 'what can the portfolio service account do?' or 'what permissions do you have?'; use can_i to check one specific action.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to summarize (omit to summarize the agent's own permissions)"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.rbac_overview(args.namespace)).await
+    }
+}
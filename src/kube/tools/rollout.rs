@@ -0,0 +1,380 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::deployment::{container_images, POD_TEMPLATE_HASH};
+use crate::kube::types::table::{age, Table};
+use crate::kube::types::{Deployment, Pod, ReplicaSet};
+use crate::kube::{percent_encode, KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::cmp::Reverse;
+use tracing::*;
+
+/// Maximum number of drifted pods listed; the rest are only counted.
+const MAX_DRIFT_LINES: usize = 20;
+
+/// Tool for checking whether a Deployment's latest version is fully rolled out, like
+/// `kubectl rollout status` with the evidence behind the answer.
+///
+/// The Deployment's newest ReplicaSet holds its current pod template, and its
+/// pod-template-hash covers images, env, and every other template field. Pods of older
+/// ReplicaSets therefore run an outdated template even when their image tag matches,
+/// and are reported as drift along with stuck old replicas and image mismatches.
+pub struct RolloutStatusTool {
+    kube_agent: KubeAgent,
+}
+
+impl RolloutStatusTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        RolloutStatusTool { kube_agent }
+    }
+
+    /// Fetches the deployment, then its ReplicaSets and pods in parallel, and compares
+    /// what runs against the current template.
+    pub async fn check_rollout(
+        &self,
+        namespace: Option<String>,
+        name: String,
+    ) -> Result<String, KubeAgentError> {
+        let namespace = namespace.unwrap_or_else(|| "default".to_string());
+        debug!("Checking rollout of deployment {}/{}", namespace, name);
+
+        let endpoint = format!(
+            "/apis/apps/v1/namespaces/{}/deployments/{}",
+            namespace, name
+        );
+        let deployment: Deployment = serde_json::from_str(&self.kube_agent.get(endpoint).await?)
+            .map_err(|e| {
+                error!("Error parsing deployment JSON response: {}", e);
+                KubeAgentError::from(e)
+            })?;
+        let selector = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.selector.as_ref())
+            .map(|selector| selector.as_query())
+            .unwrap_or_default();
+        if selector.is_empty() {
+            return Err(KubeAgentError::ParseError(format!(
+                "deployment {}/{} has no matchLabels selector",
+                namespace, name
+            )));
+        }
+
+        let query = format!("?labelSelector={}", percent_encode(&selector));
+        let replica_sets_endpoint = format!(
+            "/apis/apps/v1/namespaces/{}/replicasets{}",
+            namespace, query
+        );
+        let pods_endpoint = format!("/api/v1/namespaces/{}/pods{}", namespace, query);
+        let (replica_sets, pods) = tokio::join!(
+            self.kube_agent
+                .list_all::<ReplicaSet>(&replica_sets_endpoint, MAX_LIST_ITEMS),
+            self.kube_agent
+                .list_all::<Pod>(&pods_endpoint, MAX_LIST_ITEMS)
+        );
+        let pods = pods?;
+        let mut replica_sets: Vec<ReplicaSet> = replica_sets?
+            .items
+            .into_iter()
+            .filter(|replica_set| replica_set.is_owned_by(&name))
+            .collect();
+        replica_sets.sort_by_key(|replica_set| Reverse(replica_set.revision()));
+
+        debug!(
+            "Found {} replica sets and {} pods for deployment {}/{}",
+            replica_sets.len(),
+            pods.items.len(),
+            namespace,
+            name
+        );
+
+        Ok(rollout_report(&deployment, &replica_sets, &pods.items) + &pods.truncation_note())
+    }
+}
+
+/// Why the rollout isn't complete yet, in the order `kubectl rollout status` checks, or
+/// None once every replica runs the current template and is available.
+fn incomplete_reason(deployment: &Deployment) -> Option<String> {
+    let spec = deployment.spec.as_ref();
+    let Some(status) = deployment.status.as_ref() else {
+        return Some("the deployment has no status yet".to_string());
+    };
+    let desired = spec.and_then(|spec| spec.replicas).unwrap_or(1);
+    let total = status.replicas.unwrap_or(0);
+    let updated = status.updated_replicas.unwrap_or(0);
+    let available = status.available_replicas.unwrap_or(0);
+
+    if status.observed_generation < deployment.metadata.generation {
+        return Some("the controller hasn't observed the latest spec yet".to_string());
+    }
+    let stalled = status.conditions.iter().flatten().find(|condition| {
+        condition.type_field == "Progressing"
+            && condition.reason.as_deref() == Some("ProgressDeadlineExceeded")
+    });
+    if let Some(condition) = stalled {
+        return Some(format!(
+            "stalled, progress deadline exceeded ({})",
+            condition.message.as_deref().unwrap_or("no message")
+        ));
+    }
+    if updated < desired {
+        Some(format!(
+            "{} of {} replicas run the current template",
+            updated, desired
+        ))
+    } else if total > updated {
+        Some(format!(
+            "{} old replicas are still running",
+            total - updated
+        ))
+    } else if available < updated {
+        Some(format!(
+            "{} of {} updated replicas are available",
+            available, updated
+        ))
+    } else {
+        None
+    }
+}
+
+fn rollout_report(deployment: &Deployment, replica_sets: &[ReplicaSet], pods: &[Pod]) -> String {
+    let now = chrono::Utc::now();
+    let spec = deployment.spec.as_ref();
+    let status = deployment.status.as_ref();
+    let revision = deployment.revision();
+    let current = replica_sets
+        .iter()
+        .find(|replica_set| revision.is_some() && replica_set.revision() == revision)
+        .or(replica_sets.first());
+    let current_hash = current.and_then(|replica_set| replica_set.template_hash());
+
+    let verdict = match incomplete_reason(deployment) {
+        None => "fully rolled out".to_string(),
+        Some(reason) => format!("NOT fully rolled out: {}", reason),
+    };
+    let mut output = format!(
+        "Rollout of deployment {}/{}: {}\n",
+        deployment.metadata.namespace, deployment.metadata.name, verdict
+    );
+    if spec.and_then(|spec| spec.paused).unwrap_or(false) {
+        output.push_str(
+            "The rollout is paused; template changes won't roll out until it is resumed.\n",
+        );
+    }
+    output.push_str(&format!(
+        "Current template (revision {}): {}\n",
+        revision.map_or_else(|| "unknown".to_string(), |revision| revision.to_string()),
+        spec.and_then(|spec| spec.template.as_ref())
+            .map_or_else(|| "<none>".to_string(), |template| template.images())
+    ));
+    output.push_str(&format!(
+        "Replicas: {} desired, {} updated, {} ready, {} available, {} total\n",
+        spec.and_then(|spec| spec.replicas).unwrap_or(1),
+        status
+            .and_then(|status| status.updated_replicas)
+            .unwrap_or(0),
+        status.and_then(|status| status.ready_replicas).unwrap_or(0),
+        status
+            .and_then(|status| status.available_replicas)
+            .unwrap_or(0),
+        status.and_then(|status| status.replicas).unwrap_or(0)
+    ));
+
+    output.push_str("\nReplicaSets (newest first):\n");
+    if replica_sets.is_empty() {
+        output.push_str("  <none>\n");
+    } else {
+        let mut table = Table::new(&["NAME", "REVISION", "DESIRED", "READY", "AGE", "IMAGES"]);
+        for replica_set in replica_sets {
+            let is_current = current.is_some_and(|current| std::ptr::eq(current, replica_set));
+            table.row(vec![
+                replica_set.metadata.name.clone(),
+                match replica_set.revision() {
+                    Some(revision) if is_current => format!("{} (current)", revision),
+                    Some(revision) => revision.to_string(),
+                    None => "?".to_string(),
+                },
+                replica_set
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.replicas)
+                    .unwrap_or(0)
+                    .to_string(),
+                replica_set
+                    .status
+                    .as_ref()
+                    .and_then(|status| status.ready_replicas)
+                    .unwrap_or(0)
+                    .to_string(),
+                age(&replica_set.metadata.creation_timestamp, now),
+                replica_set
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.template.as_ref())
+                    .map_or_else(|| "<none>".to_string(), |template| template.images()),
+            ]);
+        }
+        output.push_str(&table.render());
+    }
+
+    // Old ReplicaSets scaled to zero are history kept for rollbacks; only those still
+    // running replicas are stuck
+    let stuck: Vec<&ReplicaSet> = replica_sets
+        .iter()
+        .filter(|replica_set| !current.is_some_and(|current| std::ptr::eq(current, *replica_set)))
+        .filter(|replica_set| {
+            replica_set
+                .status
+                .as_ref()
+                .and_then(|status| status.replicas)
+                .unwrap_or(0)
+                > 0
+        })
+        .collect();
+    for replica_set in &stuck {
+        output.push_str(&format!(
+            "Old ReplicaSet {} (revision {}) still runs {} replicas\n",
+            replica_set.metadata.name,
+            replica_set
+                .revision()
+                .map_or_else(|| "?".to_string(), |revision| revision.to_string()),
+            replica_set
+                .status
+                .as_ref()
+                .and_then(|status| status.replicas)
+                .unwrap_or(0)
+        ));
+    }
+
+    let drift = pod_drift(deployment, replica_sets, current_hash, pods);
+    output.push_str(&format!("\nPods ({}):\n", pods.len()));
+    if drift.is_empty() {
+        output.push_str("  Every pod runs the current template.\n");
+    } else {
+        for line in drift.iter().take(MAX_DRIFT_LINES) {
+            output.push_str(&format!("  {}\n", line));
+        }
+        if drift.len() > MAX_DRIFT_LINES {
+            output.push_str(&format!(
+                "  ... and {} more drifted pods\n",
+                drift.len() - MAX_DRIFT_LINES
+            ));
+        }
+    }
+    output
+}
+
+// One line per pod that runs an old template or different images than the template
+fn pod_drift(
+    deployment: &Deployment,
+    replica_sets: &[ReplicaSet],
+    current_hash: Option<&str>,
+    pods: &[Pod],
+) -> Vec<String> {
+    let template = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.template.as_ref())
+        .and_then(|template| template.spec.as_ref());
+
+    let mut drift = Vec::new();
+    for pod in pods {
+        let hash = pod
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(POD_TEMPLATE_HASH))
+            .map(String::as_str);
+        let containers = pod
+            .spec
+            .as_ref()
+            .map(|spec| spec.containers.as_slice())
+            .unwrap_or_default();
+
+        if hash.is_some() && hash != current_hash {
+            let revision = replica_sets
+                .iter()
+                .find(|replica_set| replica_set.template_hash() == hash)
+                .and_then(ReplicaSet::revision)
+                .map_or_else(|| "unknown".to_string(), |revision| revision.to_string());
+            drift.push(format!(
+                "{} runs old revision {} (template hash {}): {}",
+                pod.metadata.name,
+                revision,
+                hash.unwrap_or_default(),
+                container_images(containers)
+            ));
+            continue;
+        }
+
+        // Images can differ under the current hash when a pod was edited in place
+        let Some(template) = template else { continue };
+        let mismatched: Vec<String> = containers
+            .iter()
+            .filter_map(|container| {
+                let wanted = template
+                    .containers
+                    .iter()
+                    .find(|wanted| wanted.name == container.name)?;
+                (wanted.image != container.image).then(|| {
+                    format!(
+                        "{} runs {} instead of {}",
+                        container.name,
+                        container.image.as_deref().unwrap_or("<none>"),
+                        wanted.image.as_deref().unwrap_or("<none>")
+                    )
+                })
+            })
+            .collect();
+        if !mismatched.is_empty() {
+            drift.push(format!("{}: {}", pod.metadata.name, mismatched.join("; ")));
+        }
+    }
+    drift
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RolloutStatusToolArgs {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub cluster: Option<String>,
+}
+
+impl Tool for RolloutStatusTool {
+    const NAME: &'static str = "check_rollout";
+    type Args = RolloutStatusToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "Check whether a deployment's latest version is fully rolled out: compares its current pod template (revision, images, and template hash, which also covers env changes) against the ReplicaSets and pods actually running, and reports stuck old replicas, pods on old revisions, image mismatches, and stalled rollouts. Use this for questions like \"is the new version fully rolled out?\".",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace of the deployment (default is \"default\")"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the deployment"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": ["name"]
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.check_rollout(args.namespace, args.name)).await
+    }
+}
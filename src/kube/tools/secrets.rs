@@ -0,0 +1,78 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::SecretListResponse;
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for taking an inventory of the secrets in a namespace.
+///
+/// Returns only metadata: names, types, key names, and ages. Secret values are
+/// discarded while parsing the API response and never reach the model.
+pub struct ListSecretsTool {
+    kube_agent: KubeAgent,
+}
+
+impl ListSecretsTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        ListSecretsTool { kube_agent }
+    }
+
+    pub async fn list_secrets(&self, namespace: Option<String>) -> Result<String, KubeAgentError> {
+        let namespace = namespace.unwrap_or_else(|| "default".to_string());
+        let endpoint = format!("/api/v1/namespaces/{}/secrets", namespace);
+        // The raw response contains secret values, so unlike other tools it is never logged
+        let listing = self.kube_agent.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+
+        debug!("Listed {} secrets in {}", listing.items.len(), namespace);
+
+        let note = listing.truncation_note();
+        let secrets = SecretListResponse {
+            items: listing.items,
+        };
+
+        Ok(secrets.as_string() + &note)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ListSecretsToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for ListSecretsTool {
+    const NAME: &'static str = "list_secrets";
+    type Args = ListSecretsToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "List secrets in a Kubernetes namespace: names, types (e.g. kubernetes.io/tls), key names, and ages. Secret values are never returned.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to list secrets from (default is 'default')"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.list_secrets(args.namespace)).await
+    }
+}
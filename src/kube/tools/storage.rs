@@ -0,0 +1,106 @@
+use crate::kube::clusters::cluster_scope;
+use crate::kube::error::KubeAgentError;
+use crate::kube::types::{PersistentVolumeClaimListResponse, PersistentVolumeListResponse};
+use crate::kube::{KubeAgent, MAX_LIST_ITEMS};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::*;
+
+/// Tool for listing PersistentVolumeClaims and the PersistentVolumes bound to them.
+///
+/// Shows capacity, storage class, access modes, and phase, which is what's needed to
+/// diagnose pods stuck Pending on an unbound claim.
+pub struct ListPersistentVolumeClaimsTool {
+    kube_agent: KubeAgent,
+}
+
+impl ListPersistentVolumeClaimsTool {
+    pub fn new(kube_agent: KubeAgent) -> Self {
+        ListPersistentVolumeClaimsTool { kube_agent }
+    }
+
+    /// Fetch the persistent volume claims in a namespace
+    pub async fn get_claims(
+        &self,
+        namespace: &str,
+    ) -> Result<PersistentVolumeClaimListResponse, KubeAgentError> {
+        let endpoint = format!("/api/v1/namespaces/{}/persistentvolumeclaims", namespace);
+        let listing = self.kube_agent.list_all(&endpoint, MAX_LIST_ITEMS).await?;
+
+        Ok(PersistentVolumeClaimListResponse {
+            items: listing.items,
+        })
+    }
+
+    /// Fetch all persistent volumes (they are cluster-scoped)
+    pub async fn get_volumes(&self) -> Result<PersistentVolumeListResponse, KubeAgentError> {
+        let listing = self
+            .kube_agent
+            .list_all("/api/v1/persistentvolumes", MAX_LIST_ITEMS)
+            .await?;
+
+        Ok(PersistentVolumeListResponse {
+            items: listing.items,
+        })
+    }
+
+    /// Fetches claims and volumes in parallel and matches each claim to its volume.
+    ///
+    /// Listing volumes needs cluster-wide permissions, so if that fails the claims are
+    /// still returned without volume details.
+    pub async fn list_claims(&self, namespace: Option<String>) -> Result<String, KubeAgentError> {
+        let namespace = namespace.unwrap_or_else(|| "default".to_string());
+
+        let (claims_result, volumes_result) =
+            tokio::join!(self.get_claims(&namespace), self.get_volumes());
+
+        let claims = claims_result?;
+        let volumes = volumes_result.unwrap_or_else(|e| {
+            warn!("Failed to fetch persistent volumes: {}", e);
+            PersistentVolumeListResponse { items: Vec::new() }
+        });
+
+        Ok(claims.as_string(&volumes))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ListPersistentVolumeClaimsToolArgs {
+    pub namespace: Option<String>,
+    pub cluster: Option<String>,
+}
+
+impl Tool for ListPersistentVolumeClaimsTool {
+    const NAME: &'static str = "list_persistent_volume_claims";
+    type Args = ListPersistentVolumeClaimsToolArgs;
+    type Output = String;
+    type Error = KubeAgentError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        serde_json::from_value(json!({
+            "name": Self::NAME,
+            "description": "List PersistentVolumeClaims in a namespace with their bound PersistentVolumes: capacity, storage class, access modes, and phase. Use this to diagnose pods stuck Pending on unbound claims.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "The namespace to list claims from (default is 'default')"
+                    },
+                    "cluster": {
+                        "type": "string",
+                        "description": "The cluster to query (default is the home cluster; see list_clusters)"
+                    }
+                },
+                "required": []
+            }
+        }))
+        .unwrap()
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        cluster_scope(args.cluster, self.list_claims(args.namespace)).await
+    }
+}
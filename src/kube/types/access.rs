@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+// SelfSubjectAccessReview (/apis/authorization.k8s.io/v1/selfsubjectaccessreviews)
+//
+// Only the status is parsed; the request is built as JSON by the tool.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfSubjectAccessReview {
+    pub status: SubjectAccessReviewStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubjectAccessReviewStatus {
+    pub allowed: bool,
+    pub denied: Option<bool>,
+    pub reason: Option<String>,
+    #[serde(rename = "evaluationError")]
+    pub evaluation_error: Option<String>,
+}
@@ -0,0 +1,105 @@
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+
+// TLS Secret API Response (/api/v1/secrets?fieldSelector=type=kubernetes.io/tls)
+//
+// Only `tls.crt` is parsed. Every other key, including the private key in `tls.key`,
+// is skipped by the deserializer and never held in memory.
+#[derive(Debug, Deserialize)]
+pub struct TlsSecretListResponse {
+    pub items: Vec<TlsSecret>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TlsSecret {
+    pub metadata: TlsSecretMetadata,
+    pub data: Option<TlsSecretData>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TlsSecretMetadata {
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TlsSecretData {
+    #[serde(rename = "tls.crt")]
+    pub certificate: Option<String>, // base64-encoded PEM, leaf certificate first
+}
+
+/// The parts of a certificate relevant to expiry.
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub dns_names: Vec<String>,
+    pub not_after: DateTime<Utc>,
+    /// Number of certificates in the chain, including the leaf
+    pub chain_length: usize,
+    /// Earliest expiry across the chain, when an intermediate expires before the leaf
+    pub chain_not_after: Option<DateTime<Utc>>,
+}
+
+impl TlsSecret {
+    /// Decodes and parses the secret's certificate chain.
+    pub fn certificate(&self) -> Result<CertificateInfo, String> {
+        let encoded = self
+            .data
+            .as_ref()
+            .and_then(|data| data.certificate.as_deref())
+            .ok_or("no tls.crt key")?;
+        let pem = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("tls.crt is not valid base64: {}", e))?;
+
+        let blocks = Pem::iter_from_buffer(&pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("tls.crt is not valid PEM: {}", e))?;
+        let mut expiries = Vec::new();
+        let mut leaf = None;
+        for block in &blocks {
+            let cert = block
+                .parse_x509()
+                .map_err(|e| format!("invalid certificate: {}", e))?;
+            let not_after = DateTime::from_timestamp(cert.validity().not_after.timestamp(), 0)
+                .ok_or("certificate expiry is out of range")?;
+            expiries.push(not_after);
+
+            if leaf.is_none() {
+                let dns_names = cert
+                    .subject_alternative_name()
+                    .ok()
+                    .flatten()
+                    .map(|san| {
+                        san.value
+                            .general_names
+                            .iter()
+                            .filter_map(|name| match name {
+                                GeneralName::DNSName(dns) => Some(dns.to_string()),
+                                _ => None,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                leaf = Some(CertificateInfo {
+                    subject: cert.subject().to_string(),
+                    issuer: cert.issuer().to_string(),
+                    dns_names,
+                    not_after,
+                    chain_length: blocks.len(),
+                    chain_not_after: None,
+                });
+            }
+        }
+
+        let mut leaf = leaf.ok_or("tls.crt contains no certificates")?;
+        leaf.chain_not_after = expiries
+            .into_iter()
+            .min()
+            .filter(|min| *min < leaf.not_after);
+        Ok(leaf)
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ConfigMap API Response (/api/v1/namespaces/{namespace}/configmaps)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigMapListResponse {
+    pub items: Vec<ConfigMap>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigMap {
+    pub metadata: ConfigMapMetadata,
+    pub data: Option<BTreeMap<String, String>>,
+    #[serde(rename = "binaryData")]
+    pub binary_data: Option<BTreeMap<String, String>>, // base64-encoded
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigMapMetadata {
+    pub name: String,
+    pub namespace: String,
+    #[serde(rename = "creationTimestamp")]
+    pub creation_timestamp: Option<String>,
+}
+
+impl ConfigMapListResponse {
+    /// Formats the configmaps with their keys and value sizes.
+    ///
+    /// Values are only included for configmaps for which `show_values` returns true;
+    /// binary values are never included.
+    pub fn as_string(&self, show_values: impl Fn(&ConfigMap) -> bool) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("Found {} configmaps:\n\n", self.items.len()));
+
+        for configmap in &self.items {
+            let include_values = show_values(configmap);
+            let redacted = if include_values {
+                ""
+            } else {
+                " (values redacted)"
+            };
+            output.push_str(&format!(
+                "ConfigMap: {}{}\n",
+                configmap.metadata.name, redacted
+            ));
+            if let Some(created) = &configmap.metadata.creation_timestamp {
+                output.push_str(&format!("  Created: {}\n", created));
+            }
+
+            let key_count = configmap.data.as_ref().map_or(0, |data| data.len())
+                + configmap.binary_data.as_ref().map_or(0, |data| data.len());
+            if key_count == 0 {
+                output.push_str("  Keys: <none>\n\n");
+                continue;
+            }
+
+            output.push_str("  Keys:\n");
+            for (key, value) in configmap.data.iter().flatten() {
+                if include_values {
+                    output.push_str(&format!("    {} ({} bytes): {}\n", key, value.len(), value));
+                } else {
+                    output.push_str(&format!("    {} ({} bytes)\n", key, value.len()));
+                }
+            }
+            for (key, value) in configmap.binary_data.iter().flatten() {
+                // Approximate decoded size of the base64 payload
+                output.push_str(&format!(
+                    "    {} (binary, ~{} bytes)\n",
+                    key,
+                    value.len() * 3 / 4
+                ));
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
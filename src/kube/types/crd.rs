@@ -0,0 +1,123 @@
+use super::table::age;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// CustomResourceDefinition list (/apis/apiextensions.k8s.io/v1/customresourcedefinitions)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomResourceDefinition {
+    pub metadata: CrdMetadata,
+    pub spec: CrdSpec,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrdMetadata {
+    pub name: String, // "<plural>.<group>", e.g. "certificates.cert-manager.io"
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrdSpec {
+    pub group: String,
+    pub names: CrdNames,
+    pub scope: String, // "Namespaced" or "Cluster"
+    pub versions: Vec<CrdVersion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrdNames {
+    pub kind: String,
+    pub plural: String,
+    pub singular: Option<String>,
+    #[serde(rename = "shortNames")]
+    pub short_names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrdVersion {
+    pub name: String,
+    pub served: bool,
+    pub storage: bool,
+    #[serde(rename = "additionalPrinterColumns")]
+    pub additional_printer_columns: Option<Vec<PrinterColumn>>,
+}
+
+// A column `kubectl get` shows for the resource
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrinterColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_field: String, // "string", "integer", "date", ...
+    #[serde(rename = "jsonPath")]
+    pub json_path: String,
+    pub priority: Option<u32>, // above 0 only with `kubectl get -o wide`
+}
+
+impl CustomResourceDefinition {
+    /// Whether `name` refers to this resource: its full name, plural, singular, kind,
+    /// or a short name, ignoring case.
+    pub fn is_named(&self, name: &str) -> bool {
+        let names = &self.spec.names;
+        [&self.metadata.name, &names.plural, &names.kind]
+            .into_iter()
+            .chain(names.singular.as_ref())
+            .chain(names.short_names.iter().flatten())
+            .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_namespaced(&self) -> bool {
+        self.spec.scope == "Namespaced"
+    }
+
+    /// The version to read instances at: the storage version if it is served, else
+    /// the first served one.
+    pub fn preferred_version(&self) -> Option<&CrdVersion> {
+        let served = || self.spec.versions.iter().filter(|version| version.served);
+        served()
+            .find(|version| version.storage)
+            .or_else(|| served().next())
+    }
+}
+
+impl PrinterColumn {
+    /// The column's cell for `object`, "<none>" if the path matches nothing. Dates
+    /// are shown as ages, as kubectl does.
+    pub fn cell(&self, object: &Value, now: DateTime<Utc>) -> String {
+        match json_path(object, &self.json_path) {
+            Some(Value::String(date)) if self.type_field == "date" => age(date, now),
+            Some(Value::String(value)) => value.clone(),
+            Some(Value::Null) | None => "<none>".to_string(),
+            Some(value) => value.to_string(),
+        }
+    }
+}
+
+// Evaluates the subset of JSONPath printer columns use in practice: fields
+// (`.status.phase`), array indexes (`.spec.ports[0]`), and equality filters that pick
+// the first matching element (`.status.conditions[?(@.type=="Ready")].status`)
+fn json_path<'a>(object: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut value = object;
+    let mut rest = path.trim();
+    while !rest.is_empty() {
+        if let Some(filter) = rest.strip_prefix("[?(@.") {
+            let (condition, after) = filter.split_once(")]")?;
+            let (field, expected) = condition.split_once("==")?;
+            let expected = expected.trim().trim_matches(|c| c == '"' || c == '\'');
+            value = value
+                .as_array()?
+                .iter()
+                .find(|item| item.get(field.trim()).and_then(Value::as_str) == Some(expected))?;
+            rest = after;
+        } else if let Some(index) = rest.strip_prefix('[') {
+            let (index, after) = index.split_once(']')?;
+            value = value.get(index.trim().parse::<usize>().ok()?)?;
+            rest = after;
+        } else if let Some(field) = rest.strip_prefix('.') {
+            let end = field.find(['.', '[']).unwrap_or(field.len());
+            value = value.get(&field[..end])?;
+            rest = &field[end..];
+        } else {
+            return None;
+        }
+    }
+    Some(value)
+}
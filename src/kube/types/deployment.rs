@@ -0,0 +1,178 @@
+use super::network::LabelSelector;
+use super::pod::{ContainerSpec, OwnerReference, PodSpec};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Scale subresource (/apis/apps/v1/namespaces/{namespace}/deployments/{name}/scale)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Scale {
+    pub spec: ScaleSpec,
+    pub status: Option<ScaleStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScaleSpec {
+    pub replicas: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScaleStatus {
+    pub replicas: u32,
+}
+
+// Deployment (/apis/apps/v1/namespaces/{namespace}/deployments/{name}), only the fields
+// the inventory and rollout check report
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Deployment {
+    pub metadata: DeploymentMetadata,
+    pub spec: Option<DeploymentSpec>,
+    pub status: Option<DeploymentStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeploymentMetadata {
+    pub name: String,
+    pub namespace: String,
+    pub generation: Option<i64>,
+    pub annotations: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeploymentSpec {
+    pub replicas: Option<u32>,
+    pub selector: Option<LabelSelector>,
+    pub template: Option<PodTemplateSpec>,
+    pub paused: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodTemplateSpec {
+    pub spec: Option<PodSpec>,
+}
+
+impl PodTemplateSpec {
+    /// Container images, e.g. "web=ghcr.io/acme/web:1.4.2, proxy=envoy:v1.30".
+    pub fn images(&self) -> String {
+        match &self.spec {
+            Some(spec) => container_images(&spec.containers),
+            None => "<none>".to_string(),
+        }
+    }
+}
+
+/// Container images of a pod spec, e.g. "web=ghcr.io/acme/web:1.4.2".
+pub fn container_images(containers: &[ContainerSpec]) -> String {
+    containers
+        .iter()
+        .map(|container| {
+            format!(
+                "{}={}",
+                container.name,
+                container.image.as_deref().unwrap_or("<none>")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeploymentStatus {
+    #[serde(rename = "observedGeneration")]
+    pub observed_generation: Option<i64>,
+    pub replicas: Option<u32>,
+    #[serde(rename = "updatedReplicas")]
+    pub updated_replicas: Option<u32>,
+    #[serde(rename = "readyReplicas")]
+    pub ready_replicas: Option<u32>,
+    #[serde(rename = "availableReplicas")]
+    pub available_replicas: Option<u32>,
+    pub conditions: Option<Vec<DeploymentCondition>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeploymentCondition {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+// ReplicaSet list (/apis/apps/v1/namespaces/{namespace}/replicasets)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicaSet {
+    pub metadata: ReplicaSetMetadata,
+    pub spec: Option<ReplicaSetSpec>,
+    pub status: Option<ReplicaSetStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicaSetMetadata {
+    pub name: String,
+    #[serde(rename = "creationTimestamp")]
+    pub creation_timestamp: String,
+    pub labels: Option<HashMap<String, String>>,
+    pub annotations: Option<HashMap<String, String>>,
+    #[serde(rename = "ownerReferences")]
+    pub owner_references: Option<Vec<OwnerReference>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicaSetSpec {
+    pub replicas: Option<u32>,
+    pub template: Option<PodTemplateSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicaSetStatus {
+    pub replicas: Option<u32>,
+    #[serde(rename = "readyReplicas")]
+    pub ready_replicas: Option<u32>,
+}
+
+impl ReplicaSet {
+    /// The `deployment.kubernetes.io/revision` annotation the Deployment controller
+    /// numbers its ReplicaSets with; the highest is the current template.
+    pub fn revision(&self) -> Option<u64> {
+        revision(self.metadata.annotations.as_ref())
+    }
+
+    /// Hash of the pod template, also set as a label on the ReplicaSet's pods.
+    pub fn template_hash(&self) -> Option<&str> {
+        self.metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(POD_TEMPLATE_HASH))
+            .map(String::as_str)
+    }
+
+    /// Whether `deployment` controls this ReplicaSet.
+    pub fn is_owned_by(&self, deployment: &str) -> bool {
+        self.metadata
+            .owner_references
+            .iter()
+            .flatten()
+            .any(|owner| {
+                owner.kind == "Deployment"
+                    && owner.name == deployment
+                    && owner.controller.unwrap_or(false)
+            })
+    }
+}
+
+impl Deployment {
+    /// Revision of the Deployment's current pod template.
+    pub fn revision(&self) -> Option<u64> {
+        revision(self.metadata.annotations.as_ref())
+    }
+}
+
+/// Label the Deployment controller puts on ReplicaSets and their pods.
+pub const POD_TEMPLATE_HASH: &str = "pod-template-hash";
+
+fn revision(annotations: Option<&HashMap<String, String>>) -> Option<u64> {
+    annotations?
+        .get("deployment.kubernetes.io/revision")?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,63 @@
+use super::network::LabelSelector;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// PodDisruptionBudget list (/apis/policy/v1/poddisruptionbudgets)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodDisruptionBudget {
+    pub metadata: PodDisruptionBudgetMetadata,
+    pub spec: Option<PodDisruptionBudgetSpec>,
+    pub status: Option<PodDisruptionBudgetStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodDisruptionBudgetMetadata {
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodDisruptionBudgetSpec {
+    pub selector: Option<LabelSelector>,
+    #[serde(rename = "minAvailable")]
+    pub min_available: Option<Value>, // number or percentage
+    #[serde(rename = "maxUnavailable")]
+    pub max_unavailable: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodDisruptionBudgetStatus {
+    #[serde(rename = "disruptionsAllowed")]
+    pub disruptions_allowed: Option<u32>,
+    #[serde(rename = "currentHealthy")]
+    pub current_healthy: Option<u32>,
+    #[serde(rename = "desiredHealthy")]
+    pub desired_healthy: Option<u32>,
+}
+
+impl PodDisruptionBudget {
+    /// The budget as written, e.g. "minAvailable 2" or "maxUnavailable 25%".
+    pub fn budget(&self) -> String {
+        let spec = self.spec.as_ref();
+        let value = |value: &Value| match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        if let Some(min) = spec.and_then(|spec| spec.min_available.as_ref()) {
+            format!("minAvailable {}", value(min))
+        } else if let Some(max) = spec.and_then(|spec| spec.max_unavailable.as_ref()) {
+            format!("maxUnavailable {}", value(max))
+        } else {
+            "no budget".to_string()
+        }
+    }
+
+    /// Evictions the budget allows right now; zero until the controller has computed
+    /// its status.
+    pub fn disruptions_allowed(&self) -> u32 {
+        self.status
+            .as_ref()
+            .and_then(|status| status.disruptions_allowed)
+            .unwrap_or(0)
+    }
+}
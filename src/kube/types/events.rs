@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+// Event API Response (/api/v1/namespaces/{namespace}/events)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventListResponse {
+    pub items: Vec<Event>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub metadata: Option<EventMetadata>,
+    #[serde(rename = "involvedObject")]
+    pub involved_object: Option<ObjectReference>,
+    #[serde(rename = "type")]
+    pub type_field: Option<String>, // "Normal" or "Warning"
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub count: Option<u32>,
+    #[serde(rename = "firstTimestamp")]
+    pub first_timestamp: Option<String>,
+    #[serde(rename = "lastTimestamp")]
+    pub last_timestamp: Option<String>,
+    #[serde(rename = "eventTime")]
+    pub event_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventMetadata {
+    pub name: String,
+    pub namespace: Option<String>,
+}
+
+// The object an event is about
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObjectReference {
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    pub namespace: Option<String>,
+}
+
+impl Event {
+    /// One-line summary, e.g. "2024-05-01T10:00:00Z Warning BackOff (x12): Back-off
+    /// restarting failed container".
+    pub fn as_line(&self) -> String {
+        format!(
+            "{} {} {} (x{}): {}",
+            self.timestamp(),
+            self.type_field.as_deref().unwrap_or("Normal"),
+            self.reason.as_deref().unwrap_or("Unknown"),
+            self.count.unwrap_or(1),
+            self.message.as_deref().unwrap_or("").trim()
+        )
+    }
+
+    /// Most recent time the event was observed, for ordering.
+    pub fn timestamp(&self) -> &str {
+        self.last_timestamp
+            .as_deref()
+            .or(self.event_time.as_deref())
+            .or(self.first_timestamp.as_deref())
+            .unwrap_or("")
+    }
+}
@@ -0,0 +1,43 @@
+use serde::Deserialize;
+
+// One page of any Kubernetes list response
+#[derive(Debug, Deserialize)]
+pub struct ListPage<T> {
+    #[serde(default = "Vec::new")]
+    pub items: Vec<T>,
+    pub metadata: Option<ListMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListMetadata {
+    // Opaque token for fetching the next page; empty or missing on the last page
+    #[serde(rename = "continue")]
+    pub continue_token: Option<String>,
+    // Version of the collection, used as the starting point of a watch
+    #[serde(rename = "resourceVersion")]
+    pub resource_version: Option<String>,
+}
+
+/// Items collected across every page of a listing.
+#[derive(Debug)]
+pub struct Listing<T> {
+    pub items: Vec<T>,
+    /// Whether the listing stopped at the item cap before reaching the last page
+    pub truncated: bool,
+    /// Collection resourceVersion reported with the last page
+    pub resource_version: Option<String>,
+}
+
+impl<T> Listing<T> {
+    /// Note appended to tool output when the listing was cut short.
+    pub fn truncation_note(&self) -> String {
+        if self.truncated {
+            format!(
+                "\n(Results truncated after {} items; narrow the query to see more.)\n",
+                self.items.len()
+            )
+        } else {
+            String::new()
+        }
+    }
+}
@@ -52,24 +52,48 @@ pub struct NodeUsage {
     pub memory: String, // e.g., "1879200Ki"
 }
 
+// PodMetrics API Response (/apis/metrics.k8s.io/v1beta1/namespaces/{namespace}/pods/{name})
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodMetrics {
+    pub containers: Vec<ContainerMetrics>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerMetrics {
+    pub name: String,
+    pub usage: NodeUsage, // same cpu/memory quantity format as node usage
+}
+
 // Combined struct with calculated percentages
+//
+// Usage fields are omitted when the cluster has no metrics API, leaving capacity only.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NodeMetricsInfo {
     pub name: String,
-    pub cpu_cores: f64,      // CPU usage in cores (e.g., 0.161)
-    pub cpu_percent: f64,    // CPU usage percentage
-    pub memory_bytes: u64,   // Memory usage in bytes
-    pub memory_percent: f64, // Memory usage percentage
+    pub cpu_capacity_cores: f64,    // CPU capacity in cores
+    pub memory_capacity_bytes: u64, // Memory capacity in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_cores: Option<f64>, // CPU usage in cores (e.g., 0.161)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<f64>, // CPU usage percentage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>, // Memory usage in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_percent: Option<f64>, // Memory usage percentage
 }
 
 // Combined response with node metrics and usage percentages
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NodeMetricsWithUsageResponse {
+    /// Explains why usage is missing, e.g. when metrics-server isn't installed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
     pub items: Vec<NodeMetricsInfo>,
 }
 
 impl NodeMetricsInfo {
-    pub fn from_node_and_metrics(node: &Node, metrics: &NodeMetrics) -> Result<Self, String> {
+    /// Capacity without usage, for clusters without a metrics API.
+    pub fn capacity_only(node: &Node) -> Result<Self, String> {
         // Parse CPU capacity (e.g., "2" cores)
         let cpu_capacity: f64 =
             node.status.capacity.cpu.parse().map_err(|_| {
@@ -79,6 +103,20 @@ impl NodeMetricsInfo {
         // Parse memory capacity (e.g., "6026268Ki")
         let memory_capacity_ki = parse_memory_ki(&node.status.capacity.memory)?;
 
+        Ok(NodeMetricsInfo {
+            name: node.metadata.name.clone(),
+            cpu_capacity_cores: cpu_capacity,
+            memory_capacity_bytes: memory_capacity_ki * 1024,
+            cpu_cores: None,
+            cpu_percent: None,
+            memory_bytes: None,
+            memory_percent: None,
+        })
+    }
+
+    pub fn from_node_and_metrics(node: &Node, metrics: &NodeMetrics) -> Result<Self, String> {
+        let capacity = Self::capacity_only(node)?;
+
         // Parse CPU usage from nanoseconds (e.g., "160635734n")
         let cpu_usage_cores = parse_cpu_nanoseconds(&metrics.usage.cpu)?;
 
@@ -86,15 +124,17 @@ impl NodeMetricsInfo {
         let memory_usage_ki = parse_memory_ki(&metrics.usage.memory)?;
 
         // Calculate percentages
-        let cpu_percent = (cpu_usage_cores / cpu_capacity) * 100.0;
-        let memory_percent = (memory_usage_ki as f64 / memory_capacity_ki as f64) * 100.0;
+        let memory_usage_bytes = memory_usage_ki * 1024; // Convert Ki to bytes
+        let cpu_percent = (cpu_usage_cores / capacity.cpu_capacity_cores) * 100.0;
+        let memory_percent =
+            (memory_usage_bytes as f64 / capacity.memory_capacity_bytes as f64) * 100.0;
 
         Ok(NodeMetricsInfo {
-            name: node.metadata.name.clone(),
-            cpu_cores: cpu_usage_cores,
-            cpu_percent,
-            memory_bytes: memory_usage_ki * 1024, // Convert Ki to bytes
-            memory_percent,
+            cpu_cores: Some(cpu_usage_cores),
+            cpu_percent: Some(cpu_percent),
+            memory_bytes: Some(memory_usage_bytes),
+            memory_percent: Some(memory_percent),
+            ..capacity
         })
     }
 }
@@ -144,10 +184,30 @@ impl NodeMetricsListResponse {
                 })?;
 
             let info = NodeMetricsInfo::from_node_and_metrics(node, metrics)
-                .map_err(|e| KubeAgentError::ParseError(e))?;
+                .map_err(KubeAgentError::ParseError)?;
             items.push(info);
         }
 
-        Ok(NodeMetricsWithUsageResponse { items })
+        Ok(NodeMetricsWithUsageResponse { note: None, items })
+    }
+}
+
+impl NodeListResponse {
+    /// Node capacities with a note explaining that usage is unavailable.
+    pub fn capacity_only(
+        &self,
+        note: String,
+    ) -> Result<NodeMetricsWithUsageResponse, KubeAgentError> {
+        let items = self
+            .items
+            .iter()
+            .map(NodeMetricsInfo::capacity_only)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(KubeAgentError::ParseError)?;
+
+        Ok(NodeMetricsWithUsageResponse {
+            note: Some(note),
+            items,
+        })
     }
 }
@@ -1,7 +1,43 @@
+pub mod access;
+pub mod certificate;
+pub mod configmap;
+pub mod crd;
+pub mod deployment;
+pub mod disruption;
+pub mod events;
+pub mod list;
 pub mod metrics;
 pub mod namespaces;
+pub mod network;
+pub mod node;
 pub mod pod;
+pub mod quantity;
+pub mod quota;
+pub mod rbac;
+pub mod secret;
+pub mod status;
+pub mod storage;
+pub mod table;
+pub mod watch;
 
-pub use metrics::{NodeListResponse, NodeMetricsListResponse, NodeMetricsWithUsageResponse};
-pub use namespaces::NamespaceListResponse;
-pub use pod::PodListResponse;
+pub use access::SelfSubjectAccessReview;
+pub use certificate::TlsSecretListResponse;
+pub use configmap::{ConfigMap, ConfigMapListResponse};
+pub use crd::{CrdVersion, CustomResourceDefinition};
+pub use deployment::{Deployment, ReplicaSet, Scale};
+pub use disruption::PodDisruptionBudget;
+pub use events::{Event, EventListResponse};
+pub use list::{ListPage, Listing};
+pub use metrics::{
+    NodeListResponse, NodeMetricsListResponse, NodeMetricsWithUsageResponse, PodMetrics,
+};
+pub use namespaces::{NamespaceItem, NamespaceListResponse};
+pub use network::NetworkPolicyListResponse;
+pub use node::{NodeDetails, NodeInfoListResponse};
+pub use pod::{Pod, PodListResponse};
+pub use quota::{LimitRangeListResponse, ResourceQuotaListResponse};
+pub use rbac::{Role, RoleBinding, SelfSubjectReview, ServiceAccount, Subject};
+pub use secret::SecretListResponse;
+pub use status::Status;
+pub use storage::{PersistentVolumeClaimListResponse, PersistentVolumeListResponse};
+pub use watch::{RawWatchEvent, WatchEvent};
@@ -1,18 +1,18 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
-struct NamespaceMetadata {
-    name: String,
+pub struct NamespaceMetadata {
+    pub name: String,
 }
 
 #[derive(Serialize, Deserialize)]
-struct NamespaceItem {
-    metadata: NamespaceMetadata,
+pub struct NamespaceItem {
+    pub metadata: NamespaceMetadata,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct NamespaceListResponse {
-    items: Vec<NamespaceItem>,
+    pub items: Vec<NamespaceItem>,
 }
 
 impl NamespaceListResponse {
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+// NetworkPolicy API Response (/apis/networking.k8s.io/v1/namespaces/{namespace}/networkpolicies)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicyListResponse {
+    pub items: Vec<NetworkPolicy>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicy {
+    pub metadata: NetworkPolicyMetadata,
+    pub spec: NetworkPolicySpec,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicyMetadata {
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicySpec {
+    #[serde(rename = "podSelector", default)]
+    pub pod_selector: LabelSelector,
+    #[serde(rename = "policyTypes")]
+    pub policy_types: Option<Vec<String>>, // "Ingress" and/or "Egress"
+    pub ingress: Option<Vec<NetworkPolicyRule>>,
+    pub egress: Option<Vec<NetworkPolicyRule>>,
+}
+
+// Ingress rules list peers under `from`, egress rules under `to`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicyRule {
+    #[serde(alias = "to")]
+    pub from: Option<Vec<NetworkPolicyPeer>>,
+    pub ports: Option<Vec<NetworkPolicyPort>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicyPeer {
+    #[serde(rename = "podSelector")]
+    pub pod_selector: Option<LabelSelector>,
+    #[serde(rename = "namespaceSelector")]
+    pub namespace_selector: Option<LabelSelector>,
+    #[serde(rename = "ipBlock")]
+    pub ip_block: Option<IpBlock>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IpBlock {
+    pub cidr: String,
+    pub except: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkPolicyPort {
+    pub protocol: Option<String>,
+    pub port: Option<Value>, // number or named port
+    #[serde(rename = "endPort")]
+    pub end_port: Option<u32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LabelSelector {
+    #[serde(rename = "matchLabels")]
+    pub match_labels: Option<BTreeMap<String, String>>,
+    #[serde(rename = "matchExpressions")]
+    pub match_expressions: Option<Vec<LabelSelectorRequirement>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelSelectorRequirement {
+    pub key: String,
+    pub operator: String, // "In", "NotIn", "Exists" or "DoesNotExist"
+    pub values: Option<Vec<String>>,
+}
+
+impl LabelSelector {
+    /// e.g. "app=web, tier In (frontend, edge)", or `None` for the empty selector,
+    /// which matches everything.
+    pub fn describe(&self) -> Option<String> {
+        let labels = self
+            .match_labels
+            .iter()
+            .flatten()
+            .map(|(key, value)| format!("{}={}", key, value));
+        let expressions = self.match_expressions.iter().flatten().map(|expr| {
+            match expr.values.as_ref().filter(|values| !values.is_empty()) {
+                Some(values) => format!("{} {} ({})", expr.key, expr.operator, values.join(", ")),
+                None => format!("{} {}", expr.key, expr.operator),
+            }
+        });
+        let terms = labels.chain(expressions).collect::<Vec<_>>();
+        (!terms.is_empty()).then(|| terms.join(", "))
+    }
+
+    /// The `matchLabels` as a `labelSelector` query value, e.g. "app=web,tier=frontend".
+    /// Expressions are left out, so the query may select more than the selector does.
+    pub fn as_query(&self) -> String {
+        self.match_labels
+            .iter()
+            .flatten()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Whether an object with `labels` is selected; the empty selector selects every
+    /// object.
+    pub fn matches(&self, labels: Option<&HashMap<String, String>>) -> bool {
+        let value = |key: &str| labels.and_then(|labels| labels.get(key));
+        let labels_match = self
+            .match_labels
+            .iter()
+            .flatten()
+            .all(|(key, wanted)| value(key) == Some(wanted));
+        let expressions_match = self.match_expressions.iter().flatten().all(|expr| {
+            let values = expr.values.as_deref().unwrap_or_default();
+            match expr.operator.as_str() {
+                "In" => value(&expr.key).is_some_and(|value| values.contains(value)),
+                "NotIn" => !value(&expr.key).is_some_and(|value| values.contains(value)),
+                "Exists" => value(&expr.key).is_some(),
+                "DoesNotExist" => value(&expr.key).is_none(),
+                _ => false,
+            }
+        });
+        labels_match && expressions_match
+    }
+}
+
+impl NetworkPolicyPeer {
+    // e.g. "pods app=web in namespaces team=a", "all pods in this namespace", "10.0.0.0/8"
+    fn describe(&self) -> String {
+        if let Some(block) = &self.ip_block {
+            return match block.except.as_ref().filter(|except| !except.is_empty()) {
+                Some(except) => format!("IP block {} except {}", block.cidr, except.join(", ")),
+                None => format!("IP block {}", block.cidr),
+            };
+        }
+
+        let pods = match self.pod_selector.as_ref().and_then(LabelSelector::describe) {
+            Some(selector) => format!("pods {}", selector),
+            None => "all pods".to_string(),
+        };
+        let namespaces = match &self.namespace_selector {
+            None => "in this namespace".to_string(),
+            Some(selector) => match selector.describe() {
+                Some(selector) => format!("in namespaces {}", selector),
+                None => "in any namespace".to_string(),
+            },
+        };
+        format!("{} {}", pods, namespaces)
+    }
+}
+
+impl NetworkPolicyRule {
+    // e.g. "pods app=web in this namespace on TCP/8080"
+    fn describe(&self) -> String {
+        let peers = match self.from.as_ref().filter(|peers| !peers.is_empty()) {
+            Some(peers) => peers
+                .iter()
+                .map(NetworkPolicyPeer::describe)
+                .collect::<Vec<_>>()
+                .join("; or "),
+            None => "anywhere".to_string(),
+        };
+        let ports = match self.ports.as_ref().filter(|ports| !ports.is_empty()) {
+            Some(ports) => ports
+                .iter()
+                .map(|port| {
+                    let number = match &port.port {
+                        Some(Value::String(name)) => name.clone(),
+                        Some(value) => value.to_string(),
+                        None => "all".to_string(),
+                    };
+                    let range = match port.end_port {
+                        Some(end) => format!("{}-{}", number, end),
+                        None => number,
+                    };
+                    format!("{}/{}", port.protocol.as_deref().unwrap_or("TCP"), range)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => "all ports".to_string(),
+        };
+        format!("{} on {}", peers, ports)
+    }
+}
+
+impl NetworkPolicy {
+    /// Summarizes which pods the policy selects and what traffic it allows.
+    ///
+    /// Traffic in a direction the policy restricts is denied unless some rule (of this
+    /// or another policy selecting the same pods) allows it.
+    pub fn as_string(&self) -> String {
+        let spec = &self.spec;
+        let selected = match spec.pod_selector.describe() {
+            Some(selector) => format!("pods {}", selector),
+            None => "all pods in the namespace".to_string(),
+        };
+        let mut output = format!(
+            "  NetworkPolicy {} (applies to {}):\n",
+            self.metadata.name, selected
+        );
+
+        // Without policyTypes, Ingress is always restricted and Egress only if rules exist
+        let restricts = |direction: &str| match &spec.policy_types {
+            Some(types) => types.iter().any(|t| t == direction),
+            None => direction == "Ingress" || spec.egress.is_some(),
+        };
+        for (direction, rules, preposition) in [
+            ("Ingress", &spec.ingress, "from"),
+            ("Egress", &spec.egress, "to"),
+        ] {
+            if !restricts(direction) {
+                output.push_str(&format!(
+                    "    {}: not restricted by this policy\n",
+                    direction
+                ));
+                continue;
+            }
+            match rules.as_ref().filter(|rules| !rules.is_empty()) {
+                Some(rules) => {
+                    output.push_str(&format!("    {} allowed:\n", direction));
+                    for rule in rules {
+                        output.push_str(&format!("      - {} {}\n", preposition, rule.describe()));
+                    }
+                }
+                None => output.push_str(&format!("    {}: all traffic denied\n", direction)),
+            }
+        }
+
+        output
+    }
+}
+
+impl NetworkPolicyListResponse {
+    /// Groups the policies by namespace.
+    pub fn as_string(&self) -> String {
+        if self.items.is_empty() {
+            return "No NetworkPolicies found; all pod-to-pod traffic is allowed.".to_string();
+        }
+
+        let mut by_namespace: BTreeMap<&str, Vec<&NetworkPolicy>> = BTreeMap::new();
+        for policy in &self.items {
+            by_namespace
+                .entry(policy.metadata.namespace.as_str())
+                .or_default()
+                .push(policy);
+        }
+
+        let mut output = String::new();
+        for (namespace, policies) in by_namespace {
+            output.push_str(&format!("Namespace {}:\n", namespace));
+            for policy in policies {
+                output.push_str(&policy.as_string());
+            }
+            output.push('\n');
+        }
+        output.push_str("Pods not selected by any policy accept and send all traffic. Traffic is allowed if any policy selecting the pod allows it.\n");
+        output
+    }
+}
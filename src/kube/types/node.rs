@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Detailed Node API Response (/api/v1/nodes)
+//
+// Separate from the minimal `NodeListResponse` used for metrics so that node metrics
+// keep working even if a node omits one of the optional fields below.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeInfoListResponse {
+    pub items: Vec<NodeDetails>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeDetails {
+    pub metadata: NodeDetailsMetadata,
+    pub spec: Option<NodeSpec>,
+    pub status: Option<NodeDetailsStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeDetailsMetadata {
+    pub name: String,
+    #[serde(rename = "creationTimestamp")]
+    pub creation_timestamp: Option<String>,
+    pub labels: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeSpec {
+    pub taints: Option<Vec<Taint>>,
+    pub unschedulable: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Taint {
+    pub key: String,
+    pub value: Option<String>,
+    pub effect: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeDetailsStatus {
+    pub capacity: Option<BTreeMap<String, String>>,
+    pub allocatable: Option<BTreeMap<String, String>>,
+    pub conditions: Option<Vec<NodeCondition>>,
+    #[serde(rename = "nodeInfo")]
+    pub node_info: Option<NodeSystemInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeCondition {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeSystemInfo {
+    #[serde(rename = "kubeletVersion")]
+    pub kubelet_version: String,
+    #[serde(rename = "osImage")]
+    pub os_image: String,
+    #[serde(rename = "kernelVersion")]
+    pub kernel_version: String,
+    #[serde(rename = "containerRuntimeVersion")]
+    pub container_runtime_version: String,
+    pub architecture: String,
+}
+
+impl NodeDetails {
+    /// Formats the node for troubleshooting: conditions, taints, versions, and resources.
+    pub fn as_string(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("Node: {}\n", self.metadata.name));
+        if let Some(created) = &self.metadata.creation_timestamp {
+            output.push_str(&format!("  Created: {}\n", created));
+        }
+
+        let spec = self.spec.as_ref();
+        if spec.and_then(|spec| spec.unschedulable).unwrap_or(false) {
+            output.push_str("  Schedulable: false (cordoned)\n");
+        }
+
+        let status = self.status.as_ref();
+        if let Some(conditions) = status.and_then(|status| status.conditions.as_ref()) {
+            output.push_str("  Conditions:\n");
+            for condition in conditions {
+                output.push_str(&format!(
+                    "    {}: {}",
+                    condition.type_field, condition.status
+                ));
+                // Only unhealthy conditions carry a useful reason
+                if condition.is_unhealthy() {
+                    if let Some(reason) = &condition.reason {
+                        output.push_str(&format!(" ({})", reason));
+                    }
+                    if let Some(message) = &condition.message {
+                        output.push_str(&format!(" - {}", message.trim()));
+                    }
+                }
+                output.push('\n');
+            }
+        }
+
+        match spec.and_then(|spec| spec.taints.as_ref()) {
+            Some(taints) if !taints.is_empty() => {
+                output.push_str("  Taints:\n");
+                for taint in taints {
+                    let key = match &taint.value {
+                        Some(value) => format!("{}={}", taint.key, value),
+                        None => taint.key.clone(),
+                    };
+                    output.push_str(&format!("    {}:{}\n", key, taint.effect));
+                }
+            }
+            _ => output.push_str("  Taints: <none>\n"),
+        }
+
+        if let Some(info) = status.and_then(|status| status.node_info.as_ref()) {
+            output.push_str("  System Info:\n");
+            output.push_str(&format!("    Kubelet: {}\n", info.kubelet_version));
+            output.push_str(&format!("    OS Image: {}\n", info.os_image));
+            output.push_str(&format!("    Kernel: {}\n", info.kernel_version));
+            output.push_str(&format!(
+                "    Container Runtime: {}\n",
+                info.container_runtime_version
+            ));
+            output.push_str(&format!("    Architecture: {}\n", info.architecture));
+        }
+
+        let capacity = status.and_then(|status| status.capacity.as_ref());
+        let allocatable = status.and_then(|status| status.allocatable.as_ref());
+        if let Some(capacity) = capacity {
+            output.push_str("  Resources (allocatable / capacity):\n");
+            for resource in ["cpu", "memory", "pods", "ephemeral-storage"] {
+                let Some(total) = capacity.get(resource) else {
+                    continue;
+                };
+                let available = allocatable
+                    .and_then(|allocatable| allocatable.get(resource))
+                    .map(String::as_str)
+                    .unwrap_or("N/A");
+                output.push_str(&format!("    {}: {} / {}\n", resource, available, total));
+            }
+        }
+
+        if let Some(labels) = &self.metadata.labels {
+            output.push_str("  Labels:\n");
+            for (key, value) in labels {
+                output.push_str(&format!("    {}: {}\n", key, value));
+            }
+        }
+
+        output
+    }
+}
+
+impl NodeCondition {
+    /// Ready is healthy when True; pressure conditions are healthy when False.
+    pub fn is_unhealthy(&self) -> bool {
+        if self.type_field == "Ready" {
+            self.status != "True"
+        } else {
+            self.status != "False"
+        }
+    }
+}
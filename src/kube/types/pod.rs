@@ -1,4 +1,6 @@
+use super::table::{age, Table};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PodMetadata {
@@ -8,11 +10,138 @@ pub struct PodMetadata {
     #[serde(rename = "creationTimestamp")]
     pub creation_timestamp: String,
     pub labels: Option<std::collections::HashMap<String, String>>,
+    pub annotations: Option<std::collections::HashMap<String, String>>,
+    #[serde(rename = "ownerReferences")]
+    pub owner_references: Option<Vec<OwnerReference>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OwnerReference {
+    pub kind: String,
+    pub name: String,
+    pub controller: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ContainerSpec {
     pub name: String,
+    pub image: Option<String>,
+    pub resources: Option<ContainerResources>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerResources {
+    pub requests: Option<BTreeMap<String, String>>,
+    pub limits: Option<BTreeMap<String, String>>,
+}
+
+impl ContainerSpec {
+    /// Requests and limits, e.g. "requests cpu=100m, memory=128Mi; limits memory=256Mi".
+    ///
+    /// Missing values are spelled out ("no limits") so unbounded containers stand out.
+    pub fn resources_summary(&self) -> String {
+        let resources = self.resources.as_ref();
+        format!(
+            "{}; {}",
+            resource_list("requests", resources.and_then(|r| r.requests.as_ref())),
+            resource_list("limits", resources.and_then(|r| r.limits.as_ref()))
+        )
+    }
+}
+
+// e.g. "limits cpu=500m, memory=256Mi", or "no limits"
+fn resource_list(label: &str, values: Option<&BTreeMap<String, String>>) -> String {
+    match values.filter(|values| !values.is_empty()) {
+        Some(values) => format!(
+            "{} {}",
+            label,
+            values
+                .iter()
+                .map(|(resource, value)| format!("{}={}", resource, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => format!("no {}", label),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerStatus {
+    pub name: String,
+    pub ready: bool,
+    #[serde(rename = "restartCount")]
+    pub restart_count: u32,
+    pub state: Option<ContainerState>,
+    #[serde(rename = "lastState")]
+    pub last_state: Option<ContainerState>,
+}
+
+// Only one of the fields is set, depending on the container's state
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerState {
+    pub waiting: Option<ContainerStateDetail>,
+    pub running: Option<ContainerStateDetail>,
+    pub terminated: Option<ContainerStateDetail>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerStateDetail {
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<i32>,
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<String>,
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<String>,
+}
+
+impl ContainerState {
+    /// Short human-readable description, e.g. "Waiting (CrashLoopBackOff)".
+    pub fn describe(&self) -> String {
+        if let Some(waiting) = &self.waiting {
+            match &waiting.reason {
+                Some(reason) => format!("Waiting ({})", reason),
+                None => "Waiting".to_string(),
+            }
+        } else if let Some(running) = &self.running {
+            match &running.started_at {
+                Some(started_at) => format!("Running since {}", started_at),
+                None => "Running".to_string(),
+            }
+        } else if let Some(terminated) = &self.terminated {
+            format!(
+                "Terminated ({}, exit code {})",
+                terminated.reason.as_deref().unwrap_or("unknown reason"),
+                terminated
+                    .exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "N/A".to_string())
+            )
+        } else {
+            "Unknown".to_string()
+        }
+    }
+}
+
+impl ContainerStatus {
+    /// One-line status, e.g. "not ready, 4 restarts, Waiting (CrashLoopBackOff),
+    /// last Terminated (OOMKilled, exit code 137)".
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{}, {} restarts",
+            if self.ready { "ready" } else { "not ready" },
+            self.restart_count
+        );
+        if let Some(state) = &self.state {
+            summary.push_str(&format!(", {}", state.describe()));
+        }
+        // The previous run only matters once it has ended, e.g. why a container restarted
+        if let Some(last_state) = self.last_state.as_ref().filter(|s| s.terminated.is_some()) {
+            summary.push_str(&format!(", last {}", last_state.describe()));
+        }
+        summary
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +149,8 @@ pub struct PodCondition {
     #[serde(rename = "type")]
     pub type_field: String,
     pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
     #[serde(rename = "lastProbeTime")]
     pub last_probe_time: Option<String>,
     #[serde(rename = "lastTransitionTime")]
@@ -29,9 +160,13 @@ pub struct PodCondition {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PodSpecStatus {
     pub phase: String,
+    pub reason: Option<String>, // e.g. "Evicted"
+    pub message: Option<String>,
     pub conditions: Option<Vec<PodCondition>>,
     #[serde(rename = "startTime")]
     pub start_time: Option<String>,
+    #[serde(rename = "containerStatuses")]
+    pub container_statuses: Option<Vec<ContainerStatus>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +174,15 @@ pub struct PodSpec {
     pub containers: Vec<ContainerSpec>,
     #[serde(rename = "nodeName")]
     pub node_name: Option<String>,
+    pub volumes: Option<Vec<Volume>>,
+}
+
+// Only whether the volume is an emptyDir, whose data is lost when the pod is evicted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Volume {
+    pub name: String,
+    #[serde(rename = "emptyDir")]
+    pub empty_dir: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,60 +192,223 @@ pub struct Pod {
     pub status: Option<PodSpecStatus>,
 }
 
+impl Pod {
+    /// Total restarts across the pod's containers.
+    pub fn restart_count(&self) -> u32 {
+        self.status
+            .as_ref()
+            .and_then(|status| status.container_statuses.as_ref())
+            .map(|statuses| statuses.iter().map(|s| s.restart_count).sum())
+            .unwrap_or(0)
+    }
+
+    /// The workload managing this pod, e.g. "Deployment/web" or "StatefulSet/db", or
+    /// `None` for a standalone pod.
+    ///
+    /// Deployments own pods through a ReplicaSet named "<deployment>-<pod-template-hash>",
+    /// so that hash is stripped to attribute the pod to the Deployment itself.
+    pub fn workload(&self) -> Option<String> {
+        let owners = self.metadata.owner_references.as_ref()?;
+        let owner = owners
+            .iter()
+            .find(|owner| owner.controller.unwrap_or(false))
+            .or(owners.first())?;
+
+        if owner.kind == "ReplicaSet" {
+            let hash = self
+                .metadata
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get("pod-template-hash"));
+            if let Some(deployment) =
+                hash.and_then(|hash| owner.name.strip_suffix(&format!("-{}", hash)))
+            {
+                return Some(format!("Deployment/{}", deployment));
+            }
+        }
+        Some(format!("{}/{}", owner.kind, owner.name))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PodListResponse {
     pub items: Vec<Pod>,
 }
 
 impl PodListResponse {
+    /// kubectl-style table with one line per pod, followed by the containers that
+    /// are not ready or have restarted, with their state and resources.
     pub fn as_string(&self) -> String {
-        let mut output = String::new();
-        output.push_str(&format!("Found {} pods:\n\n", self.items.len()));
-
-        for (idx, pod) in self.items.iter().enumerate() {
-            output.push_str(&format!("Pod {}:\n", idx + 1));
-            output.push_str(&format!("  Name: {}\n", pod.metadata.name));
-            output.push_str(&format!("  Namespace: {}\n", pod.metadata.namespace));
-            output.push_str(&format!("  UID: {}\n", pod.metadata.uid));
-            output.push_str(&format!("  Created: {}\n", pod.metadata.creation_timestamp));
-
-            if let Some(labels) = &pod.metadata.labels {
-                output.push_str("  Labels:\n");
-                for (key, value) in labels {
-                    output.push_str(&format!("    {}: {}\n", key, value));
+        let now = chrono::Utc::now();
+        let mut table = Table::new(&[
+            "NAME", "READY", "STATUS", "RESTARTS", "AGE", "NODE", "OWNER",
+        ]);
+        let mut details = String::new();
+
+        for pod in &self.items {
+            let statuses = pod
+                .status
+                .as_ref()
+                .and_then(|status| status.container_statuses.as_deref())
+                .unwrap_or_default();
+            let containers = pod
+                .spec
+                .as_ref()
+                .map(|spec| spec.containers.as_slice())
+                .unwrap_or_default();
+            let ready = statuses.iter().filter(|status| status.ready).count();
+
+            table.row(vec![
+                pod.metadata.name.clone(),
+                format!("{}/{}", ready, containers.len()),
+                pod_status(pod).to_string(),
+                pod.restart_count().to_string(),
+                age(&pod.metadata.creation_timestamp, now),
+                pod.spec
+                    .as_ref()
+                    .and_then(|spec| spec.node_name.clone())
+                    .unwrap_or_else(|| "<none>".to_string()),
+                pod.workload().unwrap_or_else(|| "<none>".to_string()),
+            ]);
+
+            for container in containers {
+                let Some(status) = statuses.iter().find(|s| s.name == container.name) else {
+                    continue;
+                };
+                if status.ready && status.restart_count == 0 {
+                    continue;
                 }
+                details.push_str(&format!(
+                    "  {}/{}: {}\n    Resources: {}\n",
+                    pod.metadata.name,
+                    container.name,
+                    status.summary(),
+                    container.resources_summary()
+                ));
             }
+        }
 
-            if let Some(spec) = &pod.spec {
-                output.push_str(&format!(
-                    "  Node: {}\n",
-                    spec.node_name.as_deref().unwrap_or("N/A")
-                ));
-                output.push_str("  Containers:\n");
-                for container in &spec.containers {
-                    output.push_str(&format!("    - {}\n", container.name));
-                }
+        let mut output = format!("Found {} pods:\n\n{}", self.items.len(), table.render());
+        if !details.is_empty() {
+            output.push_str("\nContainers not ready or restarted:\n");
+            output.push_str(&details);
+        }
+        output
+    }
+
+    /// Compact listing grouped by namespace and then by owning workload, with pod
+    /// counts by phase.
+    ///
+    /// Each pod gets a single line (name, phase, restarts) so that cluster-wide
+    /// listings stay small enough for the model's context.
+    pub fn as_grouped_string(&self) -> String {
+        let mut by_namespace: BTreeMap<&str, Vec<&Pod>> = BTreeMap::new();
+        for pod in &self.items {
+            by_namespace
+                .entry(pod.metadata.namespace.as_str())
+                .or_default()
+                .push(pod);
+        }
+
+        let mut output = String::new();
+        output.push_str(&format!(
+            "Found {} pods in {} namespaces ({})\n\n",
+            self.items.len(),
+            by_namespace.len(),
+            phase_summary(&self.items.iter().collect::<Vec<_>>())
+        ));
+
+        for (namespace, pods) in &by_namespace {
+            output.push_str(&format!(
+                "{} ({} pods: {})\n",
+                namespace,
+                pods.len(),
+                phase_summary(pods)
+            ));
+
+            // Standalone pods sort after every workload
+            let mut by_workload: BTreeMap<(bool, String), Vec<&Pod>> = BTreeMap::new();
+            for pod in pods {
+                let workload = pod.workload();
+                by_workload
+                    .entry((workload.is_none(), workload.unwrap_or_default()))
+                    .or_default()
+                    .push(pod);
             }
 
-            if let Some(status) = &pod.status {
-                output.push_str(&format!("  Phase: {}\n", status.phase));
-                if let Some(start_time) = &status.start_time {
-                    output.push_str(&format!("  Started: {}\n", start_time));
-                }
-                if let Some(conditions) = &status.conditions {
-                    output.push_str("  Conditions:\n");
-                    for condition in conditions {
-                        output.push_str(&format!(
-                            "    {}: {}\n",
-                            condition.type_field, condition.status
-                        ));
-                    }
+            for ((standalone, workload), pods) in &by_workload {
+                let label = if *standalone {
+                    "Standalone pods"
+                } else {
+                    workload.as_str()
+                };
+                output.push_str(&format!(
+                    "  {} ({} pods: {})\n",
+                    label,
+                    pods.len(),
+                    phase_summary(pods)
+                ));
+                for pod in pods {
+                    output.push_str(&format!(
+                        "    - {} [{}] restarts: {}{}\n",
+                        pod.metadata.name,
+                        pod_phase(pod),
+                        pod.restart_count(),
+                        waiting_reasons(pod)
+                    ));
                 }
             }
-
-            output.push_str("\n");
+            output.push('\n');
         }
 
         output
     }
 }
+
+// The first waiting container's reason (e.g. "CrashLoopBackOff"), else the phase
+fn pod_status(pod: &Pod) -> &str {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_ref())
+        .into_iter()
+        .flatten()
+        .find_map(|status| status.state.as_ref()?.waiting.as_ref()?.reason.as_deref())
+        .unwrap_or_else(|| pod_phase(pod))
+}
+
+fn pod_phase(pod: &Pod) -> &str {
+    pod.status
+        .as_ref()
+        .map(|status| status.phase.as_str())
+        .unwrap_or("Unknown")
+}
+
+// e.g. " (CrashLoopBackOff)", or empty when no container is waiting with a reason
+fn waiting_reasons(pod: &Pod) -> String {
+    let reasons = pod
+        .status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|status| status.state.as_ref()?.waiting.as_ref()?.reason.as_deref())
+        .collect::<Vec<_>>();
+    if reasons.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", reasons.join(", "))
+    }
+}
+
+// e.g. "5 Running, 1 Pending"
+fn phase_summary(pods: &[&Pod]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for pod in pods {
+        *counts.entry(pod_phase(pod)).or_default() += 1;
+    }
+    counts
+        .iter()
+        .map(|(phase, count)| format!("{} {}", count, phase))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,34 @@
+/// Parses a Kubernetes resource quantity ("500m", "2", "512Mi", "1.5G", "1e3") into a
+/// plain number: cores for CPU, bytes for memory and storage, a count otherwise.
+///
+/// Returns `None` for anything that isn't a valid quantity.
+pub fn parse_quantity(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    let suffixes: [(&str, f64); 14] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0_f64.powi(2)),
+        ("Gi", 1024.0_f64.powi(3)),
+        ("Ti", 1024.0_f64.powi(4)),
+        ("Pi", 1024.0_f64.powi(5)),
+        ("Ei", 1024.0_f64.powi(6)),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+    ];
+
+    for (suffix, multiplier) in suffixes {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<f64>().ok().map(|n| n * multiplier);
+        }
+    }
+    // "E" is both the exa suffix and the exponent marker ("1e3" vs "2E")
+    if let Some(number) = quantity.strip_suffix('E') {
+        return number.parse::<f64>().ok().map(|n| n * 1e18);
+    }
+    quantity.parse().ok()
+}
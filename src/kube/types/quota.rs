@@ -0,0 +1,174 @@
+use super::quantity::parse_quantity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Usage at or above this fraction of a hard limit is flagged in the output.
+const NEAR_LIMIT: f64 = 0.9;
+
+// ResourceQuota API Response (/api/v1/namespaces/{namespace}/resourcequotas)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceQuotaListResponse {
+    pub items: Vec<ResourceQuota>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceQuota {
+    pub metadata: PolicyMetadata,
+    pub status: Option<ResourceQuotaStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyMetadata {
+    pub name: String,
+    pub namespace: String,
+}
+
+// The status repeats the enforced hard limits next to current usage
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceQuotaStatus {
+    pub hard: Option<BTreeMap<String, String>>,
+    pub used: Option<BTreeMap<String, String>>,
+}
+
+// LimitRange API Response (/api/v1/namespaces/{namespace}/limitranges)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitRangeListResponse {
+    pub items: Vec<LimitRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitRange {
+    pub metadata: PolicyMetadata,
+    pub spec: LimitRangeSpec,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitRangeSpec {
+    pub limits: Vec<LimitRangeItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitRangeItem {
+    #[serde(rename = "type")]
+    pub type_field: String, // "Container", "Pod" or "PersistentVolumeClaim"
+    pub default: Option<BTreeMap<String, String>>,
+    #[serde(rename = "defaultRequest")]
+    pub default_request: Option<BTreeMap<String, String>>,
+    pub min: Option<BTreeMap<String, String>>,
+    pub max: Option<BTreeMap<String, String>>,
+    #[serde(rename = "maxLimitRequestRatio")]
+    pub max_limit_request_ratio: Option<BTreeMap<String, String>>,
+}
+
+impl ResourceQuota {
+    /// Formats each quota resource as "used / hard (percent)", flagging those near or
+    /// at their limit.
+    pub fn as_string(&self) -> String {
+        let mut output = format!("  ResourceQuota {}:\n", self.metadata.name);
+        let status = self.status.as_ref();
+        let Some(hard) = status.and_then(|status| status.hard.as_ref()) else {
+            output.push_str("    (no usage reported yet)\n");
+            return output;
+        };
+        let used = status.and_then(|status| status.used.as_ref());
+
+        for (resource, limit) in hard {
+            let current = used
+                .and_then(|used| used.get(resource))
+                .map(String::as_str)
+                .unwrap_or("0");
+            output.push_str(&format!("    {}: {} / {}", resource, current, limit));
+
+            if let (Some(current), Some(limit)) = (parse_quantity(current), parse_quantity(limit)) {
+                if limit > 0.0 {
+                    let fraction = current / limit;
+                    output.push_str(&format!(" ({:.0}%)", fraction * 100.0));
+                    if fraction >= 1.0 {
+                        output.push_str(" - AT LIMIT");
+                    } else if fraction >= NEAR_LIMIT {
+                        output.push_str(" - near limit");
+                    }
+                } else if current >= limit {
+                    output.push_str(" - AT LIMIT");
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+impl LimitRange {
+    /// Formats the defaults and bounds for each kind of object the range applies to.
+    pub fn as_string(&self) -> String {
+        let mut output = format!("  LimitRange {}:\n", self.metadata.name);
+        for item in &self.spec.limits {
+            output.push_str(&format!("    {}:\n", item.type_field));
+            let fields = [
+                ("Default limit", &item.default),
+                ("Default request", &item.default_request),
+                ("Min", &item.min),
+                ("Max", &item.max),
+                ("Max limit/request ratio", &item.max_limit_request_ratio),
+            ];
+            for (label, values) in fields {
+                let Some(values) = values.as_ref().filter(|values| !values.is_empty()) else {
+                    continue;
+                };
+                let values = values
+                    .iter()
+                    .map(|(resource, value)| format!("{}={}", resource, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                output.push_str(&format!("      {}: {}\n", label, values));
+            }
+        }
+        output
+    }
+}
+
+impl ResourceQuotaListResponse {
+    /// Groups the quotas and the given limit ranges by namespace.
+    pub fn as_string(&self, limits: &LimitRangeListResponse) -> String {
+        let mut by_namespace: BTreeMap<&str, (Vec<&ResourceQuota>, Vec<&LimitRange>)> =
+            BTreeMap::new();
+        for quota in &self.items {
+            by_namespace
+                .entry(quota.metadata.namespace.as_str())
+                .or_default()
+                .0
+                .push(quota);
+        }
+        for range in &limits.items {
+            by_namespace
+                .entry(range.metadata.namespace.as_str())
+                .or_default()
+                .1
+                .push(range);
+        }
+
+        if by_namespace.is_empty() {
+            return "No ResourceQuotas or LimitRanges found.".to_string();
+        }
+
+        let mut output = String::new();
+        for (namespace, (quotas, ranges)) in by_namespace {
+            output.push_str(&format!("Namespace {}:\n", namespace));
+            if quotas.is_empty() {
+                output.push_str("  No ResourceQuota\n");
+            }
+            for quota in quotas {
+                output.push_str(&quota.as_string());
+            }
+            if ranges.is_empty() {
+                output.push_str("  No LimitRange\n");
+            }
+            for range in ranges {
+                output.push_str(&range.as_string());
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+// ServiceAccount list (/api/v1/namespaces/{namespace}/serviceaccounts)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceAccount {
+    pub metadata: RbacMetadata,
+}
+
+// Role and ClusterRole lists (/apis/rbac.authorization.k8s.io/v1/roles, .../clusterroles);
+// both have the same shape, and ClusterRoles have no namespace
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Role {
+    pub metadata: RbacMetadata,
+    pub rules: Option<Vec<PolicyRule>>,
+}
+
+// RoleBinding and ClusterRoleBinding lists (.../rolebindings, .../clusterrolebindings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleBinding {
+    pub metadata: RbacMetadata,
+    pub subjects: Option<Vec<Subject>>,
+    #[serde(rename = "roleRef")]
+    pub role_ref: RoleRef,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RbacMetadata {
+    pub name: String,
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyRule {
+    #[serde(default)]
+    pub verbs: Vec<String>,
+    #[serde(rename = "apiGroups", default)]
+    pub api_groups: Vec<String>,
+    #[serde(default)]
+    pub resources: Vec<String>,
+    #[serde(rename = "resourceNames", default)]
+    pub resource_names: Vec<String>,
+    #[serde(rename = "nonResourceURLs", default)]
+    pub non_resource_urls: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Subject {
+    pub kind: String, // "User", "Group", or "ServiceAccount"
+    pub name: String,
+    pub namespace: Option<String>, // ServiceAccounts only
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleRef {
+    pub kind: String, // "Role" or "ClusterRole"
+    pub name: String,
+}
+
+// SelfSubjectReview (/apis/authentication.k8s.io/v1/selfsubjectreviews): who the API
+// server thinks the caller is
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfSubjectReview {
+    pub status: SelfSubjectReviewStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfSubjectReviewStatus {
+    #[serde(rename = "userInfo")]
+    pub user_info: UserInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfo {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+impl Subject {
+    /// Whether the subject is the user `username` or one of its `groups`.
+    pub fn matches(&self, username: &str, groups: &[String]) -> bool {
+        match self.kind.as_str() {
+            "User" => self.name == username,
+            "Group" => groups.contains(&self.name),
+            "ServiceAccount" => {
+                let namespace = self.namespace.as_deref().unwrap_or_default();
+                username == format!("system:serviceaccount:{}:{}", namespace, self.name)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Subject {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.namespace {
+            Some(namespace) if self.kind == "ServiceAccount" => {
+                write!(f, "ServiceAccount {}/{}", namespace, self.name)
+            }
+            _ => write!(f, "{} {}", self.kind, self.name),
+        }
+    }
+}
+
+impl PolicyRule {
+    /// The rule as a phrase, e.g. "get, list pods, pods/log" or "get deployments.apps
+    /// named web".
+    pub fn describe(&self) -> String {
+        let verbs = if self.verbs.iter().any(|verb| verb == "*") {
+            "do anything to".to_string()
+        } else {
+            self.verbs.join(", ")
+        };
+        if !self.non_resource_urls.is_empty() {
+            return format!("{} {}", verbs, self.non_resource_urls.join(", "));
+        }
+
+        let groups: Vec<&str> = self
+            .api_groups
+            .iter()
+            .filter(|group| !group.is_empty())
+            .map(String::as_str)
+            .collect();
+        let resources: Vec<String> = self
+            .resources
+            .iter()
+            .map(|resource| {
+                let resource = if resource == "*" {
+                    "all resources"
+                } else {
+                    resource.as_str()
+                };
+                match groups.as_slice() {
+                    [] => resource.to_string(),
+                    ["*"] => format!("{} in any API group", resource),
+                    [group] => format!("{}.{}", resource, group),
+                    groups => format!("{} ({})", resource, groups.join(", ")),
+                }
+            })
+            .collect();
+
+        let mut phrase = format!("{} {}", verbs, resources.join(", "));
+        if !self.resource_names.is_empty() {
+            phrase.push_str(&format!(" named {}", self.resource_names.join(", ")));
+        }
+        phrase
+    }
+}
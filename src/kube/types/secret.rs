@@ -0,0 +1,75 @@
+use super::table::age;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Secret API Response (/api/v1/namespaces/{namespace}/secrets)
+//
+// Only Deserialize is derived and secret values are parsed as `IgnoredAny`, so values
+// are discarded while parsing and can never be formatted or serialized back out.
+#[derive(Debug, Deserialize)]
+pub struct SecretListResponse {
+    pub items: Vec<Secret>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Secret {
+    pub metadata: SecretMetadata,
+    #[serde(rename = "type")]
+    pub type_field: Option<String>, // e.g. "Opaque", "kubernetes.io/tls"
+    pub data: Option<BTreeMap<String, IgnoredAny>>,
+    #[serde(rename = "stringData")]
+    pub string_data: Option<BTreeMap<String, IgnoredAny>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecretMetadata {
+    pub name: String,
+    #[serde(rename = "creationTimestamp")]
+    pub creation_timestamp: Option<String>,
+}
+
+impl Secret {
+    /// Names of the keys stored in the secret.
+    pub fn keys(&self) -> Vec<&str> {
+        self.data
+            .iter()
+            .chain(self.string_data.iter())
+            .flat_map(|data| data.keys().map(String::as_str))
+            .collect()
+    }
+}
+
+impl SecretListResponse {
+    /// Formats the secrets' names, types, key names, and ages.
+    pub fn as_string(&self) -> String {
+        let now = chrono::Utc::now();
+        let mut output = String::new();
+        output.push_str(&format!("Found {} secrets:\n\n", self.items.len()));
+
+        for secret in &self.items {
+            output.push_str(&format!("Secret: {}\n", secret.metadata.name));
+            output.push_str(&format!(
+                "  Type: {}\n",
+                secret.type_field.as_deref().unwrap_or("Opaque")
+            ));
+
+            let age = secret
+                .metadata
+                .creation_timestamp
+                .as_deref()
+                .map(|created| age(created, now))
+                .unwrap_or_else(|| "<unknown>".to_string());
+            output.push_str(&format!("  Age: {}\n", age));
+
+            let keys = secret.keys();
+            if keys.is_empty() {
+                output.push_str("  Keys: <none>\n\n");
+            } else {
+                output.push_str(&format!("  Keys: {}\n\n", keys.join(", ")));
+            }
+        }
+
+        output
+    }
+}
@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+// Status object the API server returns with failed requests
+// (e.g. {"kind":"Status","status":"Failure","reason":"NotFound","code":404,...})
+#[derive(Debug, Deserialize)]
+pub struct Status {
+    pub kind: Option<String>,
+    pub code: Option<u16>,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// PersistentVolumeClaim API Response (/api/v1/namespaces/{namespace}/persistentvolumeclaims)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeClaimListResponse {
+    pub items: Vec<PersistentVolumeClaim>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeClaim {
+    pub metadata: StorageMetadata,
+    pub spec: PersistentVolumeClaimSpec,
+    pub status: Option<PersistentVolumeClaimStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageMetadata {
+    pub name: String,
+    pub namespace: Option<String>, // PVs are cluster-scoped
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeClaimSpec {
+    #[serde(rename = "accessModes")]
+    pub access_modes: Option<Vec<String>>,
+    #[serde(rename = "storageClassName")]
+    pub storage_class_name: Option<String>,
+    #[serde(rename = "volumeName")]
+    pub volume_name: Option<String>,
+    pub resources: Option<ResourceRequirements>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceRequirements {
+    pub requests: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeClaimStatus {
+    pub phase: Option<String>, // "Pending", "Bound" or "Lost"
+    pub capacity: Option<BTreeMap<String, String>>,
+}
+
+// PersistentVolume API Response (/api/v1/persistentvolumes)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeListResponse {
+    pub items: Vec<PersistentVolume>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolume {
+    pub metadata: StorageMetadata,
+    pub spec: PersistentVolumeSpec,
+    pub status: Option<PersistentVolumeStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeSpec {
+    pub capacity: Option<BTreeMap<String, String>>,
+    #[serde(rename = "accessModes")]
+    pub access_modes: Option<Vec<String>>,
+    #[serde(rename = "storageClassName")]
+    pub storage_class_name: Option<String>,
+    #[serde(rename = "persistentVolumeReclaimPolicy")]
+    pub reclaim_policy: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistentVolumeStatus {
+    pub phase: Option<String>,
+}
+
+impl PersistentVolumeClaimListResponse {
+    /// Formats each claim together with the volume it is bound to, if any.
+    pub fn as_string(&self, volumes: &PersistentVolumeListResponse) -> String {
+        let mut output = String::new();
+        output.push_str(&format!(
+            "Found {} persistent volume claims:\n\n",
+            self.items.len()
+        ));
+
+        for claim in &self.items {
+            let status = claim.status.as_ref();
+            let phase = status
+                .and_then(|status| status.phase.as_deref())
+                .unwrap_or("Unknown");
+            let requested = claim
+                .spec
+                .resources
+                .as_ref()
+                .and_then(|resources| resources.requests.as_ref())
+                .and_then(|requests| requests.get("storage"))
+                .map(String::as_str)
+                .unwrap_or("N/A");
+            let capacity = status
+                .and_then(|status| status.capacity.as_ref())
+                .and_then(|capacity| capacity.get("storage"))
+                .map(String::as_str)
+                .unwrap_or("N/A");
+
+            output.push_str(&format!("PVC: {}\n", claim.metadata.name));
+            output.push_str(&format!("  Phase: {}\n", phase));
+            output.push_str(&format!("  Requested: {}\n", requested));
+            output.push_str(&format!("  Capacity: {}\n", capacity));
+            let storage_class = claim.spec.storage_class_name.as_deref();
+            output.push_str(&format!(
+                "  Storage Class: {}\n",
+                storage_class.unwrap_or("<default>")
+            ));
+            output.push_str(&format!(
+                "  Access Modes: {}\n",
+                format_access_modes(claim.spec.access_modes.as_deref())
+            ));
+
+            let volume = claim.spec.volume_name.as_deref().and_then(|volume_name| {
+                volumes
+                    .items
+                    .iter()
+                    .find(|volume| volume.metadata.name == volume_name)
+            });
+            match volume {
+                Some(volume) => {
+                    output.push_str(&format!("  Volume: {}\n", volume.metadata.name));
+                    output.push_str(&format!(
+                        "    Capacity: {}\n",
+                        volume
+                            .spec
+                            .capacity
+                            .as_ref()
+                            .and_then(|capacity| capacity.get("storage"))
+                            .map(String::as_str)
+                            .unwrap_or("N/A")
+                    ));
+                    output.push_str(&format!(
+                        "    Phase: {}\n",
+                        volume
+                            .status
+                            .as_ref()
+                            .and_then(|status| status.phase.as_deref())
+                            .unwrap_or("Unknown")
+                    ));
+                    output.push_str(&format!(
+                        "    Reclaim Policy: {}\n",
+                        volume.spec.reclaim_policy.as_deref().unwrap_or("N/A")
+                    ));
+                }
+                None if phase == "Pending" => {
+                    output.push_str(
+                        "  Volume: <unbound> (no matching volume yet; check the storage class provisioner)\n",
+                    );
+                }
+                None => {
+                    output.push_str(&format!(
+                        "  Volume: {}\n",
+                        claim.spec.volume_name.as_deref().unwrap_or("<none>")
+                    ));
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+fn format_access_modes(modes: Option<&[String]>) -> String {
+    match modes {
+        Some(modes) if !modes.is_empty() => modes.join(", "),
+        _ => "N/A".to_string(),
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Spaces between columns, matching `kubectl get`.
+const COLUMN_GAP: usize = 3;
+
+/// A plain-text table with fixed-width, left-aligned columns, like `kubectl get` prints.
+///
+/// One line per row keeps list outputs compact for the model and easy to scan for users.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends a row; it should have one cell per header.
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    /// Renders the header and rows, each column padded to its widest cell.
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut output = String::new();
+        for cells in std::iter::once(self.headers.clone()).chain(self.rows.iter().cloned()) {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width + COLUMN_GAP))
+                .collect::<String>();
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Time since an RFC 3339 timestamp in kubectl's AGE style, e.g. "3d4h" or "12m".
+pub fn age(timestamp: &str, now: DateTime<Utc>) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|created| format_age(now.signed_duration_since(created)))
+        .unwrap_or_else(|_| "<unknown>".to_string())
+}
+
+// The two most significant units, e.g. "3d4h", "5h12m", "12m", "30s"
+fn format_age(age: Duration) -> String {
+    let (days, hours, minutes) = (age.num_days(), age.num_hours(), age.num_minutes());
+    if days > 0 {
+        with_remainder(days, 'd', hours % 24, 'h')
+    } else if hours > 0 {
+        with_remainder(hours, 'h', minutes % 60, 'm')
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", age.num_seconds().max(0))
+    }
+}
+
+fn with_remainder(value: i64, unit: char, remainder: i64, remainder_unit: char) -> String {
+    if remainder > 0 {
+        format!("{}{}{}{}", value, unit, remainder, remainder_unit)
+    } else {
+        format!("{}{}", value, unit)
+    }
+}
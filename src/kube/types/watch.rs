@@ -0,0 +1,18 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+// One line of a watch stream (e.g. /api/v1/pods?watch=1)
+#[derive(Debug, Deserialize)]
+pub struct RawWatchEvent {
+    #[serde(rename = "type")]
+    pub type_field: String, // "ADDED", "MODIFIED", "DELETED", "BOOKMARK", or "ERROR"
+    pub object: Value,
+}
+
+/// A change to a watched object.
+pub enum WatchEvent<T> {
+    /// The object was created or modified
+    Applied(T),
+    /// The object was deleted
+    Deleted(T),
+}
@@ -0,0 +1,362 @@
+use super::types::{Event, NodeDetails, Pod, WatchEvent};
+use super::{KubeAgent, MAX_LIST_ITEMS};
+use crate::notifier::{EventKind, Notification, Notifier};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::*;
+
+/// Delay before listing again after a failed list or watch.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// How often alerts are recomputed and new ones sent to the notifier.
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Warning events older than this no longer count as alerts.
+const WARNING_EVENT_WINDOW_MINUTES: i64 = 15;
+
+/// Maximum number of warning events reported, most recent first.
+const MAX_WARNING_EVENTS: usize = 20;
+
+/// Pods pending for longer than this are reported.
+const PENDING_ALERT_MINUTES: i64 = 5;
+
+/// Container waiting reasons that mean a pod is stuck rather than starting up.
+const UNHEALTHY_WAITING_REASONS: &[&str] = &[
+    "CrashLoopBackOff",
+    "ImagePullBackOff",
+    "ErrImagePull",
+    "InvalidImageName",
+    "CreateContainerConfigError",
+    "CreateContainerError",
+];
+
+/// An unhealthy condition found in the cluster snapshot.
+pub struct Alert {
+    /// Stable identity, so the same problem is only notified once
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct SnapshotState {
+    pods: HashMap<String, Pod>,
+    nodes: HashMap<String, NodeDetails>,
+    warning_events: HashMap<String, Event>,
+    /// Resources whose initial listing has completed
+    synced: HashSet<&'static str>,
+}
+
+/// In-memory view of the home cluster's pods, nodes, and warning events, kept
+/// current by [`ClusterWatcher`].
+#[derive(Clone, Default)]
+pub struct ClusterSnapshot {
+    state: Arc<RwLock<SnapshotState>>,
+}
+
+impl ClusterSnapshot {
+    /// Whether pods, nodes, and events have all been listed at least once.
+    pub fn is_synced(&self) -> bool {
+        self.state.read().unwrap().synced.len() == 3
+    }
+
+    /// Number of pods and nodes currently in the snapshot.
+    pub fn counts(&self) -> (usize, usize) {
+        let state = self.state.read().unwrap();
+        (state.pods.len(), state.nodes.len())
+    }
+
+    /// Everything that currently looks unhealthy, nodes first, then pods, then
+    /// recent warning events.
+    pub fn alerts(&self) -> Vec<Alert> {
+        let state = self.state.read().unwrap();
+        let now = Utc::now();
+        let mut alerts = Vec::new();
+
+        let mut nodes: Vec<&NodeDetails> = state.nodes.values().collect();
+        nodes.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        for node in nodes {
+            let conditions = node.status.as_ref().and_then(|s| s.conditions.as_ref());
+            for condition in conditions.into_iter().flatten() {
+                if !condition.is_unhealthy() {
+                    continue;
+                }
+                let problem = if condition.type_field == "Ready" {
+                    format!("is not Ready ({})", condition.status)
+                } else {
+                    format!("has {}={}", condition.type_field, condition.status)
+                };
+                alerts.push(Alert {
+                    key: format!("node/{}/{}", node.metadata.name, condition.type_field),
+                    message: format!(
+                        "Node {} {}{}",
+                        node.metadata.name,
+                        problem,
+                        detail(condition.message.as_deref())
+                    ),
+                });
+            }
+        }
+
+        let mut pods: Vec<&Pod> = state.pods.values().collect();
+        pods.sort_by(|a, b| {
+            (&a.metadata.namespace, &a.metadata.name)
+                .cmp(&(&b.metadata.namespace, &b.metadata.name))
+        });
+        for pod in pods {
+            alerts.extend(pod_alerts(pod, now));
+        }
+
+        let mut events: Vec<&Event> = state
+            .warning_events
+            .values()
+            .filter(|event| {
+                minutes_since(event.timestamp(), now)
+                    .is_some_and(|m| m < WARNING_EVENT_WINDOW_MINUTES)
+            })
+            .collect();
+        events.sort_by(|a, b| b.timestamp().cmp(a.timestamp()));
+        for event in events.into_iter().take(MAX_WARNING_EVENTS) {
+            let object = event.involved_object.as_ref();
+            let name = event.metadata.as_ref().map_or("", |m| m.name.as_str());
+            alerts.push(Alert {
+                key: format!("event/{}", name),
+                message: format!(
+                    "Warning {} on {} {}/{} (x{}){}",
+                    event.reason.as_deref().unwrap_or("Unknown"),
+                    object.and_then(|o| o.kind.as_deref()).unwrap_or("object"),
+                    object.and_then(|o| o.namespace.as_deref()).unwrap_or("-"),
+                    object.and_then(|o| o.name.as_deref()).unwrap_or("unknown"),
+                    event.count.unwrap_or(1),
+                    detail(event.message.as_deref())
+                ),
+            });
+        }
+
+        alerts
+    }
+}
+
+fn pod_alerts(pod: &Pod, now: DateTime<Utc>) -> Vec<Alert> {
+    let name = format!("{}/{}", pod.metadata.namespace, pod.metadata.name);
+    let Some(status) = &pod.status else {
+        return Vec::new();
+    };
+    let mut alerts = Vec::new();
+
+    match status.phase.as_str() {
+        "Failed" => alerts.push(Alert {
+            key: format!("pod/{}/Failed", name),
+            message: format!("Pod {} has failed", name),
+        }),
+        "Pending" => {
+            let minutes = minutes_since(&pod.metadata.creation_timestamp, now);
+            if minutes.is_some_and(|m| m >= PENDING_ALERT_MINUTES) {
+                alerts.push(Alert {
+                    key: format!("pod/{}/Pending", name),
+                    message: format!(
+                        "Pod {} has been Pending for {} minutes",
+                        name,
+                        minutes.unwrap_or_default()
+                    ),
+                });
+            }
+        }
+        _ => {}
+    }
+
+    for container in status.container_statuses.iter().flatten() {
+        let waiting = container
+            .state
+            .as_ref()
+            .and_then(|state| state.waiting.as_ref())
+            .and_then(|waiting| waiting.reason.as_deref());
+        if let Some(reason) = waiting.filter(|r| UNHEALTHY_WAITING_REASONS.contains(r)) {
+            alerts.push(Alert {
+                key: format!("pod/{}/{}/{}", name, container.name, reason),
+                message: format!(
+                    "Pod {} container {} is in {} ({} restarts)",
+                    name, container.name, reason, container.restart_count
+                ),
+            });
+        }
+    }
+
+    alerts
+}
+
+// Formats an optional condition or event message as a ": message" suffix
+fn detail(message: Option<&str>) -> String {
+    match message.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => format!(": {}", message),
+        None => String::new(),
+    }
+}
+
+fn minutes_since(timestamp: &str, now: DateTime<Utc>) -> Option<i64> {
+    let time = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    Some((now - time.with_timezone(&Utc)).num_minutes())
+}
+
+/// Background task that keeps a [`ClusterSnapshot`] current with watch streams and
+/// sends new alerts to the notifier.
+pub struct ClusterWatcher {
+    kube_agent: KubeAgent,
+    notifier: Notifier,
+}
+
+impl ClusterWatcher {
+    pub fn new(kube_agent: KubeAgent, notifier: Notifier) -> Self {
+        ClusterWatcher {
+            kube_agent,
+            notifier,
+        }
+    }
+
+    /// Spawns the pod, node, and event watchers (plus the alert notifications) and
+    /// returns the snapshot they maintain.
+    pub fn spawn(self) -> ClusterSnapshot {
+        let snapshot = ClusterSnapshot::default();
+
+        tokio::spawn(watch_resource(
+            self.kube_agent.clone(),
+            snapshot.clone(),
+            "/api/v1/pods",
+            |pod: &Pod| format!("{}/{}", pod.metadata.namespace, pod.metadata.name),
+            pods,
+        ));
+        tokio::spawn(watch_resource(
+            self.kube_agent.clone(),
+            snapshot.clone(),
+            "/api/v1/nodes",
+            |node: &NodeDetails| node.metadata.name.clone(),
+            nodes,
+        ));
+        tokio::spawn(watch_resource(
+            self.kube_agent,
+            snapshot.clone(),
+            "/api/v1/events?fieldSelector=type%3DWarning",
+            |event: &Event| {
+                let metadata = event.metadata.as_ref();
+                format!(
+                    "{}/{}",
+                    metadata.and_then(|m| m.namespace.as_deref()).unwrap_or(""),
+                    metadata.map_or("", |m| m.name.as_str())
+                )
+            },
+            warning_events,
+        ));
+
+        tokio::spawn(notify(snapshot.clone(), self.notifier));
+
+        info!("Cluster watcher started");
+        snapshot
+    }
+}
+
+fn pods(state: &mut SnapshotState) -> &mut HashMap<String, Pod> {
+    &mut state.pods
+}
+
+fn nodes(state: &mut SnapshotState) -> &mut HashMap<String, NodeDetails> {
+    &mut state.nodes
+}
+
+fn warning_events(state: &mut SnapshotState) -> &mut HashMap<String, Event> {
+    &mut state.warning_events
+}
+
+/// Lists `endpoint` into the snapshot, then applies watch events from the listing's
+/// resourceVersion on, listing again whenever the watch fails.
+async fn watch_resource<T: DeserializeOwned + Send + Sync + 'static>(
+    kube_agent: KubeAgent,
+    snapshot: ClusterSnapshot,
+    endpoint: &'static str,
+    key: fn(&T) -> String,
+    store: fn(&mut SnapshotState) -> &mut HashMap<String, T>,
+) {
+    loop {
+        let listing = match kube_agent.list_all::<T>(endpoint, MAX_LIST_ITEMS).await {
+            Ok(listing) => listing,
+            Err(e) => {
+                warn!("Cluster watcher failed to list {}: {}", endpoint, e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        {
+            let mut state = snapshot.state.write().unwrap();
+            let items = store(&mut state);
+            items.clear();
+            items.extend(listing.items.into_iter().map(|item| (key(&item), item)));
+            state.synced.insert(endpoint);
+        }
+
+        let Some(mut version) = listing.resource_version else {
+            warn!(
+                "Listing of {} has no resourceVersion, cannot watch",
+                endpoint
+            );
+            tokio::time::sleep(RETRY_DELAY).await;
+            continue;
+        };
+
+        loop {
+            let result = kube_agent
+                .watch(endpoint, &version, |event| {
+                    let mut state = snapshot.state.write().unwrap();
+                    let items = store(&mut state);
+                    match event {
+                        WatchEvent::Applied(item) => {
+                            items.insert(key(&item), item);
+                        }
+                        WatchEvent::Deleted(item) => {
+                            items.remove(&key(&item));
+                        }
+                    }
+                })
+                .await;
+
+            match result {
+                Ok(last_version) => version = last_version,
+                Err(e) => {
+                    debug!("Watch of {} ended ({}), listing again", endpoint, e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Periodically notifies alerts that weren't present on the previous check.
+async fn notify(snapshot: ClusterSnapshot, notifier: Notifier) {
+    let mut notified: HashSet<String> = HashSet::new();
+    let mut interval = tokio::time::interval(NOTIFY_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        if !snapshot.is_synced() {
+            continue;
+        }
+
+        let alerts = snapshot.alerts();
+        notified.retain(|key| alerts.iter().any(|alert| alert.key == *key));
+
+        for alert in &alerts {
+            if notified.insert(alert.key.clone()) {
+                // The notifier retries failed deliveries itself
+                debug!("Notifying alert {}", alert.key);
+                notifier.notify(Notification {
+                    kind: EventKind::ClusterAlert,
+                    text: alert.message.clone(),
+                    details: json!({ "key": alert.key }),
+                });
+            }
+        }
+    }
+}
@@ -1,55 +1,267 @@
-use crate::agent::Agent;
-use crate::environment::Environment;
+use crate::agent::builder::AgentBuilder;
+use crate::agent::hooks::{ClusterContextHook, ClusterNameHook, CurrentDateHook};
+use crate::agent::{Agent, ChatOptions};
+use crate::cli::{Cli, Command, ToolsCommand};
+use crate::config::ConfigFile;
+use crate::environment::{Environment, Profile};
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcService;
+use crate::kube::inventory::InventoryRefresher;
 use crate::kube::{KubeAgent, ListPodsTool};
+use crate::server::admission::Admission;
+use crate::server::audit::AuditLog;
+use crate::server::auth::ApiKeys;
+use crate::server::digest::DailyDigest;
+use crate::server::health::DeepHealthCheck;
+use crate::server::limits::RequestLimits;
+use crate::server::lockout::AuthLockout;
+use crate::server::moderation::Moderation;
+use crate::server::overrun::RequestThresholds;
+use crate::server::proxy::TrustedProxies;
+use crate::server::shedding::LoadShedding;
+use crate::server::usage::UsageSettings;
 use crate::server::Server;
+use crate::sessions::SessionStore;
+use crate::telegram::TelegramBot;
+use clap::Parser;
 use dotenv::dotenv;
+#[cfg(any(feature = "grpc", feature = "test-support"))]
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, info, warn};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
 mod agent;
+#[cfg(feature = "test-support")]
+mod bench;
+mod cli;
+mod config;
 mod environment;
+mod error;
+mod evals;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod kube;
+mod mcp;
+mod notifier;
+mod reporting;
 mod server;
+mod sessions;
+mod telegram;
+// Test harness: neither the tests nor the binary use all of it
+#[cfg(any(test, feature = "test-support"))]
+#[allow(dead_code)]
+mod vcr;
 
 /// Main application entry point.
 ///
-/// Initializes the AI agent API server with Kubernetes integration.
+/// Without a subcommand, starts the AI agent API server with Kubernetes integration.
 /// The server provides endpoints for chatting with an AI agent that can
 /// query portfolio information and Kubernetes cluster metrics.
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
+    let cli = Cli::parse();
+    if let Some(path) = cli.config {
+        ConfigFile::set_path(path);
+    }
+    let command = cli.command.unwrap_or(Command::Serve);
+
+    // Commands that print to stdout (or, like MCP, speak a protocol over it) log to stderr
+    let log_writer = if command.owns_stdout() {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
+    // Panics and error-level events are reported while this guard is alive
+    let error_reporting = environment::error_reporting_dsn()
+        .map(|dsn| reporting::init(&dsn, Profile::from_env().name()));
+
     // Initialize structured logging (control with RUST_LOG env var)
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_writer(log_writer)
         .with_target(true)
         .with_thread_ids(true)
-        .with_line_number(true)
+        .with_line_number(true);
+    let fmt_layer = if environment::json_logs() {
+        // Event fields (tool, duration_ms) are top-level; the request span's request_id
+        // and session_id are under "span"
+        fmt_layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed()
+    } else {
+        fmt_layer.boxed()
+    };
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(fmt_layer)
+        .with(error_reporting.is_some().then(reporting::layer))
         .init();
 
-    info!("Starting AI Agent API server");
+    // Configuration is loaded once here; other modules read it via Environment::global()
+    let env = Environment::install(Environment::new());
 
-    let env = Environment::new();
+    match command {
+        Command::Serve => serve(env).await,
+        Command::Chat { prompt } => chat(env, prompt.join(" ")).await,
+        Command::Tools {
+            command: ToolsCommand::List,
+        } => list_tools(env).await,
+        Command::Check => check(env).await,
+        Command::Telegram => telegram(env).await,
+        // `mcp` serves the agent's tools to MCP clients over stdio instead of HTTP
+        Command::Mcp => {
+            if let Err(e) = mcp::McpServer::with_default_tools(env).serve_stdio().await {
+                error!("MCP server failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Command::Eval { suite, mock } => match evals::run_cli(&suite, mock).await {
+            Ok(true) => std::process::exit(0),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                error!("Eval run failed: {}", e);
+                std::process::exit(2);
+            }
+        },
+        #[cfg(feature = "test-support")]
+        Command::MockKube { port } => mock_kube(port).await,
+        #[cfg(feature = "test-support")]
+        Command::Vcr { command } => vcr(env, command).await,
+        #[cfg(feature = "test-support")]
+        Command::Bench {
+            requests,
+            concurrency,
+            duration,
+            model_latency,
+            url,
+        } => {
+            let options = bench::BenchOptions {
+                requests,
+                concurrency,
+                duration: duration.map(Duration::from_secs),
+                model_latency: Duration::from_millis(model_latency),
+                url,
+            };
+            if let Err(e) = bench::run_cli(options).await {
+                error!("Bench run failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
 
-    let agent = match Agent::new(env.openai_api_key) {
+/// Creates the chat agent with its prompt hooks, exiting if it can't be initialized.
+fn build_agent(env: &Environment, sessions: Arc<SessionStore>) -> Agent {
+    let built = AgentBuilder::openai(&env.openai_api_key, sessions).and_then(AgentBuilder::build);
+    let mut agent = match built {
         Ok(agent) => agent,
         Err(e) => {
             error!("Failed to initialize AI agent: {}", e);
             std::process::exit(1);
         }
     };
+    agent.register_hook(CurrentDateHook);
+    if let Some(cluster_name) = env.cluster_name.clone() {
+        agent.register_hook(ClusterNameHook::new(cluster_name));
+    }
+    if env.enable_cluster_context {
+        let inventory = InventoryRefresher::new(
+            home_kube_agent(env),
+            Duration::from_secs(env.cluster_context_refresh_secs),
+        )
+        .spawn();
+        agent.register_hook(ClusterContextHook::new(inventory));
+    }
+    agent
+}
 
-    // Test Kubernetes connectivity on startup
-    if let Ok(pod_list) = ListPodsTool::new(KubeAgent::new(
+/// Client for the home cluster, as configured in the environment.
+fn home_kube_agent(env: &Environment) -> KubeAgent {
+    KubeAgent::new(
         env.kube_api_server.clone(),
         env.kube_token.clone(),
         env.kube_certificate.clone(),
-    ))
-    .list_pods(None, None)
-    .await
+    )
+    .with_timeouts(
+        Duration::from_secs(env.kube_connect_timeout_secs),
+        Duration::from_secs(env.kube_request_timeout_secs),
+    )
+    .with_accept_invalid_certs(env.kube_accept_invalid_certs)
+}
+
+/// Deep health check of OpenAI and the home cluster, as configured in the environment.
+fn deep_health_check(env: &Environment) -> DeepHealthCheck {
+    let check = DeepHealthCheck::new(env.openai_api_key.clone(), home_kube_agent(env));
+    match &env.openai_base_url {
+        Some(base_url) => check.with_openai_base_url(base_url),
+        None => check,
+    }
+}
+
+/// Prompt and history limits, as configured in the environment.
+fn request_limits(env: &Environment) -> RequestLimits {
+    RequestLimits {
+        max_prompt_chars: env.max_prompt_chars,
+        max_prompt_tokens: env.max_prompt_tokens,
+        max_history_chars: env.max_history_chars,
+        max_history_tokens: env.max_history_tokens,
+        max_history_messages: env.max_history_messages,
+        max_response_chars: env.max_response_chars,
+    }
+}
+
+/// Usage report windows and token prices, as configured in the environment.
+fn usage_settings(env: &Environment) -> UsageSettings {
+    UsageSettings {
+        window_days: env.usage_window_days.clone(),
+        prompt_price_per_mtok: env.usage_prompt_price_per_mtok,
+        response_price_per_mtok: env.usage_response_price_per_mtok,
+    }
+}
+
+/// The configured API keys, exiting if they can't be loaded.
+fn api_keys(env: &Environment) -> ApiKeys {
+    // validate() has already loaded the keys, so this only fails if the keys file just changed
+    ApiKeys::new(
+        &env.chat_api_key,
+        env.chat_api_key_hash.as_deref(),
+        env.chat_api_keys_file.as_deref(),
+    )
+    .unwrap_or_else(|e| {
+        error!("Invalid API key configuration: {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Runs the HTTP chat server.
+async fn serve(env: &Environment) {
+    info!("Starting AI Agent API server");
+
+    if let Err(problems) = env.validate() {
+        error!(
+            "Invalid configuration, refusing to start:\n  - {}",
+            problems.join("\n  - ")
+        );
+        std::process::exit(1);
+    }
+
+    let sessions = Arc::new(SessionStore::from_env(env));
+
+    let agent = Arc::new(build_agent(env, sessions.clone()));
+
+    // Test Kubernetes connectivity on startup
+    if let Ok(pod_list) = ListPodsTool::new(home_kube_agent(env))
+        .list_pods(None, None, None, None)
+        .await
     {
         info!(
             "Successfully connected to Kubernetes cluster. Found {} pods.",
@@ -68,10 +280,284 @@ async fn main() {
         "127.0.0.1:8080".to_string()
     };
 
-    let server = Server::new(agent, host.to_string(), env.chat_api_key);
+    let audit_log = env.audit_log_path.as_ref().map(|path| {
+        Arc::new(
+            AuditLog::open(path, env.audit_log_retention_days).unwrap_or_else(|e| {
+                error!("Failed to open audit log {}: {}", path, e);
+                std::process::exit(1);
+            }),
+        )
+    });
+
+    let notifier = env.notifier();
+    if let Some(audit_log) = audit_log.as_ref().filter(|_| env.daily_digest_enabled) {
+        DailyDigest::new(
+            audit_log.clone(),
+            usage_settings(env),
+            notifier.clone(),
+            env.daily_digest_hour_utc,
+        )
+        .spawn();
+    }
+    let mut admission = Admission::new(agent.clone(), api_keys(env), audit_log)
+        .with_thresholds(RequestThresholds {
+            latency_ms: env.request_warn_latency_ms,
+            tokens: env.request_warn_tokens,
+            tool_calls: env.request_warn_tool_calls,
+            notifier: notifier.clone(),
+        })
+        .with_load_shedding(LoadShedding {
+            max_latency_ms: env.shed_model_latency_ms,
+            max_error_rate: env.shed_model_error_rate,
+            window: Duration::from_secs(env.shed_window_secs),
+            min_calls: env.shed_min_calls,
+        });
+    if env.auth_lockout_base_secs > 0 {
+        admission = admission.with_auth_lockout(AuthLockout::new(
+            env.auth_lockout_free_attempts,
+            Duration::from_secs(env.auth_lockout_base_secs),
+            Duration::from_secs(env.auth_lockout_max_secs),
+        ));
+    }
+    if env.moderation_enabled {
+        let moderation = Moderation::new(
+            env.openai_api_key.clone(),
+            env.moderation_model.clone(),
+            env.abuse_cooldown_flags,
+            Duration::from_secs(env.abuse_cooldown_secs),
+            env.abuse_shadow_ban_flags,
+            Duration::from_secs(env.abuse_window_secs),
+        )
+        .with_notifier(notifier.clone());
+        admission = admission.with_moderation(match &env.openai_base_url {
+            Some(base_url) => moderation.with_openai_base_url(base_url),
+            None => moderation,
+        });
+    }
+    let admission = Arc::new(admission);
+
+    // The gRPC API shares the agent and sessions, so a conversation can move between APIs,
+    // and is admitted and audited alongside the HTTP server
+    if let Some(port) = env.grpc_port {
+        serve_grpc(
+            env,
+            port,
+            agent.clone(),
+            sessions.clone(),
+            admission.clone(),
+        )
+        .await;
+    }
+
+    let mut server = Server::new(
+        agent,
+        host.to_string(),
+        admission,
+        request_limits(env),
+        sessions,
+        deep_health_check(env).with_notifier(notifier),
+    )
+    .with_usage(usage_settings(env))
+    .with_trusted_proxies(TrustedProxies::new(
+        env.trusted_proxies.clone(),
+        env.forwarded_header,
+    ))
+    .with_pod_logs(home_kube_agent(env));
+
+    // Like the keys, this was loaded by validate() and only fails if the files just changed
+    match env.mutual_tls() {
+        Ok(Some(mtls)) => server = server.with_mutual_tls(mtls),
+        Ok(None) => {}
+        Err(e) => {
+            error!("Invalid mutual TLS configuration: {}", e);
+            std::process::exit(1);
+        }
+    }
 
     if let Err(e) = server.listen().await {
         error!("Failed to start server: {}", e);
         std::process::exit(1);
     }
 }
+
+/// Starts the gRPC API on `port` in the background.
+#[cfg(feature = "grpc")]
+async fn serve_grpc(
+    env: &Environment,
+    port: u16,
+    agent: Arc<Agent>,
+    sessions: Arc<SessionStore>,
+    admission: Arc<Admission>,
+) {
+    // Bound like the HTTP server: every interface in production, localhost otherwise
+    let ip = if env.production_mode {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let service = GrpcService::new(
+        agent,
+        sessions,
+        admission,
+        request_limits(env),
+        mcp::McpServer::default_tool_definitions(env).await,
+    );
+
+    tokio::spawn(async move {
+        if let Err(e) = service.serve(SocketAddr::from((ip, port))).await {
+            error!("gRPC server failed: {}", e);
+        }
+    });
+}
+
+#[cfg(not(feature = "grpc"))]
+async fn serve_grpc(
+    _: &Environment,
+    port: u16,
+    _: Arc<Agent>,
+    _: Arc<SessionStore>,
+    _: Arc<Admission>,
+) {
+    warn!(
+        "GRPC_PORT is set to {}, but this build lacks the grpc feature; not serving gRPC",
+        port
+    );
+}
+
+/// Answers a single prompt and prints the response to stdout.
+async fn chat(env: &Environment, prompt: String) {
+    let sessions = Arc::new(SessionStore::from_env(env));
+    let agent = build_agent(env, sessions);
+
+    match agent.chat(prompt, Vec::new(), ChatOptions::default()).await {
+        Ok(response) => println!("{}", response.text),
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Answers Telegram messages until the process exits.
+async fn telegram(env: &Environment) {
+    let Some(token) = &env.telegram_bot_token else {
+        error!("TELEGRAM_BOT_TOKEN (or TELEGRAM_BOT_TOKEN_FILE) is not set");
+        std::process::exit(1);
+    };
+    if env.telegram_allowed_users.is_empty() {
+        error!("TELEGRAM_ALLOWED_USERS must list at least one Telegram user id");
+        std::process::exit(1);
+    }
+
+    let sessions = Arc::new(SessionStore::from_env(env));
+    let agent = build_agent(env, sessions.clone());
+
+    TelegramBot::new(
+        agent,
+        sessions,
+        request_limits(env),
+        token,
+        env.telegram_allowed_users.clone(),
+    )
+    .run()
+    .await;
+}
+
+/// Prints the name and description of every tool exposed over MCP.
+async fn list_tools(env: &Environment) {
+    for definition in mcp::McpServer::default_tool_definitions(env).await {
+        println!("{}\n    {}\n", definition.name, definition.description);
+    }
+}
+
+/// Validates the configuration and checks that the home cluster is reachable with the
+/// configured credentials, reporting every problem and exiting non-zero if any.
+async fn check(env: &Environment) {
+    // Environment::new() has already exited with the parse error if the config file is invalid
+    let mut healthy = true;
+
+    match ConfigFile::path() {
+        Some(path) => println!("config file: {} (valid)", path),
+        None => println!("config file: none (environment variables only)"),
+    }
+
+    match env.validate() {
+        Ok(()) => println!("settings: valid"),
+        Err(problems) => {
+            for problem in problems {
+                println!("settings: {}", problem);
+            }
+            healthy = false;
+        }
+    }
+
+    // Listing a namespace exercises the token and RBAC, not just reachability
+    match home_kube_agent(env)
+        .get("/api/v1/namespaces?limit=1".to_string())
+        .await
+    {
+        Ok(_) => println!("kubernetes: {} reachable", env.kube_api_server),
+        Err(e) => {
+            println!("kubernetes: {} failed: {}", env.kube_api_server, e);
+            healthy = false;
+        }
+    }
+
+    if !healthy {
+        std::process::exit(1);
+    }
+}
+
+/// Serves the mock Kubernetes API on localhost until the process is stopped.
+#[cfg(feature = "test-support")]
+async fn mock_kube(port: u16) {
+    match kube::mock::MockKubeServer::start_on(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).await
+    {
+        Ok(server) => {
+            info!(
+                "Point the agent at it with KUBE_API_SERVER={} KUBE_TOKEN={}",
+                server.url(),
+                kube::mock::MOCK_TOKEN
+            );
+            server.wait().await;
+        }
+        Err(e) => {
+            error!("Failed to start the mock Kubernetes API: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the record/replay proxy on localhost until the process is stopped.
+#[cfg(feature = "test-support")]
+async fn vcr(env: &Environment, command: cli::VcrCommand) {
+    let proxy = match command {
+        cli::VcrCommand::Record {
+            upstream,
+            cassette,
+            port,
+            accept_invalid_certs,
+        } => {
+            // Credentials the agent sends through the proxy never reach the cassette
+            let secrets = vec![env.openai_api_key.clone(), env.kube_token.clone()];
+            let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+            vcr::VcrProxy::record(addr, &upstream, cassette, secrets, accept_invalid_certs).await
+        }
+        cli::VcrCommand::Replay { cassette, port } => {
+            vcr::VcrProxy::replay(SocketAddr::from((Ipv4Addr::LOCALHOST, port)), cassette).await
+        }
+    };
+    match proxy {
+        Ok(proxy) => {
+            info!(
+                "Proxy listening at {}; point KUBE_API_SERVER or OPENAI_BASE_URL at it",
+                proxy.url()
+            );
+            proxy.wait().await;
+        }
+        Err(e) => {
+            error!("Failed to start the record/replay proxy: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
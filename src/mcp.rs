@@ -0,0 +1,404 @@
+use crate::agent::tools::{
+    CiStatusTool, ContactMessageTool, FetchManyTool, FetchUrlTool, InternetSearchTool,
+    MailProvider, PageCache, PortfolioCrawler, PortfolioIndex, PortfolioPage, ProfileUrlList,
+    SearchPortfolioTool, SearchProvider, WebSearch,
+};
+use crate::environment::Environment;
+use crate::kube::cache::ResponseCache;
+use crate::kube::clusters::{load_clusters, DEFAULT_CLUSTER};
+use crate::kube::guard::WriteGuard;
+use crate::kube::throttle::ApiThrottle;
+use crate::kube::watch::{ClusterSnapshot, ClusterWatcher};
+use crate::kube::{
+    CanITool, CertificateExpiryTool, ClusterAlertsTool, CordonNodeTool, CustomResourcesTool,
+    DeletePodTool, DescribePodTool, DiagnosePodTool, DrainPlanTool, GetEventsForTool, KubeAgent,
+    ListClustersTool, ListConfigMapsTool, ListNamespacesTool, ListNetworkPoliciesTool,
+    ListPersistentVolumeClaimsTool, ListPodsTool, ListSecretsTool, NamespaceCostTool, NodeInfoTool,
+    NodeMetricsTool, RawKubeGetTool, RbacOverviewTool, ResourceQuotasTool, RolloutStatusTool,
+    ScaleDeploymentTool,
+};
+use rig::completion::ToolDefinition;
+use rig::tool::ToolDyn;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tracing::*;
+
+/// MCP protocol revision implemented by this server.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC 2.0 request or notification (notifications have no id).
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Parameters of a `tools/call` request.
+#[derive(Debug, Deserialize)]
+struct ToolCallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+/// Model Context Protocol server exposing the agent's tools over stdio.
+///
+/// Lets MCP clients (e.g. Claude Desktop) call the Kubernetes and portfolio tools
+/// directly, without going through /chat. Messages are newline-delimited JSON-RPC 2.0
+/// on stdin/stdout, so all logging must go to stderr while this server runs.
+pub struct McpServer {
+    tools: Vec<Box<dyn ToolDyn>>,
+}
+
+impl McpServer {
+    pub fn new(tools: Vec<Box<dyn ToolDyn>>) -> Self {
+        McpServer { tools }
+    }
+
+    /// Creates a server exposing the same tools the chat agent uses.
+    pub fn with_default_tools(env: &Environment) -> Self {
+        McpServer::build(env, true)
+    }
+
+    /// Definitions of the tools [`McpServer::with_default_tools`] exposes, built without
+    /// starting the cluster watcher or portfolio crawler behind them.
+    pub async fn default_tool_definitions(env: &Environment) -> Vec<ToolDefinition> {
+        McpServer::build(env, false).definitions().await
+    }
+
+    /// `background` starts the cluster watcher and portfolio crawler the tools read from.
+    fn build(env: &Environment, background: bool) -> Self {
+        let kube_cache = ResponseCache::new(
+            Duration::from_secs(env.kube_cache_ttl_secs),
+            &env.kube_cache_ttls,
+        );
+        let extra_clusters = env
+            .kube_clusters_file
+            .as_deref()
+            .map(load_clusters)
+            .unwrap_or_default();
+        let kube_agent = KubeAgent::new(
+            env.kube_api_server.clone(),
+            env.kube_token.clone(),
+            env.kube_certificate.clone(),
+        )
+        .with_timeouts(
+            Duration::from_secs(env.kube_connect_timeout_secs),
+            Duration::from_secs(env.kube_request_timeout_secs),
+        )
+        .with_accept_invalid_certs(env.kube_accept_invalid_certs)
+        .with_clusters(
+            env.cluster_name.as_deref().unwrap_or(DEFAULT_CLUSTER),
+            extra_clusters,
+        )
+        .with_cache(kube_cache)
+        .with_throttle(ApiThrottle::new(
+            env.kube_api_qps,
+            env.kube_api_burst,
+            env.kube_breaker_failures,
+            Duration::from_secs(env.kube_breaker_cooldown_secs),
+        ));
+
+        let portfolio_pages =
+            PortfolioPage::from_config(&env.portfolio_base_url, &env.portfolio_pages);
+
+        let page_cache = Arc::new(PageCache::new(Duration::from_secs(env.web_cache_ttl_secs)));
+        let fetch_url = FetchUrlTool::new(
+            env.fetch_domain_allowlist.clone(),
+            env.fetch_max_bytes,
+            Duration::from_secs(env.fetch_timeout_secs),
+            page_cache.clone(),
+        );
+
+        let portfolio_index = env.enable_portfolio_index.then(|| {
+            if !background {
+                return PortfolioIndex::default();
+            }
+            PortfolioCrawler::new(
+                &env.portfolio_base_url,
+                &portfolio_pages,
+                env.portfolio_crawl_max_depth,
+                env.portfolio_crawl_max_pages,
+                Duration::from_secs(env.portfolio_index_refresh_secs),
+            )
+            .spawn()
+        });
+
+        let mut tools: Vec<Box<dyn ToolDyn>> = vec![
+            Box::new(WebSearch::new(portfolio_pages.clone(), page_cache.clone())),
+            Box::new(ProfileUrlList::new(portfolio_pages)),
+            Box::new(fetch_url.clone()),
+            Box::new(FetchManyTool::new(fetch_url, env.fetch_max_concurrency)),
+            Box::new(ListPodsTool::new(kube_agent.for_tool::<ListPodsTool>())),
+            Box::new(ListNamespacesTool::new(
+                kube_agent.for_tool::<ListNamespacesTool>(),
+            )),
+            Box::new(NodeMetricsTool::new(
+                kube_agent.for_tool::<NodeMetricsTool>(),
+            )),
+            Box::new(DescribePodTool::new(
+                kube_agent.for_tool::<DescribePodTool>(),
+            )),
+            Box::new(DiagnosePodTool::new(
+                kube_agent.for_tool::<DiagnosePodTool>(),
+            )),
+            Box::new(GetEventsForTool::new(
+                kube_agent.for_tool::<GetEventsForTool>(),
+            )),
+            Box::new(ListConfigMapsTool::new(
+                kube_agent.for_tool::<ListConfigMapsTool>(),
+                env.configmap_value_allowlist.clone(),
+            )),
+            Box::new(ListSecretsTool::new(
+                kube_agent.for_tool::<ListSecretsTool>(),
+            )),
+            Box::new(CertificateExpiryTool::new(
+                kube_agent.for_tool::<CertificateExpiryTool>(),
+            )),
+            Box::new(ListPersistentVolumeClaimsTool::new(
+                kube_agent.for_tool::<ListPersistentVolumeClaimsTool>(),
+            )),
+            Box::new(ListNetworkPoliciesTool::new(
+                kube_agent.for_tool::<ListNetworkPoliciesTool>(),
+            )),
+            Box::new(NodeInfoTool::new(kube_agent.for_tool::<NodeInfoTool>())),
+            Box::new(ListClustersTool::new(kube_agent.clone())),
+            Box::new(RawKubeGetTool::new(
+                kube_agent.for_tool::<RawKubeGetTool>(),
+                env.raw_get_path_allowlist.clone(),
+                env.raw_get_max_bytes,
+            )),
+            Box::new(CanITool::new(kube_agent.clone())),
+            Box::new(RbacOverviewTool::new(
+                kube_agent.for_tool::<RbacOverviewTool>(),
+            )),
+            Box::new(ResourceQuotasTool::new(
+                kube_agent.for_tool::<ResourceQuotasTool>(),
+            )),
+            Box::new(RolloutStatusTool::new(
+                kube_agent.for_tool::<RolloutStatusTool>(),
+            )),
+            Box::new(DrainPlanTool::new(kube_agent.for_tool::<DrainPlanTool>())),
+            Box::new(CustomResourcesTool::new(
+                kube_agent.for_tool::<CustomResourcesTool>(),
+            )),
+            Box::new(NamespaceCostTool::new(
+                kube_agent.for_tool::<NamespaceCostTool>(),
+                env.price_table(),
+            )),
+        ];
+
+        if env.enable_cluster_watch {
+            let snapshot = if background {
+                ClusterWatcher::new(kube_agent.clone(), env.notifier()).spawn()
+            } else {
+                ClusterSnapshot::default()
+            };
+            tools.push(Box::new(ClusterAlertsTool::new(snapshot)));
+        }
+
+        if let Some(index) = portfolio_index {
+            tools.push(Box::new(SearchPortfolioTool::new(index)));
+        }
+
+        if let Some(key) = &env.search_api_key {
+            match env.search_api_provider.parse::<SearchProvider>() {
+                Ok(provider) => {
+                    tools.push(Box::new(InternetSearchTool::new(provider, key.clone())))
+                }
+                Err(e) => warn!("Not registering internet_search: {}", e),
+            }
+        }
+
+        if !env.github_repos.is_empty() {
+            tools.push(Box::new(CiStatusTool::new(
+                env.github_repos.clone(),
+                env.github_token.clone(),
+            )));
+        }
+
+        if let (Some(key), Some(from), Some(to)) = (
+            &env.contact_mail_api_key,
+            &env.contact_email_from,
+            &env.contact_email_to,
+        ) {
+            match env.contact_mail_provider.parse::<MailProvider>() {
+                Ok(provider) => tools.push(Box::new(ContactMessageTool::new(
+                    provider,
+                    key.clone(),
+                    from.clone(),
+                    to.clone(),
+                    env.contact_max_per_hour,
+                ))),
+                Err(e) => warn!("Not registering send_contact_message: {}", e),
+            }
+        }
+
+        if env.enable_write_tools {
+            let guard = WriteGuard::new(
+                env.enable_write_tools,
+                env.write_namespace_allowlist.clone(),
+            );
+            tools.push(Box::new(ScaleDeploymentTool::new(
+                kube_agent.clone(),
+                guard.clone(),
+            )));
+            tools.push(Box::new(DeletePodTool::new(
+                kube_agent.clone(),
+                guard.clone(),
+                env.delete_pod_label_allowlist.clone(),
+            )));
+            tools.push(Box::new(CordonNodeTool::new(kube_agent, guard)));
+        }
+
+        McpServer::new(tools)
+    }
+
+    /// Serves requests from stdin until it is closed.
+    pub async fn serve_stdio(&self) -> io::Result<()> {
+        info!(
+            "MCP server listening on stdio with {} tools",
+            self.tools.len()
+        );
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(response) = self.handle_message(&line).await {
+                stdout.write_all(response.to_string().as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+        }
+
+        info!("MCP client closed stdin, shutting down");
+        Ok(())
+    }
+
+    /// Handles one JSON-RPC message, returning the response (None for notifications).
+    async fn handle_message(&self, line: &str) -> Option<Value> {
+        let request: RpcRequest = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Invalid MCP message: {}", e);
+                return Some(error_response(Value::Null, PARSE_ERROR, &e.to_string()));
+            }
+        };
+
+        debug!("MCP request: {}", request.method);
+
+        // Notifications (e.g. notifications/initialized) never get a response
+        let id = request.id?;
+
+        let result = match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION")
+                }
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools().await),
+            "tools/call" => self.call_tool(request.params).await,
+            other => Err((METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    /// Definitions of every exposed tool, in registration order.
+    pub async fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions = Vec::new();
+        for tool in &self.tools {
+            definitions.push(tool.definition(String::new()).await);
+        }
+        definitions
+    }
+
+    async fn list_tools(&self) -> Value {
+        let tools: Vec<Value> = self
+            .definitions()
+            .await
+            .into_iter()
+            .map(|definition| {
+                json!({
+                    "name": definition.name,
+                    "description": definition.description,
+                    "inputSchema": definition.parameters,
+                })
+            })
+            .collect();
+        json!({ "tools": tools })
+    }
+
+    async fn call_tool(&self, params: Value) -> Result<Value, (i64, String)> {
+        let params: ToolCallParams = serde_json::from_value(params)
+            .map_err(|e| (INVALID_PARAMS, format!("Invalid tools/call params: {}", e)))?;
+
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name() == params.name)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown tool: {}", params.name)))?;
+
+        let arguments = if params.arguments.is_null() {
+            "{}".to_string()
+        } else {
+            params.arguments.to_string()
+        };
+
+        info!("MCP tool call: {} {}", params.name, arguments);
+
+        // Tool failures are reported as tool results so the client's model can see them
+        Ok(match tool.call(arguments).await {
+            Ok(output) => json!({
+                "content": [{ "type": "text", "text": unquote(output) }],
+                "isError": false
+            }),
+            Err(e) => {
+                warn!("MCP tool {} failed: {}", params.name, e);
+                json!({
+                    "content": [{ "type": "text", "text": e.to_string() }],
+                    "isError": true
+                })
+            }
+        })
+    }
+}
+
+/// Tool output arrives JSON-serialized; plain strings are unwrapped for readability.
+fn unquote(output: String) -> String {
+    match serde_json::from_str::<Value>(&output) {
+        Ok(Value::String(text)) => text,
+        _ => output,
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+}
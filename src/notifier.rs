@@ -0,0 +1,220 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::*;
+
+/// Time allowed for a single delivery attempt.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first retry, doubling with each further attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// What a notification is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A new problem found by the cluster watcher
+    ClusterAlert,
+    /// A chat request over its latency, token, or tool-call threshold
+    RequestOverrun,
+    /// A dependency failing the deep health check
+    HealthCheckFailed,
+    /// A chat client shadow-banned after repeated flagged prompts
+    AbuseShadowBan,
+    /// The day's summary of the audit log
+    DailyDigest,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EventKind::ClusterAlert => "cluster_alert",
+            EventKind::RequestOverrun => "request_overrun",
+            EventKind::HealthCheckFailed => "health_check_failed",
+            EventKind::AbuseShadowBan => "abuse_shadow_ban",
+            EventKind::DailyDigest => "daily_digest",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An event to push to the configured webhooks.
+pub struct Notification {
+    pub kind: EventKind,
+    /// Human-readable summary, one line except for digests
+    pub text: String,
+    /// Event-specific fields, as a JSON object
+    pub details: Value,
+}
+
+/// Body format a webhook expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    /// `{"event", "text", "timestamp", "details"}`
+    Generic,
+    /// `{"text"}` plus the event's details, as Slack incoming webhooks accept
+    Slack,
+}
+
+/// A webhook and the events it receives.
+pub struct Webhook {
+    url: String,
+    format: WebhookFormat,
+    /// Every event when None
+    events: Option<Vec<EventKind>>,
+}
+
+impl Webhook {
+    pub fn new(url: String, format: WebhookFormat) -> Self {
+        Webhook {
+            url,
+            format,
+            events: None,
+        }
+    }
+
+    /// Limits the webhook to the given kinds of event.
+    pub fn only(mut self, events: &[EventKind]) -> Self {
+        self.events = Some(events.to_vec());
+        self
+    }
+
+    fn wants(&self, kind: EventKind) -> bool {
+        match &self.events {
+            Some(events) => events.contains(&kind),
+            None => true,
+        }
+    }
+
+    fn body(&self, notification: &Notification) -> Value {
+        match self.format {
+            WebhookFormat::Generic => json!({
+                "event": notification.kind,
+                "text": notification.text,
+                "timestamp": Utc::now(),
+                "details": notification.details,
+            }),
+            WebhookFormat::Slack => {
+                let mut body = json!({ "text": notification.text });
+                if let (Some(body), Some(details)) =
+                    (body.as_object_mut(), notification.details.as_object())
+                {
+                    for (key, value) in details {
+                        body.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
+                body
+            }
+        }
+    }
+}
+
+/// Pushes events to webhooks in the background, retrying failed deliveries.
+///
+/// When a secret is configured, every delivery carries an `X-Signature-256` header of
+/// `sha256=<hex HMAC-SHA256 of "<X-Signature-Timestamp>.<body>">`, so receivers can check
+/// it came from this agent and isn't a replay. Cloning is cheap; the default notifier has
+/// no webhooks and drops every event.
+#[derive(Clone, Default)]
+pub struct Notifier {
+    client: reqwest::Client,
+    webhooks: Arc<Vec<Webhook>>,
+    secret: Option<Arc<str>>,
+    max_attempts: u32,
+}
+
+impl Notifier {
+    pub fn new(webhooks: Vec<Webhook>, secret: Option<String>, max_attempts: u32) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Notifier {
+            client,
+            webhooks: Arc::new(webhooks),
+            secret: secret.map(Arc::from),
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Sends `notification` to every webhook that wants it, without waiting for delivery.
+    pub fn notify(&self, notification: Notification) {
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            if !webhook.wants(notification.kind) {
+                continue;
+            }
+            let body = webhook.body(&notification).to_string();
+            let notifier = self.clone();
+            let kind = notification.kind;
+            tokio::spawn(async move { notifier.deliver(index, kind, body).await });
+        }
+    }
+
+    async fn deliver(&self, index: usize, kind: EventKind, body: String) {
+        let webhook = &self.webhooks[index];
+        let mut delay = RETRY_BASE_DELAY;
+
+        for attempt in 1..=self.max_attempts {
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .header("Content-Type", "application/json");
+            if let Some(secret) = &self.secret {
+                let timestamp = Utc::now().timestamp().to_string();
+                request = request
+                    .header("X-Signature-Timestamp", &timestamp)
+                    .header("X-Signature-256", sign(secret, &timestamp, &body));
+            }
+
+            let error = match request.body(body.clone()).send().await {
+                Ok(response) if response.status().is_success() => {
+                    debug!("Delivered {} notification to webhook {}", kind, index);
+                    return;
+                }
+                // Other client errors won't succeed on a retry
+                Ok(response)
+                    if response.status().is_client_error()
+                        && response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    warn!(
+                        "Webhook {} rejected {} notification with {}",
+                        index,
+                        kind,
+                        response.status()
+                    );
+                    return;
+                }
+                Ok(response) => format!("webhook answered {}", response.status()),
+                Err(e) => e.without_url().to_string(),
+            };
+
+            if attempt == self.max_attempts {
+                warn!(
+                    "Giving up on {} notification to webhook {} after {} attempts: {}",
+                    kind, index, attempt, error
+                );
+                return;
+            }
+            debug!(
+                "Notification to webhook {} failed (attempt {}), retrying in {:?}: {}",
+                index, attempt, delay, error
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
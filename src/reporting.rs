@@ -0,0 +1,50 @@
+use sentry::integrations::tracing::{EventFilter, SentryLayer};
+use sentry::{ClientInitGuard, Hub};
+use std::sync::Arc;
+use tracing::{Level, Metadata, Subscriber};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Starts reporting errors to the Sentry-compatible `dsn`. Panics are captured
+/// automatically; reports stop when the returned guard is dropped.
+pub fn init(dsn: &str, environment: &'static str) -> ClientInitGuard {
+    sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: Some(environment.into()),
+            ..Default::default()
+        },
+    ))
+}
+
+/// Tracing layer that turns `error!` events and failed tool calls into reports, keeping
+/// lower-level events as breadcrumbs for context.
+pub fn layer<S>() -> SentryLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    sentry::integrations::tracing::layer().event_filter(event_filter)
+}
+
+fn event_filter(metadata: &Metadata<'_>) -> EventFilter {
+    match *metadata.level() {
+        Level::ERROR => EventFilter::Event,
+        // Failed tool calls (e.g. Kubernetes API errors) are warnings with a `tool` field
+        Level::WARN if metadata.fields().field("tool").is_some() => EventFilter::Event,
+        Level::WARN | Level::INFO => EventFilter::Breadcrumb,
+        _ => EventFilter::Ignore,
+    }
+}
+
+/// Hub for one request, tagging its reports with `request_id`. Bind it to the request's
+/// future with `SentryFutureExt::bind_hub` so `tag` calls don't leak into other requests.
+pub fn request_hub(request_id: &str) -> Arc<Hub> {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| scope.set_tag("request_id", request_id));
+    hub
+}
+
+/// Tags reports from the current request with `name=value`, e.g. its session id.
+pub fn tag(name: &str, value: &str) {
+    sentry::configure_scope(|scope| scope.set_tag(name, value));
+}
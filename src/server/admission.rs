@@ -0,0 +1,260 @@
+use super::audit::{AuditLog, ChatAudit};
+use super::auth::{self, ApiKeys, KeyCheck};
+use super::lockout::AuthLockout;
+use super::moderation::{ChatClient, Moderation, Standing};
+use super::overrun::RequestThresholds;
+use super::shedding::{self, LoadShedding};
+use crate::agent::permissions::{Capabilities, Capability};
+use crate::agent::Agent;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::*;
+
+/// Why a request was turned away before reaching the agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refusal {
+    /// The source is locked out for this much longer after repeated bad API keys
+    LockedOut(Duration),
+    MissingKey,
+    /// The API key was refused, for the given reason
+    BadKey(&'static str),
+    /// The API key lacks the capability the endpoint needs
+    NotPermitted,
+    /// The models are degraded and the caller isn't an admin
+    Shed,
+    /// The client is refused for this much longer after too many flagged prompts
+    CoolingDown(Duration),
+    Flagged,
+}
+
+impl Refusal {
+    /// The HTTP status line the refusal is answered (and audited) with.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Refusal::LockedOut(_) | Refusal::CoolingDown(_) => "429 Too Many Requests",
+            Refusal::MissingKey => "401 Unauthorized",
+            Refusal::BadKey(_) | Refusal::NotPermitted => "403 Forbidden",
+            Refusal::Shed => "503 Service Unavailable",
+            Refusal::Flagged => "400 Bad Request",
+        }
+    }
+
+    /// The message sent to the caller.
+    pub fn message(&self) -> &'static str {
+        match self {
+            Refusal::LockedOut(_) => "Too many failed API key attempts",
+            Refusal::MissingKey => "Missing API key",
+            Refusal::BadKey(reason) => reason,
+            Refusal::NotPermitted => "Not permitted for this endpoint",
+            Refusal::Shed => "The agent is overloaded; try again shortly",
+            Refusal::CoolingDown(_) => "Too many flagged messages; try again later",
+            Refusal::Flagged => "Message flagged by moderation",
+        }
+    }
+
+    /// Seconds the caller should wait before trying again, if waiting helps.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Refusal::LockedOut(remaining) | Refusal::CoolingDown(remaining) => {
+                Some(remaining.as_secs().max(1))
+            }
+            Refusal::Shed => Some(shedding::RETRY_AFTER_SECS),
+            Refusal::MissingKey | Refusal::BadKey(_) | Refusal::NotPermitted | Refusal::Flagged => {
+                None
+            }
+        }
+    }
+}
+
+/// How an admitted chat request is answered.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Admitted {
+    /// By the agent
+    Agent,
+    /// With [`super::moderation::SHADOW_BAN_REPLY`], without asking the agent
+    ShadowBanned,
+}
+
+/// Decides which requests reach the agent, and records the chats that did.
+///
+/// The HTTP and gRPC APIs share one, so a source locked out or a client cooling down
+/// or shadow-banned over one API is treated the same over the other, and chats over
+/// both land in the audit log, and so in usage reports and daily digests.
+pub struct Admission {
+    agent: Arc<Agent>,
+    api_keys: ApiKeys,
+    audit_log: Option<Arc<AuditLog>>,
+    thresholds: RequestThresholds,
+    shedding: LoadShedding,
+    lockout: Option<AuthLockout>,
+    moderation: Option<Moderation>,
+}
+
+impl Admission {
+    pub fn new(agent: Arc<Agent>, api_keys: ApiKeys, audit_log: Option<Arc<AuditLog>>) -> Self {
+        Admission {
+            agent,
+            api_keys,
+            audit_log,
+            thresholds: RequestThresholds::default(),
+            shedding: LoadShedding::default(),
+            lockout: None,
+            moderation: None,
+        }
+    }
+
+    /// Reports chat requests that exceed `thresholds` (none are reported by default).
+    pub fn with_thresholds(mut self, thresholds: RequestThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Refuses chat requests from non-admin keys while the models breach the
+    /// thresholds of `shedding` (nothing is shed by default).
+    pub fn with_load_shedding(mut self, shedding: LoadShedding) -> Self {
+        self.shedding = shedding;
+        self
+    }
+
+    /// Locks out clients that repeatedly present invalid API keys.
+    pub fn with_auth_lockout(mut self, lockout: AuthLockout) -> Self {
+        self.lockout = Some(lockout);
+        self
+    }
+
+    /// Screens prompts from non-admin keys with `moderation`, throttling clients whose
+    /// prompts keep getting flagged.
+    pub fn with_moderation(mut self, moderation: Moderation) -> Self {
+        self.moderation = Some(moderation);
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_deref()
+    }
+
+    pub fn moderation(&self) -> Option<&Moderation> {
+        self.moderation.as_ref()
+    }
+
+    /// Checks the `api_key` presented from `source`, returning the key's name and the
+    /// capabilities it grants.
+    ///
+    /// Sources locked out for repeated bad keys are refused before the key is checked.
+    pub fn authenticate(
+        &self,
+        api_key: Option<&str>,
+        source: Option<IpAddr>,
+    ) -> Result<(String, Capabilities), Refusal> {
+        let lockout = self.lockout.as_ref().zip(source);
+        if let Some(remaining) = lockout.and_then(|(lockout, source)| lockout.locked_out(source)) {
+            debug!("Refusing request from locked-out client");
+            return Err(Refusal::LockedOut(remaining));
+        }
+
+        let Some(api_key) = api_key else {
+            warn!("Request missing API key");
+            return Err(Refusal::MissingKey);
+        };
+        let reason = match self.api_keys.verify(api_key) {
+            KeyCheck::Valid(name, capabilities) => {
+                debug!("API key {} validated successfully", name);
+                if let Some((lockout, source)) = lockout {
+                    lockout.record_success(source);
+                }
+                return Ok((name.to_string(), capabilities.clone()));
+            }
+            KeyCheck::Expired(name) => {
+                warn!(
+                    "Expired API key {} used ({})",
+                    name,
+                    auth::key_prefix(api_key)
+                );
+                "API key expired"
+            }
+            KeyCheck::NotYetValid(name) => {
+                warn!(
+                    "API key {} used before its validity window ({})",
+                    name,
+                    auth::key_prefix(api_key)
+                );
+                "API key not yet valid"
+            }
+            KeyCheck::Invalid => {
+                warn!("Invalid API key attempt ({})", auth::key_prefix(api_key));
+                if let Some((lockout, source)) = lockout {
+                    lockout.record_failure(source);
+                }
+                "Invalid API key"
+            }
+        };
+        Err(Refusal::BadKey(reason))
+    }
+
+    /// Decides whether `prompt`, from the key `identity` with `capabilities` at
+    /// `address`, goes to the agent.
+    ///
+    /// Requests from non-admin keys are shed while the models are degraded, and are
+    /// moderated when moderation is on. Admin requests are always admitted.
+    pub async fn admit_chat(
+        &self,
+        identity: &str,
+        address: Option<IpAddr>,
+        capabilities: &Capabilities,
+        prompt: &str,
+    ) -> Result<Admitted, Refusal> {
+        if capabilities.allows(Capability::Admin) {
+            return Ok(Admitted::Agent);
+        }
+
+        if self.shedding.is_enabled() {
+            let health = self.agent.model_health(self.shedding.window);
+            if let Some(reason) = self.shedding.reason(&health) {
+                warn!("Shedding chat request: {}", reason);
+                return Err(Refusal::Shed);
+            }
+        }
+
+        let Some(moderation) = &self.moderation else {
+            return Ok(Admitted::Agent);
+        };
+        let client = ChatClient {
+            key: identity.to_string(),
+            address,
+        };
+        match moderation.standing(&client) {
+            Standing::Allowed => {}
+            Standing::CoolingDown(remaining) => {
+                warn!("Refusing chat request from a client cooling down");
+                return Err(Refusal::CoolingDown(remaining));
+            }
+            Standing::ShadowBanned => {
+                info!("Answering shadow-banned client with the canned reply");
+                return Ok(Admitted::ShadowBanned);
+            }
+        }
+        if let Some(categories) = moderation.check(&client, prompt).await {
+            warn!("Refusing prompt flagged for {}", categories.join(", "));
+            return Err(Refusal::Flagged);
+        }
+        Ok(Admitted::Agent)
+    }
+
+    /// Records a finished chat request from `identity`, who presented `api_key` unless
+    /// identified by a client certificate, in the audit log, and reports it if it
+    /// exceeded the thresholds.
+    pub fn record(
+        &self,
+        request_id: &str,
+        identity: &str,
+        api_key: Option<&str>,
+        audit: &ChatAudit,
+        latency: Duration,
+    ) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(request_id, identity, api_key, audit, latency);
+        }
+        self.thresholds.check(request_id, audit, latency);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::agent::tokens::estimate_tokens;
+use crate::agent::trace::ToolCallRecord;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, prelude::*, BufReader};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// How often expired records are pruned while the server is running.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// What the chat handler learned about one /chat request or gRPC chat call, filled in
+/// as it goes.
+#[derive(Debug, Default)]
+pub struct ChatAudit {
+    /// The caller's address, as reported by trusted proxies
+    pub client_ip: Option<IpAddr>,
+    pub session_id: Option<String>,
+    pub prompt: Option<String>,
+    pub response: Option<String>,
+    pub model: Option<String>,
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// HTTP status line sent back, e.g. "200 OK"; for streamed responses and gRPC calls,
+    /// the status the outcome maps to
+    pub status: &'static str,
+}
+
+/// One line of the audit log
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    timestamp: DateTime<Utc>,
+    request_id: &'a str,
+    /// The API key's name, or the client certificate's identity
+    identity: &'a str,
+    /// SHA-256 of the caller's API key, so keys can be told apart without being stored
+    api_key_sha256: Option<String>,
+    client_ip: Option<IpAddr>,
+    session_id: Option<&'a str>,
+    status: u16,
+    latency_ms: u64,
+    prompt: Option<&'a str>,
+    response: Option<&'a str>,
+    model: Option<&'a str>,
+    /// Estimated with the same heuristic as the request limits
+    prompt_tokens: Option<usize>,
+    response_tokens: Option<usize>,
+    tool_calls: &'a [ToolCallRecord],
+}
+
+/// The only field pruning needs from a record
+#[derive(Deserialize)]
+struct Timestamped {
+    timestamp: DateTime<Utc>,
+}
+
+/// The fields of a record the usage report needs
+#[derive(Debug, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub identity: String,
+    pub status: u16,
+    pub prompt_tokens: Option<usize>,
+    pub response_tokens: Option<usize>,
+}
+
+/// The fields of a record the daily digest needs
+#[derive(Debug, Deserialize)]
+pub struct DigestRecord {
+    pub timestamp: DateTime<Utc>,
+    pub status: u16,
+    pub prompt: Option<String>,
+    pub prompt_tokens: Option<usize>,
+    pub response_tokens: Option<usize>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCallRecord>,
+}
+
+struct LogFile {
+    file: File,
+    last_pruned: Instant,
+}
+
+/// Append-only JSONL log of every chat request, over HTTP or gRPC, and how it was
+/// answered.
+///
+/// Records older than the retention period are dropped when the log is opened and
+/// then hourly; a retention of zero days keeps everything.
+pub struct AuditLog {
+    path: PathBuf,
+    retention_days: u64,
+    log: Mutex<LogFile>,
+}
+
+impl AuditLog {
+    /// Opens the log at `path`, creating it if needed.
+    pub fn open(path: impl Into<PathBuf>, retention_days: u64) -> io::Result<Self> {
+        let path = path.into();
+        if retention_days > 0 {
+            prune(&path, retention_days)?;
+        }
+        let file = open_append(&path)?;
+        info!("Auditing chat requests to {}", path.display());
+        Ok(AuditLog {
+            path,
+            retention_days,
+            log: Mutex::new(LogFile {
+                file,
+                last_pruned: Instant::now(),
+            }),
+        })
+    }
+
+    /// Appends a record for a finished request from `identity`, who presented `api_key`
+    /// unless identified by a client certificate. Failures are logged, never returned, so
+    /// a full disk doesn't fail the chat itself.
+    pub fn record(
+        &self,
+        request_id: &str,
+        identity: &str,
+        api_key: Option<&str>,
+        audit: &ChatAudit,
+        latency: Duration,
+    ) {
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            request_id,
+            identity,
+            api_key_sha256: api_key.map(|key| format!("{:x}", Sha256::digest(key.as_bytes()))),
+            client_ip: audit.client_ip,
+            session_id: audit.session_id.as_deref(),
+            status: audit
+                .status
+                .split(' ')
+                .next()
+                .and_then(|code| code.parse().ok())
+                .unwrap_or_default(),
+            latency_ms: latency.as_millis() as u64,
+            prompt: audit.prompt.as_deref(),
+            response: audit.response.as_deref(),
+            model: audit.model.as_deref(),
+            prompt_tokens: audit.prompt.as_deref().map(estimate_tokens),
+            response_tokens: audit.response.as_deref().map(estimate_tokens),
+            tool_calls: &audit.tool_calls,
+        };
+        let mut line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize audit record: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+
+        let mut log = self.log.lock().unwrap();
+        if self.retention_days > 0 && log.last_pruned.elapsed() >= PRUNE_INTERVAL {
+            log.last_pruned = Instant::now();
+            // Pruning replaces the file, so the handle must be reopened
+            match prune(&self.path, self.retention_days).and_then(|()| open_append(&self.path)) {
+                Ok(file) => log.file = file,
+                Err(e) => warn!("Failed to prune audit log {}: {}", self.path.display(), e),
+            }
+        }
+        if let Err(e) = log.file.write_all(line.as_bytes()) {
+            warn!(
+                "Failed to write audit record to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+
+    /// Reads the records written since `since`, skipping lines that can't be parsed.
+    pub fn usage_records(&self, since: DateTime<Utc>) -> io::Result<Vec<UsageRecord>> {
+        self.read_since(since, |record: &UsageRecord| record.timestamp)
+    }
+
+    /// Like [`Self::usage_records`], with the prompts and tool calls too.
+    pub fn digest_records(&self, since: DateTime<Utc>) -> io::Result<Vec<DigestRecord>> {
+        self.read_since(since, |record: &DigestRecord| record.timestamp)
+    }
+
+    fn read_since<T: DeserializeOwned>(
+        &self,
+        since: DateTime<Utc>,
+        timestamp: fn(&T) -> DateTime<Utc>,
+    ) -> io::Result<Vec<T>> {
+        // Holding the log keeps an hourly prune from replacing the file mid-read
+        let _log = self.log.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            match serde_json::from_str::<T>(&line?) {
+                Ok(record) if timestamp(&record) >= since => records.push(record),
+                Ok(_) => {}
+                Err(e) => debug!("Skipping unreadable audit record: {}", e),
+            }
+        }
+        Ok(records)
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+// Rewrites the log without records older than `retention_days`, keeping any line that
+// can't be parsed rather than losing it
+fn prune(path: &Path, retention_days: u64) -> io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+
+    let mut kept = String::new();
+    let mut pruned = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let expired = serde_json::from_str::<Timestamped>(&line)
+            .is_ok_and(|record| record.timestamp < cutoff);
+        if expired {
+            pruned += 1;
+        } else {
+            kept.push_str(&line);
+            kept.push('\n');
+        }
+    }
+    if pruned == 0 {
+        return Ok(());
+    }
+
+    let tmp = path.with_extension("pruning");
+    fs::write(&tmp, kept)?;
+    fs::rename(&tmp, path)?;
+    info!(
+        "Pruned {} audit records older than {} days from {}",
+        pruned,
+        retention_days,
+        path.display()
+    );
+    Ok(())
+}
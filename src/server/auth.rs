@@ -0,0 +1,224 @@
+use crate::agent::permissions::Capabilities;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use tracing::*;
+
+/// Characters of a presented key that may appear in logs.
+const LOGGED_PREFIX_CHARS: usize = 4;
+
+/// A configured API key, kept only as a hash.
+enum StoredKey {
+    Sha256([u8; 32]),
+    /// An argon2 PHC string, e.g. `$argon2id$v=19$m=19456,t=2,p=1$...`
+    Argon2(String),
+}
+
+impl StoredKey {
+    /// Parses `sha256:<64 hex digits>` or an argon2 PHC string.
+    fn parse_hash(hash: &str) -> Result<Self, String> {
+        let hash = hash.trim();
+        if let Some(hex) = hash.strip_prefix("sha256:") {
+            return decode_sha256(hex)
+                .map(StoredKey::Sha256)
+                .ok_or_else(|| "sha256 key hashes must be 64 hex digits".to_string());
+        }
+        if hash.starts_with("$argon2") {
+            return PasswordHash::new(hash)
+                .map(|_| StoredKey::Argon2(hash.to_string()))
+                .map_err(|e| format!("invalid argon2 key hash: {}", e));
+        }
+        Err("key hashes must start with sha256: or $argon2".to_string())
+    }
+
+    fn plain(key: &str) -> Self {
+        StoredKey::Sha256(Sha256::digest(key.as_bytes()).into())
+    }
+
+    fn matches(&self, presented: &str) -> bool {
+        match self {
+            // Comparing digests keeps the time taken independent of the key's length
+            StoredKey::Sha256(hash) => Sha256::digest(presented.as_bytes())
+                .as_slice()
+                .ct_eq(hash)
+                .into(),
+            StoredKey::Argon2(phc) => PasswordHash::new(phc).is_ok_and(|hash| {
+                Argon2::default()
+                    .verify_password(presented.as_bytes(), &hash)
+                    .is_ok()
+            }),
+        }
+    }
+}
+
+/// An accepted key and the window it is valid in.
+struct ApiKey {
+    /// Name used in logs, e.g. `CHAT_API_KEY` or the keys file entry's name
+    name: String,
+    key: StoredKey,
+    not_before: Option<DateTime<Utc>>,
+    expires: Option<DateTime<Utc>>,
+    capabilities: Capabilities,
+}
+
+/// Outcome of checking a presented API key.
+#[derive(Debug)]
+pub enum KeyCheck<'a> {
+    /// Matches the named key, which is currently valid and grants these capabilities
+    Valid(&'a str, &'a Capabilities),
+    /// Matches the named key, but its validity window hasn't started yet
+    NotYetValid(&'a str),
+    /// Matches the named key, but it has expired
+    Expired(&'a str),
+    /// Matches no configured key
+    Invalid,
+}
+
+#[derive(Deserialize)]
+struct KeysFile {
+    keys: Vec<KeyEntry>,
+}
+
+#[derive(Deserialize)]
+struct KeyEntry {
+    name: String,
+    hash: String,
+    not_before: Option<DateTime<Utc>>,
+    expires: Option<DateTime<Utc>>,
+    /// Everything is allowed when omitted
+    capabilities: Option<Capabilities>,
+}
+
+/// The API keys the server accepts.
+///
+/// A plain `CHAT_API_KEY` is hashed as soon as it is loaded, and `CHAT_API_KEY_HASH`
+/// lets the configuration hold only a hash. Keys from `CHAT_API_KEYS_FILE` may also
+/// carry a validity window, so a new key can be added before the old one expires, and
+/// a list of capabilities limiting what they may do.
+/// Presented keys are checked in constant time.
+pub struct ApiKeys {
+    keys: Vec<ApiKey>,
+}
+
+impl ApiKeys {
+    /// Accepts `plain` (unless empty), the key whose hash is `hash`, and the keys listed
+    /// in `keys_file`, if given.
+    pub fn new(plain: &str, hash: Option<&str>, keys_file: Option<&str>) -> Result<Self, String> {
+        let mut keys = Vec::new();
+        if !plain.trim().is_empty() {
+            keys.push(ApiKey::always("CHAT_API_KEY", StoredKey::plain(plain)));
+        }
+        if let Some(hash) = hash {
+            let key =
+                StoredKey::parse_hash(hash).map_err(|e| format!("CHAT_API_KEY_HASH: {}", e))?;
+            keys.push(ApiKey::always("CHAT_API_KEY_HASH", key));
+        }
+        if let Some(path) = keys_file {
+            keys.extend(load_keys(path)?);
+        }
+        Ok(ApiKeys { keys })
+    }
+
+    /// Whether no key at all is accepted.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Checks `presented` against every accepted key.
+    pub fn verify(&self, presented: &str) -> KeyCheck<'_> {
+        let now = Utc::now();
+        // Every key is hashed and compared, so timing doesn't reveal which one matched
+        let mut checks: Vec<KeyCheck> = self
+            .keys
+            .iter()
+            .filter(|key| key.key.matches(presented))
+            .map(|key| key.check(now))
+            .collect();
+        // The same key may be listed twice during a rotation; a valid listing wins
+        checks.sort_by_key(|check| !matches!(check, KeyCheck::Valid(..)));
+        checks.into_iter().next().unwrap_or(KeyCheck::Invalid)
+    }
+}
+
+impl ApiKey {
+    fn always(name: &str, key: StoredKey) -> Self {
+        ApiKey {
+            name: name.to_string(),
+            key,
+            not_before: None,
+            expires: None,
+            capabilities: Capabilities::all(),
+        }
+    }
+
+    fn check(&self, now: DateTime<Utc>) -> KeyCheck<'_> {
+        if self.not_before.is_some_and(|not_before| now < not_before) {
+            KeyCheck::NotYetValid(&self.name)
+        } else if self.expires.is_some_and(|expires| now >= expires) {
+            KeyCheck::Expired(&self.name)
+        } else {
+            KeyCheck::Valid(&self.name, &self.capabilities)
+        }
+    }
+}
+
+/// Loads keys from a YAML file of the form:
+///
+/// ```yaml
+/// keys:
+///   - name: widget-2026-q1
+///     hash: sha256:9f86d08...           # or an argon2 PHC string
+///     expires: 2026-04-01T00:00:00Z     # optional
+///   - name: widget-2026-q2
+///     hash: $argon2id$v=19$...
+///     not_before: 2026-03-15T00:00:00Z  # optional
+///     capabilities: [chat]              # optional, defaults to all
+/// ```
+///
+/// Entries with an invalid hash are skipped with a warning, leaving that key rejected.
+fn load_keys(path: &str) -> Result<Vec<ApiKey>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read keys file {}: {}", path, e))?;
+    let file: KeysFile = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("failed to parse keys file {}: {}", path, e))?;
+
+    Ok(file
+        .keys
+        .into_iter()
+        .filter_map(|entry| match StoredKey::parse_hash(&entry.hash) {
+            Ok(key) => {
+                debug!("Loaded API key {}", entry.name);
+                Some(ApiKey {
+                    name: entry.name,
+                    key,
+                    not_before: entry.not_before,
+                    expires: entry.expires,
+                    capabilities: entry.capabilities.unwrap_or_else(Capabilities::all),
+                })
+            }
+            Err(e) => {
+                warn!("Skipping API key {}: {}", entry.name, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// The start of a presented key, enough to tell keys apart in logs without leaking them.
+pub fn key_prefix(key: &str) -> String {
+    let prefix: String = key.chars().take(LOGGED_PREFIX_CHARS).collect();
+    format!("{}…", prefix)
+}
+
+fn decode_sha256(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
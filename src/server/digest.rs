@@ -0,0 +1,235 @@
+use super::audit::{AuditLog, DigestRecord};
+use super::usage::UsageSettings;
+use crate::notifier::{EventKind, Notification, Notifier};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::*;
+
+/// Most asked questions listed in a digest.
+const MAX_TOP_QUESTIONS: usize = 5;
+
+/// Characters of a question shown in a digest; longer ones are cut off.
+const MAX_QUESTION_CHARS: usize = 120;
+
+/// Background task that sums up the last day of the audit log every day at `hour` UTC
+/// and sends it as a `daily_digest` notification.
+///
+/// The digest counts requests, errors (responses of 400 and up), and the estimated
+/// spend at the usage report's token prices, and lists the most asked questions and
+/// how often each tool was called. Questions are told apart ignoring case and spacing,
+/// so only repeats of the same wording add up. A day without requests still gets a
+/// digest, so a bot nobody can reach doesn't go unnoticed.
+pub struct DailyDigest {
+    audit_log: Arc<AuditLog>,
+    usage: UsageSettings,
+    notifier: Notifier,
+    hour: u32,
+}
+
+/// How often one tool was called in a day.
+struct ToolUsage {
+    tool: String,
+    calls: usize,
+    failures: usize,
+}
+
+/// A question and how often it was asked in a day.
+struct Question {
+    text: String,
+    count: usize,
+}
+
+impl DailyDigest {
+    pub fn new(
+        audit_log: Arc<AuditLog>,
+        usage: UsageSettings,
+        notifier: Notifier,
+        hour: u32,
+    ) -> Self {
+        DailyDigest {
+            audit_log,
+            usage,
+            notifier,
+            hour,
+        }
+    }
+
+    /// Spawns the daily loop.
+    pub fn spawn(self) {
+        info!("Daily digest scheduled for {:02}:00 UTC", self.hour);
+        tokio::spawn(self.run());
+    }
+
+    async fn run(self) {
+        loop {
+            let now = Utc::now();
+            let next = next_run(now, self.hour);
+            tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+            self.send(next);
+        }
+    }
+
+    /// Sums up the day up to `until` and notifies it.
+    fn send(&self, until: DateTime<Utc>) {
+        let since = until - Duration::days(1);
+        let records = match self.audit_log.digest_records(since) {
+            Ok(records) => records,
+            Err(e) => {
+                warn!("Failed to read the audit log for the daily digest: {}", e);
+                return;
+            }
+        };
+        let records: Vec<&DigestRecord> = records
+            .iter()
+            .filter(|record| record.timestamp < until)
+            .collect();
+
+        let requests = records.len();
+        let errors = records.iter().filter(|record| record.status >= 400).count();
+        let error_rate = if requests > 0 {
+            errors as f64 / requests as f64
+        } else {
+            0.0
+        };
+        let prompt_tokens: u64 = records
+            .iter()
+            .map(|record| record.prompt_tokens.unwrap_or_default() as u64)
+            .sum();
+        let response_tokens: u64 = records
+            .iter()
+            .map(|record| record.response_tokens.unwrap_or_default() as u64)
+            .sum();
+        let cost = self.usage.cost(prompt_tokens, response_tokens);
+        let questions = top_questions(&records);
+        let tools = tool_usage(&records);
+
+        let mut text = format!(
+            "Daily digest for the 24h up to {}: {} requests, {} errors ({:.1}%), ${:.2} estimated spend",
+            until.format("%Y-%m-%d %H:%M UTC"),
+            requests,
+            errors,
+            error_rate * 100.0,
+            cost
+        );
+        if !questions.is_empty() {
+            text.push_str("\nTop questions:");
+            for question in &questions {
+                text.push_str(&format!("\n  {}x {}", question.count, question.text));
+            }
+        }
+        if !tools.is_empty() {
+            let calls: Vec<String> = tools
+                .iter()
+                .map(|usage| match usage.failures {
+                    0 => format!("{} {}", usage.tool, usage.calls),
+                    failures => format!("{} {} ({} failed)", usage.tool, usage.calls, failures),
+                })
+                .collect();
+            text.push_str(&format!("\nTool calls: {}", calls.join(", ")));
+        }
+
+        info!(
+            "Sending daily digest: {} requests, {} errors",
+            requests, errors
+        );
+        self.notifier.notify(Notification {
+            kind: EventKind::DailyDigest,
+            text,
+            details: json!({
+                "since": since,
+                "until": until,
+                "requests": requests,
+                "errors": errors,
+                "error_rate": error_rate,
+                "prompt_tokens": prompt_tokens,
+                "response_tokens": response_tokens,
+                "estimated_cost_usd": cost,
+                "top_questions": questions
+                    .iter()
+                    .map(|question| {
+                        json!({
+                            "question": question.text,
+                            "count": question.count,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+                "tools": tools
+                    .iter()
+                    .map(|usage| {
+                        json!({
+                            "tool": usage.tool,
+                            "calls": usage.calls,
+                            "failures": usage.failures,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            }),
+        });
+    }
+}
+
+// The first `hour` o'clock UTC after `now`
+fn next_run(now: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let time = NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or(NaiveTime::MIN);
+    let today = now.date_naive().and_time(time).and_utc();
+    if today > now {
+        today
+    } else {
+        today + Duration::days(1)
+    }
+}
+
+// The most asked questions, most often first, each shown as first worded
+fn top_questions(records: &[&DigestRecord]) -> Vec<Question> {
+    let mut questions: HashMap<String, Question> = HashMap::new();
+    for prompt in records.iter().filter_map(|record| record.prompt.as_deref()) {
+        let text = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        questions
+            .entry(text.to_lowercase())
+            .or_insert_with(|| Question {
+                text: shorten(&text),
+                count: 0,
+            })
+            .count += 1;
+    }
+
+    let mut questions: Vec<Question> = questions.into_values().collect();
+    questions.sort_by(|a, b| b.count.cmp(&a.count).then(a.text.cmp(&b.text)));
+    questions.truncate(MAX_TOP_QUESTIONS);
+    questions
+}
+
+// Calls per tool, most called first
+fn tool_usage(records: &[&DigestRecord]) -> Vec<ToolUsage> {
+    let mut tools: HashMap<&str, ToolUsage> = HashMap::new();
+    for call in records.iter().flat_map(|record| &record.tool_calls) {
+        let usage = tools
+            .entry(call.tool.as_str())
+            .or_insert_with(|| ToolUsage {
+                tool: call.tool.clone(),
+                calls: 0,
+                failures: 0,
+            });
+        usage.calls += 1;
+        if !call.success {
+            usage.failures += 1;
+        }
+    }
+
+    let mut tools: Vec<ToolUsage> = tools.into_values().collect();
+    tools.sort_by(|a, b| b.calls.cmp(&a.calls).then(a.tool.cmp(&b.tool)));
+    tools
+}
+
+fn shorten(question: &str) -> String {
+    if question.chars().count() <= MAX_QUESTION_CHARS {
+        return question.to_string();
+    }
+    let cut: String = question.chars().take(MAX_QUESTION_CHARS).collect();
+    format!("{}...", cut.trim_end())
+}
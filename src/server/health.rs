@@ -0,0 +1,132 @@
+use super::types::{DeepHealthResponse, DependencyStatus};
+use crate::kube::KubeAgent;
+use crate::notifier::{EventKind, Notification, Notifier};
+use serde_json::json;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Time each dependency may take to answer before it is reported unhealthy.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// OpenAI API base URL unless `OPENAI_BASE_URL` overrides it.
+pub const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+
+/// Checks for GET /healthz/deep: whether the agent's upstream dependencies actually
+/// answer, as opposed to GET /, which only shows the server is up.
+pub struct DeepHealthCheck {
+    openai_api_key: String,
+    openai_base_url: String,
+    kube: KubeAgent,
+    client: reqwest::Client,
+    notifier: Notifier,
+    /// Dependencies that failed their last check, so each failure is notified once
+    failing: Mutex<HashSet<&'static str>>,
+}
+
+impl DeepHealthCheck {
+    pub fn new(openai_api_key: String, kube: KubeAgent) -> Self {
+        DeepHealthCheck {
+            openai_api_key,
+            openai_base_url: OPENAI_API_BASE.to_string(),
+            kube: kube.with_timeouts(CHECK_TIMEOUT, CHECK_TIMEOUT),
+            client: reqwest::Client::new(),
+            notifier: Notifier::default(),
+            failing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Checks an OpenAI-compatible API at `base_url` instead of api.openai.com.
+    pub fn with_openai_base_url(mut self, base_url: &str) -> Self {
+        self.openai_base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Notifies when a dependency starts failing its check.
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    /// Lists OpenAI models and reads the cluster's `/version` concurrently.
+    pub async fn check(&self) -> DeepHealthResponse {
+        let (openai, kubernetes) = tokio::join!(
+            timed("openai", self.check_openai()),
+            timed("kubernetes", self.check_kube()),
+        );
+        let dependencies = vec![openai, kubernetes];
+        self.notify_failures(&dependencies);
+        DeepHealthResponse {
+            healthy: dependencies.iter().all(|dependency| dependency.healthy),
+            dependencies,
+        }
+    }
+
+    fn notify_failures(&self, dependencies: &[DependencyStatus]) {
+        let mut failing = self.failing.lock().unwrap();
+        for dependency in dependencies {
+            if dependency.healthy {
+                failing.remove(dependency.name);
+            } else if failing.insert(dependency.name) {
+                self.notifier.notify(Notification {
+                    kind: EventKind::HealthCheckFailed,
+                    text: format!(
+                        "Deep health check: {} is unhealthy: {}",
+                        dependency.name,
+                        dependency.error.as_deref().unwrap_or("unknown error")
+                    ),
+                    details: json!({
+                        "dependency": dependency.name,
+                        "error": dependency.error,
+                        "latency_ms": dependency.latency_ms,
+                    }),
+                });
+            }
+        }
+    }
+
+    /// Lists the models the key can use, the cheapest authenticated OpenAI call.
+    async fn check_openai(&self) -> Result<(), String> {
+        self.client
+            .get(format!("{}/models", self.openai_base_url))
+            .bearer_auth(&self.openai_api_key)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn check_kube(&self) -> Result<(), String> {
+        self.kube
+            .get("/version".to_string())
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Runs one check under CHECK_TIMEOUT, recording how long it took
+async fn timed(
+    name: &'static str,
+    check: impl Future<Output = Result<(), String>>,
+) -> DependencyStatus {
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())));
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match &result {
+        Ok(()) => debug!("Deep health check: {} healthy in {}ms", name, latency_ms),
+        Err(e) => warn!("Deep health check: {} unhealthy: {}", name, e),
+    }
+    DependencyStatus {
+        name,
+        healthy: result.is_ok(),
+        latency_ms,
+        error: result.err(),
+    }
+}
@@ -0,0 +1,42 @@
+/// Longest language accepted, enough for a BCP 47 tag with a region and script or a
+/// name like "Brazilian Portuguese".
+const MAX_LANGUAGE_CHARS: usize = 35;
+
+/// Checks a requested answer language, e.g. "de-DE" or "German", returning it trimmed.
+///
+/// The language is passed to the model as part of the prompt, so only short names and
+/// tags of letters, digits, spaces, hyphens, and underscores are accepted.
+pub fn parse_language(language: &str) -> Option<String> {
+    let language = language.trim();
+    let valid = !language.is_empty()
+        && language.chars().count() <= MAX_LANGUAGE_CHARS
+        && language
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+    valid.then(|| language.to_string())
+}
+
+/// The most preferred language of an Accept-Language header, e.g. "fr-CH" for
+/// `fr-CH, fr;q=0.9, en;q=0.8`.
+///
+/// Languages are ranked by their `q` weight, ties going to the one listed first. The
+/// `*` wildcard, languages with `q=0`, and malformed entries are skipped.
+pub fn preferred_language(header: &str) -> Option<String> {
+    let mut best: Option<(f32, String)> = None;
+    for entry in header.split(',') {
+        let mut parts = entry.split(';');
+        let Some(language) = parts.next().and_then(parse_language) else {
+            continue;
+        };
+        let weight = parts
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+        let Some(weight) = weight.filter(|weight| *weight > 0.0) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(best, _)| weight > *best) {
+            best = Some((weight, language));
+        }
+    }
+    best.map(|(_, language)| language)
+}
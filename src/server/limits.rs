@@ -0,0 +1,147 @@
+use crate::agent::tokens::estimate_tokens;
+use crate::server::types::HttpMessage;
+use tracing::*;
+
+/// Size limits applied to chat requests and their responses.
+///
+/// Prompts over the limit are rejected outright, while chat history is trimmed
+/// from the oldest message forward until it fits. Responses over the limit are
+/// split into pages.
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    /// Maximum prompt length in characters
+    pub max_prompt_chars: usize,
+    /// Maximum estimated prompt length in tokens
+    pub max_prompt_tokens: usize,
+    /// Maximum combined history length in characters
+    pub max_history_chars: usize,
+    /// Maximum combined estimated history length in tokens
+    pub max_history_tokens: usize,
+    /// Maximum number of history messages
+    pub max_history_messages: usize,
+    /// Maximum response length in characters; 0 disables the limit
+    pub max_response_chars: usize,
+}
+
+/// Why a prompt was rejected.
+#[derive(Debug)]
+pub enum LimitError {
+    PromptTooManyChars { chars: usize, max: usize },
+    PromptTooManyTokens { tokens: usize, max: usize },
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::PromptTooManyChars { chars, max } => {
+                write!(f, "Prompt too long: {} characters (max {})", chars, max)
+            }
+            LimitError::PromptTooManyTokens { tokens, max } => {
+                write!(f, "Prompt too long: ~{} tokens (max {})", tokens, max)
+            }
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Validates the prompt against the character and token limits.
+    pub fn check_prompt(&self, prompt: &str) -> Result<(), LimitError> {
+        let chars = prompt.chars().count();
+        if chars > self.max_prompt_chars {
+            return Err(LimitError::PromptTooManyChars {
+                chars,
+                max: self.max_prompt_chars,
+            });
+        }
+
+        let tokens = estimate_tokens(prompt);
+        if tokens > self.max_prompt_tokens {
+            return Err(LimitError::PromptTooManyTokens {
+                tokens,
+                max: self.max_prompt_tokens,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Trims chat history to fit within the configured limits.
+    ///
+    /// Keeps the most recent messages, dropping the oldest first. If trimming leaves
+    /// an assistant reply at the front, it is dropped too so the history always starts
+    /// with a user turn.
+    pub fn trim_history(&self, history: Vec<HttpMessage>) -> Vec<HttpMessage> {
+        let original_len = history.len();
+        let mut kept = Vec::new();
+        let mut chars = 0;
+        let mut tokens = 0;
+
+        for msg in history.into_iter().rev() {
+            let msg_chars = msg.content.chars().count();
+            let msg_tokens = estimate_tokens(&msg.content);
+
+            if kept.len() >= self.max_history_messages
+                || chars + msg_chars > self.max_history_chars
+                || tokens + msg_tokens > self.max_history_tokens
+            {
+                break;
+            }
+
+            chars += msg_chars;
+            tokens += msg_tokens;
+            kept.push(msg);
+        }
+
+        kept.reverse();
+
+        while kept.first().is_some_and(|msg| msg.role != "user") {
+            kept.remove(0);
+        }
+
+        if kept.len() < original_len {
+            info!(
+                "Trimmed chat history from {} to {} messages (~{} tokens kept)",
+                original_len,
+                kept.len(),
+                tokens
+            );
+        }
+
+        kept
+    }
+
+    /// Splits a response longer than `max_response_chars` into the page to send now
+    /// and the rest.
+    ///
+    /// The page ends at its last line break when that keeps at least half of it, so
+    /// listings aren't cut mid-line.
+    pub fn paginate_response(&self, text: &str) -> (String, Option<String>) {
+        if self.max_response_chars == 0 {
+            return (text.to_string(), None);
+        }
+        let Some((limit, _)) = text.char_indices().nth(self.max_response_chars) else {
+            return (text.to_string(), None);
+        };
+
+        let cut = match text[..limit].rfind('\n') {
+            Some(newline) if newline >= limit / 2 => newline + 1,
+            _ => limit,
+        };
+        (text[..cut].to_string(), Some(text[cut..].to_string()))
+    }
+}
+
+/// Marker appended to a response page, saying how much is left and how to get it.
+pub fn truncation_marker(remaining_chars: usize, continuable: bool) -> String {
+    if continuable {
+        format!(
+            "\n\n[Response truncated: {} more characters. Send \"continue\": true with the same session_id to read the rest.]",
+            remaining_chars
+        )
+    } else {
+        format!(
+            "\n\n[Response truncated: {} more characters not shown. Send a session_id to be able to continue long responses.]",
+            remaining_chars
+        )
+    }
+}
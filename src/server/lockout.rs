@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Failed API key attempts from one source address.
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Locks out source addresses that keep presenting bad API keys.
+///
+/// The first `free_attempts` failures from an address cost nothing. Each failure after
+/// that locks the address out for twice as long as the previous lockout, starting at
+/// `base_lockout` and capped at `max_lockout`; requests from a locked-out address are
+/// refused before their key is even checked. An address's failures are forgotten after a
+/// successful request, or once it has been quiet for `max_lockout`.
+///
+/// Lockouts are enforced by refusing requests rather than by sleeping, since the server
+/// handles one connection at a time and a delay would stall every other client too.
+/// Behind a reverse proxy, sources are the client addresses the proxy reports (see
+/// [`super::proxy::TrustedProxies`]).
+pub struct AuthLockout {
+    free_attempts: u32,
+    base_lockout: Duration,
+    max_lockout: Duration,
+    sources: Mutex<HashMap<IpAddr, Failures>>,
+    failures_total: AtomicU64,
+    lockouts_total: AtomicU64,
+}
+
+impl AuthLockout {
+    pub fn new(free_attempts: u32, base_lockout: Duration, max_lockout: Duration) -> Self {
+        AuthLockout {
+            free_attempts,
+            base_lockout,
+            max_lockout: max_lockout.max(base_lockout),
+            sources: Mutex::new(HashMap::new()),
+            failures_total: AtomicU64::new(0),
+            lockouts_total: AtomicU64::new(0),
+        }
+    }
+
+    /// How much longer `source` is locked out for, if it is.
+    pub fn locked_out(&self, source: IpAddr) -> Option<Duration> {
+        let sources = self.sources.lock().unwrap();
+        let locked_until = sources.get(&source)?.locked_until?;
+        locked_until.checked_duration_since(Instant::now())
+    }
+
+    /// Records a failed attempt from `source`, returning the lockout it earned, if any.
+    pub fn record_failure(&self, source: IpAddr) -> Option<Duration> {
+        let failures_total = self.failures_total.fetch_add(1, Ordering::Relaxed) + 1;
+        let now = Instant::now();
+
+        let mut sources = self.sources.lock().unwrap();
+        // Forget addresses that have been quiet long enough, so the map can't grow forever
+        sources.retain(|_, failures| now.duration_since(failures.last) < self.max_lockout);
+        let failures = sources.entry(source).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        failures.count += 1;
+        failures.last = now;
+
+        let Some(excess) = failures.count.checked_sub(self.free_attempts + 1) else {
+            info!(
+                failures_total,
+                "Failed API key attempt {} of {} from {} before lockout",
+                failures.count,
+                self.free_attempts,
+                source
+            );
+            return None;
+        };
+        let lockout = self
+            .base_lockout
+            .saturating_mul(2u32.saturating_pow(excess))
+            .min(self.max_lockout);
+        failures.locked_until = Some(now + lockout);
+
+        let lockouts_total = self.lockouts_total.fetch_add(1, Ordering::Relaxed) + 1;
+        let locked_sources = sources
+            .values()
+            .filter(|failures| failures.locked_until.is_some_and(|until| until > now))
+            .count();
+        warn!(
+            failures_total,
+            lockouts_total,
+            locked_sources,
+            "Locked out {} for {}s after {} failed API key attempts",
+            source,
+            lockout.as_secs(),
+            sources[&source].count
+        );
+        Some(lockout)
+    }
+
+    /// Forgets the failures of `source` once it authenticates successfully.
+    pub fn record_success(&self, source: IpAddr) {
+        self.sources.lock().unwrap().remove(&source);
+    }
+}
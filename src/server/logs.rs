@@ -0,0 +1,184 @@
+use crate::kube::LogStream;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::io::{self, Write};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::*;
+
+/// Log lines sent before following unless the query asks for a different number.
+const DEFAULT_TAIL_LINES: u32 = 100;
+
+/// Upper bound on the `tail_lines` a client may ask for.
+const MAX_TAIL_LINES: u32 = 5000;
+
+/// Interval of pings to followers, so a client that went away is noticed even while
+/// the pod logs nothing.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Frames queued for a follower before forwarding waits for it to catch up.
+const QUEUED_FRAMES: usize = 64;
+
+/// Appended to a client's key to derive Sec-WebSocket-Accept (RFC 6455, section 4.2.2).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+
+const CLOSE_NORMAL: u16 = 1000;
+const CLOSE_INTERNAL_ERROR: u16 = 1011;
+
+/// The pod log a GET /logs request asks to follow, from its query string
+/// (e.g. `namespace=portfolio&pod=web-0&container=app&tail_lines=50`).
+#[derive(Debug)]
+pub struct LogQuery {
+    pub namespace: String,
+    pub pod: String,
+    /// Needed for pods with several containers
+    pub container: Option<String>,
+    pub tail_lines: u32,
+}
+
+impl LogQuery {
+    /// Parses the query string, rejecting missing or malformed names.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut namespace = None;
+        let mut pod = None;
+        let mut container = None;
+        let mut tail_lines = DEFAULT_TAIL_LINES;
+
+        for (name, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            match name {
+                "namespace" => namespace = Some(checked_name(value)?),
+                "pod" => pod = Some(checked_name(value)?),
+                "container" => container = Some(checked_name(value)?),
+                "tail_lines" => {
+                    let lines: u32 = value
+                        .parse()
+                        .map_err(|_| format!("Invalid tail_lines '{}'", value))?;
+                    tail_lines = lines.min(MAX_TAIL_LINES);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(LogQuery {
+            namespace: namespace.ok_or("Missing namespace")?,
+            pod: pod.ok_or("Missing pod")?,
+            container,
+            tail_lines,
+        })
+    }
+}
+
+impl fmt::Display for LogQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.namespace, self.pod)?;
+        if let Some(container) = &self.container {
+            write!(f, " ({})", container)?;
+        }
+        Ok(())
+    }
+}
+
+// Kubernetes object names are lowercase alphanumerics, '-' and '.', which also keeps
+// them safe to put in the API path
+fn checked_name(value: &str) -> Result<String, String> {
+    let valid = !value.is_empty()
+        && value.len() <= 253
+        && value.bytes().all(|byte| {
+            byte.is_ascii_lowercase() || byte.is_ascii_digit() || b"-.".contains(&byte)
+        });
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("Invalid name '{}'", value))
+    }
+}
+
+/// Completes the WebSocket opening handshake for the client's Sec-WebSocket-Key.
+pub fn accept(stream: &mut impl Write, websocket_key: &str) -> io::Result<()> {
+    let accept_key = base64::engine::general_purpose::STANDARD
+        .encode(Sha1::digest(format!("{}{}", websocket_key, WEBSOCKET_GUID)));
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key
+    )?;
+    stream.flush()
+}
+
+/// Forwards `logs` to a WebSocket client as one text message per line, until the log
+/// ends or the client goes away.
+///
+/// Messages from the client are never read; a client that closes the connection is
+/// noticed when the next line or ping fails to send. Frames are written on the
+/// blocking pool, so a client that stops reading only stalls its own writer, until the
+/// socket's write timeout drops it, and never a runtime worker.
+pub async fn forward(stream: impl Write + Send + 'static, mut logs: LogStream, query: String) {
+    let (frames, mut queued) = mpsc::channel::<Vec<u8>>(QUEUED_FRAMES);
+    let writer = tokio::task::spawn_blocking(move || {
+        let mut stream = stream;
+        while let Some(frame) = queued.blocking_recv() {
+            stream.write_all(&frame)?;
+            stream.flush()?;
+        }
+        Ok::<_, io::Error>(())
+    });
+
+    let mut pings =
+        tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    let last = loop {
+        let frame = tokio::select! {
+            line = logs.next_line() => match line {
+                Ok(Some(line)) => frame(OPCODE_TEXT, line.as_bytes()),
+                Ok(None) => break Some(close_frame(CLOSE_NORMAL, "Log stream ended")),
+                Err(e) => {
+                    warn!("Following logs of {} failed: {}", query, e);
+                    break Some(close_frame(CLOSE_INTERNAL_ERROR, "Log stream failed"));
+                }
+            },
+            _ = pings.tick() => frame(OPCODE_PING, &[]),
+        };
+        // The writer only hangs up when writing to the client failed
+        if frames.send(frame).await.is_err() {
+            break None;
+        }
+    };
+    if let Some(last) = last {
+        let _ = frames.send(last).await;
+    }
+    drop(frames);
+
+    match writer.await {
+        Ok(Ok(())) => info!("Stopped following logs of {}", query),
+        Ok(Err(e)) => info!("Log follower of {} disconnected: {}", query, e),
+        Err(e) => error!("Log writer of {} failed: {}", query, e),
+    }
+}
+
+fn close_frame(code: u16, reason: &str) -> Vec<u8> {
+    let mut payload = code.to_be_bytes().to_vec();
+    payload.extend_from_slice(reason.as_bytes());
+    frame(OPCODE_CLOSE, &payload)
+}
+
+// Encodes one unfragmented, unmasked frame, as servers send them
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
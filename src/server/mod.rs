@@ -1,41 +1,246 @@
+pub mod admission;
+pub mod audit;
+pub mod auth;
+pub mod digest;
+pub mod health;
+pub mod language;
+pub mod limits;
+pub mod lockout;
+pub mod logs;
+pub mod moderation;
+pub mod mtls;
+pub mod openapi;
+pub mod overrun;
+pub mod proxy;
+pub mod shedding;
 pub mod types;
+pub mod usage;
 
-use crate::agent::Agent;
+use crate::agent::error::ChatError;
+use crate::agent::permissions::{Capabilities, Capability};
+use crate::agent::{Agent, AgentResponse, ChatOptions};
+use crate::error::AppError;
+use crate::kube::KubeAgent;
+use crate::reporting;
+use crate::sessions::{SessionKey, SessionStore};
+use admission::{Admission, Admitted, Refusal};
+use audit::ChatAudit;
+use chrono::Utc;
+use health::DeepHealthCheck;
+use limits::RequestLimits;
+use logs::LogQuery;
+use moderation::SHADOW_BAN_REPLY;
+use mtls::{ClientIdentity, MutualTls};
+use proxy::TrustedProxies;
 use rig::completion::Message;
+use sentry::SentryFutureExt;
+use std::future::Future;
 use std::io::{self, prelude::*};
-use std::net::{TcpListener, TcpStream};
-use tracing::{debug, error, info, warn};
-use types::{ChatRequest, Method, Path, Request};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, field, info, info_span, warn, Instrument, Span};
+use types::{
+    ChatRequest, ChatResponse, ClearFlagsRequest, ClearFlagsResponse, DryRunResponse, FinishReason,
+    HttpMessage, IncompleteResponse, Method, Path, PlannedToolCall, Request, StreamEvent,
+    CHAT_RESPONSE_VERSION,
+};
+use usage::UsageSettings;
+
+/// Largest request, headers and body together, the server reads.
+const MAX_REQUEST_BYTES: usize = 100_000;
+
+/// How long a write to a client may block before the client is dropped, so one that
+/// stops reading can't hold the server or a log follower forever.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a read from a client, including the TLS handshake, may block unless
+/// [`Server::with_read_timeout`] is set.
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// HTTP server that handles AI chat requests.
 ///
 /// Implements a custom TCP-based HTTP/1.1 server without using a web framework.
 /// Provides endpoints for health checks and AI-powered chat interactions.
 pub struct Server {
-    agent: Agent,
+    agent: Arc<Agent>,
     host: String,
-    api_key: String,
+    /// Authentication, shedding, moderation, and auditing, shared with the gRPC API
+    admission: Arc<Admission>,
+    limits: RequestLimits,
+    sessions: Arc<SessionStore>,
+    health: DeepHealthCheck,
+    usage: UsageSettings,
+    proxies: TrustedProxies,
+    mtls: Option<MutualTls>,
+    /// How long a client may keep the server waiting for its request; connections are
+    /// handled one at a time, so a silent client would otherwise block every other one
+    read_timeout: Duration,
+    /// Cluster that GET /logs follows pod logs in
+    pod_logs: Option<KubeAgent>,
+    /// Start time in hex, so request ids stay unique across restarts
+    request_id_prefix: String,
+    requests: AtomicU64,
+}
+
+/// A server running in the background, from [`Server::start`].
+#[cfg_attr(not(any(test, feature = "test-support")), allow(dead_code))]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<io::Result<()>>,
+}
+
+#[cfg_attr(not(any(test, feature = "test-support")), allow(dead_code))]
+impl ServerHandle {
+    /// The address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops accepting connections and waits for the request in progress to finish.
+    pub async fn shutdown(self) -> io::Result<()> {
+        let _ = self.shutdown.send(());
+        self.task.await.map_err(io::Error::other)?
+    }
 }
 
 impl Server {
-    pub fn new(agent: Agent, host: String, api_key: String) -> Self {
+    pub fn new(
+        agent: Arc<Agent>,
+        host: String,
+        admission: Arc<Admission>,
+        limits: RequestLimits,
+        sessions: Arc<SessionStore>,
+        health: DeepHealthCheck,
+    ) -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
         Server {
             agent,
             host,
-            api_key,
+            admission,
+            limits,
+            sessions,
+            health,
+            usage: UsageSettings::default(),
+            proxies: TrustedProxies::default(),
+            mtls: None,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            pod_logs: None,
+            request_id_prefix: format!("{:x}", started),
+            requests: AtomicU64::new(0),
         }
     }
 
+    /// Reports usage over the windows and at the token prices of `usage`.
+    pub fn with_usage(mut self, usage: UsageSettings) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Takes client addresses from the forwarding headers of requests relayed by
+    /// `proxies` (by default every client is the connection's peer).
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.proxies = proxies;
+        self
+    }
+
+    /// Serves TLS, identifying clients by their certificates instead of API keys.
+    pub fn with_mutual_tls(mut self, mtls: MutualTls) -> Self {
+        self.mtls = Some(mtls);
+        self
+    }
+
+    /// Drops clients that send nothing for `timeout` while the server waits for their
+    /// handshake or request.
+    // Only tests shorten the default
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Serves GET /logs, following the logs of pods in `kube_agent`'s cluster.
+    pub fn with_pod_logs(mut self, kube_agent: KubeAgent) -> Self {
+        self.pod_logs = Some(kube_agent);
+        self
+    }
+
     /// Starts the server and listens for incoming connections.
     ///
     /// Blocks indefinitely, handling requests synchronously (one at a time).
     /// Each connection is processed completely before accepting the next one.
     pub async fn listen(&self) -> io::Result<()> {
-        let listener = TcpListener::bind(&self.host)?;
-        info!("Server listening on {}", self.host);
+        let listener = self.bind().await?;
+        self.serve(listener, std::future::pending()).await
+    }
+
+    /// Starts the server on a background task, returning a handle with the address it
+    /// bound and a way to stop it.
+    ///
+    /// A host with port 0 (e.g. "127.0.0.1:0") binds an ephemeral port, so tests can
+    /// run servers side by side and talk to them over real connections.
+    #[cfg_attr(not(any(test, feature = "test-support")), allow(dead_code))]
+    pub async fn start(self: Arc<Self>) -> io::Result<ServerHandle> {
+        let listener = self.bind().await?;
+        let addr = listener.local_addr()?;
+        let (shutdown, stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            self.serve(listener, async {
+                let _ = stopped.await;
+            })
+            .await
+        });
+        Ok(ServerHandle {
+            addr,
+            shutdown,
+            task,
+        })
+    }
 
-        for stream in listener.incoming() {
-            match stream {
+    async fn bind(&self) -> io::Result<TcpListener> {
+        let listener = TcpListener::bind(&self.host).await?;
+        let addr = listener.local_addr()?;
+        if self.mtls.is_some() {
+            info!("Server listening on {} (mutual TLS)", addr);
+        } else {
+            info!("Server listening on {}", addr);
+        }
+        Ok(listener)
+    }
+
+    /// Handles connections from `listener` one at a time until `shutdown` completes.
+    /// The request being handled when it does is finished first.
+    async fn serve(
+        &self,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()>,
+    ) -> io::Result<()> {
+        tokio::pin!(shutdown);
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = &mut shutdown => {
+                    info!("Server shutting down");
+                    return Ok(());
+                }
+            };
+
+            match accepted.and_then(|(stream, _)| {
+                // Requests are read and written with blocking I/O
+                let stream = stream.into_std()?;
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(self.read_timeout))?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(stream)
+            }) {
                 Ok(stream) => {
                     debug!("Accepted connection from {:?}", stream.peer_addr());
                     if let Err(e) = self.handle_client(stream).await {
@@ -47,18 +252,68 @@ impl Server {
                 }
             }
         }
-
-        Ok(())
     }
 
-    /// Handles a single client connection.
+    /// Handles a single client connection inside a `request` span.
     ///
-    /// Reads the HTTP request, validates the API key, routes to appropriate handler,
+    /// Every log line for the request carries the span's `request_id` (and `client_ip`
+    /// and `session_id` once known), and a final line records the total `duration_ms`. Error reports from
+    /// the request are tagged with the same ids.
+    async fn handle_client(&self, stream: TcpStream) -> io::Result<()> {
+        let request_id = format!(
+            "{}-{}",
+            self.request_id_prefix,
+            self.requests.fetch_add(1, Ordering::Relaxed)
+        );
+        let span = info_span!(
+            "request",
+            request_id = %request_id,
+            client_ip = field::Empty,
+            session_id = field::Empty
+        );
+        let started = Instant::now();
+        let peer = stream.peer_addr().ok().map(|addr| addr.ip());
+
+        let result = async {
+            match &self.mtls {
+                None => self.handle_request(stream, &request_id, peer, None).await,
+                Some(mtls) => match mtls.accept(stream) {
+                    Ok((stream, client)) => {
+                        self.handle_request(stream, &request_id, peer, Some(client))
+                            .await
+                    }
+                    Err(e) => {
+                        warn!("Rejected TLS client: {}", e);
+                        Ok(())
+                    }
+                },
+            }
+        }
+        .instrument(span.clone())
+        .bind_hub(reporting::request_hub(&request_id))
+        .await;
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| info!(duration_ms, "Request finished"));
+        result
+    }
+
+    /// Reads the HTTP request, authenticates the caller, routes to appropriate handler,
     /// and sends the response.
-    async fn handle_client(&self, mut stream: TcpStream) -> Result<(), std::io::Error> {
-        let mut buffer = [0; 100000]; // 100KB buffer for request
-        let bytes_read = stream.read(&mut buffer)?;
-        let request_str = String::from_utf8_lossy(&buffer[..bytes_read]);
+    ///
+    /// A `client` verified by its certificate needs no API key.
+    async fn handle_request(
+        &self,
+        mut stream: impl Read + Write + Send + 'static,
+        request_id: &str,
+        peer: Option<IpAddr>,
+        client: Option<ClientIdentity>,
+    ) -> io::Result<()> {
+        let Some(request_bytes) = read_request(&mut stream)? else {
+            warn!("Request exceeds {} bytes, returning 413", MAX_REQUEST_BYTES);
+            return Self::send_response(&mut stream, "413 Payload Too Large", "Request too large");
+        };
+        let request_str = String::from_utf8_lossy(&request_bytes);
 
         match Request::parse(&request_str) {
             Some(request) => {
@@ -66,33 +321,93 @@ impl Server {
                     "Parsed request: method={:?}, path={:?}",
                     request.method, request.path
                 );
+                let client_ip = self.proxies.client_ip(peer, &request);
+                if let Some(client_ip) = client_ip {
+                    let client_ip = client_ip.to_string();
+                    Span::current().record("client_ip", client_ip.as_str());
+                    reporting::tag("client_ip", &client_ip);
+                }
 
-                // Validate API key
-                if let Some(api_key) = &request.api_key {
-                    if *api_key != self.api_key {
-                        warn!("Invalid API key attempt");
-                        return Self::send_response(
-                            &mut stream,
-                            "403 Forbidden",
-                            "Invalid API key",
-                        );
+                // The API description holds nothing secret, so browsers and client
+                // generators can fetch it without a key
+                match request.path {
+                    Path::OpenApi => return self.openapi_handler(&mut stream),
+                    Path::Docs => return self.docs_handler(&mut stream),
+                    _ => {}
+                }
+
+                // Certificate-verified clients are identified by the certificate alone
+                let from_cert = client.is_some();
+                let (identity, capabilities) = match client {
+                    Some(client) => {
+                        debug!("Client certificate identified as {}", client.name);
+                        (client.name, client.capabilities)
                     }
-                    debug!("API key validated successfully");
-                } else {
-                    warn!("Request missing API key");
-                    return Self::send_response(&mut stream, "401 Unauthorized", "Missing API key");
+                    None => match self
+                        .admission
+                        .authenticate(request.api_key.as_deref(), client_ip)
+                    {
+                        Ok(accepted) => accepted,
+                        Err(refusal) => return Self::send_refusal(&mut stream, &refusal),
+                    },
+                };
+
+                // Each endpoint other than GET / needs its capability
+                let required = match request.path {
+                    Path::Chat => Some(Capability::Chat),
+                    Path::DeepHealth
+                    | Path::Usage
+                    | Path::Logs
+                    | Path::ModerationFlags
+                    | Path::ModerationClear => Some(Capability::Admin),
+                    Path::Root | Path::Favicon | Path::OpenApi | Path::Docs => None,
+                };
+                if let Some(required) = required.filter(|&c| !capabilities.allows(c)) {
+                    warn!(
+                        "{} lacks the {} capability for {:?}",
+                        identity, required, request.path
+                    );
+                    return Self::send_refusal(&mut stream, &Refusal::NotPermitted);
                 }
 
                 match request.path {
                     Path::Chat => {
-                        self.chat_handler(&mut stream, request.method, request.body)
-                            .await
+                        let started = Instant::now();
+                        let mut audit = ChatAudit {
+                            client_ip,
+                            ..ChatAudit::default()
+                        };
+                        let result = self
+                            .chat_handler(
+                                &mut stream,
+                                &request,
+                                &identity,
+                                capabilities,
+                                &mut audit,
+                            )
+                            .await;
+                        let api_key = request.api_key.as_deref().filter(|_| !from_cert);
+                        self.admission.record(
+                            request_id,
+                            &identity,
+                            api_key,
+                            &audit,
+                            started.elapsed(),
+                        );
+                        result
                     }
                     Path::Root => self.root_handler(&mut stream),
+                    Path::DeepHealth => self.deep_health_handler(&mut stream).await,
+                    Path::Usage => self.usage_handler(&mut stream),
+                    Path::ModerationFlags => self.moderation_flags_handler(&mut stream),
+                    Path::ModerationClear => self.moderation_clear_handler(&mut stream, &request),
+                    Path::Logs => self.logs_handler(stream, &request).await,
                     Path::Favicon => {
                         debug!("Favicon request received, returning 404");
                         Self::send_response(&mut stream, "404 Not Found", "Favicon not found")
                     }
+                    Path::OpenApi => self.openapi_handler(&mut stream),
+                    Path::Docs => self.docs_handler(&mut stream),
                 }
             }
             None => {
@@ -103,12 +418,41 @@ impl Server {
         }
     }
 
+    /// Answers a request turned away by admission, with a Retry-After header when
+    /// waiting helps.
+    fn send_refusal(stream: &mut impl Write, refusal: &Refusal) -> io::Result<()> {
+        match refusal.retry_after() {
+            Some(secs) => Self::send_response_with_headers(
+                stream,
+                refusal.status(),
+                &[("Retry-After", &secs.to_string())],
+                refusal.message(),
+            ),
+            None => Self::send_response(stream, refusal.status(), refusal.message()),
+        }
+    }
+
     /// Sends an HTTP response to the client.
-    fn send_response(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    fn send_response(stream: &mut impl Write, status: &str, body: &str) -> io::Result<()> {
+        Self::send_response_with_headers(stream, status, &[], body)
+    }
+
+    /// Sends an HTTP response to the client with additional headers.
+    fn send_response_with_headers(
+        stream: &mut impl Write,
+        status: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> io::Result<()> {
         debug!("Sending response: {}", status);
+        let extra_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+            .collect();
         let response = format!(
-            "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}",
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\n\r\n{}",
             status,
+            extra_headers,
             body.len(),
             body
         );
@@ -117,36 +461,105 @@ impl Server {
     }
 
     /// Handles POST /chat requests by processing the prompt through the AI agent.
+    ///
+    /// The agent may only use the tools the caller's `capabilities` allow, and answers
+    /// in the request's language, else the Accept-Language header's. Requests from
+    /// non-admin callers may be shed or moderated on the way; see [`Admission`].
+    /// The request's session is looked up among those of the caller's `identity`.
+    /// Fills in `audit` with what was asked, how it was answered, and the status sent.
     async fn chat_handler(
         &self,
-        stream: &mut TcpStream,
-        method: Method,
-        body: Option<String>,
+        stream: &mut impl Write,
+        request: &Request,
+        identity: &str,
+        capabilities: Capabilities,
+        audit: &mut ChatAudit,
     ) -> io::Result<()> {
-        match method {
+        match request.method {
             Method::POST => {
-                let body_str = match body {
+                let body_str = match &request.body {
                     Some(b) => b,
                     None => {
                         warn!("Chat request missing body");
-                        return Self::send_response(
-                            stream,
-                            "400 Bad Request",
-                            "Missing request body",
-                        );
+                        audit.status = "400 Bad Request";
+                        return Self::send_response(stream, audit.status, "Missing request body");
                     }
                 };
 
-                match serde_json::from_str::<ChatRequest>(&body_str) {
-                    Ok(chat_req) => {
-                        info!(
-                            "Processing chat request ({} chars)",
-                            chat_req.prompt.len()
-                        );
+                match serde_json::from_str::<ChatRequest>(body_str) {
+                    Ok(mut chat_req) => {
+                        if let Some(session_id) = &chat_req.session_id {
+                            Span::current().record("session_id", session_id.as_str());
+                            reporting::tag("session_id", session_id);
+                        }
+                        audit.session_id = chat_req.session_id.clone();
+                        audit.prompt = Some(chat_req.prompt.clone());
+                        let session = chat_req
+                            .session_id
+                            .as_ref()
+                            .map(|session_id| SessionKey::new(identity, session_id.as_str()));
+                        if chat_req.continue_response {
+                            return self.continue_handler(stream, session.as_ref(), audit);
+                        }
+                        info!("Processing chat request ({} chars)", chat_req.prompt.len());
+
+                        if let Err(e) = self.limits.check_prompt(&chat_req.prompt) {
+                            warn!("Rejecting oversize prompt: {}", e);
+                            audit.status = "413 Payload Too Large";
+                            return Self::send_response(stream, audit.status, &e.to_string());
+                        }
+
+                        let language = match chat_req.language.as_deref() {
+                            Some(language) => match language::parse_language(language) {
+                                Some(language) => Some(language),
+                                None => {
+                                    warn!("Rejecting invalid language: {:?}", language);
+                                    audit.status = "400 Bad Request";
+                                    return Self::send_response(
+                                        stream,
+                                        audit.status,
+                                        "Invalid language",
+                                    );
+                                }
+                            },
+                            None => request
+                                .accept_language
+                                .as_deref()
+                                .and_then(language::preferred_language),
+                        };
+
+                        let admitted = self
+                            .admission
+                            .admit_chat(identity, audit.client_ip, &capabilities, &chat_req.prompt)
+                            .await;
+                        match admitted {
+                            Ok(Admitted::Agent) => {}
+                            Ok(Admitted::ShadowBanned) => {
+                                return Self::shadow_ban_reply(stream, &chat_req, audit);
+                            }
+                            Err(refusal) => {
+                                audit.status = refusal.status();
+                                return Self::send_refusal(stream, &refusal);
+                            }
+                        }
+
+                        // Stored session history comes first, followed by any history
+                        // supplied with the request
+                        let mut history = match &session {
+                            Some(session) => self.sessions.history(session),
+                            None => Vec::new(),
+                        };
+                        if let Some(request_history) = chat_req.chat_history.take() {
+                            history.extend(request_history);
+                        }
+                        if let Some(session) = &session {
+                            history = self.sessions.compact(&self.agent, session, history).await;
+                        }
 
                         // Convert chat history to internal message format
                         let mut chat_history: Vec<Message> = Vec::new();
-                        if let Some(history) = chat_req.chat_history {
+                        if !history.is_empty() {
+                            let history = self.limits.trim_history(history);
                             debug!("Including {} historical messages", history.len());
                             let mut converted_history = Vec::new();
                             for msg in history {
@@ -154,9 +567,10 @@ impl Server {
                                     Ok(m) => converted_history.push(m),
                                     Err(e) => {
                                         warn!("Invalid message role in chat history: {}", e);
+                                        audit.status = "400 Bad Request";
                                         return Self::send_response(
                                             stream,
-                                            "400 Bad Request",
+                                            audit.status,
                                             "Invalid message role in chat history",
                                         );
                                     }
@@ -165,18 +579,108 @@ impl Server {
                             chat_history = converted_history;
                         }
 
-                        let response = self.agent.chat(chat_req.prompt, chat_history).await;
+                        let options = ChatOptions {
+                            session: session.clone(),
+                            dry_run: chat_req.dry_run,
+                            suggest_follow_ups: chat_req.include_suggestions,
+                            capabilities: Some(capabilities),
+                            language,
+                            tool_events: None,
+                        };
+                        if chat_req.stream {
+                            return self
+                                .stream_chat(stream, chat_req, chat_history, options, audit)
+                                .await;
+                        }
+
+                        let response = self
+                            .agent
+                            .chat(chat_req.prompt.clone(), chat_history, options)
+                            .await;
                         match response {
                             Ok(resp) => {
-                                info!("Generated response ({} chars)", resp.len());
-                                debug!("Response content: {}", resp);
-                                Self::send_response(stream, "200 OK", &resp)
+                                Self::audit_response(audit, &resp);
+
+                                // Dry runs return the plan and leave the session untouched
+                                if chat_req.dry_run {
+                                    let body = serde_json::to_string(&dry_run_response(&resp))
+                                        .unwrap_or_default();
+                                    return Self::send_response_with_headers(
+                                        stream,
+                                        "200 OK",
+                                        &[
+                                            ("Content-Type", "application/json"),
+                                            ("X-Agent-Model", &resp.model),
+                                        ],
+                                        &body,
+                                    );
+                                }
+
+                                self.record_exchange(session.as_ref(), &chat_req, &resp);
+                                let (text, remaining_chars) =
+                                    self.paginate(session.as_ref(), &resp.text);
+
+                                let body = serde_json::to_string(&self.chat_response(
+                                    &chat_req,
+                                    &resp,
+                                    &text,
+                                    remaining_chars,
+                                ))
+                                .unwrap_or_default();
+                                Self::send_response_with_headers(
+                                    stream,
+                                    "200 OK",
+                                    &[
+                                        ("Content-Type", "application/json"),
+                                        ("X-Agent-Model", &resp.model),
+                                    ],
+                                    &body,
+                                )
+                            }
+                            Err(ChatError::Timeout { after, tool_trace }) => {
+                                warn!("Chat request timed out after {}s", after.as_secs());
+                                audit.tool_calls = tool_trace.clone();
+                                audit.status = "504 Gateway Timeout";
+                                let body = serde_json::to_string(&IncompleteResponse {
+                                    error: format!(
+                                        "Agent did not finish within {}s",
+                                        after.as_secs()
+                                    ),
+                                    tool_trace: &tool_trace,
+                                })
+                                .unwrap_or_default();
+                                Self::send_response_with_headers(
+                                    stream,
+                                    audit.status,
+                                    &[("Content-Type", "application/json")],
+                                    &body,
+                                )
+                            }
+                            Err(ChatError::Interrupted { source, tool_trace }) => {
+                                error!(
+                                    "Chat request failed after changing the cluster: {}",
+                                    source
+                                );
+                                audit.tool_calls = tool_trace.clone();
+                                audit.status = "502 Bad Gateway";
+                                let body = serde_json::to_string(&IncompleteResponse {
+                                    error: "The model failed after changing the cluster; see tool_trace for what was done".to_string(),
+                                    tool_trace: &tool_trace,
+                                })
+                                .unwrap_or_default();
+                                Self::send_response_with_headers(
+                                    stream,
+                                    audit.status,
+                                    &[("Content-Type", "application/json")],
+                                    &body,
+                                )
                             }
                             Err(e) => {
                                 error!("Failed to generate chat response: {}", e);
+                                audit.status = AppError::from(e).status();
                                 Self::send_response(
                                     stream,
-                                    "500 Internal Server Error",
+                                    audit.status,
                                     "Failed to generate response",
                                 )
                             }
@@ -185,22 +689,832 @@ impl Server {
                     Err(e) => {
                         warn!(
                             "Failed to parse chat request JSON (request: {}), ERROR: {}",
-                            &body_str, e
+                            body_str, e
                         );
-                        Self::send_response(stream, "400 Bad Request", "Invalid JSON body")
+                        audit.status = "400 Bad Request";
+                        Self::send_response(stream, audit.status, "Invalid JSON body")
                     }
                 }
             }
             _ => {
                 warn!("Invalid HTTP method for /chat endpoint");
-                Self::send_response(stream, "405 Method Not Allowed", "Invalid method for /chat")
+                audit.status = "405 Method Not Allowed";
+                Self::send_response(stream, audit.status, "Invalid method for /chat")
             }
         }
     }
 
+    /// Answers a chat request with server-sent events: `tool_start` and `tool_end` while
+    /// the agent works, then one `response`, `dry_run`, `timeout`, or `error` event.
+    ///
+    /// The 200 status goes out before the agent runs, so failures are reported as the
+    /// final event; the audit log still records the status the failure maps to.
+    async fn stream_chat(
+        &self,
+        stream: &mut impl Write,
+        chat_req: ChatRequest,
+        chat_history: Vec<Message>,
+        mut options: ChatOptions,
+        audit: &mut ChatAudit,
+    ) -> io::Result<()> {
+        let (tool_events, mut tool_calls) = mpsc::unbounded_channel();
+        options.tool_events = Some(tool_events);
+        let session = options.session.clone();
+
+        Self::start_event_stream(stream)?;
+
+        // A client that disconnects abandons the request along with the stream
+        let chat = self
+            .agent
+            .chat(chat_req.prompt.clone(), chat_history, options);
+        tokio::pin!(chat);
+        let result = loop {
+            tokio::select! {
+                Some(event) = tool_calls.recv() => {
+                    Self::send_event(stream, &StreamEvent::from(&event))?;
+                }
+                result = &mut chat => break result,
+            }
+        };
+        // Tool calls that completed just before the response are still queued
+        while let Ok(event) = tool_calls.try_recv() {
+            Self::send_event(stream, &StreamEvent::from(&event))?;
+        }
+
+        match result {
+            Ok(resp) => {
+                Self::audit_response(audit, &resp);
+                if chat_req.dry_run {
+                    return Self::send_event(stream, &StreamEvent::DryRun(dry_run_response(&resp)));
+                }
+
+                self.record_exchange(session.as_ref(), &chat_req, &resp);
+                let (text, remaining_chars) = self.paginate(session.as_ref(), &resp.text);
+                Self::send_event(
+                    stream,
+                    &StreamEvent::Response(self.chat_response(
+                        &chat_req,
+                        &resp,
+                        &text,
+                        remaining_chars,
+                    )),
+                )
+            }
+            Err(ChatError::Timeout { after, tool_trace }) => {
+                warn!("Chat request timed out after {}s", after.as_secs());
+                audit.tool_calls = tool_trace.clone();
+                audit.status = "504 Gateway Timeout";
+                Self::send_event(
+                    stream,
+                    &StreamEvent::Timeout(IncompleteResponse {
+                        error: format!("Agent did not finish within {}s", after.as_secs()),
+                        tool_trace: &tool_trace,
+                    }),
+                )
+            }
+            Err(e) => {
+                error!("Failed to generate chat response: {}", e);
+                // The tool calls were already streamed, but the audit log has to have them
+                if let ChatError::Interrupted { tool_trace, .. } = &e {
+                    audit.tool_calls = tool_trace.clone();
+                }
+                audit.status = AppError::from(e).status();
+                Self::send_event(
+                    stream,
+                    &StreamEvent::Error {
+                        status: audit.status,
+                        error: "Failed to generate response",
+                    },
+                )
+            }
+        }
+    }
+
+    /// Sends the headers of a server-sent event stream.
+    fn start_event_stream(stream: &mut impl Write) -> io::Result<()> {
+        // Without a Content-Length, the stream ends when the connection closes
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        )?;
+        stream.flush()
+    }
+
+    /// Answers a shadow-banned client with [`SHADOW_BAN_REPLY`], shaped like a real
+    /// answer so the client can't tell, without asking the agent.
+    fn shadow_ban_reply(
+        stream: &mut impl Write,
+        chat_req: &ChatRequest,
+        audit: &mut ChatAudit,
+    ) -> io::Result<()> {
+        audit.response = Some(SHADOW_BAN_REPLY.to_string());
+        audit.status = "200 OK";
+        let reply = ChatResponse {
+            version: CHAT_RESPONSE_VERSION,
+            response: SHADOW_BAN_REPLY,
+            session_id: chat_req.session_id.as_deref(),
+            model: None,
+            usage: Default::default(),
+            tool_trace: &[],
+            finish_reason: FinishReason::Stop,
+            suggestions: None,
+            remaining_chars: None,
+        };
+        if chat_req.stream {
+            Self::start_event_stream(stream)?;
+            return Self::send_event(stream, &StreamEvent::Response(reply));
+        }
+
+        let body = serde_json::to_string(&reply).unwrap_or_default();
+        Self::send_response_with_headers(
+            stream,
+            audit.status,
+            &[("Content-Type", "application/json")],
+            &body,
+        )
+    }
+
+    /// Writes one server-sent event.
+    fn send_event(stream: &mut impl Write, event: &StreamEvent) -> io::Result<()> {
+        debug!("Sending {} event", event.name());
+        // Serialized JSON has no raw newlines, so it fits on one data line
+        let data = serde_json::to_string(event).unwrap_or_default();
+        write!(stream, "event: {}\ndata: {}\n\n", event.name(), data)?;
+        stream.flush()
+    }
+
+    /// Logs a generated response and records it in the request's audit.
+    fn audit_response(audit: &mut ChatAudit, resp: &AgentResponse) {
+        info!(
+            "Generated response ({} chars) with {}",
+            resp.text.len(),
+            resp.model
+        );
+        debug!("Response content: {}", resp.text);
+        audit.response = Some(resp.text.clone());
+        audit.model = Some(resp.model.clone());
+        audit.tool_calls = resp.tool_trace.clone();
+        audit.status = "200 OK";
+    }
+
+    /// The /chat response body for `resp`, of which `text` is the page being sent.
+    fn chat_response<'a>(
+        &self,
+        chat_req: &'a ChatRequest,
+        resp: &'a AgentResponse,
+        text: &'a str,
+        remaining_chars: Option<usize>,
+    ) -> ChatResponse<'a> {
+        ChatResponse {
+            version: CHAT_RESPONSE_VERSION,
+            response: text,
+            session_id: chat_req.session_id.as_deref(),
+            model: Some(&resp.model),
+            usage: self.usage.estimate(&chat_req.prompt, &resp.text),
+            tool_trace: &resp.tool_trace,
+            finish_reason: finish_reason(remaining_chars),
+            suggestions: chat_req
+                .include_suggestions
+                .then_some(resp.suggestions.as_slice()),
+            remaining_chars,
+        }
+    }
+
+    /// Appends the exchange and its tool calls to the request's session, if it has one.
+    fn record_exchange(
+        &self,
+        session: Option<&SessionKey>,
+        chat_req: &ChatRequest,
+        resp: &AgentResponse,
+    ) {
+        if let Some(session) = session {
+            self.sessions.append(
+                session,
+                [
+                    HttpMessage::user(chat_req.prompt.clone()),
+                    HttpMessage::assistant(resp.text.clone()),
+                ],
+            );
+            self.sessions.record_tool_calls(session, &resp.tool_trace);
+        }
+    }
+
+    /// Cuts `text` to MAX_RESPONSE_CHARS, keeping the rest on the session (if any) for
+    /// `"continue": true`. Returns the page, ending in a truncation marker when cut,
+    /// and the number of characters left.
+    fn paginate(&self, session: Option<&SessionKey>, text: &str) -> (String, Option<usize>) {
+        let (page, rest) = self.limits.paginate_response(text);
+        let remaining_chars = rest.as_ref().map(|rest| rest.chars().count());
+        if let Some(session) = session {
+            // A new response replaces whatever was left of the previous one
+            self.sessions.set_continuation(session, rest);
+        }
+
+        match remaining_chars {
+            Some(remaining) => {
+                info!("Response truncated with {} characters left", remaining);
+                let marker = limits::truncation_marker(remaining, session.is_some());
+                (format!("{}{}", page, marker), Some(remaining))
+            }
+            None => (page, None),
+        }
+    }
+
+    /// Answers `"continue": true` with the next page of the caller's session's last
+    /// response.
+    fn continue_handler(
+        &self,
+        stream: &mut impl Write,
+        session: Option<&SessionKey>,
+        audit: &mut ChatAudit,
+    ) -> io::Result<()> {
+        let Some(session) = session else {
+            warn!("Continue request without a session_id");
+            audit.status = "400 Bad Request";
+            return Self::send_response(stream, audit.status, "continue requires a session_id");
+        };
+        let Some(rest) = self.sessions.take_continuation(session) else {
+            debug!("Nothing left to continue in session {}", session);
+            audit.status = "404 Not Found";
+            return Self::send_response(stream, audit.status, "No truncated response to continue");
+        };
+
+        // The page was generated by an earlier request, so this one used no model
+        let (text, remaining_chars) = self.paginate(Some(session), &rest);
+        let body = serde_json::to_string(&ChatResponse {
+            version: CHAT_RESPONSE_VERSION,
+            response: &text,
+            session_id: Some(&session.id),
+            model: None,
+            usage: Default::default(),
+            tool_trace: &[],
+            finish_reason: finish_reason(remaining_chars),
+            suggestions: None,
+            remaining_chars,
+        })
+        .unwrap_or_default();
+        audit.response = Some(text);
+        audit.status = "200 OK";
+        Self::send_response_with_headers(
+            stream,
+            audit.status,
+            &[("Content-Type", "application/json")],
+            &body,
+        )
+    }
+
     /// Handles GET / requests (health check endpoint).
-    fn root_handler(&self, stream: &mut TcpStream) -> io::Result<()> {
+    fn root_handler(&self, stream: &mut impl Write) -> io::Result<()> {
         debug!("Health check requested");
         Self::send_response(stream, "200 OK", "{\"healthy\": true}")
     }
+
+    /// Handles GET /openapi.json.
+    fn openapi_handler(&self, stream: &mut impl Write) -> io::Result<()> {
+        debug!("OpenAPI document requested");
+        Self::send_response_with_headers(
+            stream,
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            &openapi::document().to_string(),
+        )
+    }
+
+    /// Handles GET /docs with a Swagger UI page for the OpenAPI document.
+    fn docs_handler(&self, stream: &mut impl Write) -> io::Result<()> {
+        debug!("API docs requested");
+        Self::send_response_with_headers(
+            stream,
+            "200 OK",
+            &[("Content-Type", "text/html; charset=utf-8")],
+            openapi::DOCS_PAGE,
+        )
+    }
+
+    /// Handles GET /logs by upgrading to a WebSocket that streams the pod log named in
+    /// the query as it is written.
+    ///
+    /// The log is forwarded on a task of its own, so other requests are served while
+    /// clients tail logs.
+    async fn logs_handler(
+        &self,
+        mut stream: impl Write + Send + 'static,
+        request: &Request,
+    ) -> io::Result<()> {
+        let Some(kube_agent) = &self.pod_logs else {
+            return Self::send_response(&mut stream, "404 Not Found", "Log streaming is disabled");
+        };
+        let Some(websocket_key) = &request.websocket_key else {
+            return Self::send_response_with_headers(
+                &mut stream,
+                "426 Upgrade Required",
+                &[("Upgrade", "websocket")],
+                "Connect with a WebSocket client",
+            );
+        };
+        let query = match LogQuery::parse(request.query.as_deref().unwrap_or_default()) {
+            Ok(query) => query,
+            Err(e) => return Self::send_response(&mut stream, "400 Bad Request", &e),
+        };
+
+        // Failures to open the log (e.g. no such pod) are still plain HTTP responses
+        let log = kube_agent
+            .follow_logs(
+                &query.namespace,
+                &query.pod,
+                query.container.as_deref(),
+                query.tail_lines,
+            )
+            .await;
+        let log = match log {
+            Ok(log) => log,
+            Err(e) => {
+                warn!("Failed to follow logs of {}: {}", query, e);
+                let error = AppError::from(e);
+                return Self::send_response(&mut stream, error.status(), &error.to_string());
+            }
+        };
+
+        logs::accept(&mut stream, websocket_key)?;
+        info!("Following logs of {} over WebSocket", query);
+        tokio::spawn(logs::forward(stream, log, query.to_string()).instrument(Span::current()));
+        Ok(())
+    }
+
+    /// Handles GET /moderation/flags with the clients whose prompts were flagged.
+    fn moderation_flags_handler(&self, stream: &mut impl Write) -> io::Result<()> {
+        debug!("Moderation flags requested");
+        let Some(moderation) = self.admission.moderation() else {
+            return Self::send_response(stream, "404 Not Found", "Moderation is disabled");
+        };
+        let body = serde_json::to_string(&moderation.flags()).unwrap_or_default();
+        Self::send_response_with_headers(
+            stream,
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            &body,
+        )
+    }
+
+    /// Handles POST /moderation/clear, forgetting the flags, cool-downs, and shadow bans
+    /// of the clients matching the body's `key` and `address`.
+    fn moderation_clear_handler(
+        &self,
+        stream: &mut impl Write,
+        request: &Request,
+    ) -> io::Result<()> {
+        let Some(moderation) = self.admission.moderation() else {
+            return Self::send_response(stream, "404 Not Found", "Moderation is disabled");
+        };
+        if !matches!(request.method, Method::POST) {
+            return Self::send_response(
+                stream,
+                "405 Method Not Allowed",
+                "Invalid method for /moderation/clear",
+            );
+        }
+        let clear = request
+            .body
+            .as_deref()
+            .and_then(|body| serde_json::from_str::<ClearFlagsRequest>(body).ok());
+        let Some(clear) = clear.filter(|clear| clear.key.is_some() || clear.address.is_some())
+        else {
+            return Self::send_response(
+                stream,
+                "400 Bad Request",
+                "Expected a JSON body with a key, an address, or both",
+            );
+        };
+        let address = match clear.address.as_deref().map(str::parse::<IpAddr>) {
+            Some(Ok(address)) => Some(address),
+            Some(Err(_)) => {
+                return Self::send_response(stream, "400 Bad Request", "Invalid address");
+            }
+            None => None,
+        };
+
+        let cleared = moderation.clear(clear.key.as_deref(), address);
+        let body = serde_json::to_string(&ClearFlagsResponse { cleared }).unwrap_or_default();
+        Self::send_response_with_headers(
+            stream,
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            &body,
+        )
+    }
+
+    /// Handles GET /usage with per-key request, token, cost, and error totals read from
+    /// the audit log.
+    fn usage_handler(&self, stream: &mut impl Write) -> io::Result<()> {
+        debug!("Usage report requested");
+        let Some(audit_log) = self.admission.audit_log() else {
+            return Self::send_response(
+                stream,
+                "404 Not Found",
+                "Usage reports need the audit log (AUDIT_LOG_PATH)",
+            );
+        };
+
+        let now = Utc::now();
+        let records = match audit_log.usage_records(self.usage.since(now)) {
+            Ok(records) => records,
+            Err(e) => {
+                error!("Failed to read the audit log for a usage report: {}", e);
+                return Self::send_response(
+                    stream,
+                    "500 Internal Server Error",
+                    "Failed to read the audit log",
+                );
+            }
+        };
+        let body = serde_json::to_string(&self.usage.report(&records, now)).unwrap_or_default();
+        Self::send_response_with_headers(
+            stream,
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            &body,
+        )
+    }
+
+    /// Handles GET /healthz/deep, answering 503 if any upstream dependency is unhealthy.
+    async fn deep_health_handler(&self, stream: &mut impl Write) -> io::Result<()> {
+        debug!("Deep health check requested");
+        let report = self.health.check().await;
+        let status = if report.healthy {
+            "200 OK"
+        } else {
+            "503 Service Unavailable"
+        };
+        let body = serde_json::to_string(&report).unwrap_or_default();
+        Self::send_response_with_headers(
+            stream,
+            status,
+            &[("Content-Type", "application/json")],
+            &body,
+        )
+    }
+}
+
+/// Reads one request: its headers, then as much more as their Content-Length says the
+/// body takes, however many reads that needs. Returns None, having read no further than
+/// the headers, if the request is larger than MAX_REQUEST_BYTES.
+fn read_request(stream: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut request = Vec::new();
+    let mut chunk = [0; 16 * 1024];
+    loop {
+        match headers_end(&request) {
+            Some(end) => {
+                let expected = end + content_length(&request[..end]);
+                if expected > MAX_REQUEST_BYTES {
+                    return Ok(None);
+                }
+                if request.len() >= expected {
+                    return Ok(Some(request));
+                }
+            }
+            None if request.len() > MAX_REQUEST_BYTES => return Ok(None),
+            None => {}
+        }
+
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            // The client stopped sending; what arrived is parsed (or refused) as is
+            return Ok(Some(request));
+        }
+        request.extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// Length of the headers, including the blank line that ends them, once it has arrived.
+fn headers_end(request: &[u8]) -> Option<usize> {
+    let crlf = request.windows(4).position(|window| window == b"\r\n\r\n");
+    let lf = request.windows(2).position(|window| window == b"\n\n");
+    match (crlf, lf) {
+        (Some(crlf), Some(lf)) if lf < crlf => Some(lf + 2),
+        (Some(crlf), _) => Some(crlf + 4),
+        (None, lf) => lf.map(|lf| lf + 2),
+    }
+}
+
+/// The Content-Length declared in `headers`, or 0 if there is none.
+fn content_length(headers: &[u8]) -> usize {
+    String::from_utf8_lossy(headers)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// The tool call plan of a dry-run response.
+fn dry_run_response(resp: &AgentResponse) -> DryRunResponse<'_> {
+    DryRunResponse {
+        version: CHAT_RESPONSE_VERSION,
+        dry_run: true,
+        plan: resp
+            .tool_trace
+            .iter()
+            .map(|record| PlannedToolCall {
+                tool: &record.tool,
+                args: &record.args,
+            })
+            .collect(),
+        response: &resp.text,
+        model: &resp.model,
+    }
+}
+
+/// Whether the page sent was the whole response.
+fn finish_reason(remaining_chars: Option<usize>) -> FinishReason {
+    match remaining_chars {
+        Some(_) => FinishReason::Truncated,
+        None => FinishReason::Stop,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::builder::AgentBuilder;
+    use crate::agent::mock::MockCompletionModel;
+    use crate::environment::Environment;
+    use auth::ApiKeys;
+    use serde_json::{json, Value};
+
+    const API_KEY: &str = "test-key";
+
+    /// Starts a server over a model that always answers `reply`, on an ephemeral port.
+    async fn start(reply: &str) -> ServerHandle {
+        Arc::new(server(reply)).start().await.unwrap()
+    }
+
+    /// A server over a model that always answers `reply`, to listen on an ephemeral port.
+    fn server(reply: &str) -> Server {
+        let env = Environment::global();
+        let sessions = Arc::new(SessionStore::from_env(env));
+        let agent = AgentBuilder::new(sessions.clone())
+            .model("mock", MockCompletionModel::always(reply))
+            .build()
+            .map(Arc::new)
+            .unwrap();
+        let health = DeepHealthCheck::new(
+            String::new(),
+            KubeAgent::new("http://127.0.0.1:9".to_string(), String::new(), None),
+        );
+        let admission = Admission::new(
+            agent.clone(),
+            ApiKeys::new(API_KEY, None, None).unwrap(),
+            None,
+        );
+        Server::new(
+            agent,
+            "127.0.0.1:0".to_string(),
+            Arc::new(admission),
+            RequestLimits {
+                max_prompt_chars: env.max_prompt_chars,
+                max_prompt_tokens: env.max_prompt_tokens,
+                max_history_chars: env.max_history_chars,
+                max_history_tokens: env.max_history_tokens,
+                max_history_messages: env.max_history_messages,
+                max_response_chars: env.max_response_chars,
+            },
+            sessions,
+            health,
+        )
+    }
+
+    /// Path of a certificate or key in fixtures/mtls: a test CA, a server certificate
+    /// for localhost and 127.0.0.1, and a client certificate for `test-client`.
+    fn tls_fixture(name: &str) -> String {
+        format!("{}/fixtures/mtls/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    /// Sends GET / over TLS with the fixture client certificate and returns the status
+    /// code of the response.
+    fn tls_get(addr: SocketAddr) -> u16 {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+        use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+
+        let mut roots = RootCertStore::empty();
+        for ca in CertificateDer::pem_file_iter(tls_fixture("ca.pem")).unwrap() {
+            roots.add(ca.unwrap()).unwrap();
+        }
+        let certs = CertificateDer::pem_file_iter(tls_fixture("client.pem"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(tls_fixture("client.key")).unwrap();
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_client_auth_cert(certs, key)
+                .unwrap();
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let connection = ClientConnection::new(Arc::new(config), server_name).unwrap();
+
+        let socket = TcpStream::connect(addr).unwrap();
+        // Fail rather than hang if the server never gets to this client
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut stream = StreamOwned::new(connection, socket);
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        // The server closes without a close_notify, which ends the read with an error
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response);
+        String::from_utf8_lossy(&response)
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// A raw HTTP request with a Content-Length matching `body`.
+    fn raw_request(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        headers: &str,
+        body: &str,
+    ) -> String {
+        format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\n{}Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            addr,
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    /// Sends a raw HTTP request and returns the status code and body of the response.
+    async fn send(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        headers: &str,
+        body: &str,
+    ) -> (u16, String) {
+        let request = raw_request(addr, method, path, headers, body);
+        send_in_parts(addr, vec![request]).await
+    }
+
+    /// Sends `parts` of a raw request in separate writes, pausing between them so they
+    /// arrive as separate reads, and returns the status code and body of the response.
+    ///
+    /// The server handles connections with blocking I/O on a runtime worker, so the
+    /// client runs on the blocking pool where that can't starve it.
+    async fn send_in_parts(addr: SocketAddr, parts: Vec<String>) -> (u16, String) {
+        tokio::task::spawn_blocking(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            for (i, part) in parts.iter().enumerate() {
+                if i > 0 {
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                stream.write_all(part.as_bytes()).unwrap();
+            }
+            // The server closes the connection after responding
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            let status = response
+                .split_whitespace()
+                .nth(1)
+                .and_then(|code| code.parse().ok())
+                .unwrap();
+            let body = response
+                .split_once("\r\n\r\n")
+                .map(|(_, body)| body.to_string())
+                .unwrap_or_default();
+            (status, body)
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_chat_requests_over_a_real_connection() {
+        let server = start("Everything is running.").await;
+        let addr = server.addr();
+        let key = format!("X-API-Key: {}\r\n", API_KEY);
+        let prompt = json!({ "prompt": "How is the cluster?" }).to_string();
+
+        let (status, body) = send(addr, "POST", "/chat", &key, &prompt).await;
+        assert_eq!(status, 200, "{}", body);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["response"], "Everything is running.");
+        assert_eq!(body["model"], "mock");
+
+        let (status, _) = send(addr, "POST", "/chat", "", &prompt).await;
+        assert_eq!(status, 401);
+
+        let (status, _) = send(addr, "POST", "/chat", &key, "{\"prompt\":").await;
+        assert_eq!(status, 400);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_bodies_that_arrive_in_several_writes() {
+        let server = start("Got it.").await;
+        let addr = server.addr();
+        let key = format!("X-API-Key: {}\r\n", API_KEY);
+
+        // A 5 KB body is more than one read's worth on many stacks
+        let prompt = json!({ "prompt": "a".repeat(5000) }).to_string();
+        let (status, body) = send(addr, "POST", "/chat", &key, &prompt).await;
+        assert_eq!(status, 200, "{}", body);
+
+        let request = raw_request(addr, "POST", "/chat", &key, &prompt);
+        let (headers, body) = request.split_at(request.find("\r\n\r\n").unwrap() + 4);
+        let (first, second) = body.split_at(body.len() / 2);
+        let parts = vec![headers.to_string(), first.to_string(), second.to_string()];
+        let (status, body) = send_in_parts(addr, parts).await;
+        assert_eq!(status, 200, "{}", body);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["response"], "Got it.");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_oversize_prompts_split_across_writes_with_413() {
+        let server = start("Hello").await;
+        let addr = server.addr();
+        let key = format!("X-API-Key: {}\r\n", API_KEY);
+
+        // Over the prompt limit but under the request cap, so the prompt check refuses it
+        let prompt = json!({ "prompt": "a".repeat(90_000) }).to_string();
+        let request = raw_request(addr, "POST", "/chat", &key, &prompt);
+        let (first, second) = request.split_at(request.len() / 2);
+        let (status, body) = send_in_parts(addr, vec![first.to_string(), second.to_string()]).await;
+        assert_eq!(status, 413, "{}", body);
+        assert!(body.starts_with("Prompt too long"), "{}", body);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_requests_over_the_size_cap() {
+        let server = start("Hello").await;
+        let addr = server.addr();
+
+        // Refused from the headers alone, without reading the body
+        let request = format!(
+            "POST /chat HTTP/1.1\r\nX-API-Key: {}\r\nContent-Length: {}\r\n\r\n",
+            API_KEY,
+            MAX_REQUEST_BYTES + 1
+        );
+        let (status, body) = send_in_parts(addr, vec![request]).await;
+        assert_eq!(status, 413, "{}", body);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_stalled_tls_client_does_not_block_the_next_one() {
+        let mtls = MutualTls::load(
+            &tls_fixture("server.pem"),
+            &tls_fixture("server.key"),
+            &tls_fixture("ca.pem"),
+            None,
+        )
+        .unwrap();
+        let server = server("Hello")
+            .with_mutual_tls(mtls)
+            .with_read_timeout(Duration::from_millis(200));
+        let server = Arc::new(server).start().await.unwrap();
+        let addr = server.addr();
+
+        let (status, waited) = tokio::task::spawn_blocking(move || {
+            // Connects first and never starts the handshake
+            let _stalled = TcpStream::connect(addr).unwrap();
+            let started = Instant::now();
+            (tls_get(addr), started.elapsed())
+        })
+        .await
+        .unwrap();
+        assert_eq!(status, 200);
+        assert!(waited < Duration::from_secs(5), "waited {:?}", waited);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_accepting_connections_after_shutdown() {
+        let server = start("Hello").await;
+        let addr = server.addr();
+        assert_ne!(addr.port(), 0);
+
+        let key = format!("X-API-Key: {}\r\n", API_KEY);
+        let (status, _) = send(addr, "GET", "/", &key, "").await;
+        assert_eq!(status, 200);
+
+        server.shutdown().await.unwrap();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
 }
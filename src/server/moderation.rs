@@ -0,0 +1,315 @@
+use super::health::OPENAI_API_BASE;
+use super::types::{FlaggedClient, ModerationFlagsResponse};
+use crate::notifier::{EventKind, Notification, Notifier};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Time the moderation endpoint may take before the prompt is let through unchecked.
+const MODERATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reply sent to shadow-banned clients instead of asking the agent.
+pub const SHADOW_BAN_REPLY: &str = "Sorry, I can't help with that right now.";
+
+/// Where a chat request came from: the API key's name and, when known, the client's
+/// address. A public widget shares one key between all its visitors, so clients are
+/// told apart by address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChatClient {
+    pub key: String,
+    pub address: Option<IpAddr>,
+}
+
+/// How the abuse guard treats a client's next prompt.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Standing {
+    Allowed,
+    /// Refused for this much longer after too many flagged prompts
+    CoolingDown(Duration),
+    /// Answered with [`SHADOW_BAN_REPLY`] until an admin clears the client
+    ShadowBanned,
+}
+
+/// Flagged prompts of one client.
+struct Flags {
+    /// When each prompt in the window was flagged, oldest first
+    flagged_at: Vec<Instant>,
+    /// Categories of the flagged prompts, without duplicates
+    categories: Vec<String>,
+    cooldown_until: Option<Instant>,
+    shadow_banned: bool,
+}
+
+/// Screens prompts from non-admin keys with OpenAI's moderation endpoint and throttles
+/// clients whose prompts keep getting flagged.
+///
+/// A flagged prompt is refused. Once a client has `cooldown_flags` flagged prompts
+/// within `window`, each further one refuses all its prompts for `cooldown`. At
+/// `shadow_ban_flags` the client is shadow-banned: it keeps getting 200 responses, but
+/// with a canned reply instead of the agent's, so a troll has no signal to rotate
+/// keys or addresses on. Shadow bans last until cleared with POST /moderation/clear. A
+/// threshold of zero disables that stage.
+///
+/// Flags are kept in memory only, so a restart clears them. If the moderation endpoint
+/// fails, the prompt is let through; the guard protects the bot but must not take
+/// it down with the moderation API.
+pub struct Moderation {
+    openai_api_key: String,
+    openai_base_url: String,
+    model: String,
+    client: reqwest::Client,
+    cooldown_flags: u32,
+    cooldown: Duration,
+    shadow_ban_flags: u32,
+    window: Duration,
+    notifier: Notifier,
+    clients: Mutex<HashMap<ChatClient, Flags>>,
+}
+
+#[derive(Deserialize)]
+struct ModerationResponse {
+    results: Vec<ModerationResult>,
+}
+
+#[derive(Deserialize)]
+struct ModerationResult {
+    flagged: bool,
+    categories: HashMap<String, bool>,
+}
+
+impl Moderation {
+    pub fn new(
+        openai_api_key: String,
+        model: String,
+        cooldown_flags: u32,
+        cooldown: Duration,
+        shadow_ban_flags: u32,
+        window: Duration,
+    ) -> Self {
+        Moderation {
+            openai_api_key,
+            openai_base_url: OPENAI_API_BASE.to_string(),
+            model,
+            client: reqwest::Client::new(),
+            cooldown_flags,
+            cooldown,
+            shadow_ban_flags,
+            window,
+            notifier: Notifier::default(),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Moderates with an OpenAI-compatible API at `base_url` instead of api.openai.com.
+    pub fn with_openai_base_url(mut self, base_url: &str) -> Self {
+        self.openai_base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Notifies when a client is shadow-banned.
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    /// How `client`'s next prompt should be treated.
+    pub fn standing(&self, client: &ChatClient) -> Standing {
+        let clients = self.clients.lock().unwrap();
+        let Some(flags) = clients.get(client) else {
+            return Standing::Allowed;
+        };
+        if flags.shadow_banned {
+            return Standing::ShadowBanned;
+        }
+        match flags
+            .cooldown_until
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+        {
+            Some(remaining) => Standing::CoolingDown(remaining),
+            None => Standing::Allowed,
+        }
+    }
+
+    /// Checks `prompt` with the moderation endpoint, recording a flag against `client`
+    /// if it is flagged. Returns the flagged categories, or None if the prompt may go
+    /// ahead.
+    pub async fn check(&self, client: &ChatClient, prompt: &str) -> Option<Vec<String>> {
+        let result = match tokio::time::timeout(MODERATION_TIMEOUT, self.moderate(prompt)).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => {
+                warn!("Moderation check failed, letting the prompt through: {}", e);
+                return None;
+            }
+            Err(_) => {
+                warn!(
+                    "Moderation check timed out after {}s, letting the prompt through",
+                    MODERATION_TIMEOUT.as_secs()
+                );
+                return None;
+            }
+        };
+        if !result.flagged {
+            return None;
+        }
+
+        let mut categories: Vec<String> = result
+            .categories
+            .into_iter()
+            .filter(|(_, flagged)| *flagged)
+            .map(|(category, _)| category)
+            .collect();
+        categories.sort();
+        self.record_flag(client, &categories);
+        Some(categories)
+    }
+
+    async fn moderate(&self, prompt: &str) -> Result<ModerationResult, reqwest::Error> {
+        let response: ModerationResponse = self
+            .client
+            .post(format!("{}/moderations", self.openai_base_url))
+            .bearer_auth(&self.openai_api_key)
+            .json(&json!({ "model": self.model, "input": prompt }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response
+            .results
+            .into_iter()
+            .next()
+            .unwrap_or(ModerationResult {
+                flagged: false,
+                categories: HashMap::new(),
+            }))
+    }
+
+    fn record_flag(&self, client: &ChatClient, categories: &[String]) {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap();
+        // Forget clients with no flags left in the window, so the map can't grow forever
+        clients.retain(|_, flags| {
+            flags
+                .flagged_at
+                .retain(|at| now.duration_since(*at) < self.window);
+            flags.shadow_banned || !flags.flagged_at.is_empty()
+        });
+        let flags = clients.entry(client.clone()).or_insert(Flags {
+            flagged_at: Vec::new(),
+            categories: Vec::new(),
+            cooldown_until: None,
+            shadow_banned: false,
+        });
+        flags.flagged_at.push(now);
+        for category in categories {
+            if !flags.categories.contains(category) {
+                flags.categories.push(category.clone());
+            }
+        }
+
+        let count = flags.flagged_at.len() as u32;
+        let source = describe(client);
+        if self.shadow_ban_flags > 0 && count >= self.shadow_ban_flags && !flags.shadow_banned {
+            flags.shadow_banned = true;
+            warn!(
+                "Shadow-banned {} after {} flagged prompts ({})",
+                source,
+                count,
+                flags.categories.join(", ")
+            );
+            self.notifier.notify(Notification {
+                kind: EventKind::AbuseShadowBan,
+                text: format!(
+                    "Shadow-banned {} after {} flagged prompts ({})",
+                    source,
+                    count,
+                    flags.categories.join(", ")
+                ),
+                details: json!({
+                    "key": client.key,
+                    "address": client.address.map(|address| address.to_string()),
+                    "flags": count,
+                    "categories": flags.categories,
+                }),
+            });
+        } else if self.cooldown_flags > 0 && count >= self.cooldown_flags {
+            flags.cooldown_until = Some(now + self.cooldown);
+            warn!(
+                "Cooling down {} for {}s after {} flagged prompts",
+                source,
+                self.cooldown.as_secs(),
+                count
+            );
+        } else {
+            info!("Flagged prompt {} from {}", count, source);
+        }
+    }
+
+    /// Every client with flags in the window or a shadow ban, most flagged first.
+    pub fn flags(&self) -> ModerationFlagsResponse {
+        let now = Instant::now();
+        let clients = self.clients.lock().unwrap();
+        let mut flagged: Vec<FlaggedClient> = clients
+            .iter()
+            .filter_map(|(client, flags)| {
+                let recent: Vec<&Instant> = flags
+                    .flagged_at
+                    .iter()
+                    .filter(|at| now.duration_since(**at) < self.window)
+                    .collect();
+                if recent.is_empty() && !flags.shadow_banned {
+                    return None;
+                }
+                Some(FlaggedClient {
+                    key: client.key.clone(),
+                    address: client.address.map(|address| address.to_string()),
+                    flags: recent.len() as u32,
+                    last_flagged_secs_ago: flags
+                        .flagged_at
+                        .last()
+                        .map(|at| now.duration_since(*at).as_secs()),
+                    categories: flags.categories.clone(),
+                    cooldown_remaining_secs: flags
+                        .cooldown_until
+                        .and_then(|until| until.checked_duration_since(now))
+                        .map(|remaining| remaining.as_secs().max(1)),
+                    shadow_banned: flags.shadow_banned,
+                })
+            })
+            .collect();
+        flagged.sort_by(|a, b| b.flags.cmp(&a.flags).then(a.key.cmp(&b.key)));
+        ModerationFlagsResponse { clients: flagged }
+    }
+
+    /// Forgets the flags, cool-downs, and shadow bans of every client matching `key`
+    /// and `address` (either may be None to match any), returning how many were cleared.
+    pub fn clear(&self, key: Option<&str>, address: Option<IpAddr>) -> usize {
+        let mut clients = self.clients.lock().unwrap();
+        let before = clients.len();
+        clients.retain(|client, _| {
+            let matches = key.is_none_or(|key| client.key == key)
+                && address.is_none_or(|address| client.address == Some(address));
+            !matches
+        });
+        let cleared = before - clients.len();
+        info!(
+            "Cleared moderation flags of {} clients (key {}, address {})",
+            cleared,
+            key.unwrap_or("any"),
+            address.map_or("any".to_string(), |address| address.to_string())
+        );
+        cleared
+    }
+}
+
+// "key widget from 203.0.113.7", for logs and notifications
+fn describe(client: &ChatClient) -> String {
+    match client.address {
+        Some(address) => format!("key {} from {}", client.key, address),
+        None => format!("key {}", client.key),
+    }
+}
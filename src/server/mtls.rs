@@ -0,0 +1,185 @@
+use crate::agent::permissions::Capabilities;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+use std::net::TcpStream;
+use std::sync::Arc;
+use x509_parser::extensions::GeneralName;
+
+/// A client whose certificate was verified during the handshake.
+#[derive(Debug)]
+pub struct ClientIdentity {
+    /// The certificate name the client was identified by, e.g. `sre-oncall`
+    pub name: String,
+    pub capabilities: Capabilities,
+}
+
+#[derive(Deserialize)]
+struct IdentitiesFile {
+    identities: Vec<IdentityEntry>,
+}
+
+#[derive(Deserialize)]
+struct IdentityEntry {
+    name: String,
+    /// Everything is allowed when omitted
+    capabilities: Option<Capabilities>,
+}
+
+/// Serves TLS and requires every client to present a certificate signed by the client CA.
+///
+/// A client is identified by its certificate's subject common name or, failing that, its
+/// first DNS, email, or URI subject alternative name. With an identities file, only the
+/// listed identities are let in, each with its own capabilities; without one, any
+/// certificate the CA signed may do everything.
+pub struct MutualTls {
+    config: Arc<ServerConfig>,
+    identities: Option<HashMap<String, Capabilities>>,
+}
+
+impl MutualTls {
+    /// Loads the server's certificate chain and key, the CA that client certificates must
+    /// chain to, and optionally the identities file.
+    pub fn load(
+        cert_file: &str,
+        key_file: &str,
+        client_ca_file: &str,
+        identities_file: Option<&str>,
+    ) -> Result<Self, String> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let certs = CertificateDer::pem_file_iter(cert_file)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("failed to read TLS certificate {}: {}", cert_file, e))?;
+        let key = PrivateKeyDer::from_pem_file(key_file)
+            .map_err(|e| format!("failed to read TLS key {}: {}", key_file, e))?;
+
+        let mut roots = RootCertStore::empty();
+        let client_cas = CertificateDer::pem_file_iter(client_ca_file)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("failed to read client CA {}: {}", client_ca_file, e))?;
+        for ca in client_cas {
+            roots
+                .add(ca)
+                .map_err(|e| format!("invalid client CA in {}: {}", client_ca_file, e))?;
+        }
+        let verifier =
+            WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .map_err(|e| format!("invalid client CA {}: {}", client_ca_file, e))?;
+
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("invalid TLS certificate or key: {}", e))?;
+
+        Ok(MutualTls {
+            config: Arc::new(config),
+            identities: identities_file.map(load_identities).transpose()?,
+        })
+    }
+
+    /// Completes the handshake on `stream` and identifies the client.
+    ///
+    /// Fails if the client presents no certificate, one the client CA didn't sign, or one
+    /// whose names aren't in the identities file, and if the handshake outlasts `stream`'s
+    /// read or write timeout, which callers should set so a silent peer can't stall them.
+    pub fn accept(
+        &self,
+        mut stream: TcpStream,
+    ) -> io::Result<(StreamOwned<ServerConnection, TcpStream>, ClientIdentity)> {
+        let mut connection =
+            ServerConnection::new(self.config.clone()).map_err(io::Error::other)?;
+        while connection.is_handshaking() {
+            connection.complete_io(&mut stream)?;
+        }
+
+        let certificate = connection
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .ok_or_else(|| denied("no client certificate".to_string()))?;
+        let names = certificate_names(certificate).map_err(denied)?;
+        let client = self.identify(names.clone()).ok_or_else(|| {
+            denied(format!(
+                "client certificate names {:?} are not a known identity",
+                names
+            ))
+        })?;
+
+        Ok((StreamOwned::new(connection, stream), client))
+    }
+
+    fn identify(&self, names: Vec<String>) -> Option<ClientIdentity> {
+        match &self.identities {
+            None => names.into_iter().next().map(|name| ClientIdentity {
+                name,
+                capabilities: Capabilities::all(),
+            }),
+            Some(identities) => names.into_iter().find_map(|name| {
+                let capabilities = identities.get(&name)?.clone();
+                Some(ClientIdentity { name, capabilities })
+            }),
+        }
+    }
+}
+
+/// Loads identities from a YAML file of the form:
+///
+/// ```yaml
+/// identities:
+///   - name: sre-oncall                  # a certificate CN or SAN
+///   - name: dashboard.sre.internal
+///     capabilities: [chat, kube-read]   # optional, defaults to all
+/// ```
+fn load_identities(path: &str) -> Result<HashMap<String, Capabilities>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read identities file {}: {}", path, e))?;
+    let file: IdentitiesFile = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("failed to parse identities file {}: {}", path, e))?;
+
+    Ok(file
+        .identities
+        .into_iter()
+        .map(|entry| {
+            let capabilities = entry.capabilities.unwrap_or_else(Capabilities::all);
+            (entry.name, capabilities)
+        })
+        .collect())
+}
+
+/// The certificate's common names followed by its subject alternative names.
+fn certificate_names(der: &[u8]) -> Result<Vec<String>, String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| format!("invalid client certificate: {}", e))?;
+
+    let mut names: Vec<String> = cert
+        .subject()
+        .iter_common_name()
+        .filter_map(|cn| cn.as_str().ok())
+        .map(str::to_string)
+        .collect();
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        names.extend(
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(name)
+                    | GeneralName::RFC822Name(name)
+                    | GeneralName::URI(name) => Some(name.to_string()),
+                    _ => None,
+                }),
+        );
+    }
+    Ok(names)
+}
+
+fn denied(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, message)
+}
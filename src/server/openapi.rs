@@ -0,0 +1,251 @@
+use super::types::{
+    ChatRequest, ChatResponse, ClearFlagsRequest, ClearFlagsResponse, DeepHealthResponse,
+    DryRunResponse, IncompleteResponse, ModerationFlagsResponse, StreamEvent, UsageResponse,
+};
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value};
+
+/// Swagger UI page for GET /docs, rendering the document served at /openapi.json.
+pub const DOCS_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>rust-agent API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// Builds the OpenAPI 3.0 document describing the HTTP API.
+///
+/// Request and response schemas are generated from the types in [`super::types`], so the
+/// document can't drift from what the server actually parses and sends.
+pub fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let chat_request = gen.subschema_for::<ChatRequest>();
+    let chat_response = gen.subschema_for::<ChatResponse>();
+    let dry_run_response = gen.subschema_for::<DryRunResponse>();
+    let incomplete_response = gen.subschema_for::<IncompleteResponse>();
+    let stream_event = gen.subschema_for::<StreamEvent>();
+    let deep_health_response = gen.subschema_for::<DeepHealthResponse>();
+    let usage_response = gen.subschema_for::<UsageResponse>();
+    let moderation_flags_response = gen.subschema_for::<ModerationFlagsResponse>();
+    let clear_flags_request = gen.subschema_for::<ClearFlagsRequest>();
+    let clear_flags_response = gen.subschema_for::<ClearFlagsResponse>();
+
+    let text = |description: &str| {
+        json!({
+            "description": description,
+            "content": { "text/plain": { "schema": { "type": "string" } } }
+        })
+    };
+    let model_header = json!({
+        "X-Agent-Model": {
+            "description": "The model that answered, e.g. `openai:gpt-5.1`",
+            "schema": { "type": "string" }
+        }
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "rust-agent",
+            "description": "Chat with an AI agent about the portfolio site and its Kubernetes infrastructure.",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "security": [{ "apiKey": [] }],
+        "paths": {
+            "/": {
+                "get": {
+                    "summary": "Health check",
+                    "responses": {
+                        "200": {
+                            "description": "The server is up",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": { "healthy": { "type": "boolean" } }
+                                    }
+                                }
+                            }
+                        },
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key")
+                    }
+                }
+            },
+            "/healthz/deep": {
+                "get": {
+                    "summary": "Health check that also calls OpenAI and the cluster (needs the admin capability)",
+                    "responses": {
+                        "200": {
+                            "description": "Every dependency is healthy",
+                            "content": { "application/json": { "schema": deep_health_response } }
+                        },
+                        "503": {
+                            "description": "At least one dependency is unhealthy",
+                            "content": { "application/json": { "schema": deep_health_response } }
+                        },
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key, or the key lacks the admin capability")
+                    }
+                }
+            },
+            "/usage": {
+                "get": {
+                    "summary": "Per-key requests, tokens, estimated cost, and error rates from the audit log (needs the admin capability)",
+                    "responses": {
+                        "200": {
+                            "description": "Usage per key for each configured window",
+                            "content": { "application/json": { "schema": usage_response } }
+                        },
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key, or the key lacks the admin capability"),
+                        "404": text("The audit log is disabled (AUDIT_LOG_PATH is unset)"),
+                        "500": text("The audit log couldn't be read")
+                    }
+                }
+            },
+            "/moderation/flags": {
+                "get": {
+                    "summary": "Clients whose prompts were flagged by moderation (needs the admin capability)",
+                    "responses": {
+                        "200": {
+                            "description": "Flagged clients, most flagged first",
+                            "content": { "application/json": { "schema": moderation_flags_response } }
+                        },
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key, or the key lacks the admin capability"),
+                        "404": text("Moderation is disabled (MODERATION_ENABLED is false)")
+                    }
+                }
+            },
+            "/moderation/clear": {
+                "post": {
+                    "summary": "Forget the flags, cool-downs, and shadow bans of matching clients (needs the admin capability)",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": clear_flags_request } }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "How many clients were cleared",
+                            "content": { "application/json": { "schema": clear_flags_response } }
+                        },
+                        "400": text("Missing body, neither key nor address given, or an invalid address"),
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key, or the key lacks the admin capability"),
+                        "404": text("Moderation is disabled (MODERATION_ENABLED is false)"),
+                        "405": text("Method other than POST")
+                    }
+                }
+            },
+            "/logs": {
+                "get": {
+                    "summary": "WebSocket streaming a pod's log as it is written, one text message per line (needs the admin capability)",
+                    "parameters": [
+                        { "name": "namespace", "in": "query", "required": true, "schema": { "type": "string" } },
+                        { "name": "pod", "in": "query", "required": true, "schema": { "type": "string" } },
+                        { "name": "container", "in": "query", "schema": { "type": "string" } },
+                        { "name": "tail_lines", "in": "query", "schema": { "type": "integer", "default": 100, "maximum": 5000 } }
+                    ],
+                    "responses": {
+                        "101": text("Switched to the WebSocket protocol"),
+                        "400": text("Missing or invalid namespace, pod, container, or tail_lines"),
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key, or the key lacks the admin capability"),
+                        "404": text("No such pod, or log streaming is disabled"),
+                        "426": text("Not a WebSocket upgrade request"),
+                        "502": text("The Kubernetes API couldn't be reached")
+                    }
+                }
+            },
+            "/chat": {
+                "post": {
+                    "summary": "Ask the agent a question (needs the chat capability)",
+                    "parameters": [
+                        {
+                            "name": "Accept-Language",
+                            "in": "header",
+                            "description": "Language to answer in when the body sets no `language`",
+                            "schema": { "type": "string" }
+                        }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": chat_request } }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The agent's answer as JSON (the plan when `dry_run` is set), or server-sent events when `stream` is set",
+                            "headers": model_header,
+                            "content": {
+                                "application/json": {
+                                    "schema": { "oneOf": [chat_response, dry_run_response] }
+                                },
+                                "text/event-stream": { "schema": stream_event }
+                            }
+                        },
+                        "400": text("Missing or invalid JSON body, an invalid role in the chat history, an invalid `language`, `continue` without a `session_id`, or a prompt flagged by moderation"),
+                        "401": text("Missing API key"),
+                        "403": text("Invalid API key, or the key lacks the chat capability"),
+                        "404": text("`continue` when nothing is left of the session's last response"),
+                        "405": text("Method other than POST"),
+                        "413": text("Prompt exceeds MAX_PROMPT_CHARS or MAX_PROMPT_TOKENS"),
+                        "429": text("Locked out after too many invalid API keys, or cooling down after too many flagged prompts"),
+                        "502": {
+                            "description": "Every model in the fallback chain failed (text), or a model failed after the agent changed the cluster, which stops the chain (JSON, with the tool calls made)",
+                            "content": {
+                                "text/plain": { "schema": { "type": "string" } },
+                                "application/json": { "schema": incomplete_response }
+                            }
+                        },
+                        "503": text("Shed because the models breach SHED_MODEL_LATENCY_MS or SHED_MODEL_ERROR_RATE (non-admin keys only)"),
+                        "504": {
+                            "description": "The agent exceeded CHAT_TIMEOUT_SECS",
+                            "content": { "application/json": { "schema": incomplete_response } }
+                        }
+                    }
+                }
+            },
+            "/openapi.json": {
+                "get": {
+                    "summary": "This document",
+                    "security": [],
+                    "responses": {
+                        "200": {
+                            "description": "OpenAPI 3.0 document",
+                            "content": { "application/json": { "schema": { "type": "object" } } }
+                        }
+                    }
+                }
+            },
+            "/docs": {
+                "get": {
+                    "summary": "Swagger UI for this document",
+                    "security": [],
+                    "responses": {
+                        "200": {
+                            "description": "HTML page",
+                            "content": { "text/html": { "schema": { "type": "string" } } }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": gen.definitions(),
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" }
+            }
+        }
+    })
+}
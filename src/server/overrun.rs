@@ -0,0 +1,78 @@
+use super::audit::ChatAudit;
+use crate::agent::tokens::estimate_tokens;
+use crate::notifier::{EventKind, Notification, Notifier};
+use serde_json::json;
+use std::time::Duration;
+use tracing::*;
+
+/// Thresholds above which a finished chat request is reported as slow or over budget.
+///
+/// Reports are warn-level events carrying the request's tool trace, so runaway agent
+/// loops show up as soon as they finish, and are also sent to the notifier. A threshold
+/// of zero is never exceeded.
+#[derive(Clone, Default)]
+pub struct RequestThresholds {
+    /// Total request latency in milliseconds
+    pub latency_ms: u64,
+    /// Estimated prompt plus response tokens
+    pub tokens: usize,
+    /// Number of tool calls made while answering
+    pub tool_calls: usize,
+    pub notifier: Notifier,
+}
+
+impl RequestThresholds {
+    /// Reports `audit` if its request exceeded any threshold.
+    pub fn check(&self, request_id: &str, audit: &ChatAudit, latency: Duration) {
+        let latency_ms = latency.as_millis() as u64;
+        let tokens = audit.prompt.as_deref().map_or(0, estimate_tokens)
+            + audit.response.as_deref().map_or(0, estimate_tokens);
+        let tool_calls = audit.tool_calls.len();
+
+        let mut exceeded = Vec::new();
+        if self.latency_ms > 0 && latency_ms > self.latency_ms {
+            exceeded.push(format!(
+                "took {}ms (threshold {}ms)",
+                latency_ms, self.latency_ms
+            ));
+        }
+        if self.tokens > 0 && tokens > self.tokens {
+            exceeded.push(format!(
+                "used ~{} tokens (threshold {})",
+                tokens, self.tokens
+            ));
+        }
+        if self.tool_calls > 0 && tool_calls > self.tool_calls {
+            exceeded.push(format!(
+                "made {} tool calls (threshold {})",
+                tool_calls, self.tool_calls
+            ));
+        }
+        if exceeded.is_empty() {
+            return;
+        }
+
+        let summary = format!("Chat request {} {}", request_id, exceeded.join(", "));
+        let tool_trace = serde_json::to_value(&audit.tool_calls).unwrap_or_default();
+        warn!(
+            latency_ms,
+            tokens,
+            tool_calls,
+            tool_trace = %tool_trace,
+            "{}",
+            summary
+        );
+
+        self.notifier.notify(Notification {
+            kind: EventKind::RequestOverrun,
+            text: summary,
+            details: json!({
+                "request_id": request_id,
+                "latency_ms": latency_ms,
+                "tokens": tokens,
+                "tool_calls": tool_calls,
+                "tool_trace": tool_trace,
+            }),
+        });
+    }
+}
@@ -0,0 +1,408 @@
+use super::types::Request;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+/// An IPv4 or IPv6 network, e.g. `10.0.0.0/8` or `fd00::/8`. A bare address is a
+/// network of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    /// `0.0.0.0/0`, every IPv4 address
+    pub const ANY_IPV4: Cidr = Cidr {
+        network: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        prefix_len: 0,
+    };
+
+    /// `::/0`, every IPv6 address
+    pub const ANY_IPV6: Cidr = Cidr {
+        network: IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        prefix_len: 0,
+    };
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        if self.prefix_len == 0 {
+            return self.network.is_ipv4() == addr.to_canonical().is_ipv4();
+        }
+        match (self.network, addr.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let shift = 32 - self.prefix_len;
+                u32::from(network) >> shift == u32::from(addr) >> shift
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let shift = 128 - self.prefix_len;
+                u128::from(network) >> shift == u128::from(addr) >> shift
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+        let network = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid address in {}", s))?
+            .to_canonical();
+        let width = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= width)
+                .ok_or_else(|| format!("invalid prefix length in {}", s))?,
+            None => width,
+        };
+        Ok(Cidr {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Header trusted proxies report the addresses a request was forwarded for in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForwardedHeader {
+    /// `X-Forwarded-For: 203.0.113.7, 10.0.0.5`
+    #[default]
+    XForwardedFor,
+    /// RFC 7239 `Forwarded: for=203.0.113.7;proto=https, for=10.0.0.5`
+    Forwarded,
+}
+
+impl FromStr for ForwardedHeader {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "x-forwarded-for" => Ok(ForwardedHeader::XForwardedFor),
+            "forwarded" => Ok(ForwardedHeader::Forwarded),
+            other => Err(format!("unknown forwarding header {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for ForwardedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForwardedHeader::XForwardedFor => write!(f, "x-forwarded-for"),
+            ForwardedHeader::Forwarded => write!(f, "forwarded"),
+        }
+    }
+}
+
+/// Works out the real client address of requests that arrive through reverse proxies.
+///
+/// When the peer is in one of the trusted networks, its forwarding header is read
+/// right to left, skipping addresses that are trusted proxies too; the first one that
+/// isn't is the client. Each proxy appends the address it received the request from,
+/// so everything left of that could have been written by the client and is ignored.
+/// Peers outside the trusted networks are the client themselves, whatever headers
+/// they send. With no trusted networks, the client is always the peer.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Vec<Cidr>,
+    header: ForwardedHeader,
+}
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<Cidr>, header: ForwardedHeader) -> Self {
+        TrustedProxies { networks, header }
+    }
+
+    pub fn is_trusted(&self, addr: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(addr))
+    }
+
+    /// The address `request`, received from `peer`, was made from.
+    pub fn client_ip(&self, peer: Option<IpAddr>, request: &Request) -> Option<IpAddr> {
+        let peer = peer?.to_canonical();
+        if !self.is_trusted(peer) {
+            return Some(peer);
+        }
+        let header = match self.header {
+            ForwardedHeader::XForwardedFor => request.forwarded_for.as_deref(),
+            ForwardedHeader::Forwarded => request.forwarded.as_deref(),
+        };
+        let Some(header) = header else {
+            return Some(peer);
+        };
+
+        // An entry that isn't an address (e.g. "unknown") ends the walk at the proxy
+        // that wrote it
+        let mut client = peer;
+        for hop in forwarding_chain(self.header, header).into_iter().rev() {
+            let Some(hop) = hop else {
+                break;
+            };
+            client = hop;
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+// The addresses in a forwarding header, the original client first
+fn forwarding_chain(header: ForwardedHeader, value: &str) -> Vec<Option<IpAddr>> {
+    match header {
+        ForwardedHeader::XForwardedFor => value.split(',').map(parse_node).collect(),
+        ForwardedHeader::Forwarded => value
+            .split(',')
+            .map(|element| {
+                element
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(name, _)| name.trim().eq_ignore_ascii_case("for"))
+                    .and_then(|(_, node)| parse_node(node))
+            })
+            .collect(),
+    }
+}
+
+// "203.0.113.7", "203.0.113.7:4711", "2001:db8::1", "[2001:db8::1]:4711", optionally
+// quoted as the Forwarded header requires for IPv6
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Ok(addr) = node.parse::<IpAddr>() {
+        return Some(addr.to_canonical());
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip().to_canonical());
+    }
+    let addr = node.strip_prefix('[')?.strip_suffix(']')?;
+    addr.parse::<IpAddr>().ok().map(|addr| addr.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn request_with(header: &str) -> Request {
+        Request::parse(&format!("GET / HTTP/1.1\r\n{}\r\n\r\n", header)).unwrap()
+    }
+
+    fn trusting(networks: &[&str], header: ForwardedHeader) -> TrustedProxies {
+        TrustedProxies::new(networks.iter().map(|s| cidr(s)).collect(), header)
+    }
+
+    #[test]
+    fn takes_the_right_most_untrusted_hop_not_the_spoofed_left_most_one() {
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::XForwardedFor);
+        let request = request_with("X-Forwarded-For: 6.6.6.6, 203.0.113.7, 10.0.0.5");
+        assert_eq!(
+            proxies.client_ip(Some(ip("10.0.0.1")), &request),
+            Some(ip("203.0.113.7"))
+        );
+    }
+
+    #[test]
+    fn ignores_the_header_from_an_untrusted_peer() {
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::XForwardedFor);
+        let request = request_with("X-Forwarded-For: 203.0.113.7");
+        assert_eq!(
+            proxies.client_ip(Some(ip("198.51.100.9")), &request),
+            Some(ip("198.51.100.9"))
+        );
+        let request = request_with("Forwarded: for=203.0.113.7");
+        assert_eq!(
+            proxies.client_ip(Some(ip("198.51.100.9")), &request),
+            Some(ip("198.51.100.9"))
+        );
+    }
+
+    #[test]
+    fn ignores_everything_without_trusted_networks() {
+        let proxies = TrustedProxies::default();
+        let request = request_with("X-Forwarded-For: 203.0.113.7");
+        assert_eq!(
+            proxies.client_ip(Some(ip("10.0.0.1")), &request),
+            Some(ip("10.0.0.1"))
+        );
+        assert_eq!(proxies.client_ip(None, &request), None);
+    }
+
+    #[test]
+    fn reads_ipv6_bracketed_and_port_forms() {
+        let proxies = trusting(&["10.0.0.0/8", "fd00::/8"], ForwardedHeader::XForwardedFor);
+        for (header, client) in [
+            ("X-Forwarded-For: 2001:db8::1", "2001:db8::1"),
+            ("X-Forwarded-For: [2001:db8::1]", "2001:db8::1"),
+            ("X-Forwarded-For: [2001:db8::1]:4711", "2001:db8::1"),
+            ("X-Forwarded-For: 203.0.113.7:4711", "203.0.113.7"),
+            ("X-Forwarded-For: 203.0.113.7, fd00::5", "203.0.113.7"),
+            ("X-Forwarded-For: ::ffff:203.0.113.7", "203.0.113.7"),
+        ] {
+            assert_eq!(
+                proxies.client_ip(Some(ip("10.0.0.1")), &request_with(header)),
+                Some(ip(client)),
+                "{}",
+                header
+            );
+        }
+
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::Forwarded);
+        for (header, client) in [
+            (
+                r#"Forwarded: for="[2001:db8::1]:4711";proto=https"#,
+                "2001:db8::1",
+            ),
+            ("Forwarded: proto=https;for=203.0.113.7:4711", "203.0.113.7"),
+            ("Forwarded: for=203.0.113.7, for=10.0.0.5", "203.0.113.7"),
+        ] {
+            assert_eq!(
+                proxies.client_ip(Some(ip("10.0.0.1")), &request_with(header)),
+                Some(ip(client)),
+                "{}",
+                header
+            );
+        }
+    }
+
+    #[test]
+    fn a_peer_mapped_into_ipv6_is_still_trusted() {
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::XForwardedFor);
+        let request = request_with("X-Forwarded-For: 203.0.113.7");
+        assert_eq!(
+            proxies.client_ip(Some(ip("::ffff:10.0.0.1")), &request),
+            Some(ip("203.0.113.7"))
+        );
+    }
+
+    #[test]
+    fn stops_at_unknown_or_garbage_hops() {
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::XForwardedFor);
+        for (header, client) in [
+            ("X-Forwarded-For: 203.0.113.7, unknown", "10.0.0.1"),
+            ("X-Forwarded-For: 203.0.113.7, not-an-ip", "10.0.0.1"),
+            ("X-Forwarded-For: 203.0.113.7, 300.1.2.3", "10.0.0.1"),
+            ("X-Forwarded-For: 203.0.113.7, , 10.0.0.5", "10.0.0.5"),
+            ("X-Forwarded-For: unknown, 10.0.0.5", "10.0.0.5"),
+        ] {
+            assert_eq!(
+                proxies.client_ip(Some(ip("10.0.0.1")), &request_with(header)),
+                Some(ip(client)),
+                "{}",
+                header
+            );
+        }
+
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::Forwarded);
+        for header in [
+            "Forwarded: for=unknown",
+            "Forwarded: for=_hidden",
+            "Forwarded: proto=https",
+        ] {
+            assert_eq!(
+                proxies.client_ip(Some(ip("10.0.0.1")), &request_with(header)),
+                Some(ip("10.0.0.1")),
+                "{}",
+                header
+            );
+        }
+    }
+
+    #[test]
+    fn a_chain_of_trusted_hops_ends_at_the_left_most_one() {
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::XForwardedFor);
+        let request = request_with("X-Forwarded-For: 10.0.0.7, 10.0.0.6, 10.0.0.5");
+        assert_eq!(
+            proxies.client_ip(Some(ip("10.0.0.1")), &request),
+            Some(ip("10.0.0.7"))
+        );
+    }
+
+    #[test]
+    fn uses_the_peer_when_a_trusted_peer_sends_no_header() {
+        let proxies = trusting(&["10.0.0.0/8"], ForwardedHeader::Forwarded);
+        // Only the configured header counts
+        let request = request_with("X-Forwarded-For: 203.0.113.7");
+        assert_eq!(
+            proxies.client_ip(Some(ip("10.0.0.1")), &request),
+            Some(ip("10.0.0.1"))
+        );
+    }
+
+    #[test]
+    fn cidr_boundaries() {
+        let network = cidr("192.168.1.0/24");
+        assert!(network.contains(ip("192.168.1.0")));
+        assert!(network.contains(ip("192.168.1.255")));
+        assert!(!network.contains(ip("192.168.0.255")));
+        assert!(!network.contains(ip("192.168.2.0")));
+
+        let host = cidr("192.168.1.7/32");
+        assert!(host.contains(ip("192.168.1.7")));
+        assert!(!host.contains(ip("192.168.1.6")));
+        assert!(!host.contains(ip("192.168.1.8")));
+        assert_eq!(cidr("192.168.1.7"), host);
+
+        let host = cidr("2001:db8::7/128");
+        assert!(host.contains(ip("2001:db8::7")));
+        assert!(!host.contains(ip("2001:db8::6")));
+        assert!(!host.contains(ip("2001:db8::8")));
+        assert_eq!(cidr("2001:db8::7"), host);
+
+        let network = cidr("fd00::/8");
+        assert!(network.contains(ip("fdff:ffff::1")));
+        assert!(!network.contains(ip("fe00::1")));
+    }
+
+    #[test]
+    fn any_network_covers_only_its_own_family() {
+        assert_eq!(cidr("0.0.0.0/0"), Cidr::ANY_IPV4);
+        assert_eq!(cidr("::/0"), Cidr::ANY_IPV6);
+        assert!(Cidr::ANY_IPV4.contains(ip("0.0.0.0")));
+        assert!(Cidr::ANY_IPV4.contains(ip("255.255.255.255")));
+        assert!(Cidr::ANY_IPV4.contains(ip("::ffff:203.0.113.7")));
+        assert!(!Cidr::ANY_IPV4.contains(ip("2001:db8::1")));
+        assert!(Cidr::ANY_IPV6.contains(ip("::")));
+        assert!(Cidr::ANY_IPV6.contains(ip("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")));
+        assert!(!Cidr::ANY_IPV6.contains(ip("203.0.113.7")));
+    }
+
+    #[test]
+    fn mismatched_families_never_match() {
+        assert!(!cidr("10.0.0.0/8").contains(ip("::1")));
+        assert!(!cidr("10.0.0.1/32").contains(ip("::a00:1")));
+        assert!(!cidr("::/1").contains(ip("10.0.0.1")));
+        // An IPv4-mapped network is the IPv4 one
+        assert_eq!(cidr("::ffff:10.0.0.0/8"), cidr("10.0.0.0/8"));
+        assert!(cidr("::ffff:10.0.0.0/8").contains(ip("10.1.2.3")));
+    }
+
+    #[test]
+    fn refuses_invalid_networks() {
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("2001:db8::/129".parse::<Cidr>().is_err());
+        assert!("10.0.0.0/-1".parse::<Cidr>().is_err());
+        assert!("10.0.0/8".parse::<Cidr>().is_err());
+        assert!("example.com".parse::<Cidr>().is_err());
+    }
+}
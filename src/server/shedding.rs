@@ -0,0 +1,66 @@
+use crate::agent::stats::ModelHealth;
+use std::time::Duration;
+
+/// Seconds shed clients are told to wait before retrying.
+pub const RETRY_AFTER_SECS: u64 = 30;
+
+/// Thresholds past which chat requests from non-admin keys are turned away with 503
+/// while the model provider is degraded.
+///
+/// Slow or failing models otherwise leave requests queued behind each other until
+/// they time out; shedding the low-priority ones early keeps the model chain free for
+/// the admin requests that can still succeed. A threshold of zero never sheds.
+#[derive(Debug, Clone)]
+pub struct LoadShedding {
+    /// p90 model call latency in milliseconds
+    pub max_latency_ms: u64,
+    /// Share of failed model calls, from 0.0 to 1.0
+    pub max_error_rate: f64,
+    /// How far back model calls are measured
+    pub window: Duration,
+    /// Calls needed in the window before anything is shed, so a single slow call can't
+    /// trip shedding on a quiet server
+    pub min_calls: usize,
+}
+
+impl Default for LoadShedding {
+    fn default() -> Self {
+        LoadShedding {
+            max_latency_ms: 0,
+            max_error_rate: 0.0,
+            window: Duration::from_secs(120),
+            min_calls: 5,
+        }
+    }
+}
+
+impl LoadShedding {
+    /// Why a low-priority request should be shed given the models' `health`, or None
+    /// if it can go ahead.
+    pub fn reason(&self, health: &ModelHealth) -> Option<String> {
+        if health.calls < self.min_calls.max(1) {
+            return None;
+        }
+
+        let latency_ms = health.p90_latency.as_millis() as u64;
+        if self.max_latency_ms > 0 && latency_ms > self.max_latency_ms {
+            return Some(format!(
+                "p90 model latency {}ms exceeds {}ms",
+                latency_ms, self.max_latency_ms
+            ));
+        }
+        if self.max_error_rate > 0.0 && health.error_rate > self.max_error_rate {
+            return Some(format!(
+                "model error rate {:.0}% exceeds {:.0}%",
+                health.error_rate * 100.0,
+                self.max_error_rate * 100.0
+            ));
+        }
+        None
+    }
+
+    /// Whether either threshold is set.
+    pub fn is_enabled(&self) -> bool {
+        self.max_latency_ms > 0 || self.max_error_rate > 0.0
+    }
+}
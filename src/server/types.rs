@@ -1,8 +1,11 @@
+use crate::agent::trace::{ToolCallRecord, ToolEvent};
 use rig::completion::Message;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// HTTP methods supported by the server
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Method {
     GET,
     POST,
@@ -25,8 +28,22 @@ pub enum Path {
     Chat,
     /// GET / - Health check endpoint
     Root,
+    /// GET /healthz/deep - Health check that also calls OpenAI and the cluster
+    DeepHealth,
     /// GET /favicon.ico - Favicon request (returns 404)
     Favicon,
+    /// GET /openapi.json - OpenAPI document describing this API
+    OpenApi,
+    /// GET /docs - Swagger UI for the OpenAPI document
+    Docs,
+    /// GET /usage - Per-key usage report from the audit log
+    Usage,
+    /// GET /logs - WebSocket following a pod's log
+    Logs,
+    /// GET /moderation/flags - Clients with flagged prompts
+    ModerationFlags,
+    /// POST /moderation/clear - Forget the flags of matching clients
+    ModerationClear,
 }
 
 impl Path {
@@ -34,7 +51,14 @@ impl Path {
         match s {
             "/chat" => Some(Path::Chat),
             "/" => Some(Path::Root),
+            "/healthz/deep" => Some(Path::DeepHealth),
             "/favicon.ico" => Some(Path::Favicon),
+            "/openapi.json" => Some(Path::OpenApi),
+            "/docs" => Some(Path::Docs),
+            "/usage" => Some(Path::Usage),
+            "/logs" => Some(Path::Logs),
+            "/moderation/flags" => Some(Path::ModerationFlags),
+            "/moderation/clear" => Some(Path::ModerationClear),
             _ => None,
         }
     }
@@ -45,7 +69,17 @@ impl Path {
 pub struct Request {
     pub method: Method,
     pub path: Path,
+    /// Query string of the request target, without the `?`
+    pub query: Option<String>,
     pub api_key: Option<String>,
+    /// X-Forwarded-For header, every hop of the chain
+    pub forwarded_for: Option<String>,
+    /// RFC 7239 Forwarded header, every hop of the chain
+    pub forwarded: Option<String>,
+    /// Accept-Language header, for answering in the client's language
+    pub accept_language: Option<String>,
+    /// Sec-WebSocket-Key header of a WebSocket upgrade request
+    pub websocket_key: Option<String>,
     pub body: Option<String>,
 }
 
@@ -53,8 +87,11 @@ impl Request {
     /// Parses an HTTP/1.1 request string into a Request struct.
     ///
     /// Extracts:
-    /// - HTTP method and path from the request line
+    /// - HTTP method, path, and query string from the request line
     /// - X-API-Key header for authentication
+    /// - X-Forwarded-For and Forwarded headers, for identifying clients behind a proxy
+    /// - Accept-Language header, for answering in the client's language
+    /// - Sec-WebSocket-Key header, for WebSocket upgrades
     /// - Request body based on Content-Length header
     ///
     /// Returns None if the request is malformed or uses unsupported method/path.
@@ -64,10 +101,19 @@ impl Request {
         let mut parts = first_line.split_whitespace();
 
         let method = parts.next().and_then(Method::from_str)?;
-        let path = parts.next().and_then(Path::from_str)?;
+        let target = parts.next()?;
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        let path = Path::from_str(path)?;
 
         let mut content_length = 0;
         let mut api_key = None;
+        let mut forwarded_for: Option<String> = None;
+        let mut forwarded: Option<String> = None;
+        let mut accept_language = None;
+        let mut websocket_key = None;
 
         // Parse headers
         for line in lines.by_ref() {
@@ -79,6 +125,26 @@ impl Request {
                     api_key = Some(key_str.trim().to_string());
                 }
             }
+            if line.to_lowercase().starts_with("x-forwarded-for:") {
+                if let Some((_, addrs)) = line.split_once(':') {
+                    forwarded_for = Some(join_header(forwarded_for, addrs));
+                }
+            }
+            if line.to_lowercase().starts_with("forwarded:") {
+                if let Some((_, elements)) = line.split_once(':') {
+                    forwarded = Some(join_header(forwarded, elements));
+                }
+            }
+            if line.to_lowercase().starts_with("accept-language:") {
+                if let Some((_, languages)) = line.split_once(':') {
+                    accept_language = Some(languages.trim().to_string());
+                }
+            }
+            if line.to_lowercase().starts_with("sec-websocket-key:") {
+                if let Some((_, key)) = line.split_once(':') {
+                    websocket_key = Some(key.trim().to_string());
+                }
+            }
             if line.to_lowercase().starts_with("content-length:") {
                 if let Some(len_str) = line.split(':').nth(1) {
                     content_length = len_str.trim().parse().unwrap_or(0);
@@ -97,23 +163,285 @@ impl Request {
         Some(Request {
             method,
             path,
+            query,
             body,
             api_key,
+            forwarded_for,
+            forwarded,
+            accept_language,
+            websocket_key,
         })
     }
 }
 
+// Appends a repeated header's value, as proxies may add a line instead of extending one
+fn join_header(previous: Option<String>, value: &str) -> String {
+    match previous {
+        Some(previous) => format!("{}, {}", previous, value.trim()),
+        None => value.trim().to_string(),
+    }
+}
+
 /// Request payload for the /chat endpoint
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ChatRequest {
     /// The user's prompt/question
     pub prompt: String,
     /// Optional conversation history for context
     pub chat_history: Option<Vec<HttpMessage>>,
+    /// Optional session id; history for the session is stored server-side
+    pub session_id: Option<String>,
+    /// Language to answer in, as a name or BCP 47 tag (e.g. "German" or "de-DE").
+    /// Defaults to the Accept-Language header's preferred language, then to the
+    /// language of the prompt
+    pub language: Option<String>,
+    /// Ignored: the tool call trace is always part of the response. Accepted so older
+    /// clients keep working
+    #[serde(default)]
+    pub include_trace: bool,
+    /// When true, tools are not executed and the response is the agent's tool call plan
+    #[serde(default)]
+    pub dry_run: bool,
+    /// When true, the response includes suggested follow-up questions
+    #[serde(default)]
+    pub include_suggestions: bool,
+    /// When true, the response is a stream of server-sent events reporting each tool call
+    /// as it starts and completes, ending with the response
+    #[serde(default)]
+    pub stream: bool,
+    /// When true, the prompt is ignored and the response is the next page of the
+    /// session's last response, which was cut at MAX_RESPONSE_CHARS
+    #[serde(default, rename = "continue")]
+    pub continue_response: bool,
+}
+
+/// Version of the /chat response bodies. Fields may be added within a version; it is
+/// bumped when a field is removed or changes meaning.
+pub const CHAT_RESPONSE_VERSION: u32 = 1;
+
+/// JSON response body for /chat when `dry_run` is requested
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DryRunResponse<'a> {
+    /// [`CHAT_RESPONSE_VERSION`]
+    pub version: u32,
+    /// Always true; lets clients distinguish plans from regular responses
+    pub dry_run: bool,
+    /// Tool calls the agent would have made, in order
+    pub plan: Vec<PlannedToolCall<'a>>,
+    /// The agent's response, written without any tool results
+    pub response: &'a str,
+    /// The model that produced the plan
+    pub model: &'a str,
+}
+
+/// A tool call the agent planned during a dry run
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PlannedToolCall<'a> {
+    pub tool: &'a str,
+    pub args: &'a serde_json::Value,
+}
+
+/// JSON error body returned when a chat request exceeds its deadline, or its model
+/// fails after the agent changed the cluster
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct IncompleteResponse<'a> {
+    /// Human-readable error message
+    pub error: String,
+    /// Tool calls that completed before the request stopped
+    pub tool_trace: &'a [ToolCallRecord],
+}
+
+/// JSON response body for /chat
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChatResponse<'a> {
+    /// [`CHAT_RESPONSE_VERSION`]
+    pub version: u32,
+    /// The agent's response text
+    pub response: &'a str,
+    /// The request's session id, if it had one
+    pub session_id: Option<&'a str>,
+    /// The model that produced the response; null for `continue` pages
+    pub model: Option<&'a str>,
+    pub usage: ChatUsage,
+    /// Tool calls made while answering, in invocation order
+    pub tool_trace: &'a [ToolCallRecord],
+    pub finish_reason: FinishReason,
+    /// Suggested follow-up questions for quick-reply buttons, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<&'a [String]>,
+    /// Characters left after `response` was cut at MAX_RESPONSE_CHARS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_chars: Option<usize>,
+}
+
+/// Estimated model usage of one /chat request, as the /usage report counts it
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct ChatUsage {
+    /// Estimated tokens of the prompt, excluding history and tool output
+    pub prompt_tokens: u64,
+    /// Estimated tokens of the whole response, including any part not yet sent
+    pub response_tokens: u64,
+    /// The token estimates priced at USAGE_PROMPT_PRICE_PER_MTOK and
+    /// USAGE_RESPONSE_PRICE_PER_MTOK
+    pub estimated_cost_usd: f64,
+}
+
+/// Why a /chat response ended where it did
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The response is complete
+    Stop,
+    /// The response was cut at MAX_RESPONSE_CHARS; `continue` fetches the rest
+    Truncated,
+}
+
+/// One server-sent event of a streamed /chat response; `event` repeats the SSE event name
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent<'a> {
+    /// A tool is about to run
+    ToolStart {
+        tool: &'a str,
+        args: &'a serde_json::Value,
+    },
+    /// A tool call completed, or was refused or planned
+    ToolEnd(&'a ToolCallRecord),
+    /// The agent's answer; the last event of a successful stream
+    Response(ChatResponse<'a>),
+    /// The plan of a dry run, sent instead of `response`
+    DryRun(DryRunResponse<'a>),
+    /// The agent exceeded its deadline; the last event
+    Timeout(IncompleteResponse<'a>),
+    /// The request failed; the last event
+    Error {
+        /// HTTP status the request would have answered with
+        status: &'a str,
+        error: &'a str,
+    },
+}
+
+impl StreamEvent<'_> {
+    /// SSE event name, the same as the `event` field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::ToolStart { .. } => "tool_start",
+            StreamEvent::ToolEnd(_) => "tool_end",
+            StreamEvent::Response(_) => "response",
+            StreamEvent::DryRun(_) => "dry_run",
+            StreamEvent::Timeout(_) => "timeout",
+            StreamEvent::Error { .. } => "error",
+        }
+    }
+}
+
+impl<'a> From<&'a ToolEvent> for StreamEvent<'a> {
+    fn from(event: &'a ToolEvent) -> Self {
+        match event {
+            ToolEvent::Started { tool, args } => StreamEvent::ToolStart { tool, args },
+            ToolEvent::Finished(record) => StreamEvent::ToolEnd(record),
+        }
+    }
+}
+
+/// JSON response body for GET /healthz/deep
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DeepHealthResponse {
+    /// True only if every dependency is healthy
+    pub healthy: bool,
+    pub dependencies: Vec<DependencyStatus>,
+}
+
+/// JSON response body for GET /usage
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UsageResponse {
+    /// One entry per configured window, shortest first
+    pub windows: Vec<UsageWindow>,
+}
+
+/// Usage per API key over the last `days` days
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UsageWindow {
+    pub days: u64,
+    /// Keys that made chat requests in the window, busiest first
+    pub keys: Vec<KeyUsage>,
+}
+
+/// Chat requests of one API key (or client certificate identity) within a window
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct KeyUsage {
+    /// The key's name, or the client certificate's identity
+    pub key: String,
+    pub requests: u64,
+    /// Requests answered with a 4xx or 5xx status
+    pub errors: u64,
+    /// `errors` divided by `requests`
+    pub error_rate: f64,
+    /// Estimated tokens of the prompts, excluding history and tool output
+    pub prompt_tokens: u64,
+    /// Estimated tokens of the responses
+    pub response_tokens: u64,
+    /// The token estimates priced at USAGE_PROMPT_PRICE_PER_MTOK and
+    /// USAGE_RESPONSE_PRICE_PER_MTOK
+    pub estimated_cost_usd: f64,
+}
+
+/// JSON response body for GET /moderation/flags
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ModerationFlagsResponse {
+    /// Clients with flagged prompts in the window or a shadow ban, most flagged first
+    pub clients: Vec<FlaggedClient>,
+}
+
+/// A chat client whose prompts were flagged by moderation
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FlaggedClient {
+    /// The API key's name, or the client certificate's identity
+    pub key: String,
+    /// The client's address, if known
+    pub address: Option<String>,
+    /// Flagged prompts within ABUSE_WINDOW_SECS
+    pub flags: u32,
+    pub last_flagged_secs_ago: Option<u64>,
+    /// Moderation categories of the flagged prompts, e.g. "harassment"
+    pub categories: Vec<String>,
+    /// Seconds left of the client's cool-down, if it is cooling down
+    pub cooldown_remaining_secs: Option<u64>,
+    pub shadow_banned: bool,
+}
+
+/// Request payload for POST /moderation/clear; clients matching every given field are
+/// cleared
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClearFlagsRequest {
+    /// The API key's name
+    pub key: Option<String>,
+    /// The client's address
+    pub address: Option<String>,
+}
+
+/// JSON response body for POST /moderation/clear
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClearFlagsResponse {
+    /// Number of clients whose flags were cleared
+    pub cleared: usize,
+}
+
+/// Result of checking one upstream dependency
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DependencyStatus {
+    /// The dependency checked, e.g. "openai" or "kubernetes"
+    pub name: &'static str,
+    pub healthy: bool,
+    /// How long the check took, including a timeout
+    pub latency_ms: u64,
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// A single message in a chat conversation
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct HttpMessage {
     /// Message role: "user" or "assistant"
     pub role: String,
@@ -121,6 +449,22 @@ pub struct HttpMessage {
     pub content: String,
 }
 
+impl HttpMessage {
+    pub fn user(content: impl Into<String>) -> Self {
+        HttpMessage {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        HttpMessage {
+            role: "assistant".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
 impl TryFrom<HttpMessage> for Message {
     type Error = &'static str;
 
@@ -0,0 +1,100 @@
+use super::audit::UsageRecord;
+use super::types::{ChatUsage, KeyUsage, UsageResponse, UsageWindow};
+use crate::agent::tokens::estimate_tokens;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Windows and token prices of the GET /usage report.
+#[derive(Debug, Clone)]
+pub struct UsageSettings {
+    /// Windows in days, each ending now
+    pub window_days: Vec<u64>,
+    /// USD per million prompt tokens
+    pub prompt_price_per_mtok: f64,
+    /// USD per million response tokens
+    pub response_price_per_mtok: f64,
+}
+
+impl Default for UsageSettings {
+    fn default() -> Self {
+        UsageSettings {
+            window_days: vec![1, 7, 30],
+            prompt_price_per_mtok: 1.25,
+            response_price_per_mtok: 10.0,
+        }
+    }
+}
+
+impl UsageSettings {
+    /// Start of the longest window, before which no record is needed.
+    pub fn since(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let days = self.window_days.iter().max().copied().unwrap_or_default();
+        now - Duration::days(days as i64)
+    }
+
+    /// Estimated USD cost of the tokens at these prices.
+    pub fn cost(&self, prompt_tokens: u64, response_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.prompt_price_per_mtok
+            + response_tokens as f64 * self.response_price_per_mtok)
+            / 1_000_000.0
+    }
+
+    /// Estimates the usage of one exchange the way the audit log records it.
+    pub fn estimate(&self, prompt: &str, response: &str) -> ChatUsage {
+        let prompt_tokens = estimate_tokens(prompt) as u64;
+        let response_tokens = estimate_tokens(response) as u64;
+        ChatUsage {
+            prompt_tokens,
+            response_tokens,
+            estimated_cost_usd: self.cost(prompt_tokens, response_tokens),
+        }
+    }
+
+    /// Totals `records` per key for each window ending at `now`.
+    pub fn report(&self, records: &[UsageRecord], now: DateTime<Utc>) -> UsageResponse {
+        let mut window_days = self.window_days.clone();
+        window_days.sort_unstable();
+        window_days.dedup();
+
+        let windows = window_days
+            .into_iter()
+            .map(|days| {
+                let since = now - Duration::days(days as i64);
+                let mut keys: HashMap<&str, KeyUsage> = HashMap::new();
+                for record in records.iter().filter(|record| record.timestamp >= since) {
+                    let usage = keys
+                        .entry(record.identity.as_str())
+                        .or_insert_with(|| KeyUsage {
+                            key: record.identity.clone(),
+                            requests: 0,
+                            errors: 0,
+                            error_rate: 0.0,
+                            prompt_tokens: 0,
+                            response_tokens: 0,
+                            estimated_cost_usd: 0.0,
+                        });
+                    usage.requests += 1;
+                    if record.status >= 400 {
+                        usage.errors += 1;
+                    }
+                    usage.prompt_tokens += record.prompt_tokens.unwrap_or_default() as u64;
+                    usage.response_tokens += record.response_tokens.unwrap_or_default() as u64;
+                }
+
+                let mut keys: Vec<KeyUsage> = keys
+                    .into_values()
+                    .map(|mut usage| {
+                        usage.error_rate = usage.errors as f64 / usage.requests as f64;
+                        usage.estimated_cost_usd =
+                            self.cost(usage.prompt_tokens, usage.response_tokens);
+                        usage
+                    })
+                    .collect();
+                keys.sort_by(|a, b| b.requests.cmp(&a.requests).then(a.key.cmp(&b.key)));
+                UsageWindow { days, keys }
+            })
+            .collect();
+
+        UsageResponse { windows }
+    }
+}
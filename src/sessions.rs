@@ -0,0 +1,312 @@
+use crate::agent::tokens::estimate_tokens;
+use crate::agent::trace::ToolCallRecord;
+use crate::agent::Agent;
+use crate::environment::Environment;
+use crate::server::types::HttpMessage;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+/// Identifies a stored conversation: the caller that owns it and the `session_id` it
+/// chose.
+///
+/// The owner is the name of the caller's API key or client certificate, so a caller only
+/// ever reaches the sessions it created, however well it guesses other callers' ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    pub owner: String,
+    pub id: String,
+}
+
+impl SessionKey {
+    pub fn new(owner: impl Into<String>, id: impl Into<String>) -> Self {
+        SessionKey {
+            owner: owner.into(),
+            id: id.into(),
+        }
+    }
+}
+
+impl fmt::Display for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.id)
+    }
+}
+
+/// Server-side state for a single conversation.
+#[derive(Debug, Default, Clone)]
+pub struct Session {
+    /// Conversation history, oldest message first
+    pub history: Vec<HttpMessage>,
+    /// Every tool call made while answering in this session, oldest first
+    pub tool_trace: Vec<ToolCallRecord>,
+    /// Facts the agent chose to remember with the `remember` tool, oldest first
+    pub memories: Vec<String>,
+    /// Unsent rest of the last response, if it was too long to send at once
+    pub continuation: Option<String>,
+}
+
+/// When and how stored conversations are compacted into a summary.
+#[derive(Debug, Clone)]
+pub struct SummaryPolicy {
+    /// Estimated history size in tokens above which older turns are summarized
+    pub trigger_tokens: usize,
+    /// Number of most recent messages kept verbatim
+    pub keep_recent: usize,
+}
+
+/// How long idle sessions are kept, and how many the store holds at most.
+#[derive(Debug, Clone)]
+pub struct SessionLimits {
+    /// Sessions unused for this long are forgotten
+    pub ttl: Duration,
+    /// Most sessions kept; the least recently used is forgotten to make room
+    pub max_sessions: usize,
+}
+
+/// A session and when it was last read or written.
+struct Entry {
+    session: Session,
+    last_used: Instant,
+}
+
+/// Prefix marking the synthetic message that replaces summarized turns.
+const SUMMARY_PREFIX: &str = "[Summary of the earlier conversation]";
+
+/// Maximum number of facts remembered per session; the oldest are forgotten first.
+const MAX_MEMORIES: usize = 50;
+
+/// In-memory store of conversations keyed by [`SessionKey`].
+///
+/// Lets clients continue a conversation by sending only a `session_id` instead of
+/// replaying the entire chat history on every request. Sessions idle for longer than
+/// the TTL are forgotten, and once the store is full, starting a session forgets the
+/// least recently used one.
+pub struct SessionStore {
+    sessions: Mutex<HashMap<SessionKey, Entry>>,
+    summary_policy: SummaryPolicy,
+    limits: SessionLimits,
+}
+
+impl SessionStore {
+    pub fn new(summary_policy: SummaryPolicy, limits: SessionLimits) -> Self {
+        SessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            summary_policy,
+            limits,
+        }
+    }
+
+    /// Creates a store with the summary policy and limits configured in `env`.
+    pub fn from_env(env: &Environment) -> Self {
+        SessionStore::new(
+            SummaryPolicy {
+                trigger_tokens: env.summary_trigger_tokens,
+                keep_recent: env.summary_keep_recent,
+            },
+            SessionLimits {
+                ttl: Duration::from_secs(env.session_ttl_secs),
+                max_sessions: env.max_sessions,
+            },
+        )
+    }
+
+    /// Runs `f` on the session `key`, if it exists and hasn't expired.
+    fn read<T>(&self, key: &SessionKey, f: impl FnOnce(&mut Session) -> T) -> Option<T> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let entry = sessions.get_mut(key)?;
+        if entry.last_used.elapsed() >= self.limits.ttl {
+            debug!("Session {} expired", key);
+            sessions.remove(key);
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some(f(&mut entry.session))
+    }
+
+    /// Runs `f` on the session `key`, starting it if it doesn't exist or has expired.
+    fn write<T>(&self, key: &SessionKey, f: impl FnOnce(&mut Session) -> T) -> T {
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+        if !sessions.contains_key(key) {
+            self.make_room(&mut sessions, now);
+        }
+        let entry = sessions.entry(key.clone()).or_insert_with(|| Entry {
+            session: Session::default(),
+            last_used: now,
+        });
+        if now.duration_since(entry.last_used) >= self.limits.ttl {
+            debug!("Session {} expired, starting over", key);
+            entry.session = Session::default();
+        }
+        entry.last_used = now;
+        f(&mut entry.session)
+    }
+
+    // Forgets expired sessions, then the least recently used ones until a new one fits
+    fn make_room(&self, sessions: &mut HashMap<SessionKey, Entry>, now: Instant) {
+        sessions.retain(|_, entry| now.duration_since(entry.last_used) < self.limits.ttl);
+        while !sessions.is_empty() && sessions.len() >= self.limits.max_sessions {
+            let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            debug!("Session store full, forgetting session {}", oldest);
+            sessions.remove(&oldest);
+        }
+    }
+
+    /// Splits a history that exceeds the context budget into the older turns to
+    /// summarize and the recent turns to keep verbatim.
+    ///
+    /// Returns None if the history still fits within the budget.
+    pub fn split_for_summary(
+        &self,
+        history: &[HttpMessage],
+    ) -> Option<(Vec<HttpMessage>, Vec<HttpMessage>)> {
+        let tokens: usize = history
+            .iter()
+            .map(|msg| estimate_tokens(&msg.content))
+            .sum();
+        if tokens <= self.summary_policy.trigger_tokens
+            || history.len() <= self.summary_policy.keep_recent
+        {
+            return None;
+        }
+
+        // Start the verbatim tail on a user turn so the summary is followed by a
+        // complete exchange
+        let mut split = history.len() - self.summary_policy.keep_recent;
+        while split < history.len() && history[split].role != "user" {
+            split += 1;
+        }
+        if split == 0 || split >= history.len() {
+            return None;
+        }
+
+        debug!(
+            "History of ~{} tokens exceeds budget of {}, summarizing {} of {} messages",
+            tokens,
+            self.summary_policy.trigger_tokens,
+            split,
+            history.len()
+        );
+
+        Some((history[..split].to_vec(), history[split..].to_vec()))
+    }
+
+    /// Builds the plain-text transcript handed to the summary model.
+    pub fn transcript(messages: &[HttpMessage]) -> String {
+        messages
+            .iter()
+            .map(|msg| format!("{}: {}", msg.role, msg.content))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Builds the message that replaces summarized turns in the history.
+    pub fn summary_message(summary: &str) -> HttpMessage {
+        HttpMessage::user(format!("{}\n{}", SUMMARY_PREFIX, summary.trim()))
+    }
+
+    /// Summarizes older turns of a long session so it fits the context budget.
+    ///
+    /// The summary replaces the older turns in the store, while the most recent turns
+    /// are kept verbatim. If summarization fails, the history is returned as-is and left
+    /// to the regular trimming limits.
+    pub async fn compact(
+        &self,
+        agent: &Agent,
+        key: &SessionKey,
+        history: Vec<HttpMessage>,
+    ) -> Vec<HttpMessage> {
+        let Some((older, recent)) = self.split_for_summary(&history) else {
+            return history;
+        };
+
+        let transcript = SessionStore::transcript(&older);
+        match agent.summarize(&transcript).await {
+            Ok(summary) => {
+                info!(
+                    "Summarized {} older messages of session {}",
+                    older.len(),
+                    key
+                );
+                let mut compacted = vec![SessionStore::summary_message(&summary)];
+                compacted.extend(recent);
+                self.replace_history(key, compacted.clone());
+                compacted
+            }
+            Err(e) => {
+                warn!("Failed to summarize session {}: {}", key, e);
+                history
+            }
+        }
+    }
+
+    /// Returns a copy of the stored history for a session (empty if unknown).
+    pub fn history(&self, key: &SessionKey) -> Vec<HttpMessage> {
+        self.read(key, |session| session.history.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the stored history for a session, e.g. after summarization.
+    pub fn replace_history(&self, key: &SessionKey, history: Vec<HttpMessage>) {
+        self.write(key, |session| session.history = history);
+        debug!("Replaced history for session {}", key);
+    }
+
+    /// Appends messages to a session, creating it if necessary.
+    pub fn append(&self, key: &SessionKey, messages: impl IntoIterator<Item = HttpMessage>) {
+        let len = self.write(key, |session| {
+            session.history.extend(messages);
+            session.history.len()
+        });
+        debug!("Session {} now has {} messages", key, len);
+    }
+
+    /// Persists the tool calls made for a turn alongside the session's history.
+    pub fn record_tool_calls(&self, key: &SessionKey, records: &[ToolCallRecord]) {
+        self.write(key, |session| session.tool_trace.extend_from_slice(records));
+    }
+
+    /// Keeps the unsent rest of the session's last response for `"continue": true`,
+    /// or clears it when `rest` is None.
+    pub fn set_continuation(&self, key: &SessionKey, rest: Option<String>) {
+        self.write(key, |session| session.continuation = rest);
+    }
+
+    /// Removes and returns the unsent rest of the session's last response.
+    ///
+    /// `key` carries the caller's identity, so a caller that guesses another's
+    /// `session_id` finds nothing to continue.
+    pub fn take_continuation(&self, key: &SessionKey) -> Option<String> {
+        self.read(key, |session| session.continuation.take())
+            .flatten()
+    }
+
+    /// Stores a fact for the session, ignoring exact duplicates.
+    pub fn remember(&self, key: &SessionKey, fact: String) {
+        self.write(key, |session| {
+            if session.memories.contains(&fact) {
+                return;
+            }
+
+            session.memories.push(fact);
+            if session.memories.len() > MAX_MEMORIES {
+                session.memories.remove(0);
+            }
+        });
+    }
+
+    /// Returns the facts remembered for a session, oldest first.
+    pub fn memories(&self, key: &SessionKey) -> Vec<String> {
+        self.read(key, |session| session.memories.clone())
+            .unwrap_or_default()
+    }
+}
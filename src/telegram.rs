@@ -0,0 +1,251 @@
+use crate::agent::{Agent, ChatOptions};
+use crate::server::limits::RequestLimits;
+use crate::server::types::HttpMessage;
+use crate::sessions::{SessionKey, SessionStore};
+use rig::completion::Message;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::*;
+
+/// Seconds Telegram holds a getUpdates request open while waiting for messages.
+const POLL_TIMEOUT_SECS: u64 = 30;
+
+/// Longest text Telegram accepts in a single message; longer replies are split.
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// Pause before polling again after getUpdates fails.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+const HELP_TEXT: &str = "Ask me about the cluster, e.g. \"which pods are restarting?\". Send /reset to start a new conversation.";
+
+/// Envelope of every Bot API response.
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<TelegramMessage>,
+}
+
+#[derive(Deserialize)]
+struct TelegramMessage {
+    chat: Chat,
+    /// Missing for messages posted on behalf of a channel
+    from: Option<User>,
+    /// Missing for stickers, photos, and other non-text messages
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct User {
+    id: i64,
+}
+
+/// Telegram bot that answers messages with the chat agent, fetching them by long polling.
+///
+/// Each Telegram chat is its own session, so follow-up questions keep their context, and
+/// `/reset` starts it over. Only users in the allowlist are answered; everyone else is
+/// logged and ignored. Like the `chat` command, the bot may use every registered tool.
+pub struct TelegramBot {
+    agent: Agent,
+    sessions: Arc<SessionStore>,
+    limits: RequestLimits,
+    client: reqwest::Client,
+    /// `https://api.telegram.org/bot<token>`; contains the token, so it is never logged
+    api_base: String,
+    allowed_users: Vec<i64>,
+}
+
+impl TelegramBot {
+    pub fn new(
+        agent: Agent,
+        sessions: Arc<SessionStore>,
+        limits: RequestLimits,
+        token: &str,
+        allowed_users: Vec<i64>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+            .build()
+            .unwrap_or_default();
+        TelegramBot {
+            agent,
+            sessions,
+            limits,
+            client,
+            api_base: format!("https://api.telegram.org/bot{}", token),
+            allowed_users,
+        }
+    }
+
+    /// Polls for messages and answers them one at a time, until the process exits.
+    pub async fn run(&self) {
+        info!(
+            "Telegram bot polling for messages from {} allowed users",
+            self.allowed_users.len()
+        );
+
+        let mut offset = 0;
+        loop {
+            let updates: Vec<Update> = match self
+                .call(
+                    "getUpdates",
+                    json!({
+                        "offset": offset,
+                        "timeout": POLL_TIMEOUT_SECS,
+                        "allowed_updates": ["message"]
+                    }),
+                )
+                .await
+            {
+                Ok(updates) => updates,
+                Err(e) => {
+                    warn!("Failed to poll Telegram for messages: {}", e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            for update in updates {
+                // Acknowledges the update on the next poll, so it is never answered twice
+                offset = update.update_id + 1;
+                if let Some(message) = update.message {
+                    self.handle_message(message).await;
+                }
+            }
+        }
+    }
+
+    async fn handle_message(&self, message: TelegramMessage) {
+        let chat_id = message.chat.id;
+        let Some(user) = message
+            .from
+            .filter(|user| self.allowed_users.contains(&user.id))
+        else {
+            warn!(
+                "Ignoring Telegram message in chat {} from a user not in TELEGRAM_ALLOWED_USERS",
+                chat_id
+            );
+            return;
+        };
+        let Some(text) = message.text else {
+            debug!("Ignoring non-text Telegram message in chat {}", chat_id);
+            return;
+        };
+        debug!("Telegram message from user {} in chat {}", user.id, chat_id);
+
+        let session = SessionKey::new("telegram", chat_id.to_string());
+        let reply = match text.trim() {
+            "/start" | "/help" => HELP_TEXT.to_string(),
+            "/reset" => {
+                self.sessions.replace_history(&session, Vec::new());
+                "Started a new conversation.".to_string()
+            }
+            prompt => self.answer(chat_id, &session, prompt).await,
+        };
+
+        if let Err(e) = self.send_message(chat_id, &reply).await {
+            warn!("Failed to send Telegram reply to chat {}: {}", chat_id, e);
+        }
+    }
+
+    /// Answers `prompt` in the chat's session, returning the text to send back.
+    async fn answer(&self, chat_id: i64, session: &SessionKey, prompt: &str) -> String {
+        if let Err(e) = self.limits.check_prompt(prompt) {
+            warn!("Rejecting oversize Telegram prompt: {}", e);
+            return e.to_string();
+        }
+
+        // Shows "typing..." while the agent works; it's cosmetic, so failures don't matter
+        if let Err(e) = self
+            .call::<bool>(
+                "sendChatAction",
+                json!({ "chat_id": chat_id, "action": "typing" }),
+            )
+            .await
+        {
+            debug!("Failed to send Telegram typing indicator: {}", e);
+        }
+
+        let history: Vec<Message> = self
+            .limits
+            .trim_history(self.sessions.history(session))
+            .into_iter()
+            .filter_map(|message| message.try_into().ok())
+            .collect();
+        let options = ChatOptions {
+            session: Some(session.clone()),
+            ..ChatOptions::default()
+        };
+
+        match self.agent.chat(prompt.to_string(), history, options).await {
+            Ok(response) => {
+                info!(
+                    "Answered Telegram message ({} chars) with {}",
+                    response.text.len(),
+                    response.model
+                );
+                self.sessions.append(
+                    session,
+                    [
+                        HttpMessage::user(prompt),
+                        HttpMessage::assistant(response.text.clone()),
+                    ],
+                );
+                self.sessions
+                    .record_tool_calls(session, &response.tool_trace);
+                response.text
+            }
+            Err(e) => {
+                error!("Failed to answer Telegram message: {}", e);
+                "Sorry, I couldn't answer that. Please try again.".to_string()
+            }
+        }
+    }
+
+    /// Sends `text` to the chat, split into as many messages as Telegram requires.
+    async fn send_message(&self, chat_id: i64, text: &str) -> Result<(), String> {
+        let chars: Vec<char> = text.chars().collect();
+        for chunk in chars.chunks(MAX_MESSAGE_CHARS) {
+            let chunk: String = chunk.iter().collect();
+            self.call::<Value>("sendMessage", json!({ "chat_id": chat_id, "text": chunk }))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Calls a Bot API method, returning its result or Telegram's description of the error.
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, String> {
+        // without_url() keeps the token in the URL out of error messages
+        let response: ApiResponse<T> = self
+            .client
+            .post(format!("{}/{}", self.api_base, method))
+            .json(&params)
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?
+            .json()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        match response.result {
+            Some(result) if response.ok => Ok(result),
+            _ => Err(response
+                .description
+                .unwrap_or_else(|| format!("{} failed", method))),
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::*;
+
+/// Largest request body the proxy reads.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Time an upstream request may take while recording (model calls can be slow).
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Replaces scrubbed values in cassettes.
+const REDACTED: &str = "<redacted>";
+
+/// JSON keys whose values are always scrubbed, compared case-insensitively with `_`
+/// and `-` ignored.
+const SECRET_KEYS: &[&str] = &[
+    "apikey",
+    "authorization",
+    "password",
+    "secret",
+    "token",
+    "accesstoken",
+    "refreshtoken",
+    "clientsecret",
+];
+
+/// Request headers forwarded upstream. Nothing from the request headers is recorded.
+const FORWARDED_HEADERS: &[&str] = &["authorization", "content-type", "accept", "openai-beta"];
+
+/// One recorded request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// Path and query, e.g. "/api/v1/pods?limit=500"
+    pub path: String,
+    /// Scrubbed request body, when it was JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<Value>,
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Scrubbed response body: JSON when the response was JSON, otherwise a string
+    pub response_body: Value,
+}
+
+/// Interactions recorded against one upstream, saved as a JSON fixture file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid cassette {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, contents + "\n")
+    }
+}
+
+/// Whether the proxy forwards and records, or answers from a cassette.
+enum Mode {
+    Record {
+        upstream: String,
+        client: reqwest::Client,
+        /// Literal values scrubbed wherever they appear, e.g. the API keys in use
+        secrets: Vec<String>,
+        cassette_path: PathBuf,
+    },
+    Replay {
+        /// Whether each interaction has been replayed
+        used: Vec<bool>,
+    },
+}
+
+struct VcrState {
+    mode: Mode,
+    cassette: Cassette,
+}
+
+/// Local HTTP proxy that records traffic to an upstream API, or replays it.
+///
+/// Point a client at [`VcrProxy::url`] instead of its real API (`KUBE_API_SERVER` or
+/// `OPENAI_BASE_URL`). While recording, each request is forwarded upstream with its
+/// credentials and the exchange is appended to the cassette file, with request headers
+/// dropped, secret-looking JSON fields and Kubernetes Secret data redacted, and the
+/// given secret values scrubbed from both bodies. While replaying, each request is
+/// answered with the first unused interaction of the same method and path, preferring
+/// one whose request body matches; a request with no recorded answer gets a 599.
+///
+/// The proxy buffers whole responses, so watches and streamed model responses can't be
+/// recorded. It stops when dropped.
+pub struct VcrProxy {
+    addr: SocketAddr,
+    state: Arc<Mutex<VcrState>>,
+    task: JoinHandle<()>,
+}
+
+impl VcrProxy {
+    /// Starts recording traffic to `upstream` into the cassette at `cassette_path`,
+    /// replacing any cassette already there.
+    ///
+    /// `accept_invalid_certs` lets recording reach clusters with self-signed certificates.
+    pub async fn record(
+        addr: SocketAddr,
+        upstream: &str,
+        cassette_path: impl Into<PathBuf>,
+        secrets: Vec<String>,
+        accept_invalid_certs: bool,
+    ) -> io::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(UPSTREAM_TIMEOUT)
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .build()
+            .map_err(io::Error::other)?;
+        let mode = Mode::Record {
+            upstream: upstream.trim_end_matches('/').to_string(),
+            client,
+            secrets: secrets.into_iter().filter(|s| !s.is_empty()).collect(),
+            cassette_path: cassette_path.into(),
+        };
+        Self::start(addr, mode, Cassette::default()).await
+    }
+
+    /// Starts answering requests from the cassette at `cassette_path`.
+    pub async fn replay(addr: SocketAddr, cassette_path: impl AsRef<Path>) -> io::Result<Self> {
+        let cassette = Cassette::load(cassette_path.as_ref())?;
+        let mode = Mode::Replay {
+            used: vec![false; cassette.interactions.len()],
+        };
+        Self::start(addr, mode, cassette).await
+    }
+
+    async fn start(addr: SocketAddr, mode: Mode, cassette: Cassette) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        match &mode {
+            Mode::Record { upstream, .. } => {
+                info!("Recording proxy for {} listening on {}", upstream, addr)
+            }
+            Mode::Replay { .. } => info!(
+                "Replaying {} interactions on {}",
+                cassette.interactions.len(),
+                addr
+            ),
+        }
+
+        let state = Arc::new(Mutex::new(VcrState { mode, cassette }));
+        let task = tokio::spawn(serve(listener, state.clone()));
+        Ok(VcrProxy { addr, state, task })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to point the client at.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Interactions recorded, or replayed so far.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        let state = self.state.lock().unwrap();
+        match &state.mode {
+            Mode::Record { .. } => state.cassette.interactions.clone(),
+            Mode::Replay { used } => state
+                .cassette
+                .interactions
+                .iter()
+                .zip(used)
+                .filter(|(_, used)| **used)
+                .map(|(interaction, _)| interaction.clone())
+                .collect(),
+        }
+    }
+
+    /// Serves until the process exits.
+    pub async fn wait(mut self) {
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for VcrProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<VcrState>>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle(stream, &state).await {
+                        debug!("VCR proxy connection failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => warn!("VCR proxy failed to accept: {}", e),
+        }
+    }
+}
+
+/// A request read off the wire.
+struct ProxyRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Answers one request and closes the connection.
+async fn handle(stream: TcpStream, state: &Mutex<VcrState>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim().to_lowercase();
+            let value = value.trim().to_string();
+            if name == "content-length" {
+                content_length = value.parse().unwrap_or(0);
+            }
+            headers.push((name, value));
+        }
+    }
+    let mut body = vec![0; content_length.min(MAX_BODY_BYTES)];
+    reader.read_exact(&mut body).await?;
+
+    let request = ProxyRequest {
+        method,
+        path,
+        headers,
+        body,
+    };
+    let (status, content_type, body) = respond(state, request).await;
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reqwest::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or("Unknown"),
+        content_type.as_deref().unwrap_or("application/json"),
+        body.len()
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+async fn respond(state: &Mutex<VcrState>, request: ProxyRequest) -> (u16, Option<String>, Vec<u8>) {
+    let request_body = serde_json::from_slice::<Value>(&request.body).ok();
+
+    // Replay entirely under the lock; recording releases it for the upstream call
+    let upstream = {
+        let mut state = state.lock().unwrap();
+        let VcrState { mode, cassette } = &mut *state;
+        match mode {
+            Mode::Replay { used } => return replay(cassette, used, &request, &request_body),
+            Mode::Record {
+                upstream, client, ..
+            } => (upstream.clone(), client.clone()),
+        }
+    };
+
+    let (upstream, client) = upstream;
+    let method =
+        reqwest::Method::from_bytes(request.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+    let mut upstream_request = client.request(method, format!("{}{}", upstream, request.path));
+    for (name, value) in &request.headers {
+        if FORWARDED_HEADERS.contains(&name.as_str()) {
+            upstream_request = upstream_request.header(name, value);
+        }
+    }
+    if !request.body.is_empty() {
+        upstream_request = upstream_request.body(request.body.clone());
+    }
+
+    let response = match upstream_request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(
+                "Upstream request {} {} failed: {}",
+                request.method, request.path, e
+            );
+            return (
+                502,
+                Some("text/plain".to_string()),
+                e.without_url().to_string().into_bytes(),
+            );
+        }
+    };
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let body = match response.bytes().await {
+        Ok(body) => body.to_vec(),
+        Err(e) => {
+            warn!(
+                "Failed to read upstream response to {}: {}",
+                request.path, e
+            );
+            return (
+                502,
+                Some("text/plain".to_string()),
+                e.without_url().to_string().into_bytes(),
+            );
+        }
+    };
+
+    let mut state = state.lock().unwrap();
+    let VcrState { mode, cassette } = &mut *state;
+    if let Mode::Record {
+        secrets,
+        cassette_path,
+        ..
+    } = mode
+    {
+        let response_body = match serde_json::from_slice::<Value>(&body) {
+            Ok(json) => json,
+            Err(_) => Value::String(String::from_utf8_lossy(&body).into_owned()),
+        };
+        cassette.interactions.push(Interaction {
+            method: request.method.clone(),
+            path: scrub_text(&request.path, secrets),
+            request_body: request_body.map(|body| scrub(body, secrets)),
+            status,
+            content_type: content_type.clone(),
+            response_body: scrub(response_body, secrets),
+        });
+        // Saved after every interaction, so a recording survives the process being killed
+        if let Err(e) = cassette.save(cassette_path) {
+            error!("Failed to save cassette {}: {}", cassette_path.display(), e);
+        }
+        debug!("Recorded {} {} -> {}", request.method, request.path, status);
+    }
+    (status, content_type, body)
+}
+
+fn replay(
+    cassette: &Cassette,
+    used: &mut [bool],
+    request: &ProxyRequest,
+    request_body: &Option<Value>,
+) -> (u16, Option<String>, Vec<u8>) {
+    let candidates: Vec<usize> = cassette
+        .interactions
+        .iter()
+        .enumerate()
+        .filter(|(index, interaction)| {
+            !used[*index]
+                && interaction.method == request.method
+                && interaction.path == request.path
+        })
+        .map(|(index, _)| index)
+        .collect();
+    // Request bodies are compared after scrubbing, as they were recorded
+    let request_body = request_body.clone().map(|body| scrub(body, &[]));
+    let found = candidates
+        .iter()
+        .find(|index| cassette.interactions[**index].request_body == request_body)
+        .or(candidates.first());
+
+    let Some(&index) = found else {
+        warn!(
+            "No recorded interaction for {} {}",
+            request.method, request.path
+        );
+        return (
+            599,
+            Some("text/plain".to_string()),
+            format!(
+                "no recorded interaction for {} {}",
+                request.method, request.path
+            )
+            .into_bytes(),
+        );
+    };
+
+    used[index] = true;
+    let interaction = &cassette.interactions[index];
+    debug!(
+        "Replaying {} {} -> {}",
+        request.method, request.path, interaction.status
+    );
+    let body = match &interaction.response_body {
+        Value::String(text) if !is_json(interaction.content_type.as_deref()) => {
+            text.clone().into_bytes()
+        }
+        json => json.to_string().into_bytes(),
+    };
+    (interaction.status, interaction.content_type.clone(), body)
+}
+
+fn is_json(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| content_type.contains("json"))
+}
+
+/// Redacts secret-looking fields, the data of Kubernetes Secrets, and every occurrence
+/// of the given secret values.
+fn scrub(value: Value, secrets: &[String]) -> Value {
+    scrub_value(value, secrets, false)
+}
+
+/// `secret` is set for the objects of a SecretList, which carry no kind of their own.
+fn scrub_value(value: Value, secrets: &[String], secret: bool) -> Value {
+    match value {
+        Value::Object(map) => {
+            let kind = map.get("kind").and_then(Value::as_str).unwrap_or_default();
+            let secret = secret || kind == "Secret";
+            let secret_list = kind == "SecretList";
+            Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| {
+                        let normalized = key.to_lowercase().replace(['_', '-'], "");
+                        let redact = SECRET_KEYS.contains(&normalized.as_str())
+                            || (secret && (key == "data" || key == "stringData"));
+                        if redact && !value.is_null() {
+                            (key, Value::String(REDACTED.to_string()))
+                        } else {
+                            let secret = secret_list && key == "items";
+                            (key, scrub_value(value, secrets, secret))
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| scrub_value(item, secrets, secret))
+                .collect(),
+        ),
+        Value::String(text) => Value::String(scrub_text(&text, secrets)),
+        other => other,
+    }
+}
+
+fn scrub_text(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret.as_str(), REDACTED)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kube::mock::{MockKubeServer, MOCK_TOKEN};
+    use crate::kube::{KubeAgent, KubeAgentError};
+    use serde_json::json;
+
+    const SECRETS: &str = "/api/v1/namespaces/default/secrets";
+
+    fn local() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 0))
+    }
+
+    fn kube_agent(proxy: &VcrProxy) -> KubeAgent {
+        KubeAgent::new(proxy.url(), MOCK_TOKEN.to_string(), None)
+    }
+
+    #[tokio::test]
+    async fn records_scrubbed_exchanges_and_replays_them_offline() {
+        let cassette = std::env::temp_dir().join(format!("vcr-test-{}.json", std::process::id()));
+        let upstream = MockKubeServer::start().await.unwrap();
+        upstream.respond(
+            SECRETS,
+            200,
+            json!({
+                "kind": "SecretList",
+                "items": [{
+                    "metadata": { "name": "db", "namespace": "default" },
+                    "data": { "password": "aHVudGVyMg==" }
+                }]
+            }),
+        );
+
+        let recorder = VcrProxy::record(
+            local(),
+            &upstream.url(),
+            &cassette,
+            vec![MOCK_TOKEN.to_string()],
+            false,
+        )
+        .await
+        .unwrap();
+        let kube = kube_agent(&recorder);
+        let nodes = kube.get("/api/v1/nodes".to_string()).await.unwrap();
+        kube.get(SECRETS.to_string()).await.unwrap();
+        assert_eq!(recorder.interactions().len(), 2);
+        drop(recorder);
+        drop(upstream);
+
+        let saved = std::fs::read_to_string(&cassette).unwrap();
+        assert!(!saved.contains("aHVudGVyMg=="));
+        assert!(!saved.contains(MOCK_TOKEN));
+
+        let player = VcrProxy::replay(local(), &cassette).await.unwrap();
+        let kube = kube_agent(&player);
+        let replayed: Value =
+            serde_json::from_str(&kube.get("/api/v1/nodes".to_string()).await.unwrap()).unwrap();
+        assert_eq!(replayed, serde_json::from_str::<Value>(&nodes).unwrap());
+        let secrets: Value =
+            serde_json::from_str(&kube.get(SECRETS.to_string()).await.unwrap()).unwrap();
+        assert_eq!(secrets["items"][0]["data"], REDACTED);
+        assert!(matches!(
+            kube.get("/api/v1/pods".to_string()).await,
+            Err(KubeAgentError::ApiError { code: 599, .. })
+        ));
+        assert_eq!(player.interactions().len(), 2);
+
+        let _ = std::fs::remove_file(&cassette);
+    }
+}